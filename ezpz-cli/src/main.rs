@@ -8,7 +8,8 @@ use std::{
 
 use clap::Parser;
 use ezpz::{
-    Constraint, FailureOutcome, Warning,
+    Config, Constraint, ConstraintRequest, FailureOutcome, FreedomAnalysis, Id, SolverSession,
+    Warning,
     datatypes::outputs::{self, Point},
     textual::{Outcome, Problem},
 };
@@ -32,6 +33,24 @@ struct Cli {
     /// Show the final values assigned to each point.
     #[arg(long = "show-points")]
     show_points: bool,
+
+    /// How many decimal places to show when printing final values.
+    #[arg(long, default_value_t = 2)]
+    precision: usize,
+
+    /// Save a JSON repro of the solve to this path, for attaching to bug reports.
+    #[arg(long = "dump-repro")]
+    dump_repro: Option<PathBuf>,
+
+    /// Analyze the degrees of freedom of the solved system,
+    /// to see if it's under- or overconstrained.
+    #[arg(long)]
+    analyze: bool,
+
+    /// Also benchmark re-solving the system in a `SolverSession`, which reuses the
+    /// symbolic factorization between solves, against re-solving it from scratch.
+    #[arg(long = "bench-session")]
+    bench_session: bool,
 }
 
 impl Cli {
@@ -67,7 +86,7 @@ fn main() {
 }
 
 fn handle_output(soln: RunOutcome, cli: Cli) -> anyhow::Result<()> {
-    print_output(&soln, cli.show_points);
+    print_output(&soln, cli.show_points, cli.precision);
     if let Some(ref p) = cli.image_path {
         let output_path = p.to_string();
         visualize::save_png(&cli, &soln.0, output_path)?;
@@ -75,41 +94,103 @@ fn handle_output(soln: RunOutcome, cli: Cli) -> anyhow::Result<()> {
     Ok(())
 }
 
-type RunOutcome = (Outcome, Duration, Vec<Constraint>);
+type RunOutcome = (
+    Outcome,
+    Duration,
+    Option<SessionBenchmark>,
+    Vec<Constraint>,
+    Option<FreedomAnalysis>,
+);
+
+/// Mean time per solve, when re-solving the same system with and without a [`SolverSession`].
+struct SessionBenchmark {
+    fresh: Duration,
+    reused: Duration,
+}
 type RunResult = Result<RunOutcome, FailureOutcome>;
 
 fn main_inner(cli: &Cli) -> Result<RunResult, String> {
     let constraint_txt = read_problem(cli)?;
-    let parsed = Problem::from_str(&constraint_txt)?;
+    let parsed = Problem::from_str(&constraint_txt).map_err(|e| e.to_string())?;
 
     // Ensure problem can be solved
-    let now = std::time::Instant::now();
     let constraint_system = parsed.to_constraint_system().map_err(|e| e.to_string())?;
+    if let Some(path) = &cli.dump_repro {
+        let repro = constraint_system
+            .dump_repro(Default::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(path, repro).map_err(|e| e.to_string())?;
+    }
     let constraints = constraint_system.constraints.clone();
-    let solved = match constraint_system.solve() {
+    let solved = if cli.analyze {
+        constraint_system
+            .solve_with_config_analysis(Config::default())
+            .map(|solved| (solved.outcome, Some(solved.analysis)))
+    } else {
+        constraint_system.solve().map(|outcome| (outcome, None))
+    };
+    let (solved, analysis) = match solved {
         Ok(o) => o,
         Err(e) => return Ok(Err(e)),
     };
 
     // It succeeded. Benchmark its perf
     let constraint_system = parsed.to_constraint_system().map_err(|e| e.to_string())?;
+    let now = std::time::Instant::now();
     for _ in 0..NUM_ITERS_BENCHMARK {
         black_box(constraint_system.solve()).unwrap();
     }
     let elapsed = now.elapsed();
     let duration_per_iter = elapsed / NUM_ITERS_BENCHMARK;
+
+    let session_benchmark = cli.bench_session.then(|| {
+        bench_session(
+            &constraint_system.constraints,
+            constraint_system.initial_guesses(),
+        )
+    });
+
     let cs = constraints.iter().copied().map(Constraint::from).collect();
-    Ok(Ok((solved, duration_per_iter, cs)))
+    Ok(Ok((
+        solved,
+        duration_per_iter,
+        session_benchmark,
+        cs,
+        analysis,
+    )))
+}
+
+/// Compare re-solving from scratch to re-solving in a session, which reuses the
+/// symbolic factorization between solves. Both solve the same constraints from the same
+/// guesses, so only the symbolic work differs between them.
+fn bench_session(constraints: &[ConstraintRequest], guesses: Vec<(Id, f64)>) -> SessionBenchmark {
+    let now = std::time::Instant::now();
+    for _ in 0..NUM_ITERS_BENCHMARK {
+        black_box(ezpz::solve(constraints, guesses.clone(), Config::default())).unwrap();
+    }
+    let fresh = now.elapsed() / NUM_ITERS_BENCHMARK;
+    let mut session = SolverSession::new(constraints, Config::default());
+    let now = std::time::Instant::now();
+    for _ in 0..NUM_ITERS_BENCHMARK {
+        black_box(session.resolve(guesses.clone())).unwrap();
+    }
+    let reused = now.elapsed() / NUM_ITERS_BENCHMARK;
+    SessionBenchmark { fresh, reused }
 }
 
 /// Prints the output nicely to stdout.
-fn print_output((outcome, duration, constraints): &RunOutcome, show_points: bool) {
+fn print_output(
+    (outcome, duration, session_benchmark, constraints, analysis): &RunOutcome,
+    show_points: bool,
+    precision: usize,
+) {
     let Outcome {
         iterations,
         warnings,
         points,
         circles,
         arcs,
+        scalars,
         num_vars,
         num_eqs,
         lines: _, // these are only used for visuals
@@ -128,16 +209,24 @@ fn print_output((outcome, duration, constraints): &RunOutcome, show_points: bool
         println!("{error}: solver did not converge!")
     }
     print_performance(*duration);
+    if let Some(session_benchmark) = session_benchmark {
+        print_session_performance(session_benchmark);
+    }
+    if let Some(analysis) = analysis {
+        print_analysis(analysis, *num_vars, *num_eqs);
+    }
     if show_points {
         println!("Points:");
         for (label, outputs::Point { x, y }) in points {
-            println!("\t{label}: ({x:.2}, {y:.2})",);
+            println!("\t{label}: ({x:.precision$}, {y:.precision$})",);
         }
         if !circles.is_empty() {
             println!("Circles:");
             for (label, outputs::Circle { radius, center }) in circles {
                 let Point { x, y } = center;
-                println!("\t{label}: center = ({x:.2}, {y:.2}), radius = {radius:.2}",);
+                println!(
+                    "\t{label}: center = ({x:.precision$}, {y:.precision$}), radius = {radius:.precision$}",
+                );
             }
         }
         if !arcs.is_empty() {
@@ -149,10 +238,16 @@ fn print_output((outcome, duration, constraints): &RunOutcome, show_points: bool
                 let bx = b.x;
                 let by = b.y;
                 println!(
-                    "\t{label}: center = ({x:.2}, {y:.2}), a = ({ax:.2}, {ay:.2}), b = ({bx:.2}, {by:.2})",
+                    "\t{label}: center = ({x:.precision$}, {y:.precision$}), a = ({ax:.precision$}, {ay:.precision$}), b = ({bx:.precision$}, {by:.precision$})",
                 );
             }
         }
+        if !scalars.is_empty() {
+            println!("Scalars:");
+            for (label, value) in scalars {
+                println!("\t{label}: {value:.precision$}");
+            }
+        }
     }
 }
 
@@ -169,6 +264,41 @@ fn print_performance(duration: Duration) {
     println!("i.e. {solves_per_second} solves per second");
 }
 
+fn print_session_performance(SessionBenchmark { fresh, reused }: &SessionBenchmark) {
+    let fresh_time = format!("{}μs", fresh.as_micros());
+    let reused_time = format!("{}μs", reused.as_micros());
+    let speedup = fresh.as_secs_f64() / reused.as_secs_f64().max(f64::MIN_POSITIVE);
+    println!(
+        "Re-solving takes {fresh_time}, or {reused_time} reusing the symbolic factorization ({speedup:.2}x)"
+    );
+}
+
+fn print_analysis(analysis: &FreedomAnalysis, num_vars: usize, num_eqs: usize) {
+    use colored::Colorize;
+    let yes_no = |b: bool| if b { "yes".yellow() } else { "no".normal() };
+    // Every degree of freedom left over is a variable the equations don't pin down,
+    // and every equation beyond the Jacobian's rank is redundant (or contradicts the others).
+    let rank = num_vars - analysis.remaining_dof();
+    let redundant = num_eqs.saturating_sub(rank);
+    println!("Degrees of freedom analysis:");
+    println!("\tRemaining DOF: {}", analysis.remaining_dof());
+    println!(
+        "\tUnderconstrained: {}",
+        yes_no(analysis.is_underconstrained())
+    );
+    println!("\tOverconstrained: {}", yes_no(redundant > 0));
+    println!("\tRedundant equations: {redundant}");
+    if !analysis.redundant_constraints().is_empty() {
+        let ids: Vec<_> = analysis
+            .redundant_constraints()
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        println!("\tRedundant constraints: {}", ids.join(", ").yellow());
+    }
+    println!("\tCondition number: {:.3e}", analysis.condition_number());
+}
+
 fn print_warnings(warnings: &[Warning]) {
     use colored::Colorize;
     if !warnings.is_empty() {
@@ -209,10 +339,14 @@ fn print_failure_output(outcome: FailureOutcome) {
         warnings,
         num_vars,
         num_eqs,
+        priority_progress,
     } = outcome;
     print_warnings(&warnings);
     print_problem_size(num_vars, num_eqs);
     eprintln!("{}: {}", "Could not solve system".red(), error);
+    for (priority, residual) in priority_progress {
+        eprintln!("\tPriority {priority} reached residual {residual:.2e}");
+    }
     if num_eqs > num_vars {
         eprintln!("Your system might be overconstrained. Try removing constraints.");
     } else {
@@ -250,12 +384,34 @@ mod tests {
                 filepath: format!("../test_cases/{case}/problem.md").into(),
                 image_path: Some("test_image.png".to_owned()),
                 show_points: true,
+                precision: 2,
+                dump_repro: None,
+                analyze: false,
+                bench_session: false,
             };
             let soln = main_inner(&cli).unwrap().unwrap();
             handle_output(soln, cli).unwrap();
         }
     }
 
+    #[test]
+    fn test_analyze_inner() {
+        let cli = Cli {
+            filepath: "../test_cases/underconstrained/problem.md".into(),
+            image_path: None,
+            show_points: false,
+            precision: 2,
+            dump_repro: None,
+            analyze: true,
+            bench_session: false,
+        };
+        let soln = main_inner(&cli).unwrap().unwrap();
+        let analysis = soln.4.as_ref().unwrap();
+        assert!(analysis.is_underconstrained());
+        assert!(analysis.remaining_dof() > 0);
+        handle_output(soln, cli).unwrap();
+    }
+
     #[test]
     fn test_tiny() {
         let out = Command::new("cargo")
@@ -297,4 +453,81 @@ mod tests {
         let stdout = String::from_utf8(out.stdout).unwrap();
         assert!(stdout.contains("Problem size: 4 rows, 8 vars"));
     }
+
+    #[test]
+    fn test_analyze() {
+        let out = Command::new("cargo")
+            .args([
+                "run",
+                "--quiet",
+                "--",
+                "-f",
+                "../test_cases/underconstrained/problem.md",
+                "--analyze",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+            .wait_with_output()
+            .unwrap();
+        assert!(out.status.success());
+        let stdout = String::from_utf8(out.stdout).unwrap();
+        assert!(stdout.contains("Degrees of freedom analysis:"), "{stdout}");
+        assert!(!stdout.contains("Remaining DOF: 0"), "{stdout}");
+        assert!(stdout.contains("Underconstrained: yes"), "{stdout}");
+    }
+
+    #[test]
+    fn test_bench_session() {
+        let run = |extra: &'static [&'static str]| {
+            let out = Command::new("cargo")
+                .args([
+                    "run",
+                    "--quiet",
+                    "--",
+                    "-f",
+                    "../test_cases/tiny/problem.md",
+                ])
+                .args(extra)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap()
+                .wait_with_output()
+                .unwrap();
+            assert!(out.status.success());
+            String::from_utf8(out.stdout).unwrap()
+        };
+        // Only benchmarked when asked for.
+        let stdout = run(&[]);
+        assert!(!stdout.contains("Re-solving takes"), "{stdout}");
+        let stdout = run(&["--bench-session"]);
+        assert!(stdout.contains("Re-solving takes"), "{stdout}");
+    }
+
+    #[test]
+    fn test_precision() {
+        let out = Command::new("cargo")
+            .args([
+                "run",
+                "--quiet",
+                "--",
+                "-f",
+                "../test_cases/tiny/problem.md",
+                "--show-points",
+                "--precision",
+                "6",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+            .wait_with_output()
+            .unwrap();
+        assert!(out.status.success());
+        let stdout = String::from_utf8(out.stdout).unwrap();
+        assert!(stdout.contains("p: (0.000000, 0.000000)"), "{stdout}");
+        assert!(stdout.contains("q: (0.000000, 0.000000)"), "{stdout}");
+    }
 }
@@ -1,4 +1,6 @@
-use crate::{NonLinearSystemError, SolveOutcomeFreedomAnalysis, solver::Model};
+use crate::{
+    NonLinearSystemError, SolveOutcomeFreedomAnalysis, datatypes::inputs::DatumPoint, solver::Model,
+};
 
 pub(crate) trait Analysis: Sized {
     fn analyze(model: Model<'_>) -> Result<Self, NonLinearSystemError>;
@@ -20,6 +22,9 @@ impl Analysis for NoAnalysis {
     }
 }
 
+/// Tolerances used by [`FreedomAnalysis::rank_profile`].
+pub const RANK_PROFILE_TOLERANCES: [f64; 5] = [1e-4, 1e-6, 1e-8, 1e-10, 1e-12];
+
 /// Results from analyzing the freedom of each variable.
 /// Created from [`crate::solve_analysis`].
 #[derive(Default, Debug)]
@@ -29,6 +34,58 @@ pub struct FreedomAnalysis {
     /// add more constraints so that their positions are properly specified and don't
     /// depend on the initial guesses.
     underconstrained: Vec<crate::Id>,
+    /// How many independent ways the variables can still move without violating any constraint.
+    remaining_dof: usize,
+    /// Each pivot from the Jacobian's rank-revealing QR, relative to the largest pivot.
+    /// Used for [`FreedomAnalysis::rank_profile`].
+    relative_pivots: Vec<f64>,
+    /// Ratio of the final Jacobian's largest singular value to its smallest.
+    condition_number: f64,
+    /// Constraints whose Jacobian rows are linearly dependent on other constraints' rows.
+    redundant_constraints: Vec<usize>,
+    /// The final Jacobian, used for per-point measures like [`FreedomAnalysis::point_stiffness`].
+    jacobian: JacobianColumns,
+}
+
+/// Column-major (CSC) copy of a sparse Jacobian.
+#[derive(Default, Debug)]
+pub(crate) struct JacobianColumns {
+    /// Column `j`'s entries are at `col_ptr[j]..col_ptr[j + 1]`.
+    pub col_ptr: Vec<usize>,
+    /// Which row each entry is in.
+    pub row_idx: Vec<usize>,
+    /// Each entry's value.
+    pub vals: Vec<f64>,
+}
+
+impl JacobianColumns {
+    /// The nonzero entries (row, value) of this variable's column.
+    /// Empty if the variable isn't in the system.
+    pub fn column(&self, var: crate::Id) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let var = var as usize;
+        let range = match (self.col_ptr.get(var), self.col_ptr.get(var + 1)) {
+            (Some(&start), Some(&end)) => start..end,
+            _ => 0..0,
+        };
+        self.row_idx[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.vals[range].iter().copied())
+    }
+
+    /// Dot product of two variables' columns.
+    fn dot(&self, a: crate::Id, b: crate::Id) -> f64 {
+        // Columns only have a handful of entries (one per constraint touching the variable),
+        // so a nested scan is cheaper than anything fancier.
+        self.column(a)
+            .map(|(row_a, val_a)| {
+                self.column(b)
+                    .filter(|&(row_b, _)| row_b == row_a)
+                    .map(|(_, val_b)| val_a * val_b)
+                    .sum::<f64>()
+            })
+            .sum()
+    }
 }
 
 impl Analysis for FreedomAnalysis {
@@ -40,13 +97,32 @@ impl Analysis for FreedomAnalysis {
     fn no_constraints() -> Self {
         Self {
             underconstrained: Vec::new(),
+            remaining_dof: 0,
+            relative_pivots: Vec::new(),
+            condition_number: 1.0,
+            redundant_constraints: Vec::new(),
+            jacobian: JacobianColumns::default(),
         }
     }
 }
 
 impl FreedomAnalysis {
-    pub(crate) fn new(underconstrained: Vec<crate::Id>) -> Self {
-        Self { underconstrained }
+    pub(crate) fn new(
+        underconstrained: Vec<crate::Id>,
+        remaining_dof: usize,
+        relative_pivots: Vec<f64>,
+        condition_number: f64,
+        redundant_constraints: Vec<usize>,
+        jacobian: JacobianColumns,
+    ) -> Self {
+        Self {
+            underconstrained,
+            remaining_dof,
+            relative_pivots,
+            condition_number,
+            redundant_constraints,
+            jacobian,
+        }
     }
 
     /// Is any variable in the system underconstrained?
@@ -61,12 +137,108 @@ impl FreedomAnalysis {
         &self.underconstrained
     }
 
+    /// How many degrees of freedom are left, i.e. how many independent ways
+    /// the variables can still move without violating any constraint?
+    /// 0 means the system is fully constrained.
+    pub fn remaining_dof(&self) -> usize {
+        self.remaining_dof
+    }
+
+    /// The Jacobian's estimated rank at each tolerance in [`RANK_PROFILE_TOLERANCES`],
+    /// as `(tolerance, rank)` pairs from the loosest tolerance to the tightest.
+    /// Tolerances are relative to the Jacobian's largest pivot.
+    /// The analysis itself uses a tolerance of 1e-8, so if the rank changes near there,
+    /// the under/over-constrained verdict is sensitive to the cutoff, i.e. some constraints
+    /// are nearly redundant.
+    pub fn rank_profile(&self) -> Vec<(f64, usize)> {
+        RANK_PROFILE_TOLERANCES
+            .iter()
+            .map(|&tolerance| {
+                let rank = self
+                    .relative_pivots
+                    .iter()
+                    .take_while(|&&pivot| pivot > tolerance)
+                    .count();
+                (tolerance, rank)
+            })
+            .collect()
+    }
+
+    /// The final Jacobian's condition number, i.e. the ratio of its largest singular value
+    /// to its smallest. Large values mean the solution is numerically fragile: tiny changes to
+    /// the constraints can move the geometry a lot, even if the system technically solves.
+    /// Huge (or infinite) if the Jacobian is rank-deficient, and 1 if there are no constraints.
+    pub fn condition_number(&self) -> f64 {
+        self.condition_number
+    }
+
+    /// IDs of constraints which are redundant, because their Jacobian rows are linearly
+    /// dependent on earlier constraints' rows. Removing them wouldn't change how constrained
+    /// the system is, so they're good candidates to remove from an overconstrained sketch.
+    /// Sorted by ID, and empty if no constraint is redundant.
+    pub fn redundant_constraints(&self) -> &[usize] {
+        &self.redundant_constraints
+    }
+
+    /// How rigidly is this point held in place by the constraints?
+    /// This is the smallest singular value of the Jacobian's columns for the point's
+    /// x and y variables, i.e. how much the residual changes when nudging the point
+    /// in its least-constrained direction.
+    /// Near zero means the point is free to move (in at least one direction),
+    /// larger means it's stiffer. A continuous complement to [`FreedomAnalysis::underconstrained`].
+    /// Points which aren't part of the system have stiffness 0.
+    pub fn point_stiffness(&self, point: &DatumPoint) -> f64 {
+        let (x, y) = (point.id_x(), point.id_y());
+        // Singular values of the m×2 block are the square roots of
+        // the eigenvalues of its 2×2 Gram matrix [[a, b], [b, c]].
+        let a = self.jacobian.dot(x, x);
+        let b = self.jacobian.dot(x, y);
+        let c = self.jacobian.dot(y, y);
+        let mean = (a + c) / 2.0;
+        let smallest_eigenvalue = mean - libm::hypot((a - c) / 2.0, b);
+        libm::sqrt(libm::fmax(smallest_eigenvalue, 0.0))
+    }
+
+    /// Redo the analysis looking only at these variables' columns of the Jacobian,
+    /// treating every other variable as fixed.
+    /// `variables` must be sorted and deduplicated.
+    pub(crate) fn restricted_to(
+        self,
+        variables: &[crate::Id],
+    ) -> Result<Self, NonLinearSystemError> {
+        let (underconstrained, remaining_dof, relative_pivots) =
+            crate::solver::underconstrained_columns(&self.jacobian, variables)?;
+        Ok(Self {
+            underconstrained,
+            remaining_dof,
+            relative_pivots,
+            condition_number: self.condition_number,
+            redundant_constraints: self.redundant_constraints,
+            jacobian: self.jacobian,
+        })
+    }
+
     /// Just like [`FreedomAnalysis::underconstrained`] except it consumes the struct to take ownership.
     pub fn into_underconstrained(self) -> Vec<crate::Id> {
         self.underconstrained
     }
 }
 
+/// Serializes the analysis' results, but not the Jacobian it was computed from.
+#[cfg(feature = "serde")]
+impl serde::Serialize for FreedomAnalysis {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("FreedomAnalysis", 5)?;
+        state.serialize_field("is_underconstrained", &self.is_underconstrained())?;
+        state.serialize_field("remaining_dof", &self.remaining_dof)?;
+        state.serialize_field("underconstrained", &self.underconstrained)?;
+        state.serialize_field("condition_number", &self.condition_number)?;
+        state.serialize_field("redundant_constraints", &self.redundant_constraints)?;
+        state.end()
+    }
+}
+
 impl From<FreedomAnalysis> for Vec<crate::Id> {
     fn from(analysis: FreedomAnalysis) -> Vec<crate::Id> {
         analysis.into_underconstrained()
@@ -79,6 +251,8 @@ pub(crate) struct SolveOutcomeAnalysis<A> {
     pub analysis: A,
     /// Other data.
     pub outcome: crate::SolveOutcome,
+    /// The variables after each iteration, if the config recorded them.
+    pub iterates: Vec<Vec<f64>>,
 }
 
 impl From<SolveOutcomeFreedomAnalysis> for SolveOutcomeAnalysis<FreedomAnalysis> {
@@ -86,6 +260,7 @@ impl From<SolveOutcomeFreedomAnalysis> for SolveOutcomeAnalysis<FreedomAnalysis>
         Self {
             analysis: value.analysis,
             outcome: value.outcome,
+            iterates: Vec::new(),
         }
     }
 }
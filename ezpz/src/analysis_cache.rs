@@ -0,0 +1,118 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::{
+    Config, ConstraintRequest, FailureOutcome, FreedomAnalysis, Id, SolveOutcome, solve,
+    solve_analysis,
+};
+
+/// Reuses the expensive [`FreedomAnalysis`] across repeated solves, only recomputing it
+/// when the constraint structure changes. Useful for a UI which re-solves on every drag,
+/// but still wants to show degrees of freedom.
+///
+/// The structure is each constraint's kind, priority and the variables its residual depends on,
+/// plus which variables are being solved for. Changing a constraint's value (e.g. a distance)
+/// or any initial guess doesn't change the structure, so the cached analysis is kept.
+/// Bear in mind the analysis is computed from the Jacobian at that solve's solution,
+/// so a cached analysis can miss freedom that only appears in a degenerate configuration.
+/// ```
+/// use ezpz::{AnalysisCache, Config, Constraint, ConstraintRequest, IdGenerator};
+/// use ezpz::datatypes::inputs::DatumPoint;
+///
+/// let mut ids = IdGenerator::default();
+/// let p = DatumPoint::new(&mut ids);
+/// let q = DatumPoint::new(&mut ids);
+/// let guesses = vec![(p.id_x(), 0.0), (p.id_y(), 0.0), (q.id_x(), 3.0), (q.id_y(), 1.0)];
+/// let mut cache = AnalysisCache::default();
+/// for distance in [4.0, 5.0, 6.0] {
+///     let requests = [
+///         ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+///         ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+///         ConstraintRequest::highest_priority(Constraint::Distance(p, q, distance)),
+///     ];
+///     cache.solve(&requests, guesses.clone(), Config::default()).unwrap();
+/// }
+/// // Only the distance changed, so the analysis was only computed once.
+/// assert_eq!(cache.analyses_computed(), 1);
+/// assert_eq!(cache.analysis().unwrap().remaining_dof(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct AnalysisCache {
+    /// Hash of the constraint structure which `analysis` was computed for.
+    structure: u64,
+    /// The cached analysis, if there is one.
+    analysis: Option<FreedomAnalysis>,
+    /// How many times the analysis has been computed.
+    analyses_computed: usize,
+}
+
+impl AnalysisCache {
+    /// Just like [`crate::solve`], except if the constraint structure changed since
+    /// the last call, this also runs [`crate::solve_analysis`] and caches its analysis.
+    pub fn solve(
+        &mut self,
+        reqs: &[ConstraintRequest],
+        initial_guesses: Vec<(Id, f64)>,
+        config: Config,
+    ) -> Result<SolveOutcome, FailureOutcome> {
+        let structure = structure_hash(reqs, &initial_guesses);
+        if self.analysis.is_some() && self.structure == structure {
+            return solve(reqs, initial_guesses, config);
+        }
+        // The old analysis is for a different structure, so if this solve fails,
+        // there shouldn't be any analysis left behind.
+        self.analysis = None;
+        let out = solve_analysis(reqs, initial_guesses, config)?;
+        self.structure = structure;
+        self.analysis = Some(out.analysis);
+        self.analyses_computed += 1;
+        Ok(out.outcome)
+    }
+
+    /// The analysis for the most recently solved constraint structure.
+    /// `None` if nothing has been solved yet, or that structure failed to solve.
+    pub fn analysis(&self) -> Option<&FreedomAnalysis> {
+        self.analysis.as_ref()
+    }
+
+    /// How many times has the analysis been (re)computed?
+    pub fn analyses_computed(&self) -> usize {
+        self.analyses_computed
+    }
+
+    /// Forget the cached analysis, so the next solve recomputes it.
+    pub fn invalidate(&mut self) {
+        self.analysis = None;
+    }
+}
+
+/// Hash everything about the system which could change its freedom analysis,
+/// ignoring constraint values and initial guesses.
+fn structure_hash(reqs: &[ConstraintRequest], initial_guesses: &[(Id, f64)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    reqs.len().hash(&mut hasher);
+    for req in reqs {
+        std::mem::discriminant(req.constraint()).hash(&mut hasher);
+        req.priority().hash(&mut hasher);
+        req.is_enabled().hash(&mut hasher);
+        req.constraint()
+            .extend_dependent_variable_ids(&mut HashIds(&mut hasher));
+        // Separate each constraint's variables from the next constraint's.
+        u8::MAX.hash(&mut hasher);
+    }
+    initial_guesses.len().hash(&mut hasher);
+    for (id, _guess) in initial_guesses {
+        id.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Hashes each variable ID it's extended with, so they don't need collecting first.
+struct HashIds<'a, H>(&'a mut H);
+
+impl<H: Hasher> Extend<Id> for HashIds<'_, H> {
+    fn extend<T: IntoIterator<Item = Id>>(&mut self, iter: T) {
+        for id in iter {
+            id.hash(self.0);
+        }
+    }
+}
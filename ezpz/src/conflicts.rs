@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use crate::{Config, ConstraintRequest, Id, evaluate_at, solve};
+
+/// Find which constraints conflict with each other, before deciding on their priorities.
+/// Every constraint is solved at the same priority (keeping its weight, and whether it's
+/// enabled), and the ones left unsatisfied are split into conflict sets: groups of constraints
+/// whose residuals can't all be zeroed at once.
+///
+/// At a least-squares solution the residuals `r` are in the left null space of the Jacobian,
+/// i.e. `Jᵀr = 0`, so the rows of the unsatisfied constraints cancel each other out.
+/// Rows which share no variables can't cancel each other, so each group is a connected set
+/// of unsatisfied constraints, linked by the variables their Jacobian rows depend on.
+///
+/// Each group holds indices into `reqs`, in increasing order, and the groups are ordered by
+/// their first index. The result is empty if every constraint could be satisfied,
+/// or if the system couldn't be solved at all.
+/// ```
+/// use ezpz::{Config, Constraint, ConstraintRequest, find_conflicts};
+///
+/// let requests = [
+///     ConstraintRequest::highest_priority(Constraint::Fixed(0, 1.0)),
+///     ConstraintRequest::highest_priority(Constraint::Fixed(1, 2.0)),
+///     ConstraintRequest::new(Constraint::Fixed(0, 3.0), 4),
+/// ];
+/// let conflicts = find_conflicts(&requests, vec![(0, 0.0), (1, 0.0)], Config::default());
+/// assert_eq!(conflicts, vec![vec![0, 2]]);
+/// ```
+pub fn find_conflicts(
+    reqs: &[ConstraintRequest],
+    initial_guesses: Vec<(Id, f64)>,
+    config: Config,
+) -> Vec<Vec<usize>> {
+    let equal_priority: Vec<_> = reqs
+        .iter()
+        .map(|req| {
+            let mut equal = ConstraintRequest::new(*req.constraint(), 0).with_weight(req.weight());
+            equal.set_enabled(req.is_enabled());
+            equal
+        })
+        .collect();
+    let mut at = initial_guesses.clone();
+    let Ok(outcome) = solve(&equal_priority, initial_guesses, config) else {
+        return Vec::new();
+    };
+    if outcome.is_satisfied() {
+        return Vec::new();
+    }
+    // Final values are in the same order as the guesses.
+    for ((_id, value), &solved) in at.iter_mut().zip(outcome.final_values()) {
+        *value = solved;
+    }
+    let unsatisfied = outcome.unsatisfied();
+    let Ok((residuals, jacobian)) = evaluate_at(&equal_priority, &at) else {
+        // Can't tell the conflicts apart, so they're all one group.
+        return vec![unsatisfied.to_vec()];
+    };
+
+    // Which request each residual row came from, or None if it was satisfied.
+    let mut row_owner = Vec::with_capacity(residuals.len());
+    for (i, req) in equal_priority.iter().enumerate() {
+        if req.is_enabled() {
+            let owner = unsatisfied.binary_search(&i).ok();
+            row_owner.extend(std::iter::repeat_n(owner, req.constraint().residual_dim()));
+        }
+    }
+
+    // Union-find over positions in `unsatisfied`, joining constraints which share a variable.
+    let mut parent: Vec<usize> = (0..unsatisfied.len()).collect();
+    let mut first_user: HashMap<usize, usize> = HashMap::with_capacity(jacobian.len());
+    for &(row, column, partial_derivative) in &jacobian {
+        let Some(Some(owner)) = row_owner.get(row).copied() else {
+            continue;
+        };
+        if partial_derivative == 0.0 {
+            continue;
+        }
+        match first_user.get(&column) {
+            Some(&other) => {
+                let (a, b) = (find_root(&mut parent, owner), find_root(&mut parent, other));
+                parent[a.max(b)] = a.min(b);
+            }
+            None => {
+                first_user.insert(column, owner);
+            }
+        }
+    }
+
+    // Roots are always the smallest position in their group, so groups come out in order.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root = HashMap::with_capacity(unsatisfied.len());
+    for (position, &constraint) in unsatisfied.iter().enumerate() {
+        let root = find_root(&mut parent, position);
+        let group = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(constraint);
+    }
+    groups
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
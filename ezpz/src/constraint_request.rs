@@ -1,4 +1,7 @@
-use crate::Constraint;
+use crate::{
+    Constraint,
+    datatypes::inputs::{DatumLineSegment, DatumPoint},
+};
 
 /// A constraint that EZPZ should solve for.
 /// ```
@@ -10,6 +13,7 @@ use crate::Constraint;
 /// ```
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintRequest {
     /// The constraint itself.
     constraint: Constraint,
@@ -24,6 +28,11 @@ pub struct ConstraintRequest {
     /// solver pull harder on this constraint relative to others within the same
     /// priority tier. Defaults to 1.0.
     weight: f64,
+
+    /// Disabled constraints are left out of the solve entirely, as if they'd been removed,
+    /// but keep their position (and so their ID) in the list of requests.
+    #[cfg_attr(feature = "serde", serde(default))]
+    disabled: bool,
 }
 
 impl ConstraintRequest {
@@ -40,6 +49,7 @@ impl ConstraintRequest {
             constraint,
             priority,
             weight: 1.0,
+            disabled: false,
         }
     }
 
@@ -65,6 +75,24 @@ impl ConstraintRequest {
         self
     }
 
+    /// Suppress this constraint, or bring it back. A disabled constraint contributes nothing
+    /// to the solve, but stays in the list of requests so it can be cheaply re-enabled,
+    /// and the constraints after it keep their IDs. Constraints are enabled by default.
+    /// ```
+    /// use ezpz::{Constraint, ConstraintRequest};
+    /// let mut req = ConstraintRequest::highest_priority(Constraint::Fixed(0, 1.0));
+    /// req.set_enabled(false);
+    /// assert!(!req.is_enabled());
+    /// ```
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.disabled = !enabled;
+    }
+
+    /// Is this constraint part of the solve? See [`ConstraintRequest::set_enabled`].
+    pub fn is_enabled(&self) -> bool {
+        !self.disabled
+    }
+
     /// Get the underlying constraint.
     pub fn constraint(&self) -> &Constraint {
         &self.constraint
@@ -85,6 +113,36 @@ impl ConstraintRequest {
     }
 }
 
+/// Make a whole sketch symmetric about one axis, instead of adding a [`Constraint::Symmetric`]
+/// for every pair of points by hand.
+/// Returns `reqs` followed by a highest-priority [`Constraint::Symmetric`] about `axis`
+/// for each pair.
+/// ```
+/// use ezpz::{Constraint, ConstraintRequest, IdGenerator, enforce_symmetry};
+/// use ezpz::datatypes::inputs::{DatumLineSegment, DatumPoint};
+///
+/// let mut ids = IdGenerator::default();
+/// let [axis0, axis1, a, b] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+/// let reqs = [ConstraintRequest::highest_priority(Constraint::Distance(a, b, 2.0))];
+/// let axis = DatumLineSegment::new(axis0, axis1);
+/// let reqs = enforce_symmetry(&reqs, &[(a, b)], axis);
+/// assert_eq!(reqs.len(), 2);
+/// ```
+pub fn enforce_symmetry(
+    reqs: &[ConstraintRequest],
+    pairs: &[(DatumPoint, DatumPoint)],
+    axis: DatumLineSegment,
+) -> Vec<ConstraintRequest> {
+    let mut out = Vec::with_capacity(reqs.len() + pairs.len());
+    out.extend_from_slice(reqs);
+    out.extend(
+        pairs
+            .iter()
+            .map(|&(a, b)| ConstraintRequest::highest_priority(Constraint::Symmetric(axis, a, b))),
+    );
+    out
+}
+
 impl From<ConstraintRequest> for Constraint {
     fn from(value: ConstraintRequest) -> Self {
         value.constraint
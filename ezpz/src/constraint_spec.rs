@@ -0,0 +1,256 @@
+use crate::{
+    Constraint, SpecError,
+    datatypes::{
+        Angle, AngleKind,
+        inputs::{DatumCircularArc, DatumLineSegment, DatumPoint},
+        outputs::Point,
+    },
+};
+
+/// Build a constraint from its kind (as named by [`Constraint::constraint_kind`]) and
+/// positional arguments, for language bindings which can't construct [`Constraint`] directly.
+/// This is the programmatic equivalent of the [`crate::textual`] format.
+///
+/// Only constraints built purely from points and numbers are supported.
+/// Arguments are in the same order as the variant's fields:
+/// - A line segment takes two points, `p0` then `p1`.
+/// - An arc takes three points, its center then start then end.
+/// - An angle takes one number, in radians.
+/// - A fixed point, like [`Constraint::AnchorNear`]'s anchor, takes two numbers, x then y.
+///
+/// ```
+/// use ezpz::{Constraint, IdGenerator, constraint_from_spec, datatypes::inputs::DatumPoint};
+///
+/// let mut ids = IdGenerator::default();
+/// let p = DatumPoint::new(&mut ids);
+/// let q = DatumPoint::new(&mut ids);
+/// let distance = constraint_from_spec("Distance", &[p, q], &[4.0]).unwrap();
+/// assert!(matches!(distance, Constraint::Distance(_, _, 4.0)));
+/// // Distance needs a number too.
+/// assert!(constraint_from_spec("Distance", &[p, q], &[]).is_err());
+/// ```
+pub fn constraint_from_spec(
+    kind: &str,
+    point_args: &[DatumPoint],
+    scalar_args: &[f64],
+) -> Result<Constraint, SpecError> {
+    let arity = |points: usize, scalars: usize| {
+        if point_args.len() == points && scalar_args.len() == scalars {
+            Ok(())
+        } else {
+            Err(SpecError::WrongArity {
+                kind: kind.to_owned(),
+                expected_points: points,
+                expected_scalars: scalars,
+                points: point_args.len(),
+                scalars: scalar_args.len(),
+            })
+        }
+    };
+    let p = point_args;
+    let s = scalar_args;
+    // Every arm indexes the arguments only after `arity` has checked there's enough of them.
+    let line = |i: usize| DatumLineSegment::new(p[i], p[i + 1]);
+    let arc = |i: usize| DatumCircularArc {
+        center: p[i],
+        start: p[i + 1],
+        end: p[i + 2],
+    };
+    let constraint = match kind {
+        "Distance" => {
+            arity(2, 1)?;
+            Constraint::Distance(p[0], p[1], s[0])
+        }
+        "VerticalDistance" => {
+            arity(2, 1)?;
+            Constraint::VerticalDistance(p[0], p[1], s[0])
+        }
+        "HorizontalDistance" => {
+            arity(2, 1)?;
+            Constraint::HorizontalDistance(p[0], p[1], s[0])
+        }
+        "Vertical" => {
+            arity(2, 0)?;
+            Constraint::Vertical(line(0))
+        }
+        "Horizontal" => {
+            arity(2, 0)?;
+            Constraint::Horizontal(line(0))
+        }
+        "LinesAtAngle" => {
+            arity(4, 1)?;
+            Constraint::LinesAtAngle(
+                line(0),
+                line(2),
+                AngleKind::Other(Angle::from_radians(s[0])),
+            )
+        }
+        "FixedPoint" => {
+            arity(1, 2)?;
+            Constraint::FixedPoint(p[0], s[0], s[1])
+        }
+        "PointsCoincident" => {
+            arity(2, 0)?;
+            Constraint::PointsCoincident(p[0], p[1])
+        }
+        "LinesEqualLength" => {
+            arity(4, 0)?;
+            Constraint::LinesEqualLength(line(0), line(2))
+        }
+        "ArcRadius" => {
+            arity(3, 1)?;
+            Constraint::ArcRadius(arc(0), s[0])
+        }
+        "Arc" => {
+            arity(3, 0)?;
+            Constraint::Arc(arc(0))
+        }
+        "Midpoint" => {
+            arity(3, 0)?;
+            Constraint::Midpoint(line(0), p[2])
+        }
+        "PointLineDistance" => {
+            arity(3, 1)?;
+            Constraint::PointLineDistance(p[0], line(1), s[0])
+        }
+        "VerticalPointLineDistance" => {
+            arity(3, 1)?;
+            Constraint::VerticalPointLineDistance(p[0], line(1), s[0])
+        }
+        "HorizontalPointLineDistance" => {
+            arity(3, 1)?;
+            Constraint::HorizontalPointLineDistance(p[0], line(1), s[0])
+        }
+        "Symmetric" => {
+            arity(4, 0)?;
+            Constraint::Symmetric(line(0), p[2], p[3])
+        }
+        "PointArcCoincident" => {
+            arity(4, 0)?;
+            Constraint::PointArcCoincident(arc(0), p[3])
+        }
+        "ArcLength" => {
+            arity(3, 1)?;
+            Constraint::ArcLength(arc(0), s[0])
+        }
+        "ArcAngle" => {
+            arity(3, 1)?;
+            Constraint::ArcAngle(arc(0), Angle::from_radians(s[0]))
+        }
+        "PointsAtAngle" => {
+            arity(3, 1)?;
+            Constraint::PointsAtAngle(
+                p[0],
+                p[1],
+                p[2],
+                AngleKind::Other(Angle::from_radians(s[0])),
+            )
+        }
+        "SubtendsAngle" => {
+            arity(3, 1)?;
+            Constraint::SubtendsAngle(p[0], p[1], p[2], Angle::from_radians(s[0]))
+        }
+        "PolarOffset" => {
+            arity(2, 2)?;
+            Constraint::PolarOffset(p[0], p[1], s[0], Angle::from_radians(s[1]))
+        }
+        "Homothety" => {
+            arity(3, 1)?;
+            Constraint::Homothety(p[0], p[1], p[2], s[0])
+        }
+        "MinDistance" => {
+            arity(2, 1)?;
+            Constraint::MinDistance(p[0], p[1], s[0])
+        }
+        "LengthRatioBounds" => {
+            arity(4, 2)?;
+            Constraint::LengthRatioBounds(line(0), line(2), s[0], s[1])
+        }
+        "SameSideOfLine" => {
+            arity(4, 0)?;
+            Constraint::SameSideOfLine(line(0), p[2], p[3])
+        }
+        "Concyclic" => {
+            arity(6, 0)?;
+            Constraint::Concyclic(arc(0), arc(3))
+        }
+        "Circumcenter" => {
+            arity(4, 0)?;
+            Constraint::Circumcenter(p[0], p[1], p[2], p[3])
+        }
+        "ArcSagitta" => {
+            arity(3, 1)?;
+            Constraint::ArcSagitta(arc(0), s[0])
+        }
+        "ArcSegmentArea" => {
+            arity(3, 1)?;
+            Constraint::ArcSegmentArea(arc(0), s[0])
+        }
+        "EqualLengthToCommon" => {
+            arity(3, 0)?;
+            Constraint::EqualLengthToCommon(p[0], p[1], p[2])
+        }
+        "AnchorNear" => {
+            arity(1, 3)?;
+            Constraint::AnchorNear(p[0], Point { x: s[0], y: s[1] }, s[2])
+        }
+        "OnGrid" => {
+            arity(1, 3)?;
+            Constraint::OnGrid(p[0], s[0], Point { x: s[1], y: s[2] })
+        }
+        "DistanceSum" => {
+            arity(3, 1)?;
+            Constraint::DistanceSum(p[0], p[1], p[2], s[0])
+        }
+        "PointOnLine" => {
+            arity(3, 0)?;
+            Constraint::PointOnLine(p[0], line(1))
+        }
+        "Collinear" => {
+            arity(3, 0)?;
+            Constraint::Collinear(p[0], p[1], p[2])
+        }
+        "ArcMidpoint" => {
+            arity(4, 0)?;
+            Constraint::ArcMidpoint(arc(0), p[3])
+        }
+        "LineAngle" => {
+            arity(2, 1)?;
+            Constraint::LineAngle(line(0), Angle::from_radians(s[0]))
+        }
+        "EqualAngle" => {
+            arity(8, 0)?;
+            Constraint::EqualAngle(line(0), line(2), line(4), line(6))
+        }
+        "ArcChordAngle" => {
+            arity(3, 1)?;
+            Constraint::ArcChordAngle(arc(0), Angle::from_radians(s[0]))
+        }
+        "ReverseTangentArcs" => {
+            arity(6, 0)?;
+            Constraint::ReverseTangentArcs(arc(0), arc(3))
+        }
+        "AngleBisectorLine" => {
+            arity(6, 0)?;
+            Constraint::AngleBisectorLine(line(0), line(2), line(4))
+        }
+        "SymmetricAngle" => {
+            arity(6, 0)?;
+            Constraint::SymmetricAngle(line(0), line(2), line(4))
+        }
+        "EquidistantLinePoint" => {
+            arity(4, 0)?;
+            Constraint::EquidistantLinePoint(p[0], line(1), p[3])
+        }
+        "ParallelOffset" => {
+            arity(4, 1)?;
+            Constraint::ParallelOffset(line(0), line(2), s[0])
+        }
+        _ => {
+            return Err(SpecError::UnknownKind {
+                kind: kind.to_owned(),
+            });
+        }
+    };
+    Ok(constraint)
+}
@@ -33,6 +33,7 @@ impl AsRef<Constraint> for ConstraintEntry<'_> {
 /// Each geometric constraint we support.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
 pub enum Constraint {
     /// This line must be tangent to the circle
@@ -58,6 +59,9 @@ pub enum Constraint {
     LinesAtAngle(DatumLineSegment, DatumLineSegment, AngleKind),
     /// Some scalar value is fixed.
     Fixed(Id, f64),
+    /// This point is fixed at the given X and Y.
+    /// Like two [`Constraint::Fixed`], but reported as one constraint.
+    FixedPoint(DatumPoint, f64, f64),
     /// These two scalar values are the same.
     /// E.g. set two circles to have the same radius.
     ScalarEqual(Id, Id),
@@ -90,6 +94,221 @@ pub enum Constraint {
     ArcAngle(DatumCircularArc, Angle),
     /// The oriented angle from (p1 - p0) to (p2 - p0) should equal the given angle.
     PointsAtAngle(DatumPoint, DatumPoint, DatumPoint, AngleKind),
+    /// Seen from the first point, the segment between the second and third points should
+    /// subtend this angle, measured CCW from the third point to the second.
+    /// So the first point lies on the arc through both others where the inscribed angle is this
+    /// angle. Unlike [`Constraint::PointsAtAngle`], this has one residual, not two.
+    SubtendsAngle(DatumPoint, DatumPoint, DatumPoint, Angle),
+    /// The second point should be at the given polar offset (distance, then direction
+    /// measured CCW from the +X axis) from the first point.
+    PolarOffset(DatumPoint, DatumPoint, f64, Angle),
+    /// The point should lie on the conic section.
+    PointOnConic(DatumPoint, DatumConic),
+    /// The copy should be the original point scaled about the pivot
+    /// by the given (solved-for) scale factor, i.e.
+    /// `copy = pivot + scale * (original - pivot)`.
+    ScaledCopy(DatumPoint, Id, DatumPoint, DatumPoint),
+    /// The image should be the original point scaled about the center by this fixed ratio,
+    /// i.e. `image - center = ratio * (original - center)`.
+    /// A ratio of -1 reflects the original through the center, and 1 makes them coincide.
+    /// See [`Constraint::ScaledCopy`] to solve for the ratio instead.
+    Homothety(DatumPoint, DatumPoint, DatumPoint, f64),
+    /// The given point should be the given (perpendicular) distance away from the infinite line.
+    /// The distance is signed, positive on the side the line's normal points to.
+    PointInfiniteLineDistance(DatumPoint, DatumLine, f64),
+    /// This infinite line must be tangent to the circle.
+    /// [`LineSide::Left`] is the side the line's normal points to.
+    InfiniteLineTangentToCircle(DatumLine, DatumCircle, LineSide),
+    /// These two points should be at least the given distance apart.
+    /// This is one-sided: it has no effect once the points are far enough apart.
+    /// See [`Constraint::min_distance_to_any`] for keeping clear of several points.
+    MinDistance(DatumPoint, DatumPoint, f64),
+    /// The first line's length divided by the second line's should stay within
+    /// `[min_ratio, max_ratio]`, given in that order.
+    /// This is one-sided at each bound: it has no effect while the ratio is within the bounds,
+    /// and pulls the ratio back to the nearest bound once it's outside.
+    LengthRatioBounds(DatumLineSegment, DatumLineSegment, f64, f64),
+    /// The second point should stay on the same side of the line as the first (reference) point.
+    /// This is one-sided: it has no effect while the points are on the same side,
+    /// and penalizes the second point's distance past the line once it crosses.
+    SameSideOfLine(DatumLineSegment, DatumPoint, DatumPoint),
+    /// The arc's radius (measured from its center to its start) should equal the circle's radius.
+    ArcCircleEqualRadius(DatumCircularArc, DatumCircle),
+    /// These two arcs should lie on the same circle,
+    /// i.e. their centers coincide and their radii are equal.
+    /// Each radius is measured from the arc's center to its start,
+    /// so combine with [`Constraint::Arc`] to keep the arcs' ends on the circle too.
+    Concyclic(DatumCircularArc, DatumCircularArc),
+    /// The first point should be the circumcenter of the other three,
+    /// i.e. equidistant from all of them.
+    Circumcenter(DatumPoint, DatumPoint, DatumPoint, DatumPoint),
+    /// The arc's sagitta (the height of the arc above its chord) should be the given length.
+    /// The radius is measured from the arc's center to its start, and the sagitta is
+    /// `r - sqrt(r² - (chord/2)²)`, i.e. the height of the shorter of the two arcs the chord
+    /// cuts off the circle. Combine with [`Constraint::Arc`] to keep the end on the circle too.
+    ArcSagitta(DatumCircularArc, f64),
+    /// The circular segment between the arc and its chord should have this area.
+    /// The radius is measured from the arc's center to its start, and the arc sweeps
+    /// counterclockwise from start to end, so the area is `r²(θ - sin θ)/2` for a sweep of θ.
+    /// Combine with [`Constraint::Arc`] to keep the end on the circle too.
+    ArcSegmentArea(DatumCircularArc, f64),
+    /// The second and third points should be the same distance from the first (common) point,
+    /// like spokes of equal length. This is the same equidistance as [`Constraint::Arc`],
+    /// without needing an arc.
+    EqualLengthToCommon(DatumPoint, DatumPoint, DatumPoint),
+    /// The point should stay within the given radius of the anchor, e.g. a cursor dragging it.
+    /// This is one-sided: it has no effect while the point is within the radius,
+    /// and pulls the point back towards the anchor once it's further away.
+    AnchorNear(DatumPoint, outputs::Point, f64),
+    /// The point should snap to the nearest intersection of a square grid,
+    /// with the given spacing between grid lines, and one intersection at the given origin.
+    /// Each axis's residual is the distance to the nearest grid line along it,
+    /// so a point already on the grid is left alone.
+    OnGrid(DatumPoint, f64, outputs::Point),
+    /// The distances from the first point to the second and third points (the foci)
+    /// should add up to the given length, i.e. the first point lies on that ellipse.
+    DistanceSum(DatumPoint, DatumPoint, DatumPoint, f64),
+    /// The circle should be a fillet in the corner between the two lines,
+    /// i.e. its center should be one radius away from each line.
+    /// Constrain the radius itself with [`Constraint::CircleRadius`].
+    FilletRadius(DatumLineSegment, DatumLineSegment, DatumCircle),
+    /// The point should lie on the circle, whose radius can be a solver variable.
+    /// This works both ways: a free point slides onto the circle, and a fixed point
+    /// moves and resizes a free circle so it passes through the point.
+    PointOnCircle(DatumPoint, DatumCircle),
+    /// The point should lie on the (infinite) line through the segment's two points.
+    /// Equivalent to a [`Constraint::PointLineDistance`] of 0.
+    PointOnLine(DatumPoint, DatumLineSegment),
+    /// The point should be where the (infinite) line through the segment meets the circle,
+    /// i.e. both a [`Constraint::PointOnLine`] and a [`Constraint::PointOnCircle`].
+    /// A line usually crosses a circle twice, and the solver picks whichever intersection
+    /// is nearer the point's initial guess.
+    LineCircleIntersection(DatumPoint, DatumLineSegment, DatumCircle),
+    /// The three points should lie on one line, without needing a line segment.
+    /// The residual is the signed area `(b - a) × (c - a)`, so unlike most residuals
+    /// it's in units of area, and it's still well-defined when points coincide.
+    Collinear(DatumPoint, DatumPoint, DatumPoint),
+    /// The (infinite) line through the segment should be tangent to both circles,
+    /// e.g. a belt running between two pulleys.
+    CommonTangent(DatumLineSegment, DatumCircle, DatumCircle, TangencyKind),
+    /// The point halfway along the arc (in angle, going counterclockwise from start to end)
+    /// should be at the given point, e.g. to dimension an arc by its apex.
+    /// The arc's radius is the distance from its center to its start.
+    ArcMidpoint(DatumCircularArc, DatumPoint),
+    /// The line's direction, from `p0` to `p1`, should be at this angle
+    /// counterclockwise from the X axis.
+    LineAngle(DatumLineSegment, Angle),
+    /// The angle from the first line to the second (CCW) should equal
+    /// the angle from the third line to the fourth.
+    EqualAngle(
+        DatumLineSegment,
+        DatumLineSegment,
+        DatumLineSegment,
+        DatumLineSegment,
+    ),
+    /// The line should leave the arc tangentially at the given end of the arc,
+    /// i.e. be perpendicular to the arc's radius there. The arc's end is usually one of
+    /// the line's points too, but that has to be constrained separately.
+    /// The residual is `(end - center) · (p1 - p0)`, so like [`Constraint::Collinear`]
+    /// it's in units of area.
+    LineArcTangent(DatumLineSegment, DatumCircularArc, Endpoint),
+    /// The arc's chord, from its start to its end, should be at this angle
+    /// counterclockwise from the X axis. Like [`Constraint::LineAngle`] for the chord.
+    ArcChordAngle(DatumCircularArc, Angle),
+    /// The two arcs should meet smoothly with reversed curvature, like the inflection of an
+    /// S-curve: their radii at the first arc's end and the second arc's end should be
+    /// parallel, with the centers on opposite sides of the shared tangent.
+    /// Arcs always go counterclockwise, so where a path turns from one arc onto the other,
+    /// the second arc runs backwards, i.e. the join is both arcs' ends.
+    /// The ends are usually the same point, but that has to be constrained separately.
+    /// The residuals are `ra × rb`, and `ra · rb` as an inequality, where `ra` and `rb` are
+    /// the radii to the ends, so like [`Constraint::Collinear`] they're in units of area.
+    ReverseTangentArcs(DatumCircularArc, DatumCircularArc),
+    /// The first scalar should equal a linear combination of the others plus a constant,
+    /// i.e. `result = c0 * term0 + c1 * term1 + constant`, e.g. so a total length is
+    /// the sum of its parts, or one dimension is driven by the difference of two others.
+    /// Give a term a coefficient of 0 to leave it out.
+    DistanceLinearCombo(Id, [(Id, f64); 2], f64),
+    /// The first line should bisect the angle between the second and third lines,
+    /// i.e. the angle from the second line to the first equals the angle from the first to the third.
+    /// Lines have no orientation here, so reversing the bisector still satisfies this.
+    AngleBisectorLine(DatumLineSegment, DatumLineSegment, DatumLineSegment),
+    /// The angle from the first (reference) line to the second should be the negative of
+    /// the angle from the reference line to the third, e.g. for the arms of a symmetric V.
+    /// Angles are measured between the lines' directions (p0 towards p1), so reversing an arm
+    /// changes its angle by 180 degrees. This is the same geometry as
+    /// [`Constraint::AngleBisectorLine`] with the reference as the bisector.
+    SymmetricAngle(DatumLineSegment, DatumLineSegment, DatumLineSegment),
+    /// The circle's radius should be the given multiple of some scalar variable,
+    /// e.g. so one driving scalar controls several related radii.
+    RadiusRatio(DatumCircle, Id, f64),
+    /// The point should be the same distance from the line (its directrix)
+    /// as from the other point (its focus), i.e. it lies on the parabola they define.
+    /// The distance to the line is unsigned, so the point can be on either side of it.
+    EquidistantLinePoint(DatumPoint, DatumLineSegment, DatumPoint),
+    /// The line should be tangent to the circle while pointing at the given angle
+    /// (measured CCW from the +X axis, from p0 towards p1).
+    /// The circle is on the left of the line's direction, so add 180 degrees to the angle
+    /// to put it on the right instead.
+    TangentAtAngle(DatumLineSegment, DatumCircle, Angle),
+    /// The second line should be parallel to the first, offset from it by the given
+    /// perpendicular distance, e.g. for the two faces of a wall with some thickness.
+    /// The offset is signed like [`Constraint::PointLineDistance`], measured from the first
+    /// line to the second line's first point.
+    ParallelOffset(DatumLineSegment, DatumLineSegment, f64),
+    /// Just like [`Constraint::ParallelOffset`], except the offset is this scalar variable,
+    /// e.g. so one driving scalar sets the thickness of several walls.
+    ParallelOffsetScalar(DatumLineSegment, DatumLineSegment, Id),
+    /// The angle from the first line to the second (CCW, in radians) should equal
+    /// this scalar variable, e.g. so one [`Constraint::Fixed`] on it drives a whole linkage.
+    AngleEqualsScalar(DatumLineSegment, DatumLineSegment, Id),
+    /// The arc's central angle (CCW from its start to its end, in radians) should equal
+    /// this scalar variable. Like [`Constraint::ArcAngle`], but driven by a solved variable,
+    /// so one [`Constraint::Fixed`] on it opens or closes the arc.
+    ArcCentralAngleScalar(DatumCircularArc, Id),
+}
+
+/// How the solver should treat one row of a constraint's residual.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ResidualKind {
+    /// Satisfied when the residual is 0.
+    Equality,
+    /// Satisfied when the residual is 0 or negative,
+    /// so only positive residuals are penalized.
+    Inequality,
+}
+
+impl ResidualKind {
+    /// How much of this raw residual counts as error?
+    pub(crate) fn clamp(self, residual: f64) -> f64 {
+        match self {
+            ResidualKind::Equality => residual,
+            ResidualKind::Inequality => libm::fmax(residual, 0.0),
+        }
+    }
+
+    /// Does this raw residual contribute to the system, i.e. should its Jacobian row be used?
+    pub(crate) fn is_active(self, residual: f64) -> bool {
+        match self {
+            ResidualKind::Equality => true,
+            ResidualKind::Inequality => residual > 0.0,
+        }
+    }
+}
+
+/// What one row of a constraint's residual measures,
+/// so the solver can hold it to the right tolerance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ResidualUnit {
+    /// A distance.
+    Length,
+    /// An angular error (in radians), multiplied by this length to put it in length units.
+    Angle {
+        /// The length the angular error was multiplied by.
+        scale: f64,
+    },
+    /// Neither of those, e.g. an area.
+    Other,
 }
 
 /// Describes one value in one row of the Jacobian matrix.
@@ -104,6 +323,7 @@ pub(crate) struct JacobianVar {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
 /// Which side of a directed line a constraint refers to.
 pub enum LineSide {
@@ -117,6 +337,7 @@ pub enum LineSide {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
 /// Which side of a circle a constraint refers to.
 pub enum CircleSide {
@@ -128,6 +349,50 @@ pub enum CircleSide {
     Interior,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+/// Which common tangent of two circles a line is, see [`Constraint::CommonTangent`].
+pub enum TangencyKind {
+    /// Both circles are on the same side of the line, like an open belt.
+    External,
+    /// The circles are on opposite sides of the line, like a crossed belt.
+    Internal,
+}
+
+impl TangencyKind {
+    /// Signed distance from the line to the second circle's center, as a multiple of
+    /// its radius, when the first circle's center is on the positive side.
+    fn sign(self) -> f64 {
+        match self {
+            TangencyKind::External => 1.0,
+            TangencyKind::Internal => -1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+/// One end of an arc, see [`Constraint::LineArcTangent`].
+pub enum Endpoint {
+    /// The arc's start point.
+    Start,
+    /// The arc's end point.
+    End,
+}
+
+impl Endpoint {
+    fn of(self, arc: &DatumCircularArc) -> DatumPoint {
+        match self {
+            Endpoint::Start => arc.start,
+            Endpoint::End => arc.end,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum PointArcCoincidentPart {
     Interior,
@@ -143,6 +408,15 @@ impl std::fmt::Debug for JacobianVar {
 }
 
 impl Constraint {
+    /// A guess for one of this constraint's scalars, implied by the constraint's constants,
+    /// see [`crate::Config::with_seed_scalars_from_constraints`].
+    pub(crate) fn scalar_seed(&self) -> Option<(Id, f64)> {
+        match self {
+            Constraint::CircleRadius(circle, radius) => Some((circle.radius.id, *radius)),
+            _ => None,
+        }
+    }
+
     pub(crate) fn set_from_initial_values(&mut self, initial_values: &[f64]) {
         match self {
             Constraint::LineTangentToCircle(line, circle, side) if *side == LineSide::Undefined => {
@@ -164,6 +438,22 @@ impl Constraint {
                     LineSide::Right
                 };
             }
+            Constraint::InfiniteLineTangentToCircle(line, circle, side)
+                if *side == LineSide::Undefined =>
+            {
+                let angle = initial_values[line.angle as usize];
+                let offset = initial_values[line.offset as usize];
+                let c = V::new(
+                    initial_values[circle.center.id_x() as usize],
+                    initial_values[circle.center.id_y() as usize],
+                );
+                let n = V::new(libm::cos(angle), libm::sin(angle));
+                *side = if n.dot(c) - offset >= 0.0 {
+                    LineSide::Left
+                } else {
+                    LineSide::Right
+                };
+            }
             Constraint::CircleTangentToCircle(circle_a, circle_b, side)
                 if *side == CircleSide::Undefined =>
             {
@@ -233,6 +523,7 @@ impl Constraint {
                 out.extend(line1.all_variables());
             }
             Constraint::Fixed(id, _scalar) => out.extend([*id]),
+            Constraint::FixedPoint(p, _x, _y) => out.extend(p.all_variables()),
             Constraint::ScalarEqual(x, y) => out.extend([*x, *y]),
             Constraint::PointsCoincident(p0, p1) => {
                 out.extend(p0.all_variables());
@@ -277,6 +568,187 @@ impl Constraint {
                 out.extend(p1.all_variables());
                 out.extend(p2.all_variables());
             }
+            Constraint::PolarOffset(from, to, _r, _theta) => {
+                out.extend(from.all_variables());
+                out.extend(to.all_variables());
+            }
+            Constraint::PointOnConic(point, conic) => {
+                out.extend(point.all_variables());
+                out.extend(conic.all_variables());
+            }
+            Constraint::ScaledCopy(pivot, scale, original, copy) => {
+                out.extend(pivot.all_variables());
+                out.extend([*scale]);
+                out.extend(original.all_variables());
+                out.extend(copy.all_variables());
+            }
+            Constraint::Homothety(center, original, image, _ratio) => {
+                out.extend(center.all_variables());
+                out.extend(original.all_variables());
+                out.extend(image.all_variables());
+            }
+            Constraint::PointInfiniteLineDistance(point, line, _distance) => {
+                out.extend(point.all_variables());
+                out.extend(line.all_variables());
+            }
+            Constraint::InfiniteLineTangentToCircle(line, circle, _side) => {
+                out.extend(line.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::MinDistance(p0, p1, _dist) => {
+                out.extend(p0.all_variables());
+                out.extend(p1.all_variables());
+            }
+            Constraint::LengthRatioBounds(line0, line1, _min_ratio, _max_ratio) => {
+                out.extend(line0.all_variables());
+                out.extend(line1.all_variables());
+            }
+            Constraint::SameSideOfLine(line, reference, point) => {
+                out.extend(line.all_variables());
+                out.extend(reference.all_variables());
+                out.extend(point.all_variables());
+            }
+            Constraint::ArcCircleEqualRadius(arc, circle) => {
+                out.extend(arc.center.all_variables());
+                out.extend(arc.start.all_variables());
+                out.extend([circle.radius.id]);
+            }
+            Constraint::Concyclic(arc0, arc1) => {
+                out.extend(arc0.center.all_variables());
+                out.extend(arc0.start.all_variables());
+                out.extend(arc1.center.all_variables());
+                out.extend(arc1.start.all_variables());
+            }
+            Constraint::Circumcenter(center, a, b, c) => {
+                out.extend(center.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+                out.extend(c.all_variables());
+            }
+            Constraint::ArcSagitta(circular_arc, _sagitta) => {
+                out.extend(circular_arc.all_variables());
+            }
+            Constraint::ArcSegmentArea(circular_arc, _area) => {
+                out.extend(circular_arc.all_variables());
+            }
+            Constraint::EqualLengthToCommon(common, a, b) => {
+                out.extend(common.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
+            Constraint::AnchorNear(point, _anchor, _radius) => {
+                out.extend(point.all_variables());
+            }
+            Constraint::OnGrid(point, _spacing, _origin) => {
+                out.extend(point.all_variables());
+            }
+            Constraint::DistanceSum(p, f1, f2, _sum) => {
+                out.extend(p.all_variables());
+                out.extend(f1.all_variables());
+                out.extend(f2.all_variables());
+            }
+            Constraint::FilletRadius(line0, line1, circle) => {
+                out.extend(line0.all_variables());
+                out.extend(line1.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::PointOnCircle(point, circle) => {
+                out.extend(point.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::PointOnLine(point, line) => {
+                out.extend(point.all_variables());
+                out.extend(line.all_variables());
+            }
+            Constraint::LineCircleIntersection(point, line, circle) => {
+                out.extend(point.all_variables());
+                out.extend(line.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::Collinear(a, b, c) => {
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+                out.extend(c.all_variables());
+            }
+            Constraint::CommonTangent(line, circle0, circle1, _kind) => {
+                out.extend(line.all_variables());
+                out.extend(circle0.all_variables());
+                out.extend(circle1.all_variables());
+            }
+            Constraint::ArcMidpoint(circular_arc, target) => {
+                out.extend(circular_arc.all_variables());
+                out.extend(target.all_variables());
+            }
+            Constraint::LineAngle(line, _angle) => out.extend(line.all_variables()),
+            Constraint::EqualAngle(a, b, c, d) => {
+                for line in [a, b, c, d] {
+                    out.extend(line.all_variables());
+                }
+            }
+            Constraint::LineArcTangent(line, circular_arc, end) => {
+                out.extend(line.all_variables());
+                out.extend(circular_arc.center.all_variables());
+                out.extend(end.of(circular_arc).all_variables());
+            }
+            Constraint::ArcChordAngle(circular_arc, _angle) => {
+                out.extend(circular_arc.start.all_variables());
+                out.extend(circular_arc.end.all_variables());
+            }
+            Constraint::ReverseTangentArcs(arc0, arc1) => {
+                for arc in [arc0, arc1] {
+                    out.extend(arc.center.all_variables());
+                    out.extend(arc.end.all_variables());
+                }
+            }
+            Constraint::DistanceLinearCombo(result, terms, _constant) => {
+                out.extend([*result]);
+                out.extend(terms.iter().map(|(id, _coefficient)| *id));
+            }
+            Constraint::AngleBisectorLine(bisector, a, b) => {
+                out.extend(bisector.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
+            Constraint::SymmetricAngle(reference, a, b) => {
+                out.extend(reference.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
+            Constraint::RadiusRatio(circle, scalar, _ratio) => {
+                out.extend([circle.radius.id, *scalar]);
+            }
+            Constraint::EquidistantLinePoint(point, directrix, focus) => {
+                out.extend(point.all_variables());
+                out.extend(directrix.all_variables());
+                out.extend(focus.all_variables());
+            }
+            Constraint::TangentAtAngle(line, circle, _angle) => {
+                out.extend(line.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::ParallelOffset(line_a, line_b, _offset) => {
+                out.extend(line_a.all_variables());
+                out.extend(line_b.all_variables());
+            }
+            Constraint::ParallelOffsetScalar(line_a, line_b, scalar) => {
+                out.extend(line_a.all_variables());
+                out.extend(line_b.all_variables());
+                out.extend([*scalar]);
+            }
+            Constraint::AngleEqualsScalar(line0, line1, scalar) => {
+                out.extend(line0.all_variables());
+                out.extend(line1.all_variables());
+                out.extend([*scalar]);
+            }
+            Constraint::ArcCentralAngleScalar(arc, scalar) => {
+                out.extend(arc.all_variables());
+                out.extend([*scalar]);
+            }
+            Constraint::SubtendsAngle(p, a, b, _angle) => {
+                out.extend(p.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
         }
     }
 
@@ -327,6 +799,7 @@ impl Constraint {
                 out.extend(line1.all_variables());
             }
             Constraint::Fixed(id, _scalar) => out.extend([*id]),
+            Constraint::FixedPoint(p, _x, _y) => out.extend(p.all_variables()),
             Constraint::ScalarEqual(x, y) => out.extend([*x, *y]),
             Constraint::PointsCoincident(p0, p1) => {
                 out.extend(p0.all_variables());
@@ -371,11 +844,186 @@ impl Constraint {
                 out.extend(p1.all_variables());
                 out.extend(p2.all_variables());
             }
+            Constraint::PolarOffset(from, to, _r, _theta) => {
+                out.extend(from.all_variables());
+                out.extend(to.all_variables());
+            }
+            Constraint::PointOnConic(point, conic) => {
+                out.extend(point.all_variables());
+                out.extend(conic.all_variables());
+            }
+            Constraint::ScaledCopy(pivot, scale, original, copy) => {
+                out.extend(pivot.all_variables());
+                out.extend([*scale]);
+                out.extend(original.all_variables());
+                out.extend(copy.all_variables());
+            }
+            Constraint::Homothety(center, original, image, _ratio) => {
+                out.extend(center.all_variables());
+                out.extend(original.all_variables());
+                out.extend(image.all_variables());
+            }
+            Constraint::PointInfiniteLineDistance(point, line, _distance) => {
+                out.extend(point.all_variables());
+                out.extend(line.all_variables());
+            }
+            Constraint::InfiniteLineTangentToCircle(line, circle, _side) => {
+                out.extend(line.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::MinDistance(p0, p1, _dist) => {
+                out.extend(p0.all_variables());
+                out.extend(p1.all_variables());
+            }
+            Constraint::LengthRatioBounds(line0, line1, _min_ratio, _max_ratio) => {
+                out.extend(line0.all_variables());
+                out.extend(line1.all_variables());
+            }
+            Constraint::SameSideOfLine(line, reference, point) => {
+                out.extend(line.all_variables());
+                out.extend(reference.all_variables());
+                out.extend(point.all_variables());
+            }
+            Constraint::ArcCircleEqualRadius(arc, circle) => {
+                out.extend(arc.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::Concyclic(arc0, arc1) => {
+                out.extend(arc0.all_variables());
+                out.extend(arc1.all_variables());
+            }
+            Constraint::Circumcenter(center, a, b, c) => {
+                out.extend(center.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+                out.extend(c.all_variables());
+            }
+            Constraint::ArcSagitta(circular_arc, _sagitta) => {
+                out.extend(circular_arc.all_variables());
+            }
+            Constraint::ArcSegmentArea(circular_arc, _area) => {
+                out.extend(circular_arc.all_variables());
+            }
+            Constraint::EqualLengthToCommon(common, a, b) => {
+                out.extend(common.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
+            Constraint::AnchorNear(point, _anchor, _radius) => {
+                out.extend(point.all_variables());
+            }
+            Constraint::OnGrid(point, _spacing, _origin) => {
+                out.extend(point.all_variables());
+            }
+            Constraint::DistanceSum(p, f1, f2, _sum) => {
+                out.extend(p.all_variables());
+                out.extend(f1.all_variables());
+                out.extend(f2.all_variables());
+            }
+            Constraint::FilletRadius(line0, line1, circle) => {
+                out.extend(line0.all_variables());
+                out.extend(line1.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::PointOnCircle(point, circle) => {
+                out.extend(point.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::PointOnLine(point, line) => {
+                out.extend(point.all_variables());
+                out.extend(line.all_variables());
+            }
+            Constraint::LineCircleIntersection(point, line, circle) => {
+                out.extend(point.all_variables());
+                out.extend(line.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::Collinear(a, b, c) => {
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+                out.extend(c.all_variables());
+            }
+            Constraint::CommonTangent(line, circle0, circle1, _kind) => {
+                out.extend(line.all_variables());
+                out.extend(circle0.all_variables());
+                out.extend(circle1.all_variables());
+            }
+            Constraint::ArcMidpoint(circular_arc, target) => {
+                out.extend(circular_arc.all_variables());
+                out.extend(target.all_variables());
+            }
+            Constraint::LineAngle(line, _angle) => out.extend(line.all_variables()),
+            Constraint::EqualAngle(a, b, c, d) => {
+                for line in [a, b, c, d] {
+                    out.extend(line.all_variables());
+                }
+            }
+            Constraint::LineArcTangent(line, circular_arc, _end) => {
+                out.extend(line.all_variables());
+                out.extend(circular_arc.all_variables());
+            }
+            Constraint::ArcChordAngle(circular_arc, _angle) => {
+                out.extend(circular_arc.all_variables());
+            }
+            Constraint::ReverseTangentArcs(arc0, arc1) => {
+                out.extend(arc0.all_variables());
+                out.extend(arc1.all_variables());
+            }
+            Constraint::DistanceLinearCombo(result, terms, _constant) => {
+                out.extend([*result]);
+                out.extend(terms.iter().map(|(id, _coefficient)| *id));
+            }
+            Constraint::AngleBisectorLine(bisector, a, b) => {
+                out.extend(bisector.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
+            Constraint::SymmetricAngle(reference, a, b) => {
+                out.extend(reference.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
+            Constraint::RadiusRatio(circle, scalar, _ratio) => {
+                out.extend(circle.all_variables());
+                out.extend([*scalar]);
+            }
+            Constraint::EquidistantLinePoint(point, directrix, focus) => {
+                out.extend(point.all_variables());
+                out.extend(directrix.all_variables());
+                out.extend(focus.all_variables());
+            }
+            Constraint::TangentAtAngle(line, circle, _angle) => {
+                out.extend(line.all_variables());
+                out.extend(circle.all_variables());
+            }
+            Constraint::ParallelOffset(line_a, line_b, _offset) => {
+                out.extend(line_a.all_variables());
+                out.extend(line_b.all_variables());
+            }
+            Constraint::ParallelOffsetScalar(line_a, line_b, scalar) => {
+                out.extend(line_a.all_variables());
+                out.extend(line_b.all_variables());
+                out.extend([*scalar]);
+            }
+            Constraint::AngleEqualsScalar(line0, line1, scalar) => {
+                out.extend(line0.all_variables());
+                out.extend(line1.all_variables());
+                out.extend([*scalar]);
+            }
+            Constraint::ArcCentralAngleScalar(arc, scalar) => {
+                out.extend(arc.all_variables());
+                out.extend([*scalar]);
+            }
+            Constraint::SubtendsAngle(p, a, b, _angle) => {
+                out.extend(p.all_variables());
+                out.extend(a.all_variables());
+                out.extend(b.all_variables());
+            }
         }
     }
 
     /// For each row of the Jacobian matrix, which variables are involved in them?
-    pub(crate) fn nonzeroes(&self, row0: &mut Vec<Id>, row1: &mut Vec<Id>, _row2: &mut Vec<Id>) {
+    pub(crate) fn nonzeroes(&self, row0: &mut Vec<Id>, row1: &mut Vec<Id>, row2: &mut Vec<Id>) {
         match self {
             Constraint::LineTangentToCircle(line, circle, _side) => {
                 row0.extend(line.all_variables());
@@ -407,6 +1055,10 @@ impl Constraint {
                 row0.extend(line1.all_variables());
             }
             Constraint::Fixed(id, _scalar) => row0.push(*id),
+            Constraint::FixedPoint(p, _x, _y) => {
+                row0.push(p.id_x());
+                row1.push(p.id_y());
+            }
             Constraint::ScalarEqual(x, y) => row0.extend([x, y]),
             Constraint::PointsCoincident(p0, p1) => {
                 row0.push(p0.id_x());
@@ -426,8 +1078,8 @@ impl Constraint {
                     Constraint::Distance(arc.center, arc.start, *radius),
                     Constraint::Distance(arc.center, arc.end, *radius),
                 );
-                constraints.0.nonzeroes(row0, row1, _row2);
-                constraints.1.nonzeroes(row1, row0, _row2);
+                constraints.0.nonzeroes(row0, row1, row2);
+                constraints.1.nonzeroes(row1, row0, row2);
             }
             Constraint::Arc(arc) => {
                 row0.extend(arc.all_variables());
@@ -478,7 +1130,7 @@ impl Constraint {
                 },
                 AngleKind::Other(*angle),
             )
-            .nonzeroes(row0, row1, _row2),
+            .nonzeroes(row0, row1, row2),
             Constraint::PointsAtAngle(p0, p1, p2, _angle) => {
                 row0.extend(p0.all_variables());
                 row0.extend(p1.all_variables());
@@ -487,31 +1139,219 @@ impl Constraint {
                 row1.extend(p1.all_variables());
                 row1.extend(p2.all_variables());
             }
-        }
-    }
-
-    /// How close is this constraint to being satisfied?
-    /// For performance reasons (avoiding allocations), this doesn't return a `Vec<f64>`,
-    /// instead it takes one as a mutable argument and writes out all residuals to that.
-    /// Most constraints have a residual measured as a single number (scalar),
-    /// but some constraints have two residuals (e.g. one for the X axis and one for the Y axis).
-    /// That's why there's two possible residuals to calculate (and therefore, two &mut residual to write into).
-    pub(crate) fn residual(
-        &self,
-        layout: &Layout,
-        current_assignments: &[f64],
-        residual0: &mut f64,
-        residual1: &mut f64,
-        _residual2: &mut f64,
-        degenerate: &mut bool,
-    ) {
-        match self {
-            Constraint::LineTangentToCircle(line, circle, side) => {
-                // Get current state of the entities.
-                let p0_x = current_assignments[layout.index_of(line.p0.id_x())];
-                let p0_y = current_assignments[layout.index_of(line.p0.id_y())];
-                let p0 = V::new(p0_x, p0_y);
-
+            Constraint::PolarOffset(from, to, _r, _theta) => {
+                row0.extend(from.all_variables());
+                row0.extend(to.all_variables());
+                row1.extend(from.all_variables());
+                row1.extend(to.all_variables());
+            }
+            Constraint::PointOnConic(point, conic) => {
+                row0.extend(point.all_variables());
+                row0.extend(conic.all_variables());
+            }
+            Constraint::ScaledCopy(pivot, scale, original, copy) => {
+                row0.extend([pivot.id_x(), *scale, original.id_x(), copy.id_x()]);
+                row1.extend([pivot.id_y(), *scale, original.id_y(), copy.id_y()]);
+            }
+            Constraint::Homothety(center, original, image, _ratio) => {
+                row0.extend(&[center.id_x(), original.id_x(), image.id_x()]);
+                row1.extend(&[center.id_y(), original.id_y(), image.id_y()]);
+            }
+            Constraint::PointInfiniteLineDistance(point, line, _distance) => {
+                row0.extend(point.all_variables());
+                row0.extend(line.all_variables());
+            }
+            Constraint::InfiniteLineTangentToCircle(line, circle, _side) => {
+                row0.extend(line.all_variables());
+                row0.extend(circle.all_variables());
+            }
+            Constraint::MinDistance(p0, p1, _dist) => {
+                row0.extend(p0.all_variables());
+                row0.extend(p1.all_variables());
+            }
+            Constraint::LengthRatioBounds(line0, line1, _min_ratio, _max_ratio) => {
+                row0.extend(line0.all_variables());
+                row0.extend(line1.all_variables());
+                row1.extend(line0.all_variables());
+                row1.extend(line1.all_variables());
+            }
+            Constraint::SameSideOfLine(line, reference, point) => {
+                row0.extend(line.all_variables());
+                row0.extend(reference.all_variables());
+                row0.extend(point.all_variables());
+            }
+            Constraint::ArcCircleEqualRadius(arc, circle) => {
+                Constraint::DistanceVar(arc.center, arc.start, circle.radius)
+                    .nonzeroes(row0, row1, row2);
+            }
+            Constraint::Concyclic(arc0, arc1) => {
+                let (centers, radii) = concyclic_parts(arc0, arc1);
+                centers.nonzeroes(row0, row1, row2);
+                radii.nonzeroes(row2, row0, row1);
+            }
+            Constraint::Circumcenter(center, a, b, c) => {
+                row0.extend(center.all_variables());
+                row0.extend(a.all_variables());
+                row0.extend(b.all_variables());
+                row1.extend(center.all_variables());
+                row1.extend(b.all_variables());
+                row1.extend(c.all_variables());
+            }
+            Constraint::ArcSagitta(circular_arc, _sagitta) => {
+                row0.extend(circular_arc.all_variables());
+            }
+            Constraint::ArcSegmentArea(circular_arc, _area) => {
+                row0.extend(circular_arc.all_variables());
+            }
+            Constraint::EqualLengthToCommon(common, a, b) => {
+                equal_length_to_common_arc(*common, *a, *b).nonzeroes(row0, row1, row2);
+            }
+            Constraint::AnchorNear(point, _anchor, _radius) => {
+                row0.extend(point.all_variables());
+            }
+            Constraint::OnGrid(point, _spacing, _origin) => {
+                row0.extend([point.id_x()]);
+                row1.extend([point.id_y()]);
+            }
+            Constraint::DistanceSum(p, f1, f2, _sum) => {
+                row0.extend(p.all_variables());
+                row0.extend(f1.all_variables());
+                row0.extend(f2.all_variables());
+            }
+            Constraint::FilletRadius(line0, line1, circle) => {
+                row0.extend(circle.all_variables());
+                row0.extend(line0.all_variables());
+                row1.extend(circle.all_variables());
+                row1.extend(line1.all_variables());
+            }
+            Constraint::PointOnCircle(point, circle) => {
+                Constraint::DistanceVar(circle.center, *point, circle.radius)
+                    .nonzeroes(row0, row1, row2);
+            }
+            Constraint::PointOnLine(point, line) => {
+                row0.extend(point.all_variables());
+                row0.extend(line.all_variables());
+            }
+            Constraint::LineCircleIntersection(point, line, circle) => {
+                let (on_line, on_circle) = line_circle_intersection_parts(*point, line, circle);
+                on_line.nonzeroes(row0, row1, row2);
+                on_circle.nonzeroes(row1, row0, row2);
+            }
+            Constraint::Collinear(a, b, c) => {
+                row0.extend(a.all_variables());
+                row0.extend(b.all_variables());
+                row0.extend(c.all_variables());
+            }
+            Constraint::CommonTangent(line, circle0, circle1, _kind) => {
+                row0.extend(line.all_variables());
+                row0.extend(circle0.all_variables());
+                row1.extend(line.all_variables());
+                row1.extend(circle1.all_variables());
+            }
+            Constraint::ArcMidpoint(circular_arc, target) => {
+                row0.extend(circular_arc.all_variables());
+                row0.push(target.id_x());
+                row1.extend(circular_arc.all_variables());
+                row1.push(target.id_y());
+            }
+            Constraint::LineAngle(line, _angle) => row0.extend(line.all_variables()),
+            Constraint::EqualAngle(a, b, c, d) => {
+                for line in [a, b, c, d] {
+                    row0.extend(line.all_variables());
+                }
+            }
+            Constraint::LineArcTangent(line, circular_arc, end) => {
+                row0.extend(line.all_variables());
+                row0.extend(circular_arc.center.all_variables());
+                row0.extend(end.of(circular_arc).all_variables());
+            }
+            Constraint::ArcChordAngle(circular_arc, _angle) => {
+                row0.extend(circular_arc.start.all_variables());
+                row0.extend(circular_arc.end.all_variables());
+            }
+            Constraint::ReverseTangentArcs(arc0, arc1) => {
+                for arc in [arc0, arc1] {
+                    row0.extend(arc.center.all_variables());
+                    row0.extend(arc.end.all_variables());
+                    row1.extend(arc.center.all_variables());
+                    row1.extend(arc.end.all_variables());
+                }
+            }
+            Constraint::DistanceLinearCombo(result, terms, _constant) => {
+                row0.push(*result);
+                row0.extend(terms.iter().map(|(id, _coefficient)| *id));
+            }
+            Constraint::AngleBisectorLine(bisector, a, b) => {
+                row0.extend(bisector.all_variables());
+                row0.extend(a.all_variables());
+                row0.extend(b.all_variables());
+            }
+            Constraint::SymmetricAngle(reference, a, b) => {
+                row0.extend(reference.all_variables());
+                row0.extend(a.all_variables());
+                row0.extend(b.all_variables());
+            }
+            Constraint::RadiusRatio(circle, scalar, _ratio) => {
+                row0.extend([circle.radius.id, *scalar]);
+            }
+            Constraint::EquidistantLinePoint(point, directrix, focus) => {
+                row0.extend(point.all_variables());
+                row0.extend(directrix.all_variables());
+                row0.extend(focus.all_variables());
+            }
+            Constraint::TangentAtAngle(line, circle, _angle) => {
+                row0.extend(line.all_variables());
+                row0.extend(circle.all_variables());
+                row1.extend(line.all_variables());
+            }
+            Constraint::ParallelOffset(line_a, line_b, _offset) => {
+                row0.extend(line_a.all_variables());
+                row0.extend(line_b.all_variables());
+                row1.extend(line_b.p0.all_variables());
+                row1.extend(line_a.all_variables());
+            }
+            Constraint::ParallelOffsetScalar(line_a, line_b, scalar) => {
+                Constraint::ParallelOffset(*line_a, *line_b, 0.0).nonzeroes(row0, row1, row2);
+                row1.extend([*scalar]);
+            }
+            Constraint::AngleEqualsScalar(line0, line1, scalar) => {
+                row0.extend(line0.all_variables());
+                row0.extend(line1.all_variables());
+                row0.extend([*scalar]);
+            }
+            Constraint::ArcCentralAngleScalar(arc, scalar) => {
+                arc_central_angle_scalar_part(arc, *scalar).nonzeroes(row0, row1, row2);
+            }
+            Constraint::SubtendsAngle(p, a, b, _angle) => {
+                row0.extend(p.all_variables());
+                row0.extend(a.all_variables());
+                row0.extend(b.all_variables());
+            }
+        }
+    }
+
+    /// How close is this constraint to being satisfied?
+    /// For performance reasons (avoiding allocations), this doesn't return a `Vec<f64>`,
+    /// instead it takes one as a mutable argument and writes out all residuals to that.
+    /// Most constraints have a residual measured as a single number (scalar),
+    /// but some constraints have two residuals (e.g. one for the X axis and one for the Y axis).
+    /// That's why there's two possible residuals to calculate (and therefore, two &mut residual to write into).
+    pub(crate) fn residual(
+        &self,
+        layout: &Layout,
+        current_assignments: &[f64],
+        residual0: &mut f64,
+        residual1: &mut f64,
+        residual2: &mut f64,
+        degenerate: &mut bool,
+    ) {
+        match self {
+            Constraint::LineTangentToCircle(line, circle, side) => {
+                // Get current state of the entities.
+                let p0_x = current_assignments[layout.index_of(line.p0.id_x())];
+                let p0_y = current_assignments[layout.index_of(line.p0.id_y())];
+                let p0 = V::new(p0_x, p0_y);
+
                 let p1_x = current_assignments[layout.index_of(line.p1.id_x())];
                 let p1_y = current_assignments[layout.index_of(line.p1.id_y())];
                 let p1 = V::new(p1_x, p1_y);
@@ -608,6 +1448,11 @@ impl Constraint {
                 let actual = current_assignments[layout.index_of(*id)];
                 *residual0 = actual - expected;
             }
+            Constraint::FixedPoint(p, expected_x, expected_y) => {
+                let actual = point_vector(current_assignments, *p, layout);
+                *residual0 = actual.x - expected_x;
+                *residual1 = actual.y - expected_y;
+            }
             Constraint::ScalarEqual(x, y) => {
                 // Residual equation R: x-y=0
                 let vx = current_assignments[layout.index_of(*x)];
@@ -668,7 +1513,7 @@ impl Constraint {
                     current_assignments,
                     residual0,
                     residual1,
-                    _residual2,
+                    residual2,
                     degenerate,
                 );
                 constraints.1.residual(
@@ -676,7 +1521,7 @@ impl Constraint {
                     current_assignments,
                     residual1,
                     residual0,
-                    _residual2,
+                    residual2,
                     degenerate,
                 );
             }
@@ -910,7 +1755,7 @@ impl Constraint {
                 current_assignments,
                 residual0,
                 residual1,
-                _residual2,
+                residual2,
                 degenerate,
             ),
             Constraint::PointsAtAngle(p0, p1, p2, expected_angle) => {
@@ -946,1349 +1791,3370 @@ impl Constraint {
                 *residual0 = res.x;
                 *residual1 = res.y;
             }
-        }
-    }
-
-    /// How many equations does this constraint correspond to?
-    /// Each equation is a residual function (a measure of error)
-    pub(crate) fn residual_dim(&self) -> usize {
-        match self {
-            Constraint::LineTangentToCircle(..) => 1,
-            Constraint::CircleTangentToCircle(..) => 1,
-            Constraint::Distance(..) => 1,
-            Constraint::DistanceVar(..) => 1,
-            Constraint::VerticalDistance(..) => 1,
-            Constraint::HorizontalDistance(..) => 1,
-            Constraint::Vertical(..) => 1,
-            Constraint::Horizontal(..) => 1,
-            Constraint::Fixed(..) => 1,
-            Constraint::ScalarEqual(_, _) => 1,
-            Constraint::LinesAtAngle(..) => 1,
-            Constraint::PointsCoincident(..) => 2,
-            Constraint::CircleRadius(..) => 1,
-            Constraint::LinesEqualLength(..) => 1,
-            Constraint::ArcRadius(..) => 2,
-            Constraint::Arc(..) => 1,
-            Constraint::Midpoint(..) => 2,
-            Constraint::PointLineDistance(..) => 1,
-            Constraint::VerticalPointLineDistance(..) => 1,
-            Constraint::HorizontalPointLineDistance(..) => 1,
-            Constraint::Symmetric(..) => 2,
-            Constraint::PointArcCoincident(..) => 2,
-            Constraint::ArcLength(..) => 2,
-            Constraint::ArcAngle(circular_arc, angle) => Constraint::LinesAtAngle(
-                DatumLineSegment {
-                    p0: circular_arc.center,
-                    p1: circular_arc.start,
-                },
-                DatumLineSegment {
-                    p0: circular_arc.center,
-                    p1: circular_arc.end,
-                },
-                AngleKind::Other(*angle),
-            )
-            .residual_dim(),
-            Constraint::PointsAtAngle(..) => 2,
-        }
-    }
-
-    /// Used to construct part of a Jacobian matrix.
-    /// For performance reasons (avoiding allocations), this doesn't return a
-    /// `Vec<JacobianVar>` for each Jacobian row, instead takes the output rows as
-    /// mutable arguments and writes out all nonzero variables for each row to
-    /// one of them.
-    pub(crate) fn jacobian_rows(
-        &self,
-        layout: &Layout,
-        current_assignments: &[f64],
-        row0: &mut Vec<JacobianVar>,
-        row1: &mut Vec<JacobianVar>,
-        _row2: &mut Vec<JacobianVar>,
-        degenerate: &mut bool,
-    ) {
-        match self {
-            Constraint::LineTangentToCircle(line, circle, side) => {
-                // Residual: R = cross(u, v) / |u| - |r|
-                // where u = p1 - p0 and v = c - p0.
-                let p0 = V::new(
-                    current_assignments[layout.index_of(line.p0.id_x())],
-                    current_assignments[layout.index_of(line.p0.id_y())],
+            Constraint::PolarOffset(from, to, r, theta) => {
+                let d = V::new(
+                    current_assignments[layout.index_of(to.id_x())]
+                        - current_assignments[layout.index_of(from.id_x())],
+                    current_assignments[layout.index_of(to.id_y())]
+                        - current_assignments[layout.index_of(from.id_y())],
                 );
+                let dist = d.magnitude();
+                *residual0 = dist - r;
 
-                let p1 = V::new(
-                    current_assignments[layout.index_of(line.p1.id_x())],
-                    current_assignments[layout.index_of(line.p1.id_y())],
-                );
+                // The direction is undefined when the points coincide.
+                if dist <= EPSILON {
+                    *residual1 = 0.0;
+                    *degenerate = true;
+                    return;
+                }
 
+                // The angular error is scaled by the target radius, turning it into the arc
+                // length between the actual and desired positions. This keeps the residual in
+                // length units like every other constraint.
+                let actual = libm::atan2(d.y, d.x);
+                *residual1 = r.abs() * wrap_angle_delta(actual - theta.to_radians());
+            }
+            Constraint::PointOnConic(point, conic) => {
+                // Residual: R = Ax² + Bxy + Cy² + Dx + Ey + F
+                let x = current_assignments[layout.index_of(point.id_x())];
+                let y = current_assignments[layout.index_of(point.id_y())];
+                let a = current_assignments[layout.index_of(conic.a)];
+                let b = current_assignments[layout.index_of(conic.b)];
+                let c = current_assignments[layout.index_of(conic.c)];
+                let d = current_assignments[layout.index_of(conic.d)];
+                let e = current_assignments[layout.index_of(conic.e)];
+                let f = current_assignments[layout.index_of(conic.f)];
+                *residual0 = a * x * x + b * x * y + c * y * y + d * x + e * y + f;
+            }
+            Constraint::ScaledCopy(pivot, scale, original, copy) => {
+                // Residuals:
+                // R0 = copy.x - pivot.x - s * (original.x - pivot.x)
+                // R1 = copy.y - pivot.y - s * (original.y - pivot.y)
+                let px = current_assignments[layout.index_of(pivot.id_x())];
+                let py = current_assignments[layout.index_of(pivot.id_y())];
+                let s = current_assignments[layout.index_of(*scale)];
+                let ox = current_assignments[layout.index_of(original.id_x())];
+                let oy = current_assignments[layout.index_of(original.id_y())];
+                let cx = current_assignments[layout.index_of(copy.id_x())];
+                let cy = current_assignments[layout.index_of(copy.id_y())];
+                *residual0 = cx - px - s * (ox - px);
+                *residual1 = cy - py - s * (oy - py);
+            }
+            Constraint::Homothety(center, original, image, ratio) => {
+                let cx = current_assignments[layout.index_of(center.id_x())];
+                let cy = current_assignments[layout.index_of(center.id_y())];
+                let ox = current_assignments[layout.index_of(original.id_x())];
+                let oy = current_assignments[layout.index_of(original.id_y())];
+                let ix = current_assignments[layout.index_of(image.id_x())];
+                let iy = current_assignments[layout.index_of(image.id_y())];
+                // Equation:
+                //   i - c = ratio * (o - c)
+                *residual0 = ix - cx - ratio * (ox - cx);
+                *residual1 = iy - cy - ratio * (oy - cy);
+            }
+            Constraint::PointInfiniteLineDistance(point, line, target_distance) => {
+                // The line's normal is a unit vector, so the signed distance is just
+                // the projection onto the normal, less the line's offset.
+                let p = V::new(
+                    current_assignments[layout.index_of(point.id_x())],
+                    current_assignments[layout.index_of(point.id_y())],
+                );
+                let actual_distance =
+                    signed_distance_to_line(current_assignments, *line, layout, p);
+                *residual0 = actual_distance - target_distance;
+            }
+            Constraint::InfiniteLineTangentToCircle(line, circle, side) => {
                 let c = V::new(
                     current_assignments[layout.index_of(circle.center.id_x())],
                     current_assignments[layout.index_of(circle.center.id_y())],
                 );
-
-                let u = p1 - p0;
-                let mag_u = u.magnitude();
-
-                // Handle degenerate line case
-                if mag_u <= EPSILON {
-                    // TODO: Could revert to point circle constraint here
+                // NOTE: Taking abs to guard against negative radius
+                let radius = current_assignments[layout.index_of(circle.radius.id)].abs();
+                let side_sign = if *side == LineSide::Right { -1.0 } else { 1.0 };
+                let cen_dist =
+                    side_sign * signed_distance_to_line(current_assignments, *line, layout, c);
+                *residual0 = cen_dist - radius;
+            }
+            Constraint::MinDistance(p0, p1, min_distance) => {
+                let p0 = V::new(
+                    current_assignments[layout.index_of(p0.id_x())],
+                    current_assignments[layout.index_of(p0.id_y())],
+                );
+                let p1 = V::new(
+                    current_assignments[layout.index_of(p1.id_x())],
+                    current_assignments[layout.index_of(p1.id_y())],
+                );
+                // Inequality: only points which are too close together get penalized.
+                let actual_distance = p0.euclidean_distance(p1);
+                *residual0 = min_distance - actual_distance;
+            }
+            Constraint::LengthRatioBounds(line0, line1, min_ratio, max_ratio) => {
+                // Inequalities, in length units rather than as a ratio,
+                // so a short second line doesn't blow up the residual:
+                // R0 = |line0| - max·|line1|
+                // R1 = min·|line1| - |line0|
+                let len0 = line_vector(current_assignments, line0, layout).magnitude();
+                let len1 = line_vector(current_assignments, line1, layout).magnitude();
+                *residual0 = len0 - max_ratio * len1;
+                *residual1 = min_ratio * len1 - len0;
+            }
+            Constraint::SameSideOfLine(line, reference, point) => {
+                let (a, b, c) = equation_of_line(current_assignments, line, layout);
+                let denominator = libm::hypot(a, b);
+                if denominator < EPSILON {
+                    *residual0 = 0.0;
                     *degenerate = true;
                     return;
                 }
-
-                let v = c - p0;
-                let cross_uv = u.cross_2d(v);
-                let mag_u_cubed = mag_u * mag_u * mag_u;
-                let side_sign = if *side == LineSide::Right { -1.0 } else { 1.0 };
-                let dr_du_x = side_sign * (-(u.x * cross_uv) / mag_u_cubed + v.y / mag_u);
-                let dr_du_y = side_sign * (-(u.y * cross_uv) / mag_u_cubed - v.x / mag_u);
-                let dr_dv_x = side_sign * (-u.y / mag_u);
-                let dr_dv_y = side_sign * (u.x / mag_u);
-
-                let dr_dx0 = -(dr_du_x + dr_dv_x);
-                let dr_dy0 = -(dr_du_y + dr_dv_y);
-                let dr_dx1 = dr_du_x;
-                let dr_dy1 = dr_du_y;
-                let dr_dxc = dr_dv_x;
-                let dr_dyc = dr_dv_y;
-
-                // The residual uses |radius| to guard against negative values, so we have to
-                // differentiate through the abs
-                let radius = current_assignments[layout.index_of(circle.radius.id)];
-                let dr_dr = -radius.signum();
-
-                let coeffs = [
-                    JacobianVar {
-                        id: line.p0.id_x(),
-                        partial_derivative: dr_dx0,
-                    },
-                    JacobianVar {
-                        id: line.p0.id_y(),
-                        partial_derivative: dr_dy0,
-                    },
-                    JacobianVar {
-                        id: line.p1.id_x(),
-                        partial_derivative: dr_dx1,
-                    },
-                    JacobianVar {
-                        id: line.p1.id_y(),
-                        partial_derivative: dr_dy1,
-                    },
-                    JacobianVar {
-                        id: circle.center.id_x(),
-                        partial_derivative: dr_dxc,
-                    },
-                    JacobianVar {
-                        id: circle.center.id_y(),
-                        partial_derivative: dr_dyc,
-                    },
-                    JacobianVar {
-                        id: circle.radius.id,
-                        partial_derivative: dr_dr,
-                    },
-                ];
-                row0.extend(coeffs.as_slice());
+                let rx = current_assignments[layout.index_of(reference.id_x())];
+                let ry = current_assignments[layout.index_of(reference.id_y())];
+                let px = current_assignments[layout.index_of(point.id_x())];
+                let py = current_assignments[layout.index_of(point.id_y())];
+                // Signed distance of the point from the line, flipped so that
+                // the reference point's side is positive.
+                let side_sign = same_side_sign(a * rx + b * ry + c);
+                let distance = side_sign * (a * px + b * py + c) / denominator;
+                // Inequality: only penalize the point once it's on the wrong side.
+                *residual0 = -distance;
             }
-            Constraint::CircleTangentToCircle(circle_a, circle_b, side) => {
-                let a_c = V::new(
-                    current_assignments[layout.index_of(circle_a.center.id_x())],
-                    current_assignments[layout.index_of(circle_a.center.id_y())],
+            Constraint::ArcCircleEqualRadius(arc, circle) => {
+                // Residual: R = |start - center| - r, which is exactly `DistanceVar`.
+                Constraint::DistanceVar(arc.center, arc.start, circle.radius).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
                 );
-                let a_r = current_assignments[layout.index_of(circle_a.radius.id)];
-
-                let b_c = V::new(
-                    current_assignments[layout.index_of(circle_b.center.id_x())],
-                    current_assignments[layout.index_of(circle_b.center.id_y())],
+            }
+            Constraint::Concyclic(arc0, arc1) => {
+                // Residuals:
+                // R0, R1 = arc0.center - arc1.center
+                // R2 = |arc0.start - arc0.center| - |arc1.start - arc1.center|
+                let (centers, radii) = concyclic_parts(arc0, arc1);
+                centers.residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
                 );
-                let b_r = current_assignments[layout.index_of(circle_b.radius.id)];
-
-                let d = b_c - a_c;
-                let mag_d = d.magnitude();
-
-                if mag_d <= EPSILON {
+                radii.residual(
+                    layout,
+                    current_assignments,
+                    residual2,
+                    residual0,
+                    residual1,
+                    degenerate,
+                );
+            }
+            Constraint::Circumcenter(center, a, b, c) => {
+                let [o, a, b, c] = [center, a, b, c].map(|p| {
+                    V::new(
+                        current_assignments[layout.index_of(p.id_x())],
+                        current_assignments[layout.index_of(p.id_y())],
+                    )
+                });
+                let (Some(ab), Some(bc)) = (bisector_distance(o, a, b), bisector_distance(o, b, c))
+                else {
                     *degenerate = true;
                     return;
-                }
-
-                let u_d = d * mag_d.recip();
-
-                let dr_dax = u_d.x;
-                let dr_day = u_d.y;
-                let dr_dbx = -u_d.x;
-                let dr_dby = -u_d.y;
-
-                // The residual uses |a_r| and |b_r| to guard against negative values so we have to
-                // differentiate through the abs
-                let a_sign = a_r.signum();
-                let b_sign = b_r.signum();
-                let (dr_dar, dr_dbr) = if *side == CircleSide::Interior {
-                    // r = ||a_r| - |b_r|| - dist
-                    let inner = (a_r.abs() - b_r.abs()).signum();
-                    (inner * a_sign, -inner * b_sign)
-                } else {
-                    // r = |a_r| + |b_r| - dist
-                    (a_sign, b_sign)
                 };
-
-                let coeffs = [
-                    JacobianVar {
-                        id: circle_a.center.id_x(),
-                        partial_derivative: dr_dax,
-                    },
-                    JacobianVar {
-                        id: circle_a.center.id_y(),
-                        partial_derivative: dr_day,
-                    },
-                    JacobianVar {
-                        id: circle_a.radius.id,
-                        partial_derivative: dr_dar,
-                    },
-                    JacobianVar {
-                        id: circle_b.center.id_x(),
-                        partial_derivative: dr_dbx,
-                    },
-                    JacobianVar {
-                        id: circle_b.center.id_y(),
-                        partial_derivative: dr_dby,
-                    },
-                    JacobianVar {
-                        id: circle_b.radius.id,
-                        partial_derivative: dr_dbr,
-                    },
-                ];
-                row0.extend(coeffs.as_slice());
+                *residual0 = ab.residual;
+                *residual1 = bc.residual;
             }
-            Constraint::Distance(p0, p1, _expected_distance) => {
-                // Residual: R = sqrt((x1-x2)**2 + (y1-y2)**2) - d
-                // ∂R/∂x0 = (x0 - x1) / sqrt((x0 - x1)**2 + (y0 - y1)**2)
-                // ∂R/∂y0 = (y0 - y1) / sqrt((x0 - x1)**2 + (y0 - y1)**2)
-                // ∂R/∂x1 = (-x0 + x1)/ sqrt((x0 - x1)**2 + (y0 - y1)**2)
-                // ∂R/∂y1 = (-y0 + y1)/ sqrt((x0 - x1)**2 + (y0 - y1)**2)
-
-                // Derivatives wrt p0 and p2's X/Y coordinates.
-                let x0 = current_assignments[layout.index_of(p0.id_x())];
-                let y0 = current_assignments[layout.index_of(p0.id_y())];
-                let x1 = current_assignments[layout.index_of(p1.id_x())];
-                let y1 = current_assignments[layout.index_of(p1.id_y())];
-
-                let dist = V::new(x0, y0).euclidean_distance(V::new(x1, y1));
-                if dist < EPSILON {
+            Constraint::ArcSagitta(circular_arc, sagitta) => {
+                let [c, a, b] =
+                    [circular_arc.center, circular_arc.start, circular_arc.end].map(|p| {
+                        V::new(
+                            current_assignments[layout.index_of(p.id_x())],
+                            current_assignments[layout.index_of(p.id_y())],
+                        )
+                    });
+                let Some(pds) = arc_sagitta(c, a, b) else {
                     *degenerate = true;
                     return;
-                }
-                let dr_dx0 = (x0 - x1) / dist;
-                let dr_dy0 = (y0 - y1) / dist;
-                let dr_dx1 = (-x0 + x1) / dist;
-                let dr_dy1 = (-y0 + y1) / dist;
-
-                row0.extend(
-                    [
-                        JacobianVar {
-                            id: p0.id_x(),
-                            partial_derivative: dr_dx0,
-                        },
-                        JacobianVar {
-                            id: p0.id_y(),
-                            partial_derivative: dr_dy0,
-                        },
-                        JacobianVar {
-                            id: p1.id_x(),
-                            partial_derivative: dr_dx1,
-                        },
-                        JacobianVar {
-                            id: p1.id_y(),
-                            partial_derivative: dr_dy1,
-                        },
-                    ]
-                    .as_slice(),
+                };
+                *residual0 = pds.sagitta - sagitta;
+            }
+            Constraint::ArcSegmentArea(circular_arc, area) => {
+                let [c, a, b] =
+                    [circular_arc.center, circular_arc.start, circular_arc.end].map(|p| {
+                        V::new(
+                            current_assignments[layout.index_of(p.id_x())],
+                            current_assignments[layout.index_of(p.id_y())],
+                        )
+                    });
+                let Some(pds) = arc_segment_area(c, a, b, *area) else {
+                    *degenerate = true;
+                    return;
+                };
+                *residual0 = pds.residual;
+            }
+            Constraint::EqualLengthToCommon(common, a, b) => {
+                // Residual: R = |a - common| - |b - common|, which is exactly `Arc`.
+                equal_length_to_common_arc(*common, *a, *b).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
                 );
             }
-            Constraint::DistanceVar(p, q, d) => {
-                let px = current_assignments[layout.index_of(p.id_x())];
-                let py = current_assignments[layout.index_of(p.id_y())];
-                let qx = current_assignments[layout.index_of(q.id_x())];
-                let qy = current_assignments[layout.index_of(q.id_y())];
-                /* Derivative math, from ezpz-sympy:
-                residual = norm(p - q) - d
-                df_dp = normalized(p - q)
-                df_dq = -df_dp
-                df_dd = -1
-                */
-                let dist = V::new(px, py).euclidean_distance(V::new(qx, qy));
-                if dist < EPSILON {
+            Constraint::AnchorNear(point, anchor, radius) => {
+                let p = V::new(
+                    current_assignments[layout.index_of(point.id_x())],
+                    current_assignments[layout.index_of(point.id_y())],
+                );
+                // Inequality: only points outside the radius get penalized.
+                let distance = p.euclidean_distance(V::new(anchor.x, anchor.y));
+                *residual0 = distance - radius;
+            }
+            Constraint::OnGrid(point, spacing, origin) => {
+                // R0 = x - nearest grid line's x
+                // R1 = y - nearest grid line's y
+                if spacing.abs() < EPSILON {
                     *degenerate = true;
                     return;
                 }
-                let df_dpx = (px - qx) * dist.recip();
-                let df_dpy = (py - qy) * dist.recip();
-                let df_dqx = -(px - qx) * dist.recip();
-                let df_dqy = -(py - qy) * dist.recip();
-                let df_dd = -1.0;
-                row0.extend(
-                    [
-                        JacobianVar {
-                            id: p.id_x(),
-                            partial_derivative: df_dpx,
-                        },
-                        JacobianVar {
-                            id: p.id_y(),
-                            partial_derivative: df_dpy,
-                        },
-                        JacobianVar {
-                            id: q.id_x(),
-                            partial_derivative: df_dqx,
-                        },
-                        JacobianVar {
-                            id: q.id_y(),
-                            partial_derivative: df_dqy,
-                        },
-                        JacobianVar {
-                            id: d.id,
-                            partial_derivative: df_dd,
-                        },
-                    ]
-                    .as_slice(),
-                );
+                let x = current_assignments[layout.index_of(point.id_x())];
+                let y = current_assignments[layout.index_of(point.id_y())];
+                *residual0 = grid_offset(x, origin.x, *spacing);
+                *residual1 = grid_offset(y, origin.y, *spacing);
             }
-            Constraint::VerticalDistance(p0, p1, _expected_distance) => {
-                // Residual: p0y - p1y - d = 0
-                // ∂R/∂y0 = 1
-                // ∂R/∂y1 = -1
-                row0.extend(
-                    [
-                        JacobianVar {
-                            id: p0.id_y(),
-                            partial_derivative: 1.0,
-                        },
-                        JacobianVar {
-                            id: p1.id_y(),
-                            partial_derivative: -1.0,
-                        },
-                    ]
-                    .as_slice(),
+            Constraint::DistanceSum(p, f1, f2, sum) => {
+                // R = |p - f1| + |p - f2| - sum
+                let p = point_vector(current_assignments, *p, layout);
+                let f1 = point_vector(current_assignments, *f1, layout);
+                let f2 = point_vector(current_assignments, *f2, layout);
+                *residual0 = p.euclidean_distance(f1) + p.euclidean_distance(f2) - sum;
+            }
+            Constraint::FilletRadius(line0, line1, circle) => {
+                // R0 = |distance from center to line0| - r
+                // R1 = |distance from center to line1| - r
+                let center = point_vector(current_assignments, circle.center, layout);
+                let radius = current_assignments[layout.index_of(circle.radius.id)];
+                for (line, residual) in [(line0, residual0), (line1, residual1)] {
+                    let (a, b, c) = equation_of_line(current_assignments, line, layout);
+                    let denominator = libm::hypot(a, b);
+                    if denominator < EPSILON {
+                        *degenerate = true;
+                        return;
+                    }
+                    *residual = ((a * center.x + b * center.y + c) / denominator).abs() - radius;
+                }
+            }
+            Constraint::PointOnCircle(point, circle) => {
+                // Residual: R = |point - center| - r, which is exactly `DistanceVar`.
+                Constraint::DistanceVar(circle.center, *point, circle.radius).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
                 );
             }
-            Constraint::HorizontalDistance(p0, p1, _expected_distance) => {
-                // Residual: p0x - p1x - d = 0
-                // ∂R/∂x0 = 1
-                // ∂R/∂x1 = -1
-                row0.extend(
-                    [
-                        JacobianVar {
-                            id: p0.id_x(),
-                            partial_derivative: 1.0,
-                        },
-                        JacobianVar {
-                            id: p1.id_x(),
-                            partial_derivative: -1.0,
-                        },
-                    ]
-                    .as_slice(),
+            Constraint::PointOnLine(point, line) => {
+                // Residual: R = the signed distance from the point to the line.
+                Constraint::PointLineDistance(*point, *line, 0.0).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
                 );
             }
-            Constraint::Vertical(line) => {
-                // Residual: R = x0 - x1
-                // ∂R/∂x for p0 and p1.
-                let dr_dx0 = 1.0;
-                let dr_dx1 = -1.0;
-
-                // Get the 'x' variable ID for the line's points.
-                let p0_x_id = line.p0.id_x();
-                let p1_x_id = line.p1.id_x();
-
-                row0.extend(
-                    [
-                        JacobianVar {
-                            id: p0_x_id,
-                            partial_derivative: dr_dx0,
-                        },
-                        JacobianVar {
-                            id: p1_x_id,
-                            partial_derivative: dr_dx1,
-                        },
-                    ]
-                    .as_slice(),
+            Constraint::LineCircleIntersection(point, line, circle) => {
+                // Residuals:
+                // R0 = the signed distance from the point to the line, i.e. the cross product
+                //      (p1 - p0) × (point - p0) over the line's length,
+                // R1 = |point - center| - r.
+                // `PointLineDistance` reports a zero-length line as degenerate.
+                let (on_line, on_circle) = line_circle_intersection_parts(*point, line, circle);
+                on_line.residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
                 );
+                on_circle.residual(
+                    layout,
+                    current_assignments,
+                    residual1,
+                    residual0,
+                    residual2,
+                    degenerate,
+                );
+                // A zero-radius circle only meets the line if its center is on it.
+                // Keep the residuals, so the solver can still grow the circle back.
+                if current_assignments[layout.index_of(circle.radius.id)].abs() <= EPSILON {
+                    *degenerate = true;
+                }
             }
-            Constraint::Horizontal(line) => {
-                // Residual: R = y1 - y2
-                // ∂R/∂y for p0 and p1.
-                let dr_dy0 = 1.0;
-                let dr_dy1 = -1.0;
+            Constraint::Collinear(a, b, c) => {
+                // R = (b - a) × (c - a)
+                let a = point_vector(current_assignments, *a, layout);
+                let b = point_vector(current_assignments, *b, layout);
+                let c = point_vector(current_assignments, *c, layout);
+                *residual0 = (b - a).cross_2d(c - a);
+            }
+            Constraint::CommonTangent(line, circle0, circle1, kind) => {
+                // With d0 and d1 the signed distances from each center to the line,
+                // R0 = |d0| - r0
+                // R1 = sign(d0)·d1 - r1 for an external tangent, or + r1 for an internal one.
+                let (a, b, c) = equation_of_line(current_assignments, line, layout);
+                let denominator = libm::hypot(a, b);
+                if denominator < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let center0 = point_vector(current_assignments, circle0.center, layout);
+                let center1 = point_vector(current_assignments, circle1.center, layout);
+                let d0 = (a * center0.x + b * center0.y + c) / denominator;
+                let d1 = (a * center1.x + b * center1.y + c) / denominator;
+                let r0 = current_assignments[layout.index_of(circle0.radius.id)];
+                let r1 = current_assignments[layout.index_of(circle1.radius.id)];
+                *residual0 = d0.abs() - r0;
+                *residual1 = d0.signum() * d1 - kind.sign() * r1;
+            }
+            Constraint::ArcMidpoint(circular_arc, target) => {
+                // R0, R1 = midpoint - target
+                let [c, a, b] = [circular_arc.center, circular_arc.start, circular_arc.end]
+                    .map(|p| point_vector(current_assignments, p, layout));
+                let Some(pds) = arc_midpoint(c, a, b) else {
+                    *degenerate = true;
+                    return;
+                };
+                let target = point_vector(current_assignments, *target, layout);
+                *residual0 = pds.midpoint.x - target.x;
+                *residual1 = pds.midpoint.y - target.y;
+            }
+            Constraint::LineAngle(line, angle) => {
+                let u = line_vector(current_assignments, line, layout);
+                let len = u.magnitude();
+                if len <= EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                // Like `TangentAtAngle`, the angular error is scaled by the line's length
+                // to keep the residual in length units.
+                let actual = libm::atan2(u.y, u.x);
+                *residual0 = len * wrap_angle_delta(actual - angle.to_radians());
+            }
+            Constraint::EqualAngle(a, b, c, d) => {
+                let Some(pds) = equal_angle(
+                    [a, b, c, d].map(|line| line_vector(current_assignments, line, layout)),
+                ) else {
+                    *degenerate = true;
+                    return;
+                };
+                *residual0 = pds.residual;
+            }
+            Constraint::LineArcTangent(line, circular_arc, end) => {
+                // R = (end - center) · (p1 - p0)
+                let radius = point_vector(current_assignments, end.of(circular_arc), layout)
+                    - point_vector(current_assignments, circular_arc.center, layout);
+                let u = line_vector(current_assignments, line, layout);
+                *residual0 = radius.dot(u);
+            }
+            Constraint::ArcChordAngle(circular_arc, angle) => {
+                let chord = DatumLineSegment::new(circular_arc.start, circular_arc.end);
+                Constraint::LineAngle(chord, *angle).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
+                );
+            }
+            Constraint::ReverseTangentArcs(arc0, arc1) => {
+                // R0 = ra × rb
+                // R1 = ra · rb, an inequality: same-side centers have parallel radii.
+                let ra = point_vector(current_assignments, arc0.end, layout)
+                    - point_vector(current_assignments, arc0.center, layout);
+                let rb = point_vector(current_assignments, arc1.end, layout)
+                    - point_vector(current_assignments, arc1.center, layout);
+                *residual0 = ra.cross_2d(rb);
+                *residual1 = ra.dot(rb);
+            }
+            Constraint::DistanceLinearCombo(result, terms, constant) => {
+                // Residual equation R: result - (Σ cᵢ * termᵢ + constant) = 0
+                let combo: f64 = terms
+                    .iter()
+                    .map(|(id, coefficient)| {
+                        coefficient * current_assignments[layout.index_of(*id)]
+                    })
+                    .sum();
+                *residual0 = current_assignments[layout.index_of(*result)] - (combo + constant);
+            }
+            Constraint::AngleBisectorLine(bisector, a, b) => {
+                let Some(pds) = angle_bisector(
+                    line_vector(current_assignments, bisector, layout),
+                    line_vector(current_assignments, a, layout),
+                    line_vector(current_assignments, b, layout),
+                ) else {
+                    *degenerate = true;
+                    return;
+                };
+                *residual0 = pds.residual;
+            }
+            Constraint::SymmetricAngle(reference, a, b) => {
+                let Some(pds) = symmetric_angle(
+                    line_vector(current_assignments, reference, layout),
+                    line_vector(current_assignments, a, layout),
+                    line_vector(current_assignments, b, layout),
+                ) else {
+                    *degenerate = true;
+                    return;
+                };
+                *residual0 = pds.residual;
+            }
+            Constraint::RadiusRatio(circle, scalar, ratio) => {
+                // Residual: R = r - k·s
+                let radius = current_assignments[layout.index_of(circle.radius.id)];
+                let scalar = current_assignments[layout.index_of(*scalar)];
+                *residual0 = radius - ratio * scalar;
+            }
+            Constraint::EquidistantLinePoint(point, directrix, focus) => {
+                // Residual: R = |distance from p to the directrix| - |p - focus|
+                // See `PointLineDistance` for the distance from the point to the line.
+                let px = current_assignments[layout.index_of(point.id_x())];
+                let py = current_assignments[layout.index_of(point.id_y())];
+                let fx = current_assignments[layout.index_of(focus.id_x())];
+                let fy = current_assignments[layout.index_of(focus.id_y())];
+                let (a, b, c) = equation_of_line(current_assignments, directrix, layout);
+                let denominator = libm::hypot(a, b);
+                let focus_distance = libm::hypot(px - fx, py - fy);
+                if denominator < EPSILON || focus_distance < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let line_distance = (a * px + b * py + c) / denominator;
+                *residual0 = line_distance.abs() - focus_distance;
+            }
+            Constraint::TangentAtAngle(line, circle, angle) => {
+                // The first row is just the tangency residual.
+                Constraint::LineTangentToCircle(*line, *circle, LineSide::Left).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
+                );
+                if *degenerate {
+                    return;
+                }
+                // Like `PolarOffset`, the angular error is scaled by the line's length
+                // to keep the residual in length units.
+                let u = line_vector(current_assignments, line, layout);
+                let actual = libm::atan2(u.y, u.x);
+                *residual1 = u.magnitude() * wrap_angle_delta(actual - angle.to_radians());
+            }
+            Constraint::ParallelOffset(line_a, line_b, offset) => {
+                // The first row is just the parallelism residual, which also
+                // guards against either line having zero length.
+                Constraint::lines_parallel([*line_a, *line_b]).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
+                );
+                if *degenerate {
+                    return;
+                }
+                // The second row is `PointLineDistance` from the second line's first point.
+                let px = current_assignments[layout.index_of(line_b.p0.id_x())];
+                let py = current_assignments[layout.index_of(line_b.p0.id_y())];
+                let (a, b, c) = equation_of_line(current_assignments, line_a, layout);
+                *residual1 = (a * px + b * py + c) / libm::hypot(a, b) - offset;
+            }
+            Constraint::ParallelOffsetScalar(line_a, line_b, scalar) => {
+                let offset = current_assignments[layout.index_of(*scalar)];
+                Constraint::ParallelOffset(*line_a, *line_b, offset).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
+                );
+            }
+            Constraint::AngleEqualsScalar(line0, line1, scalar) => {
+                let Some(pds) = oriented_angle_delta(
+                    line_vector(current_assignments, line0, layout),
+                    line_vector(current_assignments, line1, layout),
+                    current_assignments[layout.index_of(*scalar)],
+                ) else {
+                    *degenerate = true;
+                    return;
+                };
+                *residual0 = pds.residual;
+            }
+            Constraint::ArcCentralAngleScalar(arc, scalar) => {
+                arc_central_angle_scalar_part(arc, *scalar).residual(
+                    layout,
+                    current_assignments,
+                    residual0,
+                    residual1,
+                    residual2,
+                    degenerate,
+                );
+            }
+            Constraint::SubtendsAngle(p, a, b, angle) => {
+                let [p, a, b] = [p, a, b].map(|point| {
+                    V::new(
+                        current_assignments[layout.index_of(point.id_x())],
+                        current_assignments[layout.index_of(point.id_y())],
+                    )
+                });
+                let Some(pds) = oriented_angle_delta(b - p, a - p, angle.to_radians()) else {
+                    *degenerate = true;
+                    return;
+                };
+                *residual0 = pds.residual;
+            }
+        }
+    }
 
-                // Get the 'y' variable ID for the line's points.
-                let p0_y_id = line.p0.id_y();
-                let p1_y_id = line.p1.id_y();
+    /// Extend `out` with the IDs of variables this constraint treats as angles (in radians),
+    /// i.e. where adding any multiple of 2π doesn't change the geometry.
+    pub(crate) fn extend_angle_variable_ids(&self, out: &mut impl Extend<Id>) {
+        match self {
+            Constraint::PointInfiniteLineDistance(_, line, _)
+            | Constraint::InfiniteLineTangentToCircle(line, ..) => out.extend([line.angle]),
+            Constraint::AngleEqualsScalar(_, _, scalar)
+            | Constraint::ArcCentralAngleScalar(_, scalar) => out.extend([*scalar]),
+            _ => {}
+        }
+    }
 
-                row0.extend(
-                    [
-                        JacobianVar {
-                            id: p0_y_id,
-                            partial_derivative: dr_dy0,
-                        },
-                        JacobianVar {
-                            id: p1_y_id,
-                            partial_derivative: dr_dy1,
-                        },
-                    ]
-                    .as_slice(),
+    /// Is every residual of this constraint a linear function of its variables?
+    /// If every constraint in a system is linear, the solver can skip iterating and
+    /// solve the whole system directly.
+    pub fn is_linear(&self) -> bool {
+        matches!(
+            self,
+            Constraint::Fixed(..)
+                | Constraint::FixedPoint(..)
+                | Constraint::ScalarEqual(..)
+                | Constraint::DistanceLinearCombo(..)
+                | Constraint::Vertical(..)
+                | Constraint::Horizontal(..)
+                | Constraint::VerticalDistance(..)
+                | Constraint::HorizontalDistance(..)
+                | Constraint::PointsCoincident(..)
+                | Constraint::Midpoint(..)
+                | Constraint::Homothety(..)
+        )
+    }
+
+    /// Is each residual row an equality or an inequality?
+    /// Only the first [`Constraint::residual_dim`] entries are meaningful.
+    /// [`Constraint::residual`] and [`Constraint::jacobian_rows`] report inequality rows
+    /// unclamped, it's up to the caller to apply [`ResidualKind::clamp`].
+    pub(crate) fn residual_kinds(&self) -> [ResidualKind; 3] {
+        use ResidualKind::{Equality, Inequality};
+        match self {
+            Constraint::MinDistance(..)
+            | Constraint::SameSideOfLine(..)
+            | Constraint::AnchorNear(..) => [Inequality, Equality, Equality],
+            Constraint::LengthRatioBounds(..) => [Inequality, Inequality, Equality],
+            Constraint::ReverseTangentArcs(..) => [Equality, Inequality, Equality],
+            _ => [Equality; 3],
+        }
+    }
+
+    /// What does each residual row measure, at these values?
+    /// Only the first [`Constraint::residual_dim`] entries are meaningful.
+    /// Angle rows report the length their angular error is scaled by, which depends on
+    /// the geometry's current size.
+    pub(crate) fn residual_units(
+        &self,
+        layout: &Layout,
+        current_assignments: &[f64],
+    ) -> [ResidualUnit; 3] {
+        use ResidualUnit::{Angle, Length, Other};
+        let distance = |p: &DatumPoint, q: &DatumPoint| {
+            point_vector(current_assignments, *p, layout).euclidean_distance(point_vector(
+                current_assignments,
+                *q,
+                layout,
+            ))
+        };
+        let length = |line: &DatumLineSegment| distance(&line.p0, &line.p1);
+        // `LinesAtAngle` and `PointsAtAngle` are `|u|·|v|·sin(error)`, over the mean length.
+        let product_over_mean = |a: f64, b: f64| Angle {
+            scale: a * b / ((a + b) * 0.5),
+        };
+        match self {
+            Constraint::LinesAtAngle(line0, line1, _) => [
+                product_over_mean(length(line0), length(line1)),
+                Length,
+                Length,
+            ],
+            Constraint::ArcAngle(arc, _) => [
+                product_over_mean(
+                    distance(&arc.center, &arc.start),
+                    distance(&arc.center, &arc.end),
+                ),
+                Length,
+                Length,
+            ],
+            Constraint::PointsAtAngle(p0, p1, p2, _) => {
+                let angle = product_over_mean(distance(p0, p1), distance(p0, p2));
+                [angle, angle, Length]
+            }
+            Constraint::PolarOffset(_, _, r, _) => [Length, Angle { scale: r.abs() }, Length],
+            Constraint::TangentAtAngle(line, ..) => [
+                Length,
+                Angle {
+                    scale: length(line),
+                },
+                Length,
+            ],
+            Constraint::LineAngle(line, _) => [
+                Angle {
+                    scale: length(line),
+                },
+                Length,
+                Length,
+            ],
+            Constraint::ArcChordAngle(arc, _) => [
+                Angle {
+                    scale: distance(&arc.start, &arc.end),
+                },
+                Length,
+                Length,
+            ],
+            Constraint::EqualAngle(a, b, c, d) => [
+                Angle {
+                    scale: [a, b, c, d].map(length).iter().sum::<f64>() / 4.0,
+                },
+                Length,
+                Length,
+            ],
+            Constraint::AngleBisectorLine(line0, line1, line2)
+            | Constraint::SymmetricAngle(line0, line1, line2) => [
+                Angle {
+                    scale: (length(line0) + length(line1) + length(line2)) / 3.0,
+                },
+                Length,
+                Length,
+            ],
+            Constraint::AngleEqualsScalar(line0, line1, _) => [
+                Angle {
+                    scale: (length(line0) + length(line1)) * 0.5,
+                },
+                Length,
+                Length,
+            ],
+            Constraint::ArcCentralAngleScalar(arc, _) => [
+                Angle {
+                    scale: (distance(&arc.center, &arc.start) + distance(&arc.center, &arc.end))
+                        * 0.5,
+                },
+                Length,
+                Length,
+            ],
+            Constraint::SubtendsAngle(p, a, b, _) => [
+                Angle {
+                    scale: (distance(p, a) + distance(p, b)) * 0.5,
+                },
+                Length,
+                Length,
+            ],
+            // The first row is the parallelism residual, like `LinesAtAngle`.
+            Constraint::ParallelOffset(line_a, line_b, _)
+            | Constraint::ParallelOffsetScalar(line_a, line_b, _) => [
+                product_over_mean(length(line_a), length(line_b)),
+                Length,
+                Length,
+            ],
+            Constraint::PointOnConic(..)
+            | Constraint::Collinear(..)
+            | Constraint::LineArcTangent(..)
+            | Constraint::ReverseTangentArcs(..) => [Other; 3],
+            Constraint::LineTangentToCircle(..)
+            | Constraint::CircleTangentToCircle(..)
+            | Constraint::Distance(..)
+            | Constraint::DistanceVar(..)
+            | Constraint::VerticalDistance(..)
+            | Constraint::HorizontalDistance(..)
+            | Constraint::Vertical(..)
+            | Constraint::Horizontal(..)
+            | Constraint::Fixed(..)
+            | Constraint::FixedPoint(..)
+            | Constraint::PointsCoincident(..)
+            | Constraint::CircleRadius(..)
+            | Constraint::LinesEqualLength(..)
+            | Constraint::ArcRadius(..)
+            | Constraint::Arc(..)
+            | Constraint::Midpoint(..)
+            | Constraint::PointLineDistance(..)
+            | Constraint::VerticalPointLineDistance(..)
+            | Constraint::HorizontalPointLineDistance(..)
+            | Constraint::Symmetric(..)
+            | Constraint::ScalarEqual(..)
+            | Constraint::PointArcCoincident(..)
+            | Constraint::ArcLength(..)
+            | Constraint::ScaledCopy(..)
+            | Constraint::Homothety(..)
+            | Constraint::PointInfiniteLineDistance(..)
+            | Constraint::InfiniteLineTangentToCircle(..)
+            | Constraint::MinDistance(..)
+            | Constraint::LengthRatioBounds(..)
+            | Constraint::SameSideOfLine(..)
+            | Constraint::ArcCircleEqualRadius(..)
+            | Constraint::Concyclic(..)
+            | Constraint::Circumcenter(..)
+            | Constraint::ArcSagitta(..)
+            | Constraint::ArcSegmentArea(..)
+            | Constraint::EqualLengthToCommon(..)
+            | Constraint::AnchorNear(..)
+            | Constraint::OnGrid(..)
+            | Constraint::DistanceSum(..)
+            | Constraint::FilletRadius(..)
+            | Constraint::PointOnCircle(..)
+            | Constraint::PointOnLine(..)
+            | Constraint::LineCircleIntersection(..)
+            | Constraint::CommonTangent(..)
+            | Constraint::ArcMidpoint(..)
+            | Constraint::DistanceLinearCombo(..)
+            | Constraint::RadiusRatio(..)
+            | Constraint::EquidistantLinePoint(..) => [Length; 3],
+        }
+    }
+
+    /// How many equations does this constraint correspond to?
+    /// Each equation is a residual function (a measure of error)
+    pub(crate) fn residual_dim(&self) -> usize {
+        match self {
+            Constraint::LineTangentToCircle(..) => 1,
+            Constraint::CircleTangentToCircle(..) => 1,
+            Constraint::Distance(..) => 1,
+            Constraint::DistanceVar(..) => 1,
+            Constraint::VerticalDistance(..) => 1,
+            Constraint::HorizontalDistance(..) => 1,
+            Constraint::Vertical(..) => 1,
+            Constraint::Horizontal(..) => 1,
+            Constraint::Fixed(..) => 1,
+            Constraint::FixedPoint(..) => 2,
+            Constraint::ScalarEqual(_, _) => 1,
+            Constraint::LinesAtAngle(..) => 1,
+            Constraint::PointsCoincident(..) => 2,
+            Constraint::CircleRadius(..) => 1,
+            Constraint::LinesEqualLength(..) => 1,
+            Constraint::ArcRadius(..) => 2,
+            Constraint::Arc(..) => 1,
+            Constraint::Midpoint(..) => 2,
+            Constraint::PointLineDistance(..) => 1,
+            Constraint::VerticalPointLineDistance(..) => 1,
+            Constraint::HorizontalPointLineDistance(..) => 1,
+            Constraint::Symmetric(..) => 2,
+            Constraint::PointArcCoincident(..) => 2,
+            Constraint::ArcLength(..) => 2,
+            Constraint::ArcAngle(circular_arc, angle) => Constraint::LinesAtAngle(
+                DatumLineSegment {
+                    p0: circular_arc.center,
+                    p1: circular_arc.start,
+                },
+                DatumLineSegment {
+                    p0: circular_arc.center,
+                    p1: circular_arc.end,
+                },
+                AngleKind::Other(*angle),
+            )
+            .residual_dim(),
+            Constraint::PointsAtAngle(..) => 2,
+            Constraint::PolarOffset(..) => 2,
+            Constraint::PointOnConic(..) => 1,
+            Constraint::ScaledCopy(..) => 2,
+            Constraint::Homothety(..) => 2,
+            Constraint::PointInfiniteLineDistance(..) => 1,
+            Constraint::InfiniteLineTangentToCircle(..) => 1,
+            Constraint::MinDistance(..) => 1,
+            Constraint::LengthRatioBounds(..) => 2,
+            Constraint::SameSideOfLine(..) => 1,
+            Constraint::ArcCircleEqualRadius(..) => 1,
+            Constraint::Concyclic(..) => 3,
+            Constraint::Circumcenter(..) => 2,
+            Constraint::ArcSagitta(..) => 1,
+            Constraint::ArcSegmentArea(..) => 1,
+            Constraint::EqualLengthToCommon(..) => 1,
+            Constraint::AnchorNear(..) => 1,
+            Constraint::OnGrid(..) => 2,
+            Constraint::DistanceSum(..) => 1,
+            Constraint::FilletRadius(..) => 2,
+            Constraint::PointOnCircle(..) => 1,
+            Constraint::PointOnLine(..) => 1,
+            Constraint::LineCircleIntersection(..) => 2,
+            Constraint::Collinear(..) => 1,
+            Constraint::CommonTangent(..) => 2,
+            Constraint::ArcMidpoint(..) => 2,
+            Constraint::LineAngle(..) => 1,
+            Constraint::EqualAngle(..) => 1,
+            Constraint::LineArcTangent(..) => 1,
+            Constraint::ArcChordAngle(..) => 1,
+            Constraint::ReverseTangentArcs(..) => 2,
+            Constraint::DistanceLinearCombo(..) => 1,
+            Constraint::AngleBisectorLine(..) => 1,
+            Constraint::SymmetricAngle(..) => 1,
+            Constraint::RadiusRatio(..) => 1,
+            Constraint::EquidistantLinePoint(..) => 1,
+            Constraint::TangentAtAngle(..) => 2,
+            Constraint::ParallelOffset(..) => 2,
+            Constraint::ParallelOffsetScalar(..) => 2,
+            Constraint::AngleEqualsScalar(..) => 1,
+            Constraint::ArcCentralAngleScalar(..) => 1,
+            Constraint::SubtendsAngle(..) => 1,
+        }
+    }
+
+    /// Used to construct part of a Jacobian matrix.
+    /// For performance reasons (avoiding allocations), this doesn't return a
+    /// `Vec<JacobianVar>` for each Jacobian row, instead takes the output rows as
+    /// mutable arguments and writes out all nonzero variables for each row to
+    /// one of them.
+    pub(crate) fn jacobian_rows(
+        &self,
+        layout: &Layout,
+        current_assignments: &[f64],
+        row0: &mut Vec<JacobianVar>,
+        row1: &mut Vec<JacobianVar>,
+        row2: &mut Vec<JacobianVar>,
+        degenerate: &mut bool,
+    ) {
+        match self {
+            Constraint::LineTangentToCircle(line, circle, side) => {
+                // Residual: R = cross(u, v) / |u| - |r|
+                // where u = p1 - p0 and v = c - p0.
+                let p0 = V::new(
+                    current_assignments[layout.index_of(line.p0.id_x())],
+                    current_assignments[layout.index_of(line.p0.id_y())],
+                );
+
+                let p1 = V::new(
+                    current_assignments[layout.index_of(line.p1.id_x())],
+                    current_assignments[layout.index_of(line.p1.id_y())],
+                );
+
+                let c = V::new(
+                    current_assignments[layout.index_of(circle.center.id_x())],
+                    current_assignments[layout.index_of(circle.center.id_y())],
+                );
+
+                let u = p1 - p0;
+                let mag_u = u.magnitude();
+
+                // Handle degenerate line case
+                if mag_u <= EPSILON {
+                    // TODO: Could revert to point circle constraint here
+                    *degenerate = true;
+                    return;
+                }
+
+                let v = c - p0;
+                let cross_uv = u.cross_2d(v);
+                let mag_u_cubed = mag_u * mag_u * mag_u;
+                let side_sign = if *side == LineSide::Right { -1.0 } else { 1.0 };
+                let dr_du_x = side_sign * (-(u.x * cross_uv) / mag_u_cubed + v.y / mag_u);
+                let dr_du_y = side_sign * (-(u.y * cross_uv) / mag_u_cubed - v.x / mag_u);
+                let dr_dv_x = side_sign * (-u.y / mag_u);
+                let dr_dv_y = side_sign * (u.x / mag_u);
+
+                let dr_dx0 = -(dr_du_x + dr_dv_x);
+                let dr_dy0 = -(dr_du_y + dr_dv_y);
+                let dr_dx1 = dr_du_x;
+                let dr_dy1 = dr_du_y;
+                let dr_dxc = dr_dv_x;
+                let dr_dyc = dr_dv_y;
+
+                // The residual uses |radius| to guard against negative values, so we have to
+                // differentiate through the abs
+                let radius = current_assignments[layout.index_of(circle.radius.id)];
+                let dr_dr = -radius.signum();
+
+                let coeffs = [
+                    JacobianVar {
+                        id: line.p0.id_x(),
+                        partial_derivative: dr_dx0,
+                    },
+                    JacobianVar {
+                        id: line.p0.id_y(),
+                        partial_derivative: dr_dy0,
+                    },
+                    JacobianVar {
+                        id: line.p1.id_x(),
+                        partial_derivative: dr_dx1,
+                    },
+                    JacobianVar {
+                        id: line.p1.id_y(),
+                        partial_derivative: dr_dy1,
+                    },
+                    JacobianVar {
+                        id: circle.center.id_x(),
+                        partial_derivative: dr_dxc,
+                    },
+                    JacobianVar {
+                        id: circle.center.id_y(),
+                        partial_derivative: dr_dyc,
+                    },
+                    JacobianVar {
+                        id: circle.radius.id,
+                        partial_derivative: dr_dr,
+                    },
+                ];
+                row0.extend(coeffs.as_slice());
+            }
+            Constraint::CircleTangentToCircle(circle_a, circle_b, side) => {
+                let a_c = V::new(
+                    current_assignments[layout.index_of(circle_a.center.id_x())],
+                    current_assignments[layout.index_of(circle_a.center.id_y())],
+                );
+                let a_r = current_assignments[layout.index_of(circle_a.radius.id)];
+
+                let b_c = V::new(
+                    current_assignments[layout.index_of(circle_b.center.id_x())],
+                    current_assignments[layout.index_of(circle_b.center.id_y())],
+                );
+                let b_r = current_assignments[layout.index_of(circle_b.radius.id)];
+
+                let d = b_c - a_c;
+                let mag_d = d.magnitude();
+
+                if mag_d <= EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+
+                let u_d = d * mag_d.recip();
+
+                let dr_dax = u_d.x;
+                let dr_day = u_d.y;
+                let dr_dbx = -u_d.x;
+                let dr_dby = -u_d.y;
+
+                // The residual uses |a_r| and |b_r| to guard against negative values so we have to
+                // differentiate through the abs
+                let a_sign = a_r.signum();
+                let b_sign = b_r.signum();
+                let (dr_dar, dr_dbr) = if *side == CircleSide::Interior {
+                    // r = ||a_r| - |b_r|| - dist
+                    let inner = (a_r.abs() - b_r.abs()).signum();
+                    (inner * a_sign, -inner * b_sign)
+                } else {
+                    // r = |a_r| + |b_r| - dist
+                    (a_sign, b_sign)
+                };
+
+                let coeffs = [
+                    JacobianVar {
+                        id: circle_a.center.id_x(),
+                        partial_derivative: dr_dax,
+                    },
+                    JacobianVar {
+                        id: circle_a.center.id_y(),
+                        partial_derivative: dr_day,
+                    },
+                    JacobianVar {
+                        id: circle_a.radius.id,
+                        partial_derivative: dr_dar,
+                    },
+                    JacobianVar {
+                        id: circle_b.center.id_x(),
+                        partial_derivative: dr_dbx,
+                    },
+                    JacobianVar {
+                        id: circle_b.center.id_y(),
+                        partial_derivative: dr_dby,
+                    },
+                    JacobianVar {
+                        id: circle_b.radius.id,
+                        partial_derivative: dr_dbr,
+                    },
+                ];
+                row0.extend(coeffs.as_slice());
+            }
+            Constraint::Distance(p0, p1, _expected_distance) => {
+                // Residual: R = sqrt((x1-x2)**2 + (y1-y2)**2) - d
+                // ∂R/∂x0 = (x0 - x1) / sqrt((x0 - x1)**2 + (y0 - y1)**2)
+                // ∂R/∂y0 = (y0 - y1) / sqrt((x0 - x1)**2 + (y0 - y1)**2)
+                // ∂R/∂x1 = (-x0 + x1)/ sqrt((x0 - x1)**2 + (y0 - y1)**2)
+                // ∂R/∂y1 = (-y0 + y1)/ sqrt((x0 - x1)**2 + (y0 - y1)**2)
+
+                // Derivatives wrt p0 and p2's X/Y coordinates.
+                let x0 = current_assignments[layout.index_of(p0.id_x())];
+                let y0 = current_assignments[layout.index_of(p0.id_y())];
+                let x1 = current_assignments[layout.index_of(p1.id_x())];
+                let y1 = current_assignments[layout.index_of(p1.id_y())];
+
+                let dist = V::new(x0, y0).euclidean_distance(V::new(x1, y1));
+                if dist < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let dr_dx0 = (x0 - x1) / dist;
+                let dr_dy0 = (y0 - y1) / dist;
+                let dr_dx1 = (-x0 + x1) / dist;
+                let dr_dy1 = (-y0 + y1) / dist;
+
+                row0.extend(
+                    [
+                        JacobianVar {
+                            id: p0.id_x(),
+                            partial_derivative: dr_dx0,
+                        },
+                        JacobianVar {
+                            id: p0.id_y(),
+                            partial_derivative: dr_dy0,
+                        },
+                        JacobianVar {
+                            id: p1.id_x(),
+                            partial_derivative: dr_dx1,
+                        },
+                        JacobianVar {
+                            id: p1.id_y(),
+                            partial_derivative: dr_dy1,
+                        },
+                    ]
+                    .as_slice(),
+                );
+            }
+            Constraint::DistanceVar(p, q, d) => {
+                let px = current_assignments[layout.index_of(p.id_x())];
+                let py = current_assignments[layout.index_of(p.id_y())];
+                let qx = current_assignments[layout.index_of(q.id_x())];
+                let qy = current_assignments[layout.index_of(q.id_y())];
+                /* Derivative math, from ezpz-sympy:
+                residual = norm(p - q) - d
+                df_dp = normalized(p - q)
+                df_dq = -df_dp
+                df_dd = -1
+                */
+                let dist = V::new(px, py).euclidean_distance(V::new(qx, qy));
+                if dist < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let df_dpx = (px - qx) * dist.recip();
+                let df_dpy = (py - qy) * dist.recip();
+                let df_dqx = -(px - qx) * dist.recip();
+                let df_dqy = -(py - qy) * dist.recip();
+                let df_dd = -1.0;
+                row0.extend(
+                    [
+                        JacobianVar {
+                            id: p.id_x(),
+                            partial_derivative: df_dpx,
+                        },
+                        JacobianVar {
+                            id: p.id_y(),
+                            partial_derivative: df_dpy,
+                        },
+                        JacobianVar {
+                            id: q.id_x(),
+                            partial_derivative: df_dqx,
+                        },
+                        JacobianVar {
+                            id: q.id_y(),
+                            partial_derivative: df_dqy,
+                        },
+                        JacobianVar {
+                            id: d.id,
+                            partial_derivative: df_dd,
+                        },
+                    ]
+                    .as_slice(),
+                );
+            }
+            Constraint::VerticalDistance(p0, p1, _expected_distance) => {
+                // Residual: p0y - p1y - d = 0
+                // ∂R/∂y0 = 1
+                // ∂R/∂y1 = -1
+                row0.extend(
+                    [
+                        JacobianVar {
+                            id: p0.id_y(),
+                            partial_derivative: 1.0,
+                        },
+                        JacobianVar {
+                            id: p1.id_y(),
+                            partial_derivative: -1.0,
+                        },
+                    ]
+                    .as_slice(),
+                );
+            }
+            Constraint::HorizontalDistance(p0, p1, _expected_distance) => {
+                // Residual: p0x - p1x - d = 0
+                // ∂R/∂x0 = 1
+                // ∂R/∂x1 = -1
+                row0.extend(
+                    [
+                        JacobianVar {
+                            id: p0.id_x(),
+                            partial_derivative: 1.0,
+                        },
+                        JacobianVar {
+                            id: p1.id_x(),
+                            partial_derivative: -1.0,
+                        },
+                    ]
+                    .as_slice(),
+                );
+            }
+            Constraint::Vertical(line) => {
+                // Residual: R = x0 - x1
+                // ∂R/∂x for p0 and p1.
+                let dr_dx0 = 1.0;
+                let dr_dx1 = -1.0;
+
+                // Get the 'x' variable ID for the line's points.
+                let p0_x_id = line.p0.id_x();
+                let p1_x_id = line.p1.id_x();
+
+                row0.extend(
+                    [
+                        JacobianVar {
+                            id: p0_x_id,
+                            partial_derivative: dr_dx0,
+                        },
+                        JacobianVar {
+                            id: p1_x_id,
+                            partial_derivative: dr_dx1,
+                        },
+                    ]
+                    .as_slice(),
+                );
+            }
+            Constraint::Horizontal(line) => {
+                // Residual: R = y1 - y2
+                // ∂R/∂y for p0 and p1.
+                let dr_dy0 = 1.0;
+                let dr_dy1 = -1.0;
+
+                // Get the 'y' variable ID for the line's points.
+                let p0_y_id = line.p0.id_y();
+                let p1_y_id = line.p1.id_y();
+
+                row0.extend(
+                    [
+                        JacobianVar {
+                            id: p0_y_id,
+                            partial_derivative: dr_dy0,
+                        },
+                        JacobianVar {
+                            id: p1_y_id,
+                            partial_derivative: dr_dy1,
+                        },
+                    ]
+                    .as_slice(),
+                );
+            }
+            Constraint::Fixed(id, _expected) => {
+                row0.extend(
+                    [JacobianVar {
+                        id: *id,
+                        partial_derivative: 1.0,
+                    }]
+                    .as_slice(),
+                );
+            }
+            Constraint::FixedPoint(p, _expected_x, _expected_y) => {
+                // R0 = x - expected_x, R1 = y - expected_y.
+                row0.push(JacobianVar {
+                    id: p.id_x(),
+                    partial_derivative: 1.0,
+                });
+                row1.push(JacobianVar {
+                    id: p.id_y(),
+                    partial_derivative: 1.0,
+                });
+            }
+            Constraint::ScalarEqual(x, y) => {
+                // Residual equation R: x-y=0
+                // dR/dx: 1
+                // dR/dy: -1
+                row0.push(JacobianVar {
+                    id: *x,
+                    partial_derivative: 1.0,
+                });
+                row0.push(JacobianVar {
+                    id: *y,
+                    partial_derivative: -1.0,
+                });
+            }
+            Constraint::LinesAtAngle(line0, line1, expected_angle) => {
+                let x0 = current_assignments[layout.index_of(line0.p0.id_x())];
+                let y0 = current_assignments[layout.index_of(line0.p0.id_y())];
+                let x1 = current_assignments[layout.index_of(line0.p1.id_x())];
+                let y1 = current_assignments[layout.index_of(line0.p1.id_y())];
+                let x2 = current_assignments[layout.index_of(line1.p0.id_x())];
+                let y2 = current_assignments[layout.index_of(line1.p0.id_y())];
+                let x3 = current_assignments[layout.index_of(line1.p1.id_x())];
+                let y3 = current_assignments[layout.index_of(line1.p1.id_y())];
+
+                let u = V::new(x1 - x0, y1 - y0);
+                let v = V::new(x3 - x2, y3 - y2);
+
+                let len_u = u.magnitude();
+                let len_v = v.magnitude();
+                if (len_u <= EPSILON) || (len_v <= EPSILON) {
+                    *degenerate = true;
+                    return;
+                }
+
+                let u_hat = u * (1.0 / len_u);
+                let v_hat = v * (1.0 / len_v);
+
+                let rot = rotation_for_angle_kind(*expected_angle);
+                let s = (len_u + len_v) * 0.5;
+
+                /*
+                    Residual
+
+                        r = a / s
+                        a := u × R⁻¹v
+                        s := (|u| + |v|) / 2
+
+                    Differentiate in u (via quotient rule)
+
+                        ∂a/∂u = perp_cw(R⁻¹v)
+                        ∂s/∂u = û/2
+                        ∂r/∂u = ((∂a/∂u) - (a/s)·(∂s/∂u)) / s
+
+                    Symmetric for v with ∂a/∂v = perp_ccw(R u), ∂s/∂v = v̂/2
+                */
+                let a = u.cross_2d(rot.inverse().apply(v));
+                let inv_s = 1.0 / s;
+                let t = a * inv_s * 0.5;
+                let df_du = (rot.inverse().apply(v).perp_cw() - u_hat * t) * inv_s;
+                let df_dv = (rot.apply(u).perp_ccw() - v_hat * t) * inv_s;
+
+                let pds = PartialDerivatives4Points {
+                    x0: -df_du.x,
+                    y0: -df_du.y,
+                    x1: df_du.x,
+                    y1: df_du.y,
+                    x2: -df_dv.x,
+                    y2: -df_dv.y,
+                    x3: df_dv.x,
+                    y3: df_dv.y,
+                };
+
+                let jvars = pds.jvars(line0, line1);
+                row0.extend(jvars.as_slice());
+            }
+            Constraint::LinesEqualLength(line0, line1) => {
+                // Get all points
+                let x0 = current_assignments[layout.index_of(line0.p0.id_x())];
+                let y0 = current_assignments[layout.index_of(line0.p0.id_y())];
+                let x1 = current_assignments[layout.index_of(line0.p1.id_x())];
+                let y1 = current_assignments[layout.index_of(line0.p1.id_y())];
+                let l0 = (V::new(x0, y0), V::new(x1, y1));
+                let x2 = current_assignments[layout.index_of(line1.p0.id_x())];
+                let y2 = current_assignments[layout.index_of(line1.p0.id_y())];
+                let x3 = current_assignments[layout.index_of(line1.p1.id_x())];
+                let y3 = current_assignments[layout.index_of(line1.p1.id_y())];
+                let l1 = (V::new(x2, y2), V::new(x3, y3));
+
+                // Calculate lengths of each line.
+                let len0 = l0.0.euclidean_distance(l0.1);
+                let len1 = l1.0.euclidean_distance(l1.1);
+
+                // Avoid division by 0
+                if len0 < EPSILON || len1 < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+
+                // Calculate derivatives.
+                let pds = PartialDerivatives4Points {
+                    x0: (x0 - x1) / len0,
+                    y0: (y0 - y1) / len0,
+                    x1: (-x0 + x1) / len0,
+                    y1: (-y0 + y1) / len0,
+                    x2: (-x2 + x3) / len1,
+                    y2: (-y2 + y3) / len1,
+                    x3: (x2 - x3) / len1,
+                    y3: (y2 - y3) / len1,
+                };
+                let jvars = pds.jvars(line0, line1);
+                row0.extend(jvars.as_slice());
+            }
+            Constraint::PointsCoincident(p0, p1) => {
+                // Residuals:
+                // R0 = x0 - x1,
+                // R1 = y0 - y1.
+                //
+                // For R0 = x0 - x1:
+                // ∂R0/∂x0 = 1
+                // ∂R0/∂y0 = 0
+                // ∂R0/∂x1 = -1
+                // ∂R0/∂y1 = 0
+                //
+                // For R1 = y0 - y1:
+                // ∂R1/∂x0 = 0
+                // ∂R1/∂y0 = 1
+                // ∂R1/∂x1 = 0
+                // ∂R1/∂y1 = -1
+
+                let dr0_dx0 = 1.0;
+                // dr0_dy0 = 0.0
+                let dr0_dx1 = -1.0;
+                // dr0_dy1 = 0.0
+
+                // dr1_dx0 = 0.0
+                let dr1_dy0 = 1.0;
+                // dr1_dx1 = 0.0
+                let dr1_dy1 = -1.0;
+
+                // We only care about nonzero derivs here.
+                row0.extend([
+                    JacobianVar {
+                        id: p0.id_x(),
+                        partial_derivative: dr0_dx0,
+                    },
+                    JacobianVar {
+                        id: p1.id_x(),
+                        partial_derivative: dr0_dx1,
+                    },
+                ]);
+                row1.extend([
+                    JacobianVar {
+                        id: p0.id_y(),
+                        partial_derivative: dr1_dy0,
+                    },
+                    JacobianVar {
+                        id: p1.id_y(),
+                        partial_derivative: dr1_dy1,
+                    },
+                ]);
+            }
+            Constraint::CircleRadius(circle, _expected_radius) => {
+                // Residual is R = r_expected - r_actual
+                // Only partial derivative which is nonzero is ∂R/∂r_current, which is 1.
+                row0.push(JacobianVar {
+                    id: circle.radius.id,
+                    partial_derivative: 1.0,
+                });
+            }
+            Constraint::ArcRadius(arc, radius) => {
+                // This is really just equivalent to 2 constraints,
+                // distance(center, start) and distance(center, end).
+                let constraints = (
+                    Constraint::Distance(arc.center, arc.start, *radius),
+                    Constraint::Distance(arc.center, arc.end, *radius),
+                );
+                constraints.0.jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+                constraints.1.jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row1,
+                    row0,
+                    row2,
+                    degenerate,
+                );
+            }
+            Constraint::Arc(arc) => {
+                // Residual: R = |start - c| - |end - c|. With us = start - c and ue = end - c,
+                // the partials are unit-vector components (O(1), scale-invariant):
+                // ∂R/∂start = us / |us|
+                // ∂R/∂end   = -ue / |ue|
+                // ∂R/∂c     = -us / |us| + ue / |ue|
+
+                let start_x = current_assignments[layout.index_of(arc.start.id_x())];
+                let start_y = current_assignments[layout.index_of(arc.start.id_y())];
+                let end_x = current_assignments[layout.index_of(arc.end.id_x())];
+                let end_y = current_assignments[layout.index_of(arc.end.id_y())];
+                let cx = current_assignments[layout.index_of(arc.center.id_x())];
+                let cy = current_assignments[layout.index_of(arc.center.id_y())];
+
+                let usx = start_x - cx;
+                let usy = start_y - cy;
+
+                let uex = end_x - cx;
+                let uey = end_y - cy;
+
+                let dist0 = libm::hypot(usx, usy);
+                let dist1 = libm::hypot(uex, uey);
+                if dist0 <= EPSILON || dist1 <= EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+
+                // Calculate derivative values for distance constraint.
+                let dx_start = usx / dist0;
+                let dy_start = usy / dist0;
+                let dx_end = -uex / dist1;
+                let dy_end = -uey / dist1;
+                let dx_c = -usx / dist0 + uex / dist1;
+                let dy_c = -usy / dist0 + uey / dist1;
+
+                row0.extend([
+                    JacobianVar {
+                        id: arc.start.id_x(),
+                        partial_derivative: dx_start,
+                    },
+                    JacobianVar {
+                        id: arc.start.id_y(),
+                        partial_derivative: dy_start,
+                    },
+                    JacobianVar {
+                        id: arc.end.id_x(),
+                        partial_derivative: dx_end,
+                    },
+                    JacobianVar {
+                        id: arc.end.id_y(),
+                        partial_derivative: dy_end,
+                    },
+                    JacobianVar {
+                        id: arc.center.id_x(),
+                        partial_derivative: dx_c,
+                    },
+                    JacobianVar {
+                        id: arc.center.id_y(),
+                        partial_derivative: dy_c,
+                    },
+                ]);
+            }
+            Constraint::Midpoint(line, point) => {
+                let p = line.p0;
+                let q = line.p1;
+                // Equation:
+                // (note that a = the midpoint)
+                //   ax = (px + qx)/2,
+                // ∴ ax - px/2 - qx/2 = 0
+                //
+                // This has partial derivatives:
+                //   ∂R/∂ ax =  1
+                //   ∂R/∂ px = -0.5
+                //   ∂R/∂ qx = -0.5
+                //   ∂R/∂ ay =  1
+                //   ∂R/∂ py = -0.5
+                //   ∂R/∂ qy = -0.5
+                row0.extend([
+                    JacobianVar {
+                        id: point.id_x(),
+                        partial_derivative: 1.0,
+                    },
+                    JacobianVar {
+                        id: p.id_x(),
+                        partial_derivative: -0.5,
+                    },
+                    JacobianVar {
+                        id: q.id_x(),
+                        partial_derivative: -0.5,
+                    },
+                ]);
+                row1.extend([
+                    JacobianVar {
+                        id: point.id_y(),
+                        partial_derivative: 1.0,
+                    },
+                    JacobianVar {
+                        id: p.id_y(),
+                        partial_derivative: -0.5,
+                    },
+                    JacobianVar {
+                        id: q.id_y(),
+                        partial_derivative: -0.5,
+                    },
+                ]);
+            }
+            Constraint::PointLineDistance(point, line, _distance) => {
+                // Equation:
+                //
+                // Given a line in format Ax + By + C = 0,
+                // and a point (px, py), then the actual distance is
+                //
+                // (A.px + B.py + C)  /  sqrt(A^2 + B^2)
+                //
+                // Note that we use a signed direction, so there's no absolute value
+                // of the numerator, as you'd usually see. This stops the solver
+                // from randomly flipping which side of the line the point is on.
+                let px = current_assignments[layout.index_of(point.id_x())];
+                let py = current_assignments[layout.index_of(point.id_y())];
+                let p0x = current_assignments[layout.index_of(line.p0.id_x())];
+                let p0y = current_assignments[layout.index_of(line.p0.id_y())];
+                let p1x = current_assignments[layout.index_of(line.p1.id_x())];
+                let p1y = current_assignments[layout.index_of(line.p1.id_y())];
+
+                let partial_derivatives = pds_for_point_line(
+                    *point,
+                    line,
+                    PointLineVars {
+                        px,
+                        py,
+                        p0x,
+                        p0y,
+                        p1x,
+                        p1y,
+                    },
+                );
+
+                row0.extend(partial_derivatives);
+            }
+            Constraint::VerticalPointLineDistance(point, line, _distance) => {
+                // Mirrors `HorizontalPointLineDistance` with x and y swapped
+                let id_ax = point.id_x();
+                let id_ay = point.id_y();
+                let id_px = line.p0.id_x();
+                let id_py = line.p0.id_y();
+                let id_qx = line.p1.id_x();
+                let id_qy = line.p1.id_y();
+                let ax = current_assignments[layout.index_of(id_ax)];
+                let px = current_assignments[layout.index_of(id_px)];
+                let py = current_assignments[layout.index_of(id_py)];
+                let qx = current_assignments[layout.index_of(id_qx)];
+                let qy = current_assignments[layout.index_of(id_qy)];
+
+                let dx = qx - px;
+                let dy = qy - py;
+                if dx.abs() <= EPSILON || (dx * dx + dy * dy) <= EPSILON * EPSILON {
+                    // vertical or zero-length line
+                    *degenerate = true;
+                    return;
+                }
+
+                let dpx = (ax - qx) * (py - qy) * libm::pow(px - qx, -2.0);
+                let dpy = (-ax + qx) * (px - qx).recip();
+
+                let dqx = -(ax - px) * (py - qy) * libm::pow(px - qx, -2.0);
+                let dqy = (ax - px) * (px - qx).recip();
+
+                let dax = (-py + qy) * (px - qx).recip();
+                let day = 1.0;
+
+                row0.extend([
+                    JacobianVar {
+                        id: id_ax,
+                        partial_derivative: dax,
+                    },
+                    JacobianVar {
+                        id: id_ay,
+                        partial_derivative: day,
+                    },
+                    JacobianVar {
+                        id: id_px,
+                        partial_derivative: dpx,
+                    },
+                    JacobianVar {
+                        id: id_py,
+                        partial_derivative: dpy,
+                    },
+                    JacobianVar {
+                        id: id_qx,
+                        partial_derivative: dqx,
+                    },
+                    JacobianVar {
+                        id: id_qy,
+                        partial_derivative: dqy,
+                    },
+                ]);
+            }
+            Constraint::HorizontalPointLineDistance(point, line, _distance) => {
+                // See notebook:
+                // https://github.com/KittyCAD/ezpz-sympy/blob/main/main.py
+                let id_ax = point.id_x();
+                let id_ay = point.id_y();
+                let id_px = line.p0.id_x();
+                let id_py = line.p0.id_y();
+                let id_qx = line.p1.id_x();
+                let id_qy = line.p1.id_y();
+                let ay = current_assignments[layout.index_of(id_ay)];
+                let px = current_assignments[layout.index_of(id_px)];
+                let py = current_assignments[layout.index_of(id_py)];
+                let qx = current_assignments[layout.index_of(id_qx)];
+                let qy = current_assignments[layout.index_of(id_qy)];
+                let dx = qx - px;
+                let dy = qy - py;
+                if dy.abs() < EPSILON || (dx * dx + dy * dy) < EPSILON * EPSILON {
+                    // vertical or zero-length line
+                    *degenerate = true;
+                    return;
+                }
+                let dpx = (-ay + qy) * (py - qy).recip();
+                let dpy = (ay - qy) * (px - qx) * libm::pow(py - qy, -2.0);
+                let dqx = (ay - py) * (py - qy).recip();
+                let dqy = -(ay - py) * (px - qx) * libm::pow(py - qy, -2.0);
+                let dax = 1.0;
+                let day = (-px + qx) * (py - qy).recip();
+                row0.extend([
+                    JacobianVar {
+                        id: id_ax,
+                        partial_derivative: dax,
+                    },
+                    JacobianVar {
+                        id: id_ay,
+                        partial_derivative: day,
+                    },
+                    JacobianVar {
+                        id: id_px,
+                        partial_derivative: dpx,
+                    },
+                    JacobianVar {
+                        id: id_py,
+                        partial_derivative: dpy,
+                    },
+                    JacobianVar {
+                        id: id_qx,
+                        partial_derivative: dqx,
+                    },
+                    JacobianVar {
+                        id: id_qy,
+                        partial_derivative: dqy,
+                    },
+                ]);
+            }
+            Constraint::Symmetric(line, a, b) => {
+                let id_px = line.p0.id_x();
+                let id_py = line.p0.id_y();
+                let id_qx = line.p1.id_x();
+                let id_qy = line.p1.id_y();
+                let id_ax = a.id_x();
+                let id_ay = a.id_y();
+                let id_bx = b.id_x();
+                let id_by = b.id_y();
+
+                let values = SymmetricVars {
+                    px: current_assignments[layout.index_of(id_px)],
+                    py: current_assignments[layout.index_of(id_py)],
+                    qx: current_assignments[layout.index_of(id_qx)],
+                    qy: current_assignments[layout.index_of(id_qy)],
+                    ax: current_assignments[layout.index_of(a.id_x())],
+                    ay: current_assignments[layout.index_of(a.id_y())],
+                };
+                let Some(pds) = pds_from_symmetric(values) else {
+                    *degenerate = true;
+                    return;
+                };
+
+                row0.extend([
+                    JacobianVar {
+                        id: id_px,
+                        partial_derivative: pds.dpx[0],
+                    },
+                    JacobianVar {
+                        id: id_py,
+                        partial_derivative: pds.dpy[0],
+                    },
+                    JacobianVar {
+                        id: id_qx,
+                        partial_derivative: pds.dqx[0],
+                    },
+                    JacobianVar {
+                        id: id_qy,
+                        partial_derivative: pds.dqy[0],
+                    },
+                    JacobianVar {
+                        id: id_ax,
+                        partial_derivative: pds.dax[0],
+                    },
+                    JacobianVar {
+                        id: id_ay,
+                        partial_derivative: pds.day[0],
+                    },
+                    JacobianVar {
+                        id: id_bx,
+                        partial_derivative: pds.dbx[0],
+                    },
+                    JacobianVar {
+                        id: id_by,
+                        partial_derivative: pds.dby[0],
+                    },
+                ]);
+                row1.extend([
+                    JacobianVar {
+                        id: id_px,
+                        partial_derivative: pds.dpx[1],
+                    },
+                    JacobianVar {
+                        id: id_py,
+                        partial_derivative: pds.dpy[1],
+                    },
+                    JacobianVar {
+                        id: id_qx,
+                        partial_derivative: pds.dqx[1],
+                    },
+                    JacobianVar {
+                        id: id_qy,
+                        partial_derivative: pds.dqy[1],
+                    },
+                    JacobianVar {
+                        id: id_ax,
+                        partial_derivative: pds.dax[1],
+                    },
+                    JacobianVar {
+                        id: id_ay,
+                        partial_derivative: pds.day[1],
+                    },
+                    JacobianVar {
+                        id: id_bx,
+                        partial_derivative: pds.dbx[1],
+                    },
+                    JacobianVar {
+                        id: id_by,
+                        partial_derivative: pds.dby[1],
+                    },
+                ]);
+            }
+            Constraint::PointArcCoincident(circular_arc, point) => {
+                let id_cx = circular_arc.center.id_x();
+                let id_cy = circular_arc.center.id_y();
+                let cx = current_assignments[layout.index_of(id_cx)];
+                let cy = current_assignments[layout.index_of(id_cy)];
+                let c = V::new(cx, cy);
+
+                let id_sx = circular_arc.start.id_x();
+                let id_sy = circular_arc.start.id_y();
+                let sx = current_assignments[layout.index_of(id_sx)];
+                let sy = current_assignments[layout.index_of(id_sy)];
+                let s = V::new(sx, sy) - c;
+
+                let id_ex = circular_arc.end.id_x();
+                let id_ey = circular_arc.end.id_y();
+                let ex = current_assignments[layout.index_of(id_ex)];
+                let ey = current_assignments[layout.index_of(id_ey)];
+                let e = V::new(ex, ey) - c;
+
+                let id_px = point.id_x();
+                let id_py = point.id_y();
+                let px = current_assignments[layout.index_of(id_px)];
+                let py = current_assignments[layout.index_of(id_py)];
+                let p = V::new(px, py) - c;
+
+                let r = s.magnitude();
+                let r_e = e.magnitude();
+                let r_p = p.magnitude();
+                if r < EPSILON || r_e < EPSILON || r_p < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+
+                let u_s = s * r.recip();
+                let u_e = e * r_e.recip();
+                let e_proj = e * (r / r_e);
+
+                let (j_s, j_e, j_p) = match classify_point_arc_coincident(s, e_proj, p) {
+                    PointArcCoincidentPart::Interior => {
+                        // Point is inside arc
+                        // Residual: f = p * (r/‖p‖ - 1)
+                        // ∂f/∂s = u_p u_sᵀ
+                        // ∂f/∂e = 0
+                        // ∂f/∂p = (r/r_p - 1)I - (r/r_p) u_p u_pᵀ
+                        let u_p = p * r_p.recip();
+                        let r_over_rp = r / r_p;
+                        (
+                            [
+                                [u_p.x * u_s.x, u_p.y * u_s.x],
+                                [u_p.x * u_s.y, u_p.y * u_s.y],
+                            ],
+                            [[0.0, 0.0], [0.0, 0.0]],
+                            [
+                                [
+                                    (r_over_rp - 1.0) - r_over_rp * u_p.x * u_p.x,
+                                    -r_over_rp * u_p.y * u_p.x,
+                                ],
+                                [
+                                    -r_over_rp * u_p.x * u_p.y,
+                                    (r_over_rp - 1.0) - r_over_rp * u_p.y * u_p.y,
+                                ],
+                            ],
+                        )
+                    }
+                    PointArcCoincidentPart::End => {
+                        // Point is closer to arc end
+                        // Residual: f = (r/r_e) * e - p
+                        // ∂f/∂s = u_e u_sᵀ
+                        // ∂f/∂e = (r/r_e)(I - u_e u_eᵀ)
+                        // ∂f/∂p = -I
+                        let r_over_re = r / r_e;
+                        (
+                            [
+                                [u_e.x * u_s.x, u_e.y * u_s.x],
+                                [u_e.x * u_s.y, u_e.y * u_s.y],
+                            ],
+                            [
+                                [
+                                    r_over_re * (1.0 - u_e.x * u_e.x),
+                                    -r_over_re * u_e.y * u_e.x,
+                                ],
+                                [
+                                    -r_over_re * u_e.x * u_e.y,
+                                    r_over_re * (1.0 - u_e.y * u_e.y),
+                                ],
+                            ],
+                            [[-1.0, 0.0], [0.0, -1.0]],
+                        )
+                    }
+                    PointArcCoincidentPart::Start => {
+                        // Point is closer to arc start
+                        // Residual: f = s - p
+                        // ∂f/∂s = I
+                        // ∂f/∂e = 0
+                        // ∂f/∂p = -I
+                        (
+                            [[1.0, 0.0], [0.0, 1.0]],
+                            [[0.0, 0.0], [0.0, 0.0]],
+                            [[-1.0, 0.0], [0.0, -1.0]],
+                        )
+                    }
+                };
+
+                // ∂f/∂c = -(∂f/∂s + ∂f/∂e + ∂f/∂p)
+                let j_o = [
+                    [
+                        -(j_s[0][0] + j_e[0][0] + j_p[0][0]),
+                        -(j_s[0][1] + j_e[0][1] + j_p[0][1]),
+                    ],
+                    [
+                        -(j_s[1][0] + j_e[1][0] + j_p[1][0]),
+                        -(j_s[1][1] + j_e[1][1] + j_p[1][1]),
+                    ],
+                ];
+
+                row0.extend([
+                    JacobianVar {
+                        id: id_cx,
+                        partial_derivative: j_o[0][0],
+                    },
+                    JacobianVar {
+                        id: id_cy,
+                        partial_derivative: j_o[1][0],
+                    },
+                    JacobianVar {
+                        id: id_sx,
+                        partial_derivative: j_s[0][0],
+                    },
+                    JacobianVar {
+                        id: id_sy,
+                        partial_derivative: j_s[1][0],
+                    },
+                    JacobianVar {
+                        id: id_ex,
+                        partial_derivative: j_e[0][0],
+                    },
+                    JacobianVar {
+                        id: id_ey,
+                        partial_derivative: j_e[1][0],
+                    },
+                    JacobianVar {
+                        id: id_px,
+                        partial_derivative: j_p[0][0],
+                    },
+                    JacobianVar {
+                        id: id_py,
+                        partial_derivative: j_p[1][0],
+                    },
+                ]);
+                row1.extend([
+                    JacobianVar {
+                        id: id_cx,
+                        partial_derivative: j_o[0][1],
+                    },
+                    JacobianVar {
+                        id: id_cy,
+                        partial_derivative: j_o[1][1],
+                    },
+                    JacobianVar {
+                        id: id_sx,
+                        partial_derivative: j_s[0][1],
+                    },
+                    JacobianVar {
+                        id: id_sy,
+                        partial_derivative: j_s[1][1],
+                    },
+                    JacobianVar {
+                        id: id_ex,
+                        partial_derivative: j_e[0][1],
+                    },
+                    JacobianVar {
+                        id: id_ey,
+                        partial_derivative: j_e[1][1],
+                    },
+                    JacobianVar {
+                        id: id_px,
+                        partial_derivative: j_p[0][1],
+                    },
+                    JacobianVar {
+                        id: id_py,
+                        partial_derivative: j_p[1][1],
+                    },
+                ]);
+            }
+            Constraint::ArcLength(circular_arc, d) => {
+                let id_cx = circular_arc.center.id_x();
+                let id_cy = circular_arc.center.id_y();
+                let id_ax = circular_arc.start.id_x();
+                let id_ay = circular_arc.start.id_y();
+                let id_bx = circular_arc.end.id_x();
+                let id_by = circular_arc.end.id_y();
+                let cx = current_assignments[layout.index_of(id_cx)];
+                let cy = current_assignments[layout.index_of(id_cy)];
+                let ax = current_assignments[layout.index_of(id_ax)];
+                let ay = current_assignments[layout.index_of(id_ay)];
+
+                let ux = ax - cx;
+                let uy = ay - cy;
+                let r2 = ux * ux + uy * uy;
+                if r2 <= EPSILON * EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+
+                // Partials of res = (b - c) - R(α)·(a - c) with α = d / r, r = |a - c|.
+                // Let Ru = R(α)·u; rotating Ru by dα yields (-Ruy, Rux), and
+                // ∂α/∂u = -d·u / r³. The shared factor below is k = d / r³.
+                let r = r2.sqrt();
+                let alpha = d / r;
+                let sa = libm::sin(alpha);
+                let ca = libm::cos(alpha);
+                let rux = ca * ux - sa * uy;
+                let ruy = sa * ux + ca * uy;
+                let k = d / (r2 * r);
+
+                // res0 = (bx - cx) - rux
+                let r0dax = -ca - ruy * ux * k;
+                let r0day = sa - ruy * uy * k;
+                let r0dbx = 1.0;
+                let r0dby = 0.0;
+                let r0dcx = -1.0 + ca + ruy * ux * k;
+                let r0dcy = -sa + ruy * uy * k;
+
+                // res1 = (by - cy) - ruy
+                let r1dax = -sa + rux * ux * k;
+                let r1day = -ca + rux * uy * k;
+                let r1dbx = 0.0;
+                let r1dby = 1.0;
+                let r1dcx = sa - rux * ux * k;
+                let r1dcy = -1.0 + ca - rux * uy * k;
+
+                row0.extend([
+                    JacobianVar {
+                        id: id_ax,
+                        partial_derivative: r0dax,
+                    },
+                    JacobianVar {
+                        id: id_ay,
+                        partial_derivative: r0day,
+                    },
+                    JacobianVar {
+                        id: id_bx,
+                        partial_derivative: r0dbx,
+                    },
+                    JacobianVar {
+                        id: id_by,
+                        partial_derivative: r0dby,
+                    },
+                    JacobianVar {
+                        id: id_cx,
+                        partial_derivative: r0dcx,
+                    },
+                    JacobianVar {
+                        id: id_cy,
+                        partial_derivative: r0dcy,
+                    },
+                ]);
+                row1.extend([
+                    JacobianVar {
+                        id: id_ax,
+                        partial_derivative: r1dax,
+                    },
+                    JacobianVar {
+                        id: id_ay,
+                        partial_derivative: r1day,
+                    },
+                    JacobianVar {
+                        id: id_bx,
+                        partial_derivative: r1dbx,
+                    },
+                    JacobianVar {
+                        id: id_by,
+                        partial_derivative: r1dby,
+                    },
+                    JacobianVar {
+                        id: id_cx,
+                        partial_derivative: r1dcx,
+                    },
+                    JacobianVar {
+                        id: id_cy,
+                        partial_derivative: r1dcy,
+                    },
+                ]);
+            }
+            Constraint::ArcAngle(circular_arc, angle) => Constraint::LinesAtAngle(
+                DatumLineSegment {
+                    p0: circular_arc.center,
+                    p1: circular_arc.start,
+                },
+                DatumLineSegment {
+                    p0: circular_arc.center,
+                    p1: circular_arc.end,
+                },
+                AngleKind::Other(*angle),
+            )
+            .jacobian_rows(layout, current_assignments, row0, row1, row2, degenerate),
+            Constraint::PointsAtAngle(p0, p1, p2, expected_angle) => {
+                let p0v = V::new(
+                    current_assignments[layout.index_of(p0.id_x())],
+                    current_assignments[layout.index_of(p0.id_y())],
                 );
-            }
-            Constraint::Fixed(id, _expected) => {
-                row0.extend(
-                    [JacobianVar {
-                        id: *id,
-                        partial_derivative: 1.0,
-                    }]
-                    .as_slice(),
+                let p1v = V::new(
+                    current_assignments[layout.index_of(p1.id_x())],
+                    current_assignments[layout.index_of(p1.id_y())],
+                );
+                let p2v = V::new(
+                    current_assignments[layout.index_of(p2.id_x())],
+                    current_assignments[layout.index_of(p2.id_y())],
                 );
-            }
-            Constraint::ScalarEqual(x, y) => {
-                // Residual equation R: x-y=0
-                // dR/dx: 1
-                // dR/dy: -1
-                row0.push(JacobianVar {
-                    id: *x,
-                    partial_derivative: 1.0,
-                });
-                row0.push(JacobianVar {
-                    id: *y,
-                    partial_derivative: -1.0,
-                });
-            }
-            Constraint::LinesAtAngle(line0, line1, expected_angle) => {
-                let x0 = current_assignments[layout.index_of(line0.p0.id_x())];
-                let y0 = current_assignments[layout.index_of(line0.p0.id_y())];
-                let x1 = current_assignments[layout.index_of(line0.p1.id_x())];
-                let y1 = current_assignments[layout.index_of(line0.p1.id_y())];
-                let x2 = current_assignments[layout.index_of(line1.p0.id_x())];
-                let y2 = current_assignments[layout.index_of(line1.p0.id_y())];
-                let x3 = current_assignments[layout.index_of(line1.p1.id_x())];
-                let y3 = current_assignments[layout.index_of(line1.p1.id_y())];
-
-                let u = V::new(x1 - x0, y1 - y0);
-                let v = V::new(x3 - x2, y3 - y2);
 
+                let u = p1v - p0v;
+                let v = p2v - p0v;
                 let len_u = u.magnitude();
                 let len_v = v.magnitude();
-                if (len_u <= EPSILON) || (len_v <= EPSILON) {
+
+                if len_u <= EPSILON || len_v <= EPSILON {
                     *degenerate = true;
                     return;
                 }
 
-                let u_hat = u * (1.0 / len_u);
-                let v_hat = v * (1.0 / len_v);
+                let inv_len_u = 1.0 / len_u;
+                let inv_len_v = 1.0 / len_v;
+                let u_hat = u * inv_len_u;
+                let v_hat = v * inv_len_v;
 
                 let rot = rotation_for_angle_kind(*expected_angle);
                 let s = (len_u + len_v) * 0.5;
 
+                // Columns of R: R*e1 = (ca, sa), R*e2 = (-sa, ca)
+                let rot_e1 = rot.apply(V::new(1.0, 0.0));
+                let rot_e2 = rot.apply(V::new(0.0, 1.0));
+
                 /*
                     Residual
 
                         r = a / s
-                        a := u × R⁻¹v
+                        a := (|u| v - |v| R u)
                         s := (|u| + |v|) / 2
 
-                    Differentiate in u (via quotient rule)
+                    Differentiate in u and v (via quotient/chain rule)
 
-                        ∂a/∂u = perp_cw(R⁻¹v)
-                        ∂s/∂u = û/2
-                        ∂r/∂u = ((∂a/∂u) - (a/s)·(∂s/∂u)) / s
+                        ∂a/∂u = v ûᵀ - |v| R
+                        ∂r/∂u = (∂a/∂u - r (∂s/∂u)ᵀ) / s = ((v - r/2) ûᵀ - |v| R) / s
 
-                    Symmetric for v with ∂a/∂v = perp_ccw(R u), ∂s/∂v = v̂/2
+                        ∂a/∂v = |u| I - (R u) v̂ᵀ
+                        ∂r/∂v = (∂a/∂v - r (∂s/∂v)ᵀ) / s = (|u| I - (R u + r/2) v̂ᵀ) / s
                 */
-                let a = u.cross_2d(rot.inverse().apply(v));
                 let inv_s = 1.0 / s;
-                let t = a * inv_s * 0.5;
-                let df_du = (rot.inverse().apply(v).perp_cw() - u_hat * t) * inv_s;
-                let df_dv = (rot.apply(u).perp_ccw() - v_hat * t) * inv_s;
-
-                let pds = PartialDerivatives4Points {
-                    x0: -df_du.x,
-                    y0: -df_du.y,
-                    x1: df_du.x,
-                    y1: df_du.y,
-                    x2: -df_dv.x,
-                    y2: -df_dv.y,
-                    x3: df_dv.x,
-                    y3: df_dv.y,
-                };
-
-                let jvars = pds.jvars(line0, line1);
-                row0.extend(jvars.as_slice());
-            }
-            Constraint::LinesEqualLength(line0, line1) => {
-                // Get all points
-                let x0 = current_assignments[layout.index_of(line0.p0.id_x())];
-                let y0 = current_assignments[layout.index_of(line0.p0.id_y())];
-                let x1 = current_assignments[layout.index_of(line0.p1.id_x())];
-                let y1 = current_assignments[layout.index_of(line0.p1.id_y())];
-                let l0 = (V::new(x0, y0), V::new(x1, y1));
-                let x2 = current_assignments[layout.index_of(line1.p0.id_x())];
-                let y2 = current_assignments[layout.index_of(line1.p0.id_y())];
-                let x3 = current_assignments[layout.index_of(line1.p1.id_x())];
-                let y3 = current_assignments[layout.index_of(line1.p1.id_y())];
-                let l1 = (V::new(x2, y2), V::new(x3, y3));
-
-                // Calculate lengths of each line.
-                let len0 = l0.0.euclidean_distance(l0.1);
-                let len1 = l1.0.euclidean_distance(l1.1);
-
-                // Avoid division by 0
-                if len0 < EPSILON || len1 < EPSILON {
-                    *degenerate = true;
-                    return;
-                }
-
-                // Calculate derivatives.
-                let pds = PartialDerivatives4Points {
-                    x0: (x0 - x1) / len0,
-                    y0: (y0 - y1) / len0,
-                    x1: (-x0 + x1) / len0,
-                    y1: (-y0 + y1) / len0,
-                    x2: (-x2 + x3) / len1,
-                    y2: (-y2 + y3) / len1,
-                    x3: (x2 - x3) / len1,
-                    y3: (y2 - y3) / len1,
-                };
-                let jvars = pds.jvars(line0, line1);
-                row0.extend(jvars.as_slice());
-            }
-            Constraint::PointsCoincident(p0, p1) => {
-                // Residuals:
-                // R0 = x0 - x1,
-                // R1 = y0 - y1.
-                //
-                // For R0 = x0 - x1:
-                // ∂R0/∂x0 = 1
-                // ∂R0/∂y0 = 0
-                // ∂R0/∂x1 = -1
-                // ∂R0/∂y1 = 0
-                //
-                // For R1 = y0 - y1:
-                // ∂R1/∂x0 = 0
-                // ∂R1/∂y0 = 1
-                // ∂R1/∂x1 = 0
-                // ∂R1/∂y1 = -1
-
-                let dr0_dx0 = 1.0;
-                // dr0_dy0 = 0.0
-                let dr0_dx1 = -1.0;
-                // dr0_dy1 = 0.0
-
-                // dr1_dx0 = 0.0
-                let dr1_dy0 = 1.0;
-                // dr1_dx1 = 0.0
-                let dr1_dy1 = -1.0;
+                let rot_u = rot.apply(u);
+                let res = (v * len_u - rot_u * len_v) * inv_s;
+                let half_res = res * 0.5;
+                let dr_du0 = ((v - half_res) * u_hat.x - rot_e1 * len_v) * inv_s;
+                let dr_du1 = ((v - half_res) * u_hat.y - rot_e2 * len_v) * inv_s;
+                let dr_dv0 = (V::new(len_u, 0.0) - (rot_u + half_res) * v_hat.x) * inv_s;
+                let dr_dv1 = (V::new(0.0, len_u) - (rot_u + half_res) * v_hat.y) * inv_s;
 
-                // We only care about nonzero derivs here.
+                // ∂r/∂p0 = -(∂r/∂u + ∂r/∂v)
+                // ∂r/∂p1 = ∂r/∂u
+                // ∂r/∂p2 = ∂r/∂v
                 row0.extend([
                     JacobianVar {
                         id: p0.id_x(),
-                        partial_derivative: dr0_dx0,
+                        partial_derivative: -(dr_du0.x + dr_dv0.x),
+                    },
+                    JacobianVar {
+                        id: p0.id_y(),
+                        partial_derivative: -(dr_du1.x + dr_dv1.x),
                     },
                     JacobianVar {
                         id: p1.id_x(),
-                        partial_derivative: dr0_dx1,
+                        partial_derivative: dr_du0.x,
+                    },
+                    JacobianVar {
+                        id: p1.id_y(),
+                        partial_derivative: dr_du1.x,
+                    },
+                    JacobianVar {
+                        id: p2.id_x(),
+                        partial_derivative: dr_dv0.x,
+                    },
+                    JacobianVar {
+                        id: p2.id_y(),
+                        partial_derivative: dr_dv1.x,
                     },
                 ]);
                 row1.extend([
+                    JacobianVar {
+                        id: p0.id_x(),
+                        partial_derivative: -(dr_du0.y + dr_dv0.y),
+                    },
                     JacobianVar {
                         id: p0.id_y(),
-                        partial_derivative: dr1_dy0,
+                        partial_derivative: -(dr_du1.y + dr_dv1.y),
+                    },
+                    JacobianVar {
+                        id: p1.id_x(),
+                        partial_derivative: dr_du0.y,
                     },
                     JacobianVar {
                         id: p1.id_y(),
-                        partial_derivative: dr1_dy1,
+                        partial_derivative: dr_du1.y,
                     },
-                ]);
-            }
-            Constraint::CircleRadius(circle, _expected_radius) => {
-                // Residual is R = r_expected - r_actual
-                // Only partial derivative which is nonzero is ∂R/∂r_current, which is 1.
-                row0.push(JacobianVar {
-                    id: circle.radius.id,
-                    partial_derivative: 1.0,
-                });
-            }
-            Constraint::ArcRadius(arc, radius) => {
-                // This is really just equivalent to 2 constraints,
-                // distance(center, start) and distance(center, end).
-                let constraints = (
-                    Constraint::Distance(arc.center, arc.start, *radius),
-                    Constraint::Distance(arc.center, arc.end, *radius),
-                );
-                constraints.0.jacobian_rows(
-                    layout,
-                    current_assignments,
-                    row0,
-                    row1,
-                    _row2,
-                    degenerate,
-                );
-                constraints.1.jacobian_rows(
-                    layout,
-                    current_assignments,
-                    row1,
-                    row0,
-                    _row2,
-                    degenerate,
-                );
-            }
-            Constraint::Arc(arc) => {
-                // Residual: R = |start - c| - |end - c|. With us = start - c and ue = end - c,
-                // the partials are unit-vector components (O(1), scale-invariant):
-                // ∂R/∂start = us / |us|
-                // ∂R/∂end   = -ue / |ue|
-                // ∂R/∂c     = -us / |us| + ue / |ue|
-
-                let start_x = current_assignments[layout.index_of(arc.start.id_x())];
-                let start_y = current_assignments[layout.index_of(arc.start.id_y())];
-                let end_x = current_assignments[layout.index_of(arc.end.id_x())];
-                let end_y = current_assignments[layout.index_of(arc.end.id_y())];
-                let cx = current_assignments[layout.index_of(arc.center.id_x())];
-                let cy = current_assignments[layout.index_of(arc.center.id_y())];
-
-                let usx = start_x - cx;
-                let usy = start_y - cy;
-
-                let uex = end_x - cx;
-                let uey = end_y - cy;
-
-                let dist0 = libm::hypot(usx, usy);
-                let dist1 = libm::hypot(uex, uey);
-                if dist0 <= EPSILON || dist1 <= EPSILON {
+                    JacobianVar {
+                        id: p2.id_x(),
+                        partial_derivative: dr_dv0.y,
+                    },
+                    JacobianVar {
+                        id: p2.id_y(),
+                        partial_derivative: dr_dv1.y,
+                    },
+                ]);
+            }
+            Constraint::PolarOffset(from, to, r, _theta) => {
+                let d = V::new(
+                    current_assignments[layout.index_of(to.id_x())]
+                        - current_assignments[layout.index_of(from.id_x())],
+                    current_assignments[layout.index_of(to.id_y())]
+                        - current_assignments[layout.index_of(from.id_y())],
+                );
+                let dist = d.magnitude();
+                if dist <= EPSILON {
                     *degenerate = true;
                     return;
                 }
 
-                // Calculate derivative values for distance constraint.
-                let dx_start = usx / dist0;
-                let dy_start = usy / dist0;
-                let dx_end = -uex / dist1;
-                let dy_end = -uey / dist1;
-                let dx_c = -usx / dist0 + uex / dist1;
-                let dy_c = -usy / dist0 + uey / dist1;
+                // R0 = |d| - r          ∂R0/∂to = d / |d|
+                // R1 = |r|·(atan2(d) - θ)  ∂R1/∂to = |r|·perp_ccw(d) / |d|²
+                // and ∂R/∂from = -∂R/∂to for both.
+                let dr0 = d * dist.recip();
+                let dr1 = d.perp_ccw() * (r.abs() / (dist * dist));
 
                 row0.extend([
                     JacobianVar {
-                        id: arc.start.id_x(),
-                        partial_derivative: dx_start,
+                        id: from.id_x(),
+                        partial_derivative: -dr0.x,
                     },
                     JacobianVar {
-                        id: arc.start.id_y(),
-                        partial_derivative: dy_start,
+                        id: from.id_y(),
+                        partial_derivative: -dr0.y,
                     },
                     JacobianVar {
-                        id: arc.end.id_x(),
-                        partial_derivative: dx_end,
+                        id: to.id_x(),
+                        partial_derivative: dr0.x,
                     },
                     JacobianVar {
-                        id: arc.end.id_y(),
-                        partial_derivative: dy_end,
+                        id: to.id_y(),
+                        partial_derivative: dr0.y,
+                    },
+                ]);
+                row1.extend([
+                    JacobianVar {
+                        id: from.id_x(),
+                        partial_derivative: -dr1.x,
                     },
                     JacobianVar {
-                        id: arc.center.id_x(),
-                        partial_derivative: dx_c,
+                        id: from.id_y(),
+                        partial_derivative: -dr1.y,
                     },
                     JacobianVar {
-                        id: arc.center.id_y(),
-                        partial_derivative: dy_c,
+                        id: to.id_x(),
+                        partial_derivative: dr1.x,
+                    },
+                    JacobianVar {
+                        id: to.id_y(),
+                        partial_derivative: dr1.y,
                     },
                 ]);
             }
-            Constraint::Midpoint(line, point) => {
-                let p = line.p0;
-                let q = line.p1;
-                // Equation:
-                // (note that a = the midpoint)
-                //   ax = (px + qx)/2,
-                // ∴ ax - px/2 - qx/2 = 0
-                //
-                // This has partial derivatives:
-                //   ∂R/∂ ax =  1
-                //   ∂R/∂ px = -0.5
-                //   ∂R/∂ qx = -0.5
-                //   ∂R/∂ ay =  1
-                //   ∂R/∂ py = -0.5
-                //   ∂R/∂ qy = -0.5
+            Constraint::PointOnConic(point, conic) => {
+                // Residual: R = Ax² + Bxy + Cy² + Dx + Ey + F
+                // ∂R/∂x = 2Ax + By + D
+                // ∂R/∂y = Bx + 2Cy + E
+                // and the partials wrt each coefficient are just the monomials it multiplies.
+                let x = current_assignments[layout.index_of(point.id_x())];
+                let y = current_assignments[layout.index_of(point.id_y())];
+                let a = current_assignments[layout.index_of(conic.a)];
+                let b = current_assignments[layout.index_of(conic.b)];
+                let c = current_assignments[layout.index_of(conic.c)];
+                let d = current_assignments[layout.index_of(conic.d)];
+                let e = current_assignments[layout.index_of(conic.e)];
                 row0.extend([
                     JacobianVar {
                         id: point.id_x(),
+                        partial_derivative: 2.0 * a * x + b * y + d,
+                    },
+                    JacobianVar {
+                        id: point.id_y(),
+                        partial_derivative: b * x + 2.0 * c * y + e,
+                    },
+                    JacobianVar {
+                        id: conic.a,
+                        partial_derivative: x * x,
+                    },
+                    JacobianVar {
+                        id: conic.b,
+                        partial_derivative: x * y,
+                    },
+                    JacobianVar {
+                        id: conic.c,
+                        partial_derivative: y * y,
+                    },
+                    JacobianVar {
+                        id: conic.d,
+                        partial_derivative: x,
+                    },
+                    JacobianVar {
+                        id: conic.e,
+                        partial_derivative: y,
+                    },
+                    JacobianVar {
+                        id: conic.f,
                         partial_derivative: 1.0,
                     },
+                ]);
+            }
+            Constraint::ScaledCopy(pivot, scale, original, copy) => {
+                // R0 = copy.x - pivot.x - s * (original.x - pivot.x)
+                // ∂R0/∂copy.x = 1
+                // ∂R0/∂pivot.x = s - 1
+                // ∂R0/∂original.x = -s
+                // ∂R0/∂s = -(original.x - pivot.x)
+                // and likewise for R1 in the Y axis.
+                let px = current_assignments[layout.index_of(pivot.id_x())];
+                let py = current_assignments[layout.index_of(pivot.id_y())];
+                let s = current_assignments[layout.index_of(*scale)];
+                let ox = current_assignments[layout.index_of(original.id_x())];
+                let oy = current_assignments[layout.index_of(original.id_y())];
+                row0.extend([
+                    JacobianVar {
+                        id: pivot.id_x(),
+                        partial_derivative: s - 1.0,
+                    },
                     JacobianVar {
-                        id: p.id_x(),
-                        partial_derivative: -0.5,
+                        id: *scale,
+                        partial_derivative: px - ox,
                     },
                     JacobianVar {
-                        id: q.id_x(),
-                        partial_derivative: -0.5,
+                        id: original.id_x(),
+                        partial_derivative: -s,
+                    },
+                    JacobianVar {
+                        id: copy.id_x(),
+                        partial_derivative: 1.0,
                     },
                 ]);
                 row1.extend([
                     JacobianVar {
-                        id: point.id_y(),
-                        partial_derivative: 1.0,
+                        id: pivot.id_y(),
+                        partial_derivative: s - 1.0,
                     },
                     JacobianVar {
-                        id: p.id_y(),
-                        partial_derivative: -0.5,
+                        id: *scale,
+                        partial_derivative: py - oy,
                     },
                     JacobianVar {
-                        id: q.id_y(),
-                        partial_derivative: -0.5,
+                        id: original.id_y(),
+                        partial_derivative: -s,
+                    },
+                    JacobianVar {
+                        id: copy.id_y(),
+                        partial_derivative: 1.0,
                     },
                 ]);
             }
-            Constraint::PointLineDistance(point, line, _distance) => {
+            Constraint::Homothety(center, original, image, ratio) => {
                 // Equation:
-                //
-                // Given a line in format Ax + By + C = 0,
-                // and a point (px, py), then the actual distance is
-                //
-                // (A.px + B.py + C)  /  sqrt(A^2 + B^2)
-                //
-                // Note that we use a signed direction, so there's no absolute value
-                // of the numerator, as you'd usually see. This stops the solver
-                // from randomly flipping which side of the line the point is on.
-                let px = current_assignments[layout.index_of(point.id_x())];
-                let py = current_assignments[layout.index_of(point.id_y())];
-                let p0x = current_assignments[layout.index_of(line.p0.id_x())];
-                let p0y = current_assignments[layout.index_of(line.p0.id_y())];
-                let p1x = current_assignments[layout.index_of(line.p1.id_x())];
-                let p1y = current_assignments[layout.index_of(line.p1.id_y())];
-
-                let partial_derivatives = pds_for_point_line(
-                    *point,
-                    line,
-                    PointLineVars {
-                        px,
-                        py,
-                        p0x,
-                        p0y,
-                        p1x,
-                        p1y,
-                    },
-                );
-
-                row0.extend(partial_derivatives);
-            }
-            Constraint::VerticalPointLineDistance(point, line, _distance) => {
-                // Mirrors `HorizontalPointLineDistance` with x and y swapped
-                let id_ax = point.id_x();
-                let id_ay = point.id_y();
-                let id_px = line.p0.id_x();
-                let id_py = line.p0.id_y();
-                let id_qx = line.p1.id_x();
-                let id_qy = line.p1.id_y();
-                let ax = current_assignments[layout.index_of(id_ax)];
-                let px = current_assignments[layout.index_of(id_px)];
-                let py = current_assignments[layout.index_of(id_py)];
-                let qx = current_assignments[layout.index_of(id_qx)];
-                let qy = current_assignments[layout.index_of(id_qy)];
-
-                let dx = qx - px;
-                let dy = qy - py;
-                if dx.abs() <= EPSILON || (dx * dx + dy * dy) <= EPSILON * EPSILON {
-                    // vertical or zero-length line
-                    *degenerate = true;
-                    return;
+                //   R = i - c - ratio * (o - c)
+                // This has constant partial derivatives:
+                //   ∂R/∂i = 1
+                //   ∂R/∂o = -ratio
+                //   ∂R/∂c = ratio - 1
+                for (row, [c, o, i]) in [
+                    (&mut *row0, [center.id_x(), original.id_x(), image.id_x()]),
+                    (&mut *row1, [center.id_y(), original.id_y(), image.id_y()]),
+                ] {
+                    row.extend([
+                        JacobianVar {
+                            id: c,
+                            partial_derivative: ratio - 1.0,
+                        },
+                        JacobianVar {
+                            id: o,
+                            partial_derivative: -ratio,
+                        },
+                        JacobianVar {
+                            id: i,
+                            partial_derivative: 1.0,
+                        },
+                    ]);
                 }
-
-                let dpx = (ax - qx) * (py - qy) * libm::pow(px - qx, -2.0);
-                let dpy = (-ax + qx) * (px - qx).recip();
-
-                let dqx = -(ax - px) * (py - qy) * libm::pow(px - qx, -2.0);
-                let dqy = (ax - px) * (px - qx).recip();
-
-                let dax = (-py + qy) * (px - qx).recip();
-                let day = 1.0;
-
+            }
+            Constraint::PointInfiniteLineDistance(point, line, _distance) => {
+                // R = px cos(θ) + py sin(θ) - offset - d
+                let p = V::new(
+                    current_assignments[layout.index_of(point.id_x())],
+                    current_assignments[layout.index_of(point.id_y())],
+                );
+                let pds = pds_for_point_infinite_line(current_assignments, *line, layout, p);
                 row0.extend([
                     JacobianVar {
-                        id: id_ax,
-                        partial_derivative: dax,
+                        id: point.id_x(),
+                        partial_derivative: pds.dp.x,
                     },
                     JacobianVar {
-                        id: id_ay,
-                        partial_derivative: day,
+                        id: point.id_y(),
+                        partial_derivative: pds.dp.y,
                     },
                     JacobianVar {
-                        id: id_px,
-                        partial_derivative: dpx,
+                        id: line.angle,
+                        partial_derivative: pds.dangle,
                     },
                     JacobianVar {
-                        id: id_py,
-                        partial_derivative: dpy,
+                        id: line.offset,
+                        partial_derivative: -1.0,
                     },
+                ]);
+            }
+            Constraint::InfiniteLineTangentToCircle(line, circle, side) => {
+                // R = side * (cx cos(θ) + cy sin(θ) - offset) - |r|
+                let c = V::new(
+                    current_assignments[layout.index_of(circle.center.id_x())],
+                    current_assignments[layout.index_of(circle.center.id_y())],
+                );
+                let pds = pds_for_point_infinite_line(current_assignments, *line, layout, c);
+                let side_sign = if *side == LineSide::Right { -1.0 } else { 1.0 };
+                // The residual uses |radius| to guard against negative values, so we have to
+                // differentiate through the abs
+                let radius = current_assignments[layout.index_of(circle.radius.id)];
+                row0.extend([
                     JacobianVar {
-                        id: id_qx,
-                        partial_derivative: dqx,
+                        id: line.angle,
+                        partial_derivative: side_sign * pds.dangle,
                     },
                     JacobianVar {
-                        id: id_qy,
-                        partial_derivative: dqy,
+                        id: line.offset,
+                        partial_derivative: -side_sign,
+                    },
+                    JacobianVar {
+                        id: circle.center.id_x(),
+                        partial_derivative: side_sign * pds.dp.x,
+                    },
+                    JacobianVar {
+                        id: circle.center.id_y(),
+                        partial_derivative: side_sign * pds.dp.y,
+                    },
+                    JacobianVar {
+                        id: circle.radius.id,
+                        partial_derivative: -radius.signum(),
                     },
                 ]);
             }
-            Constraint::HorizontalPointLineDistance(point, line, _distance) => {
-                // See notebook:
-                // https://github.com/KittyCAD/ezpz-sympy/blob/main/main.py
-                let id_ax = point.id_x();
-                let id_ay = point.id_y();
-                let id_px = line.p0.id_x();
-                let id_py = line.p0.id_y();
-                let id_qx = line.p1.id_x();
-                let id_qy = line.p1.id_y();
-                let ay = current_assignments[layout.index_of(id_ay)];
-                let px = current_assignments[layout.index_of(id_px)];
-                let py = current_assignments[layout.index_of(id_py)];
-                let qx = current_assignments[layout.index_of(id_qx)];
-                let qy = current_assignments[layout.index_of(id_qy)];
-                let dx = qx - px;
-                let dy = qy - py;
-                if dy.abs() < EPSILON || (dx * dx + dy * dy) < EPSILON * EPSILON {
-                    // vertical or zero-length line
+            Constraint::MinDistance(p0, p1, _min_distance) => {
+                // Residual: R = d - |p0 - p1|, an inequality (so it's clamped to >= 0 by the solver).
+                // This is just the negated `Distance` gradient.
+                let x0 = current_assignments[layout.index_of(p0.id_x())];
+                let y0 = current_assignments[layout.index_of(p0.id_y())];
+                let x1 = current_assignments[layout.index_of(p1.id_x())];
+                let y1 = current_assignments[layout.index_of(p1.id_y())];
+
+                let dist = V::new(x0, y0).euclidean_distance(V::new(x1, y1));
+                if dist < EPSILON {
                     *degenerate = true;
                     return;
                 }
-                let dpx = (-ay + qy) * (py - qy).recip();
-                let dpy = (ay - qy) * (px - qx) * libm::pow(py - qy, -2.0);
-                let dqx = (ay - py) * (py - qy).recip();
-                let dqy = -(ay - py) * (px - qx) * libm::pow(py - qy, -2.0);
-                let dax = 1.0;
-                let day = (-px + qx) * (py - qy).recip();
+                let scale = -1.0 / dist;
+                let dr_dx0 = scale * (x0 - x1);
+                let dr_dy0 = scale * (y0 - y1);
                 row0.extend([
                     JacobianVar {
-                        id: id_ax,
-                        partial_derivative: dax,
+                        id: p0.id_x(),
+                        partial_derivative: dr_dx0,
                     },
                     JacobianVar {
-                        id: id_ay,
-                        partial_derivative: day,
+                        id: p0.id_y(),
+                        partial_derivative: dr_dy0,
                     },
                     JacobianVar {
-                        id: id_px,
-                        partial_derivative: dpx,
+                        id: p1.id_x(),
+                        partial_derivative: -dr_dx0,
                     },
                     JacobianVar {
-                        id: id_py,
-                        partial_derivative: dpy,
+                        id: p1.id_y(),
+                        partial_derivative: -dr_dy0,
+                    },
+                ]);
+            }
+            Constraint::LengthRatioBounds(line0, line1, min_ratio, max_ratio) => {
+                // ∂|line|/∂p1 is the line's unit vector, and ∂|line|/∂p0 is its negation.
+                let u0 = line_vector(current_assignments, line0, layout);
+                let u1 = line_vector(current_assignments, line1, layout);
+                let (len0, len1) = (u0.magnitude(), u1.magnitude());
+                if len0 < EPSILON || len1 < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let (u0, u1) = (u0 * (1.0 / len0), u1 * (1.0 / len1));
+                let line_partials = |line: &DatumLineSegment, d: V| {
+                    [
+                        JacobianVar {
+                            id: line.p0.id_x(),
+                            partial_derivative: -d.x,
+                        },
+                        JacobianVar {
+                            id: line.p0.id_y(),
+                            partial_derivative: -d.y,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_x(),
+                            partial_derivative: d.x,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_y(),
+                            partial_derivative: d.y,
+                        },
+                    ]
+                };
+                // R0 = |line0| - max·|line1|
+                row0.extend(line_partials(line0, u0));
+                row0.extend(line_partials(line1, u1 * -max_ratio));
+                // R1 = min·|line1| - |line0|
+                row1.extend(line_partials(line0, u0 * -1.0));
+                row1.extend(line_partials(line1, u1 * *min_ratio));
+            }
+            Constraint::SameSideOfLine(line, reference, point) => {
+                // Residual: R = -s * dist(point, line), where s is the sign of the
+                // reference point's side (held constant, so this is a sub-gradient).
+                // It's an inequality, so the solver clamps it to >= 0.
+                // This is the negated (and signed) `PointLineDistance` gradient.
+                let px = current_assignments[layout.index_of(point.id_x())];
+                let py = current_assignments[layout.index_of(point.id_y())];
+                let p0x = current_assignments[layout.index_of(line.p0.id_x())];
+                let p0y = current_assignments[layout.index_of(line.p0.id_y())];
+                let p1x = current_assignments[layout.index_of(line.p1.id_x())];
+                let p1y = current_assignments[layout.index_of(line.p1.id_y())];
+                if libm::hypot(p1x - p0x, p1y - p0y) < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let rx = current_assignments[layout.index_of(reference.id_x())];
+                let ry = current_assignments[layout.index_of(reference.id_y())];
+                let (a, b, c) = inner_equation_of_line(p0x, p0y, p1x, p1y);
+                let scale = -same_side_sign(a * rx + b * ry + c);
+
+                let partial_derivatives = pds_for_point_line(
+                    *point,
+                    line,
+                    PointLineVars {
+                        px,
+                        py,
+                        p0x,
+                        p0y,
+                        p1x,
+                        p1y,
                     },
+                );
+                row0.extend(partial_derivatives.map(|jv| JacobianVar {
+                    id: jv.id,
+                    partial_derivative: scale * jv.partial_derivative,
+                }));
+                row0.extend([
                     JacobianVar {
-                        id: id_qx,
-                        partial_derivative: dqx,
+                        id: reference.id_x(),
+                        partial_derivative: 0.0,
                     },
                     JacobianVar {
-                        id: id_qy,
-                        partial_derivative: dqy,
+                        id: reference.id_y(),
+                        partial_derivative: 0.0,
                     },
                 ]);
             }
-            Constraint::Symmetric(line, a, b) => {
-                let id_px = line.p0.id_x();
-                let id_py = line.p0.id_y();
-                let id_qx = line.p1.id_x();
-                let id_qy = line.p1.id_y();
-                let id_ax = a.id_x();
-                let id_ay = a.id_y();
-                let id_bx = b.id_x();
-                let id_by = b.id_y();
-
-                let values = SymmetricVars {
-                    px: current_assignments[layout.index_of(id_px)],
-                    py: current_assignments[layout.index_of(id_py)],
-                    qx: current_assignments[layout.index_of(id_qx)],
-                    qy: current_assignments[layout.index_of(id_qy)],
-                    ax: current_assignments[layout.index_of(a.id_x())],
-                    ay: current_assignments[layout.index_of(a.id_y())],
+            Constraint::ArcCircleEqualRadius(arc, circle) => {
+                // The distance partials, plus ∂R/∂r = -1.
+                // `DistanceVar` already reports a zero-radius arc as degenerate.
+                Constraint::DistanceVar(arc.center, arc.start, circle.radius).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+            }
+            Constraint::Concyclic(arc0, arc1) => {
+                let (centers, radii) = concyclic_parts(arc0, arc1);
+                centers.jacobian_rows(layout, current_assignments, row0, row1, row2, degenerate);
+                radii.jacobian_rows(layout, current_assignments, row2, row0, row1, degenerate);
+            }
+            Constraint::Circumcenter(center, a, b, c) => {
+                // Each residual is the (smooth) difference of squared distances to a pair of
+                // points, normalized to the center's distance from that pair's perpendicular
+                // bisector. See `bisector_distance`.
+                let [vo, va, vb, vc] = [center, a, b, c].map(|p| {
+                    V::new(
+                        current_assignments[layout.index_of(p.id_x())],
+                        current_assignments[layout.index_of(p.id_y())],
+                    )
+                });
+                let (Some(ab), Some(bc)) =
+                    (bisector_distance(vo, va, vb), bisector_distance(vo, vb, vc))
+                else {
+                    *degenerate = true;
+                    return;
                 };
-                let Some(pds) = pds_from_symmetric(values) else {
+                for (row, pds, p, q) in [(&mut *row0, ab, a, b), (&mut *row1, bc, b, c)] {
+                    row.extend([
+                        JacobianVar {
+                            id: center.id_x(),
+                            partial_derivative: pds.d_o.x,
+                        },
+                        JacobianVar {
+                            id: center.id_y(),
+                            partial_derivative: pds.d_o.y,
+                        },
+                        JacobianVar {
+                            id: p.id_x(),
+                            partial_derivative: pds.d_a.x,
+                        },
+                        JacobianVar {
+                            id: p.id_y(),
+                            partial_derivative: pds.d_a.y,
+                        },
+                        JacobianVar {
+                            id: q.id_x(),
+                            partial_derivative: pds.d_b.x,
+                        },
+                        JacobianVar {
+                            id: q.id_y(),
+                            partial_derivative: pds.d_b.y,
+                        },
+                    ]);
+                }
+            }
+            Constraint::ArcSagitta(circular_arc, _sagitta) => {
+                let [c, a, b] = [circular_arc.center, circular_arc.start, circular_arc.end];
+                let [vc, va, vb] = [c, a, b].map(|p| {
+                    V::new(
+                        current_assignments[layout.index_of(p.id_x())],
+                        current_assignments[layout.index_of(p.id_y())],
+                    )
+                });
+                let Some(pds) = arc_sagitta(vc, va, vb) else {
                     *degenerate = true;
                     return;
                 };
-
                 row0.extend([
                     JacobianVar {
-                        id: id_px,
-                        partial_derivative: pds.dpx[0],
-                    },
-                    JacobianVar {
-                        id: id_py,
-                        partial_derivative: pds.dpy[0],
-                    },
-                    JacobianVar {
-                        id: id_qx,
-                        partial_derivative: pds.dqx[0],
+                        id: c.id_x(),
+                        partial_derivative: pds.d_center.x,
                     },
                     JacobianVar {
-                        id: id_qy,
-                        partial_derivative: pds.dqy[0],
+                        id: c.id_y(),
+                        partial_derivative: pds.d_center.y,
                     },
                     JacobianVar {
-                        id: id_ax,
-                        partial_derivative: pds.dax[0],
+                        id: a.id_x(),
+                        partial_derivative: pds.d_start.x,
                     },
                     JacobianVar {
-                        id: id_ay,
-                        partial_derivative: pds.day[0],
+                        id: a.id_y(),
+                        partial_derivative: pds.d_start.y,
                     },
                     JacobianVar {
-                        id: id_bx,
-                        partial_derivative: pds.dbx[0],
+                        id: b.id_x(),
+                        partial_derivative: pds.d_end.x,
                     },
                     JacobianVar {
-                        id: id_by,
-                        partial_derivative: pds.dby[0],
+                        id: b.id_y(),
+                        partial_derivative: pds.d_end.y,
                     },
                 ]);
-                row1.extend([
-                    JacobianVar {
-                        id: id_px,
-                        partial_derivative: pds.dpx[1],
-                    },
-                    JacobianVar {
-                        id: id_py,
-                        partial_derivative: pds.dpy[1],
-                    },
+            }
+            Constraint::ArcSegmentArea(circular_arc, area) => {
+                let [c, a, b] = [circular_arc.center, circular_arc.start, circular_arc.end];
+                let [vc, va, vb] = [c, a, b].map(|p| {
+                    V::new(
+                        current_assignments[layout.index_of(p.id_x())],
+                        current_assignments[layout.index_of(p.id_y())],
+                    )
+                });
+                let Some(pds) = arc_segment_area(vc, va, vb, *area) else {
+                    *degenerate = true;
+                    return;
+                };
+                row0.extend([
                     JacobianVar {
-                        id: id_qx,
-                        partial_derivative: pds.dqx[1],
+                        id: c.id_x(),
+                        partial_derivative: pds.d_center.x,
                     },
                     JacobianVar {
-                        id: id_qy,
-                        partial_derivative: pds.dqy[1],
+                        id: c.id_y(),
+                        partial_derivative: pds.d_center.y,
                     },
                     JacobianVar {
-                        id: id_ax,
-                        partial_derivative: pds.dax[1],
+                        id: a.id_x(),
+                        partial_derivative: pds.d_start.x,
                     },
                     JacobianVar {
-                        id: id_ay,
-                        partial_derivative: pds.day[1],
+                        id: a.id_y(),
+                        partial_derivative: pds.d_start.y,
                     },
                     JacobianVar {
-                        id: id_bx,
-                        partial_derivative: pds.dbx[1],
+                        id: b.id_x(),
+                        partial_derivative: pds.d_end.x,
                     },
                     JacobianVar {
-                        id: id_by,
-                        partial_derivative: pds.dby[1],
+                        id: b.id_y(),
+                        partial_derivative: pds.d_end.y,
                     },
                 ]);
             }
-            Constraint::PointArcCoincident(circular_arc, point) => {
-                let id_cx = circular_arc.center.id_x();
-                let id_cy = circular_arc.center.id_y();
-                let cx = current_assignments[layout.index_of(id_cx)];
-                let cy = current_assignments[layout.index_of(id_cy)];
-                let c = V::new(cx, cy);
-
-                let id_sx = circular_arc.start.id_x();
-                let id_sy = circular_arc.start.id_y();
-                let sx = current_assignments[layout.index_of(id_sx)];
-                let sy = current_assignments[layout.index_of(id_sy)];
-                let s = V::new(sx, sy) - c;
-
-                let id_ex = circular_arc.end.id_x();
-                let id_ey = circular_arc.end.id_y();
-                let ex = current_assignments[layout.index_of(id_ex)];
-                let ey = current_assignments[layout.index_of(id_ey)];
-                let e = V::new(ex, ey) - c;
-
-                let id_px = point.id_x();
-                let id_py = point.id_y();
-                let px = current_assignments[layout.index_of(id_px)];
-                let py = current_assignments[layout.index_of(id_py)];
-                let p = V::new(px, py) - c;
-
-                let r = s.magnitude();
-                let r_e = e.magnitude();
-                let r_p = p.magnitude();
-                if r < EPSILON || r_e < EPSILON || r_p < EPSILON {
+            Constraint::EqualLengthToCommon(common, a, b) => {
+                equal_length_to_common_arc(*common, *a, *b).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+            }
+            Constraint::AnchorNear(point, anchor, _radius) => {
+                // Residual: R = |p - anchor| - radius, an inequality (so it's clamped to >= 0 by the solver).
+                let x = current_assignments[layout.index_of(point.id_x())];
+                let y = current_assignments[layout.index_of(point.id_y())];
+                let dist = V::new(x, y).euclidean_distance(V::new(anchor.x, anchor.y));
+                if dist < EPSILON {
                     *degenerate = true;
                     return;
                 }
-
-                let u_s = s * r.recip();
-                let u_e = e * r_e.recip();
-                let e_proj = e * (r / r_e);
-
-                let (j_s, j_e, j_p) = match classify_point_arc_coincident(s, e_proj, p) {
-                    PointArcCoincidentPart::Interior => {
-                        // Point is inside arc
-                        // Residual: f = p * (r/‖p‖ - 1)
-                        // ∂f/∂s = u_p u_sᵀ
-                        // ∂f/∂e = 0
-                        // ∂f/∂p = (r/r_p - 1)I - (r/r_p) u_p u_pᵀ
-                        let u_p = p * r_p.recip();
-                        let r_over_rp = r / r_p;
-                        (
-                            [
-                                [u_p.x * u_s.x, u_p.y * u_s.x],
-                                [u_p.x * u_s.y, u_p.y * u_s.y],
-                            ],
-                            [[0.0, 0.0], [0.0, 0.0]],
-                            [
-                                [
-                                    (r_over_rp - 1.0) - r_over_rp * u_p.x * u_p.x,
-                                    -r_over_rp * u_p.y * u_p.x,
-                                ],
-                                [
-                                    -r_over_rp * u_p.x * u_p.y,
-                                    (r_over_rp - 1.0) - r_over_rp * u_p.y * u_p.y,
-                                ],
-                            ],
-                        )
-                    }
-                    PointArcCoincidentPart::End => {
-                        // Point is closer to arc end
-                        // Residual: f = (r/r_e) * e - p
-                        // ∂f/∂s = u_e u_sᵀ
-                        // ∂f/∂e = (r/r_e)(I - u_e u_eᵀ)
-                        // ∂f/∂p = -I
-                        let r_over_re = r / r_e;
-                        (
-                            [
-                                [u_e.x * u_s.x, u_e.y * u_s.x],
-                                [u_e.x * u_s.y, u_e.y * u_s.y],
-                            ],
-                            [
-                                [
-                                    r_over_re * (1.0 - u_e.x * u_e.x),
-                                    -r_over_re * u_e.y * u_e.x,
-                                ],
-                                [
-                                    -r_over_re * u_e.x * u_e.y,
-                                    r_over_re * (1.0 - u_e.y * u_e.y),
-                                ],
-                            ],
-                            [[-1.0, 0.0], [0.0, -1.0]],
-                        )
-                    }
-                    PointArcCoincidentPart::Start => {
-                        // Point is closer to arc start
-                        // Residual: f = s - p
-                        // ∂f/∂s = I
-                        // ∂f/∂e = 0
-                        // ∂f/∂p = -I
-                        (
-                            [[1.0, 0.0], [0.0, 1.0]],
-                            [[0.0, 0.0], [0.0, 0.0]],
-                            [[-1.0, 0.0], [0.0, -1.0]],
-                        )
-                    }
-                };
-
-                // ∂f/∂c = -(∂f/∂s + ∂f/∂e + ∂f/∂p)
-                let j_o = [
-                    [
-                        -(j_s[0][0] + j_e[0][0] + j_p[0][0]),
-                        -(j_s[0][1] + j_e[0][1] + j_p[0][1]),
-                    ],
-                    [
-                        -(j_s[1][0] + j_e[1][0] + j_p[1][0]),
-                        -(j_s[1][1] + j_e[1][1] + j_p[1][1]),
-                    ],
-                ];
-
                 row0.extend([
                     JacobianVar {
-                        id: id_cx,
-                        partial_derivative: j_o[0][0],
+                        id: point.id_x(),
+                        partial_derivative: (x - anchor.x) / dist,
                     },
                     JacobianVar {
-                        id: id_cy,
-                        partial_derivative: j_o[1][0],
+                        id: point.id_y(),
+                        partial_derivative: (y - anchor.y) / dist,
                     },
+                ]);
+            }
+            Constraint::OnGrid(point, spacing, _origin) => {
+                // Sub-gradient: the nearest grid line is held constant,
+                // so each row is just that axis's coordinate.
+                if spacing.abs() < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                row0.extend([JacobianVar {
+                    id: point.id_x(),
+                    partial_derivative: 1.0,
+                }]);
+                row1.extend([JacobianVar {
+                    id: point.id_y(),
+                    partial_derivative: 1.0,
+                }]);
+            }
+            Constraint::DistanceSum(p, f1, f2, _sum) => {
+                // Each distance's partials are the unit vector from its focus to p,
+                // which is undefined when p is on that focus.
+                let pv = point_vector(current_assignments, *p, layout);
+                let u1 = pv - point_vector(current_assignments, *f1, layout);
+                let u2 = pv - point_vector(current_assignments, *f2, layout);
+                let (d1, d2) = (u1.magnitude(), u2.magnitude());
+                if d1 < EPSILON || d2 < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let u1 = u1 * (1.0 / d1);
+                let u2 = u2 * (1.0 / d2);
+                row0.extend([
                     JacobianVar {
-                        id: id_sx,
-                        partial_derivative: j_s[0][0],
+                        id: p.id_x(),
+                        partial_derivative: u1.x + u2.x,
                     },
                     JacobianVar {
-                        id: id_sy,
-                        partial_derivative: j_s[1][0],
+                        id: p.id_y(),
+                        partial_derivative: u1.y + u2.y,
                     },
                     JacobianVar {
-                        id: id_ex,
-                        partial_derivative: j_e[0][0],
+                        id: f1.id_x(),
+                        partial_derivative: -u1.x,
                     },
                     JacobianVar {
-                        id: id_ey,
-                        partial_derivative: j_e[1][0],
+                        id: f1.id_y(),
+                        partial_derivative: -u1.y,
                     },
                     JacobianVar {
-                        id: id_px,
-                        partial_derivative: j_p[0][0],
+                        id: f2.id_x(),
+                        partial_derivative: -u2.x,
                     },
                     JacobianVar {
-                        id: id_py,
-                        partial_derivative: j_p[1][0],
+                        id: f2.id_y(),
+                        partial_derivative: -u2.y,
                     },
                 ]);
-                row1.extend([
-                    JacobianVar {
-                        id: id_cx,
-                        partial_derivative: j_o[0][1],
-                    },
-                    JacobianVar {
-                        id: id_cy,
-                        partial_derivative: j_o[1][1],
-                    },
+            }
+            Constraint::FilletRadius(line0, line1, circle) => {
+                // Each row is `PointLineDistance`'s partials, flipped by the side of the line
+                // the center is on to differentiate through the absolute value.
+                let center = point_vector(current_assignments, circle.center, layout);
+                for (line, row) in [(line0, row0), (line1, row1)] {
+                    let (a, b, c) = equation_of_line(current_assignments, line, layout);
+                    let denominator = libm::hypot(a, b);
+                    if denominator < EPSILON {
+                        *degenerate = true;
+                        return;
+                    }
+                    let side_sign = (a * center.x + b * center.y + c).signum();
+                    let partial_derivatives = pds_for_point_line(
+                        circle.center,
+                        line,
+                        PointLineVars {
+                            px: center.x,
+                            py: center.y,
+                            p0x: current_assignments[layout.index_of(line.p0.id_x())],
+                            p0y: current_assignments[layout.index_of(line.p0.id_y())],
+                            p1x: current_assignments[layout.index_of(line.p1.id_x())],
+                            p1y: current_assignments[layout.index_of(line.p1.id_y())],
+                        },
+                    );
+                    row.extend(partial_derivatives.map(|jvar| JacobianVar {
+                        id: jvar.id,
+                        partial_derivative: side_sign * jvar.partial_derivative,
+                    }));
+                    row.extend([JacobianVar {
+                        id: circle.radius.id,
+                        partial_derivative: -1.0,
+                    }]);
+                }
+            }
+            Constraint::PointOnCircle(point, circle) => {
+                // The distance partials, plus ∂R/∂r = -1.
+                // `DistanceVar` already reports the point being on the center as degenerate.
+                Constraint::DistanceVar(circle.center, *point, circle.radius).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+            }
+            Constraint::PointOnLine(point, line) => {
+                Constraint::PointLineDistance(*point, *line, 0.0).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+            }
+            Constraint::LineCircleIntersection(point, line, circle) => {
+                // Each row is its own constraint's partials.
+                // `DistanceVar` already reports the point being on the center as degenerate.
+                let (on_line, on_circle) = line_circle_intersection_parts(*point, line, circle);
+                on_line.jacobian_rows(layout, current_assignments, row0, row1, row2, degenerate);
+                on_circle.jacobian_rows(layout, current_assignments, row1, row0, row2, degenerate);
+                if current_assignments[layout.index_of(circle.radius.id)].abs() <= EPSILON {
+                    *degenerate = true;
+                }
+            }
+            Constraint::Collinear(a, b, c) => {
+                // With u = b - a and v = c - a, R = u.x·v.y - u.y·v.x, so
+                // ∂R/∂b = (v.y, -v.x), ∂R/∂c = (-u.y, u.x) and ∂R/∂a = -(∂R/∂b + ∂R/∂c).
+                let va = point_vector(current_assignments, *a, layout);
+                let u = point_vector(current_assignments, *b, layout) - va;
+                let v = point_vector(current_assignments, *c, layout) - va;
+                row0.extend([
                     JacobianVar {
-                        id: id_sx,
-                        partial_derivative: j_s[0][1],
+                        id: a.id_x(),
+                        partial_derivative: u.y - v.y,
                     },
                     JacobianVar {
-                        id: id_sy,
-                        partial_derivative: j_s[1][1],
+                        id: a.id_y(),
+                        partial_derivative: v.x - u.x,
                     },
                     JacobianVar {
-                        id: id_ex,
-                        partial_derivative: j_e[0][1],
+                        id: b.id_x(),
+                        partial_derivative: v.y,
                     },
                     JacobianVar {
-                        id: id_ey,
-                        partial_derivative: j_e[1][1],
+                        id: b.id_y(),
+                        partial_derivative: -v.x,
                     },
                     JacobianVar {
-                        id: id_px,
-                        partial_derivative: j_p[0][1],
+                        id: c.id_x(),
+                        partial_derivative: -u.y,
                     },
                     JacobianVar {
-                        id: id_py,
-                        partial_derivative: j_p[1][1],
+                        id: c.id_y(),
+                        partial_derivative: u.x,
                     },
                 ]);
             }
-            Constraint::ArcLength(circular_arc, d) => {
-                let id_cx = circular_arc.center.id_x();
-                let id_cy = circular_arc.center.id_y();
-                let id_ax = circular_arc.start.id_x();
-                let id_ay = circular_arc.start.id_y();
-                let id_bx = circular_arc.end.id_x();
-                let id_by = circular_arc.end.id_y();
-                let cx = current_assignments[layout.index_of(id_cx)];
-                let cy = current_assignments[layout.index_of(id_cy)];
-                let ax = current_assignments[layout.index_of(id_ax)];
-                let ay = current_assignments[layout.index_of(id_ay)];
-
-                let ux = ax - cx;
-                let uy = ay - cy;
-                let r2 = ux * ux + uy * uy;
-                if r2 <= EPSILON * EPSILON {
+            Constraint::CommonTangent(line, circle0, circle1, kind) => {
+                // Both rows are `PointLineDistance`'s partials, flipped by the side of the line
+                // the first center is on, just like `FilletRadius`.
+                let (a, b, c) = equation_of_line(current_assignments, line, layout);
+                let denominator = libm::hypot(a, b);
+                if denominator < EPSILON {
                     *degenerate = true;
                     return;
                 }
-
-                // Partials of res = (b - c) - R(α)·(a - c) with α = d / r, r = |a - c|.
-                // Let Ru = R(α)·u; rotating Ru by dα yields (-Ruy, Rux), and
-                // ∂α/∂u = -d·u / r³. The shared factor below is k = d / r³.
-                let r = r2.sqrt();
-                let alpha = d / r;
-                let sa = libm::sin(alpha);
-                let ca = libm::cos(alpha);
-                let rux = ca * ux - sa * uy;
-                let ruy = sa * ux + ca * uy;
-                let k = d / (r2 * r);
-
-                // res0 = (bx - cx) - rux
-                let r0dax = -ca - ruy * ux * k;
-                let r0day = sa - ruy * uy * k;
-                let r0dbx = 1.0;
-                let r0dby = 0.0;
-                let r0dcx = -1.0 + ca + ruy * ux * k;
-                let r0dcy = -sa + ruy * uy * k;
-
-                // res1 = (by - cy) - ruy
-                let r1dax = -sa + rux * ux * k;
-                let r1day = -ca + rux * uy * k;
-                let r1dbx = 0.0;
-                let r1dby = 1.0;
-                let r1dcx = sa - rux * ux * k;
-                let r1dcy = -1.0 + ca - rux * uy * k;
-
+                let center0 = point_vector(current_assignments, circle0.center, layout);
+                let side_sign = (a * center0.x + b * center0.y + c).signum();
+                for (circle, row, dr_dr) in [(circle0, row0, -1.0), (circle1, row1, -kind.sign())] {
+                    let center = point_vector(current_assignments, circle.center, layout);
+                    let partial_derivatives = pds_for_point_line(
+                        circle.center,
+                        line,
+                        PointLineVars {
+                            px: center.x,
+                            py: center.y,
+                            p0x: current_assignments[layout.index_of(line.p0.id_x())],
+                            p0y: current_assignments[layout.index_of(line.p0.id_y())],
+                            p1x: current_assignments[layout.index_of(line.p1.id_x())],
+                            p1y: current_assignments[layout.index_of(line.p1.id_y())],
+                        },
+                    );
+                    row.extend(partial_derivatives.map(|jvar| JacobianVar {
+                        id: jvar.id,
+                        partial_derivative: side_sign * jvar.partial_derivative,
+                    }));
+                    row.extend([JacobianVar {
+                        id: circle.radius.id,
+                        partial_derivative: dr_dr,
+                    }]);
+                }
+            }
+            Constraint::ArcMidpoint(circular_arc, target) => {
+                let [c, a, b] = [circular_arc.center, circular_arc.start, circular_arc.end]
+                    .map(|p| point_vector(current_assignments, p, layout));
+                let Some(pds) = arc_midpoint(c, a, b) else {
+                    *degenerate = true;
+                    return;
+                };
+                // Row 0 is the midpoint's X component, and row 1 is its Y component.
+                let points = [
+                    (circular_arc.center, pds.d_center),
+                    (circular_arc.start, pds.d_start),
+                    (circular_arc.end, pds.d_end),
+                ];
+                for (point, [d_x, d_y]) in points {
+                    row0.extend([
+                        JacobianVar {
+                            id: point.id_x(),
+                            partial_derivative: d_x.x,
+                        },
+                        JacobianVar {
+                            id: point.id_y(),
+                            partial_derivative: d_y.x,
+                        },
+                    ]);
+                    row1.extend([
+                        JacobianVar {
+                            id: point.id_x(),
+                            partial_derivative: d_x.y,
+                        },
+                        JacobianVar {
+                            id: point.id_y(),
+                            partial_derivative: d_y.y,
+                        },
+                    ]);
+                }
+                row0.push(JacobianVar {
+                    id: target.id_x(),
+                    partial_derivative: -1.0,
+                });
+                row1.push(JacobianVar {
+                    id: target.id_y(),
+                    partial_derivative: -1.0,
+                });
+            }
+            Constraint::LineAngle(line, angle) => {
+                let u = line_vector(current_assignments, line, layout);
+                let len = u.magnitude();
+                if len <= EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                // R = |u|·(atan2(u) - θ) where u = p1 - p0, so
+                // ∂R/∂p1 = (atan2(u) - θ)·u / |u| + |u|·perp_ccw(u) / |u|²
+                // and ∂R/∂p0 = -∂R/∂p1.
+                let delta = wrap_angle_delta(libm::atan2(u.y, u.x) - angle.to_radians());
+                let dr = (u * delta + u.perp_ccw()) * len.recip();
                 row0.extend([
                     JacobianVar {
-                        id: id_ax,
-                        partial_derivative: r0dax,
+                        id: line.p0.id_x(),
+                        partial_derivative: -dr.x,
                     },
                     JacobianVar {
-                        id: id_ay,
-                        partial_derivative: r0day,
+                        id: line.p0.id_y(),
+                        partial_derivative: -dr.y,
                     },
                     JacobianVar {
-                        id: id_bx,
-                        partial_derivative: r0dbx,
+                        id: line.p1.id_x(),
+                        partial_derivative: dr.x,
                     },
                     JacobianVar {
-                        id: id_by,
-                        partial_derivative: r0dby,
+                        id: line.p1.id_y(),
+                        partial_derivative: dr.y,
                     },
+                ]);
+            }
+            Constraint::EqualAngle(a, b, c, d) => {
+                let lines = [a, b, c, d];
+                let Some(pds) =
+                    equal_angle(lines.map(|line| line_vector(current_assignments, line, layout)))
+                else {
+                    *degenerate = true;
+                    return;
+                };
+                // Each line's vector is p1 - p0, so p0's partials are the negation of p1's.
+                for (line, d) in lines.into_iter().zip(pds.d_lines) {
+                    row0.extend([
+                        JacobianVar {
+                            id: line.p0.id_x(),
+                            partial_derivative: -d.x,
+                        },
+                        JacobianVar {
+                            id: line.p0.id_y(),
+                            partial_derivative: -d.y,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_x(),
+                            partial_derivative: d.x,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_y(),
+                            partial_derivative: d.y,
+                        },
+                    ]);
+                }
+            }
+            Constraint::LineArcTangent(line, circular_arc, end) => {
+                // R = r · u where r = end - center and u = p1 - p0, so
+                // ∂R/∂end = u, ∂R/∂center = -u, ∂R/∂p1 = r and ∂R/∂p0 = -r.
+                let end = end.of(circular_arc);
+                let radius = point_vector(current_assignments, end, layout)
+                    - point_vector(current_assignments, circular_arc.center, layout);
+                let u = line_vector(current_assignments, line, layout);
+                row0.extend([
                     JacobianVar {
-                        id: id_cx,
-                        partial_derivative: r0dcx,
+                        id: end.id_x(),
+                        partial_derivative: u.x,
                     },
                     JacobianVar {
-                        id: id_cy,
-                        partial_derivative: r0dcy,
+                        id: end.id_y(),
+                        partial_derivative: u.y,
                     },
-                ]);
-                row1.extend([
                     JacobianVar {
-                        id: id_ax,
-                        partial_derivative: r1dax,
+                        id: circular_arc.center.id_x(),
+                        partial_derivative: -u.x,
                     },
                     JacobianVar {
-                        id: id_ay,
-                        partial_derivative: r1day,
+                        id: circular_arc.center.id_y(),
+                        partial_derivative: -u.y,
                     },
                     JacobianVar {
-                        id: id_bx,
-                        partial_derivative: r1dbx,
+                        id: line.p1.id_x(),
+                        partial_derivative: radius.x,
                     },
                     JacobianVar {
-                        id: id_by,
-                        partial_derivative: r1dby,
+                        id: line.p1.id_y(),
+                        partial_derivative: radius.y,
                     },
                     JacobianVar {
-                        id: id_cx,
-                        partial_derivative: r1dcx,
+                        id: line.p0.id_x(),
+                        partial_derivative: -radius.x,
                     },
                     JacobianVar {
-                        id: id_cy,
-                        partial_derivative: r1dcy,
+                        id: line.p0.id_y(),
+                        partial_derivative: -radius.y,
                     },
                 ]);
             }
-            Constraint::ArcAngle(circular_arc, angle) => Constraint::LinesAtAngle(
-                DatumLineSegment {
-                    p0: circular_arc.center,
-                    p1: circular_arc.start,
-                },
-                DatumLineSegment {
-                    p0: circular_arc.center,
-                    p1: circular_arc.end,
-                },
-                AngleKind::Other(*angle),
-            )
-            .jacobian_rows(layout, current_assignments, row0, row1, _row2, degenerate),
-            Constraint::PointsAtAngle(p0, p1, p2, expected_angle) => {
-                let p0v = V::new(
-                    current_assignments[layout.index_of(p0.id_x())],
-                    current_assignments[layout.index_of(p0.id_y())],
-                );
-                let p1v = V::new(
-                    current_assignments[layout.index_of(p1.id_x())],
-                    current_assignments[layout.index_of(p1.id_y())],
-                );
-                let p2v = V::new(
-                    current_assignments[layout.index_of(p2.id_x())],
-                    current_assignments[layout.index_of(p2.id_y())],
+            Constraint::ArcChordAngle(circular_arc, angle) => {
+                let chord = DatumLineSegment::new(circular_arc.start, circular_arc.end);
+                Constraint::LineAngle(chord, *angle).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
                 );
-
-                let u = p1v - p0v;
-                let v = p2v - p0v;
-                let len_u = u.magnitude();
-                let len_v = v.magnitude();
-
-                if len_u <= EPSILON || len_v <= EPSILON {
+            }
+            Constraint::ReverseTangentArcs(arc0, arc1) => {
+                // R0 = ra × rb, so ∂R0/∂ra = (rb.y, -rb.x) and ∂R0/∂rb = (-ra.y, ra.x).
+                // R1 = ra · rb, so ∂R1/∂ra = rb and ∂R1/∂rb = ra.
+                // Each radius is end - center, so the center's partials are negated.
+                let ra = point_vector(current_assignments, arc0.end, layout)
+                    - point_vector(current_assignments, arc0.center, layout);
+                let rb = point_vector(current_assignments, arc1.end, layout)
+                    - point_vector(current_assignments, arc1.center, layout);
+                let radius_partials = [
+                    (arc0, V::new(rb.y, -rb.x), rb),
+                    (arc1, V::new(-ra.y, ra.x), ra),
+                ];
+                for (arc, cross_partial, dot_partial) in radius_partials {
+                    row0.extend([
+                        JacobianVar {
+                            id: arc.end.id_x(),
+                            partial_derivative: cross_partial.x,
+                        },
+                        JacobianVar {
+                            id: arc.end.id_y(),
+                            partial_derivative: cross_partial.y,
+                        },
+                        JacobianVar {
+                            id: arc.center.id_x(),
+                            partial_derivative: -cross_partial.x,
+                        },
+                        JacobianVar {
+                            id: arc.center.id_y(),
+                            partial_derivative: -cross_partial.y,
+                        },
+                    ]);
+                    row1.extend([
+                        JacobianVar {
+                            id: arc.end.id_x(),
+                            partial_derivative: dot_partial.x,
+                        },
+                        JacobianVar {
+                            id: arc.end.id_y(),
+                            partial_derivative: dot_partial.y,
+                        },
+                        JacobianVar {
+                            id: arc.center.id_x(),
+                            partial_derivative: -dot_partial.x,
+                        },
+                        JacobianVar {
+                            id: arc.center.id_y(),
+                            partial_derivative: -dot_partial.y,
+                        },
+                    ]);
+                }
+            }
+            Constraint::DistanceLinearCombo(result, terms, _constant) => {
+                // Residual equation R: result - (Σ cᵢ * termᵢ + constant) = 0
+                // dR/dresult: 1
+                // dR/dtermᵢ: -cᵢ
+                row0.push(JacobianVar {
+                    id: *result,
+                    partial_derivative: 1.0,
+                });
+                row0.extend(terms.iter().map(|(id, coefficient)| JacobianVar {
+                    id: *id,
+                    partial_derivative: -coefficient,
+                }));
+            }
+            Constraint::AngleBisectorLine(bisector, a, b) => {
+                let Some(pds) = angle_bisector(
+                    line_vector(current_assignments, bisector, layout),
+                    line_vector(current_assignments, a, layout),
+                    line_vector(current_assignments, b, layout),
+                ) else {
+                    *degenerate = true;
+                    return;
+                };
+                // Each line's vector is p1 - p0, so p0's partials are the negation of p1's.
+                for (line, d) in [(bisector, pds.d_bisector), (a, pds.d_a), (b, pds.d_b)] {
+                    row0.extend([
+                        JacobianVar {
+                            id: line.p0.id_x(),
+                            partial_derivative: -d.x,
+                        },
+                        JacobianVar {
+                            id: line.p0.id_y(),
+                            partial_derivative: -d.y,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_x(),
+                            partial_derivative: d.x,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_y(),
+                            partial_derivative: d.y,
+                        },
+                    ]);
+                }
+            }
+            Constraint::SymmetricAngle(reference, a, b) => {
+                let Some(pds) = symmetric_angle(
+                    line_vector(current_assignments, reference, layout),
+                    line_vector(current_assignments, a, layout),
+                    line_vector(current_assignments, b, layout),
+                ) else {
                     *degenerate = true;
                     return;
+                };
+                // Each line's vector is p1 - p0, so p0's partials are the negation of p1's.
+                for (line, d) in [(reference, pds.d_bisector), (a, pds.d_a), (b, pds.d_b)] {
+                    row0.extend([
+                        JacobianVar {
+                            id: line.p0.id_x(),
+                            partial_derivative: -d.x,
+                        },
+                        JacobianVar {
+                            id: line.p0.id_y(),
+                            partial_derivative: -d.y,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_x(),
+                            partial_derivative: d.x,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_y(),
+                            partial_derivative: d.y,
+                        },
+                    ]);
                 }
-
-                let inv_len_u = 1.0 / len_u;
-                let inv_len_v = 1.0 / len_v;
-                let u_hat = u * inv_len_u;
-                let v_hat = v * inv_len_v;
-
-                let rot = rotation_for_angle_kind(*expected_angle);
-                let s = (len_u + len_v) * 0.5;
-
-                // Columns of R: R*e1 = (ca, sa), R*e2 = (-sa, ca)
-                let rot_e1 = rot.apply(V::new(1.0, 0.0));
-                let rot_e2 = rot.apply(V::new(0.0, 1.0));
-
-                /*
-                    Residual
-
-                        r = a / s
-                        a := (|u| v - |v| R u)
-                        s := (|u| + |v|) / 2
-
-                    Differentiate in u and v (via quotient/chain rule)
-
-                        ∂a/∂u = v ûᵀ - |v| R
-                        ∂r/∂u = (∂a/∂u - r (∂s/∂u)ᵀ) / s = ((v - r/2) ûᵀ - |v| R) / s
-
-                        ∂a/∂v = |u| I - (R u) v̂ᵀ
-                        ∂r/∂v = (∂a/∂v - r (∂s/∂v)ᵀ) / s = (|u| I - (R u + r/2) v̂ᵀ) / s
-                */
-                let inv_s = 1.0 / s;
-                let rot_u = rot.apply(u);
-                let res = (v * len_u - rot_u * len_v) * inv_s;
-                let half_res = res * 0.5;
-                let dr_du0 = ((v - half_res) * u_hat.x - rot_e1 * len_v) * inv_s;
-                let dr_du1 = ((v - half_res) * u_hat.y - rot_e2 * len_v) * inv_s;
-                let dr_dv0 = (V::new(len_u, 0.0) - (rot_u + half_res) * v_hat.x) * inv_s;
-                let dr_dv1 = (V::new(0.0, len_u) - (rot_u + half_res) * v_hat.y) * inv_s;
-
-                // ∂r/∂p0 = -(∂r/∂u + ∂r/∂v)
-                // ∂r/∂p1 = ∂r/∂u
-                // ∂r/∂p2 = ∂r/∂v
+            }
+            Constraint::RadiusRatio(circle, scalar, ratio) => {
+                // Residual: R = r - k·s
+                // ∂R/∂r = 1
+                // ∂R/∂s = -k
                 row0.extend([
                     JacobianVar {
-                        id: p0.id_x(),
-                        partial_derivative: -(dr_du0.x + dr_dv0.x),
+                        id: circle.radius.id,
+                        partial_derivative: 1.0,
                     },
                     JacobianVar {
-                        id: p0.id_y(),
-                        partial_derivative: -(dr_du1.x + dr_dv1.x),
+                        id: *scalar,
+                        partial_derivative: -ratio,
+                    },
+                ]);
+            }
+            Constraint::EquidistantLinePoint(point, directrix, focus) => {
+                // Residual: R = |distance from p to the directrix| - |p - focus|
+                // So the partials are the (signed) point-line distance partials,
+                // flipped to match the absolute value, minus the point-point distance partials.
+                let px = current_assignments[layout.index_of(point.id_x())];
+                let py = current_assignments[layout.index_of(point.id_y())];
+                let p0x = current_assignments[layout.index_of(directrix.p0.id_x())];
+                let p0y = current_assignments[layout.index_of(directrix.p0.id_y())];
+                let p1x = current_assignments[layout.index_of(directrix.p1.id_x())];
+                let p1y = current_assignments[layout.index_of(directrix.p1.id_y())];
+                let fx = current_assignments[layout.index_of(focus.id_x())];
+                let fy = current_assignments[layout.index_of(focus.id_y())];
+                let (a, b, c) = inner_equation_of_line(p0x, p0y, p1x, p1y);
+                let focus_distance = libm::hypot(px - fx, py - fy);
+                if libm::hypot(a, b) < EPSILON || focus_distance < EPSILON {
+                    *degenerate = true;
+                    return;
+                }
+                let sign = (a * px + b * py + c).signum();
+                let line_pds = pds_for_point_line(
+                    *point,
+                    directrix,
+                    PointLineVars {
+                        px,
+                        py,
+                        p0x,
+                        p0y,
+                        p1x,
+                        p1y,
                     },
+                );
+                row0.extend(line_pds.map(|jvar| JacobianVar {
+                    id: jvar.id,
+                    partial_derivative: sign * jvar.partial_derivative,
+                }));
+                let dx = (px - fx) / focus_distance;
+                let dy = (py - fy) / focus_distance;
+                row0.extend([
                     JacobianVar {
-                        id: p1.id_x(),
-                        partial_derivative: dr_du0.x,
+                        id: point.id_x(),
+                        partial_derivative: -dx,
                     },
                     JacobianVar {
-                        id: p1.id_y(),
-                        partial_derivative: dr_du1.x,
+                        id: point.id_y(),
+                        partial_derivative: -dy,
                     },
                     JacobianVar {
-                        id: p2.id_x(),
-                        partial_derivative: dr_dv0.x,
+                        id: focus.id_x(),
+                        partial_derivative: dx,
                     },
                     JacobianVar {
-                        id: p2.id_y(),
-                        partial_derivative: dr_dv1.x,
+                        id: focus.id_y(),
+                        partial_derivative: dy,
                     },
                 ]);
+            }
+            Constraint::TangentAtAngle(line, circle, angle) => {
+                Constraint::LineTangentToCircle(*line, *circle, LineSide::Left).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+                if *degenerate {
+                    return;
+                }
+                // R1 = |u|·(atan2(u) - θ) where u = p1 - p0, so
+                // ∂R1/∂p1 = (atan2(u) - θ)·u / |u| + |u|·perp_ccw(u) / |u|²
+                // and ∂R1/∂p0 = -∂R1/∂p1.
+                let u = line_vector(current_assignments, line, layout);
+                let len = u.magnitude();
+                let delta = wrap_angle_delta(libm::atan2(u.y, u.x) - angle.to_radians());
+                let dr1 = (u * delta + u.perp_ccw()) * len.recip();
                 row1.extend([
                     JacobianVar {
-                        id: p0.id_x(),
-                        partial_derivative: -(dr_du0.y + dr_dv0.y),
-                    },
-                    JacobianVar {
-                        id: p0.id_y(),
-                        partial_derivative: -(dr_du1.y + dr_dv1.y),
-                    },
-                    JacobianVar {
-                        id: p1.id_x(),
-                        partial_derivative: dr_du0.y,
+                        id: line.p0.id_x(),
+                        partial_derivative: -dr1.x,
                     },
                     JacobianVar {
-                        id: p1.id_y(),
-                        partial_derivative: dr_du1.y,
+                        id: line.p0.id_y(),
+                        partial_derivative: -dr1.y,
                     },
                     JacobianVar {
-                        id: p2.id_x(),
-                        partial_derivative: dr_dv0.y,
+                        id: line.p1.id_x(),
+                        partial_derivative: dr1.x,
                     },
                     JacobianVar {
-                        id: p2.id_y(),
-                        partial_derivative: dr_dv1.y,
+                        id: line.p1.id_y(),
+                        partial_derivative: dr1.y,
                     },
                 ]);
             }
+            Constraint::ParallelOffset(line_a, line_b, _offset) => {
+                Constraint::lines_parallel([*line_a, *line_b]).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+                if *degenerate {
+                    return;
+                }
+                let px = current_assignments[layout.index_of(line_b.p0.id_x())];
+                let py = current_assignments[layout.index_of(line_b.p0.id_y())];
+                let p0x = current_assignments[layout.index_of(line_a.p0.id_x())];
+                let p0y = current_assignments[layout.index_of(line_a.p0.id_y())];
+                let p1x = current_assignments[layout.index_of(line_a.p1.id_x())];
+                let p1y = current_assignments[layout.index_of(line_a.p1.id_y())];
+                row1.extend(pds_for_point_line(
+                    line_b.p0,
+                    line_a,
+                    PointLineVars {
+                        px,
+                        py,
+                        p0x,
+                        p0y,
+                        p1x,
+                        p1y,
+                    },
+                ));
+            }
+            Constraint::ParallelOffsetScalar(line_a, line_b, scalar) => {
+                // The offset is a constant in `ParallelOffset`'s Jacobian, so it doesn't matter.
+                Constraint::ParallelOffset(*line_a, *line_b, 0.0).jacobian_rows(
+                    layout,
+                    current_assignments,
+                    row0,
+                    row1,
+                    row2,
+                    degenerate,
+                );
+                if *degenerate {
+                    return;
+                }
+                // R1 = signed distance - s, so ∂R1/∂s = -1
+                row1.extend([JacobianVar {
+                    id: *scalar,
+                    partial_derivative: -1.0,
+                }]);
+            }
+            Constraint::AngleEqualsScalar(line0, line1, scalar) => {
+                let Some(pds) = oriented_angle_delta(
+                    line_vector(current_assignments, line0, layout),
+                    line_vector(current_assignments, line1, layout),
+                    current_assignments[layout.index_of(*scalar)],
+                ) else {
+                    *degenerate = true;
+                    return;
+                };
+                // Each line's vector is p1 - p0, so p0's partials are the negation of p1's.
+                for (line, d) in [(line0, pds.d_u), (line1, pds.d_v)] {
+                    row0.extend([
+                        JacobianVar {
+                            id: line.p0.id_x(),
+                            partial_derivative: -d.x,
+                        },
+                        JacobianVar {
+                            id: line.p0.id_y(),
+                            partial_derivative: -d.y,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_x(),
+                            partial_derivative: d.x,
+                        },
+                        JacobianVar {
+                            id: line.p1.id_y(),
+                            partial_derivative: d.y,
+                        },
+                    ]);
+                }
+                row0.extend([JacobianVar {
+                    id: *scalar,
+                    partial_derivative: pds.d_scalar,
+                }]);
+            }
+            Constraint::ArcCentralAngleScalar(arc, scalar) => arc_central_angle_scalar_part(
+                arc, *scalar,
+            )
+            .jacobian_rows(layout, current_assignments, row0, row1, row2, degenerate),
+            Constraint::SubtendsAngle(p, a, b, angle) => {
+                let [vp, va, vb] = [p, a, b].map(|point| {
+                    V::new(
+                        current_assignments[layout.index_of(point.id_x())],
+                        current_assignments[layout.index_of(point.id_y())],
+                    )
+                });
+                let Some(pds) = oriented_angle_delta(vb - vp, va - vp, angle.to_radians()) else {
+                    *degenerate = true;
+                    return;
+                };
+                // Both vectors start at p, so p's partials are the negation of the others'.
+                let d_p = (pds.d_u + pds.d_v) * -1.0;
+                for (point, d) in [(p, d_p), (a, pds.d_v), (b, pds.d_u)] {
+                    row0.extend([
+                        JacobianVar {
+                            id: point.id_x(),
+                            partial_derivative: d.x,
+                        },
+                        JacobianVar {
+                            id: point.id_y(),
+                            partial_derivative: d.y,
+                        },
+                    ]);
+                }
+            }
         }
     }
 
@@ -2305,6 +5171,7 @@ impl Constraint {
             Constraint::Vertical(..) => "Vertical",
             Constraint::Horizontal(..) => "Horizontal",
             Constraint::Fixed(..) => "Fixed",
+            Constraint::FixedPoint(..) => "FixedPoint",
             Constraint::LinesAtAngle(..) => "LinesAtAngle",
             Constraint::PointsCoincident(..) => "PointsCoincident",
             Constraint::CircleRadius(..) => "CircleRadius",
@@ -2325,10 +5192,88 @@ impl Constraint {
             Constraint::ArcLength(..) => "ArcLength",
             Constraint::ArcAngle(..) => "ArcAngle",
             Constraint::PointsAtAngle(..) => "PointsAtAngle",
+            Constraint::PolarOffset(..) => "PolarOffset",
+            Constraint::PointOnConic(..) => "PointOnConic",
+            Constraint::ScaledCopy(..) => "ScaledCopy",
+            Constraint::Homothety(..) => "Homothety",
+            Constraint::PointInfiniteLineDistance(..) => "PointInfiniteLineDistance",
+            Constraint::InfiniteLineTangentToCircle(..) => "InfiniteLineTangentToCircle",
+            Constraint::MinDistance(..) => "MinDistance",
+            Constraint::LengthRatioBounds(..) => "LengthRatioBounds",
+            Constraint::SameSideOfLine(..) => "SameSideOfLine",
+            Constraint::ArcCircleEqualRadius(..) => "ArcCircleEqualRadius",
+            Constraint::Concyclic(..) => "Concyclic",
+            Constraint::Circumcenter(..) => "Circumcenter",
+            Constraint::ArcSagitta(..) => "ArcSagitta",
+            Constraint::ArcSegmentArea(..) => "ArcSegmentArea",
+            Constraint::EqualLengthToCommon(..) => "EqualLengthToCommon",
+            Constraint::AnchorNear(..) => "AnchorNear",
+            Constraint::OnGrid(..) => "OnGrid",
+            Constraint::DistanceSum(..) => "DistanceSum",
+            Constraint::FilletRadius(..) => "FilletRadius",
+            Constraint::PointOnCircle(..) => "PointOnCircle",
+            Constraint::PointOnLine(..) => "PointOnLine",
+            Constraint::LineCircleIntersection(..) => "LineCircleIntersection",
+            Constraint::Collinear(..) => "Collinear",
+            Constraint::CommonTangent(..) => "CommonTangent",
+            Constraint::ArcMidpoint(..) => "ArcMidpoint",
+            Constraint::LineAngle(..) => "LineAngle",
+            Constraint::EqualAngle(..) => "EqualAngle",
+            Constraint::LineArcTangent(..) => "LineArcTangent",
+            Constraint::ArcChordAngle(..) => "ArcChordAngle",
+            Constraint::ReverseTangentArcs(..) => "ReverseTangentArcs",
+            Constraint::DistanceLinearCombo(..) => "DistanceLinearCombo",
+            Constraint::AngleBisectorLine(..) => "AngleBisectorLine",
+            Constraint::SymmetricAngle(..) => "SymmetricAngle",
+            Constraint::RadiusRatio(..) => "RadiusRatio",
+            Constraint::EquidistantLinePoint(..) => "EquidistantLinePoint",
+            Constraint::TangentAtAngle(..) => "TangentAtAngle",
+            Constraint::ParallelOffset(..) => "ParallelOffset",
+            Constraint::ParallelOffsetScalar(..) => "ParallelOffsetScalar",
+            Constraint::AngleEqualsScalar(..) => "AngleEqualsScalar",
+            Constraint::SubtendsAngle(..) => "SubtendsAngle",
+            Constraint::ArcCentralAngleScalar(..) => "ArcCentralAngleScalar",
         }
     }
 }
 
+/// Signed distance from the infinite line to the point `p`,
+/// positive on the side the line's normal points to.
+fn signed_distance_to_line(
+    current_assignments: &[f64],
+    line: DatumLine,
+    layout: &Layout,
+    p: V,
+) -> f64 {
+    let angle = current_assignments[layout.index_of(line.angle)];
+    let offset = current_assignments[layout.index_of(line.offset)];
+    let n = V::new(libm::cos(angle), libm::sin(angle));
+    n.dot(p) - offset
+}
+
+/// Partial derivatives of [`signed_distance_to_line`].
+/// The partial derivative with respect to the line's offset is always -1.
+struct PointInfiniteLinePds {
+    /// Partial derivatives with respect to the point's X and Y.
+    dp: V,
+    /// Partial derivative with respect to the line's normal angle.
+    dangle: f64,
+}
+
+fn pds_for_point_infinite_line(
+    current_assignments: &[f64],
+    line: DatumLine,
+    layout: &Layout,
+    p: V,
+) -> PointInfiniteLinePds {
+    let angle = current_assignments[layout.index_of(line.angle)];
+    let (sin, cos) = (libm::sin(angle), libm::cos(angle));
+    PointInfiniteLinePds {
+        dp: V::new(cos, sin),
+        dangle: -p.x * sin + p.y * cos,
+    }
+}
+
 struct PointLineVars {
     px: f64,
     py: f64,
@@ -2568,6 +5513,144 @@ impl PartialDerivatives4Points {
     }
 }
 
+/// The point's position.
+fn point_vector(current_assignments: &[f64], point: DatumPoint, layout: &Layout) -> V {
+    V::new(
+        current_assignments[layout.index_of(point.id_x())],
+        current_assignments[layout.index_of(point.id_y())],
+    )
+}
+
+/// The vector from a line's first point to its second.
+fn line_vector(current_assignments: &[f64], line: &DatumLineSegment, layout: &Layout) -> V {
+    V::new(
+        current_assignments[layout.index_of(line.p1.id_x())]
+            - current_assignments[layout.index_of(line.p0.id_x())],
+        current_assignments[layout.index_of(line.p1.id_y())]
+            - current_assignments[layout.index_of(line.p0.id_y())],
+    )
+}
+
+/// The residual and partial derivatives from [`angle_bisector`],
+/// with respect to each line's vector.
+#[derive(Clone, Copy)]
+struct AngleBisectorPds {
+    residual: f64,
+    d_bisector: V,
+    d_a: V,
+    d_b: V,
+}
+
+/// How far is the line with vector `bisector` from bisecting the angle between `a` and `b`?
+/// The angle from `a` to the bisector minus the angle from the bisector to `b` is
+/// `2θ_bisector - θ_a - θ_b`, wrapped into (-π, π] and scaled by the lines' mean length
+/// so that it's in length units.
+/// Returns `None` if any line has zero length, as its angle is undefined.
+fn angle_bisector(bisector: V, a: V, b: V) -> Option<AngleBisectorPds> {
+    let (len_bisector, len_a, len_b) = (bisector.magnitude(), a.magnitude(), b.magnitude());
+    if len_bisector <= EPSILON || len_a <= EPSILON || len_b <= EPSILON {
+        return None;
+    }
+    let angle = |v: V| libm::atan2(v.y, v.x);
+    let w = wrap_angle_delta(2.0 * angle(bisector) - angle(a) - angle(b));
+    let s = (len_bisector + len_a + len_b) / 3.0;
+    // With R = s·w:
+    // ∂R/∂u = w·∂s/∂u + s·∂w/∂u
+    // ∂s/∂u = û/3
+    // ∂θ/∂u = perp_ccw(u)/|u|², so ∂w/∂bisector is twice that, and ∂w/∂a, ∂w/∂b are negated.
+    let partial = |v: V, len: f64, angle_coefficient: f64| {
+        v * (w / (3.0 * len)) + v.perp_ccw() * (s * angle_coefficient / (len * len))
+    };
+    Some(AngleBisectorPds {
+        residual: s * w,
+        d_bisector: partial(bisector, len_bisector, 2.0),
+        d_a: partial(a, len_a, -1.0),
+        d_b: partial(b, len_b, -1.0),
+    })
+}
+
+/// How far are `a` and `b` from making equal but opposite angles to `reference`?
+/// The residual is `θ_a + θ_b - 2θ_reference` (each angle measured from `reference`),
+/// wrapped into (-π, π] and scaled by the lines' mean length so that it's in length units.
+/// That's exactly the negation of [`angle_bisector`], with `reference` as the bisector.
+/// Returns `None` if any line has zero length, as its angle is undefined.
+fn symmetric_angle(reference: V, a: V, b: V) -> Option<AngleBisectorPds> {
+    let pds = angle_bisector(reference, a, b)?;
+    Some(AngleBisectorPds {
+        residual: -pds.residual,
+        d_bisector: pds.d_bisector * -1.0,
+        d_a: pds.d_a * -1.0,
+        d_b: pds.d_b * -1.0,
+    })
+}
+
+/// The residual and partial derivatives from [`oriented_angle_delta`].
+#[derive(Clone, Copy)]
+struct OrientedAngleDeltaPds {
+    residual: f64,
+    d_u: V,
+    d_v: V,
+    d_scalar: f64,
+}
+
+/// How far is the angle from vector `u` to vector `v` from `target` (in radians)?
+/// The difference is wrapped into (-π, π] and scaled by the lines' mean length
+/// so that it's in length units.
+/// Returns `None` if either vector has zero length, as its angle is undefined.
+fn oriented_angle_delta(u: V, v: V, target: f64) -> Option<OrientedAngleDeltaPds> {
+    let (len_u, len_v) = (u.magnitude(), v.magnitude());
+    if len_u <= EPSILON || len_v <= EPSILON {
+        return None;
+    }
+    let w = wrap_angle_delta(libm::atan2(u.cross_2d(v), u.dot(v)) - target);
+    let s = (len_u + len_v) * 0.5;
+    // With R = s·w:
+    // ∂R/∂u = w·û/2 - s·perp_ccw(u)/|u|²
+    // ∂R/∂v = w·v̂/2 + s·perp_ccw(v)/|v|²
+    // ∂R/∂target = -s
+    Some(OrientedAngleDeltaPds {
+        residual: s * w,
+        d_u: u * (w / (2.0 * len_u)) - u.perp_ccw() * (s / (len_u * len_u)),
+        d_v: v * (w / (2.0 * len_v)) + v.perp_ccw() * (s / (len_v * len_v)),
+        d_scalar: -s,
+    })
+}
+
+/// The residual and partial derivatives from [`equal_angle`].
+#[derive(Clone, Copy)]
+struct EqualAnglePds {
+    residual: f64,
+    /// With respect to each line's vector.
+    d_lines: [V; 4],
+}
+
+/// How far is the angle from vector `a` to `b` from the angle from `c` to `d`?
+/// The difference is wrapped into (-π, π] and scaled by the lines' mean length
+/// so that it's in length units.
+/// Returns `None` if any vector has zero length, as its angle is undefined.
+fn equal_angle(lines: [V; 4]) -> Option<EqualAnglePds> {
+    let lengths = lines.map(V::magnitude);
+    if lengths.iter().any(|&len| len <= EPSILON) {
+        return None;
+    }
+    let [a, b, c, d] = lines;
+    let angle = |u: V, v: V| libm::atan2(u.cross_2d(v), u.dot(v));
+    let w = wrap_angle_delta(angle(a, b) - angle(c, d));
+    let s = lengths.iter().sum::<f64>() / 4.0;
+    // With R = s·w:
+    // ∂R/∂x = w·x̂/4 ± s·perp_ccw(x)/|x|² for each line x,
+    // where the sign is - for a and d, whose angles are subtracted, and + for b and c.
+    let signs = [-1.0, 1.0, 1.0, -1.0];
+    let mut d_lines = lines;
+    for ((d, len), sign) in d_lines.iter_mut().zip(lengths).zip(signs) {
+        *d = *d * (w / (4.0 * len)) + d.perp_ccw() * (sign * s / (len * len));
+    }
+    Some(EqualAnglePds {
+        residual: s * w,
+        d_lines,
+    })
+}
+
 fn get_line_ends(
     current_assignments: &[f64],
     line0: &DatumLineSegment,
@@ -2605,6 +5688,220 @@ fn classify_point_arc_coincident(s: V, e: V, p: V) -> PointArcCoincidentPart {
     }
 }
 
+/// The sagitta and partial derivatives from [`arc_sagitta`].
+#[derive(Clone, Copy)]
+struct SagittaPds {
+    sagitta: f64,
+    d_center: V,
+    d_start: V,
+    d_end: V,
+}
+
+/// Sagitta `r - q` of the arc with this center, start and end, where `r = |start - center|`,
+/// `h` is half the chord `|end - start|`, and `q = sqrt(r² - h²)` is the center's distance
+/// from the chord.
+/// Returns `None` if the radius is zero, or the chord is (nearly) as long as the diameter,
+/// where the sagitta's derivative with respect to the radius blows up.
+fn arc_sagitta(center: V, start: V, end: V) -> Option<SagittaPds> {
+    let u = start - center;
+    let chord = end - start;
+    let r = u.magnitude();
+    let q_sq = u.magnitude_squared() - chord.magnitude_squared() / 4.0;
+    if r < EPSILON || q_sq < EPSILON * EPSILON {
+        return None;
+    }
+    let q = libm::sqrt(q_sq);
+    // ∂s/∂r = 1 - r/q, and ∂r/∂start = u/r.
+    // ∂s/∂h = h/q, and ∂h/∂end = chord/4h, so the h terms cancel and
+    // ∂s/∂end = chord/4q, even for a zero-length chord.
+    let d_radius = u * ((1.0 - r / q) / r);
+    let d_end = chord * (0.25 / q);
+    Some(SagittaPds {
+        sagitta: r - q,
+        d_center: d_radius * -1.0,
+        d_start: d_radius - d_end,
+        d_end,
+    })
+}
+
+/// The midpoint and partial derivatives from [`arc_midpoint`].
+/// Each partial is with respect to a point's X then Y component,
+/// and is a vector of the midpoint's X and Y partials.
+#[derive(Clone, Copy)]
+struct ArcMidpointPds {
+    midpoint: V,
+    d_center: [V; 2],
+    d_start: [V; 2],
+    d_end: [V; 2],
+}
+
+/// The point halfway (in angle) along the arc with this center, start and end,
+/// going counterclockwise from start to end, on the circle of radius `r = |start - center|`.
+/// With û and v̂ the directions from the center to the start and end, the midpoint's
+/// direction is `m̂ = perp_cw(v̂ - û) / |v̂ - û|`, which is the angle bisector of û and v̂
+/// for sweeps up to π and its opposite beyond, and still defined for a sweep of exactly π.
+/// Returns `None` if the radius is zero, or the start and end are in the same direction
+/// from the center, where the sweep could be 0 or a full turn.
+fn arc_midpoint(center: V, start: V, end: V) -> Option<ArcMidpointPds> {
+    let u = start - center;
+    let v = end - center;
+    let (r, len_v) = (u.magnitude(), v.magnitude());
+    if r < EPSILON || len_v < EPSILON {
+        return None;
+    }
+    let (u_hat, v_hat) = (u * (1.0 / r), v * (1.0 / len_v));
+    let w = (v_hat - u_hat).perp_cw();
+    let len_w = w.magnitude();
+    if len_w < EPSILON {
+        return None;
+    }
+    let m_hat = w * (1.0 / len_w);
+    // How a unit vector `x / |x|` changes when `x` moves by `dx`.
+    let d_unit = |unit: V, len: f64, dx: V| (dx - unit * unit.dot(dx)) * (1.0 / len);
+    // With M = c + r·m̂:
+    // ∂M/∂start = m̂·∂r/∂start + r·∂m̂/∂û·∂û/∂start, where ∂r/∂start = û,
+    // ∂M/∂end = r·∂m̂/∂v̂·∂v̂/∂end,
+    // ∂M/∂center = I - ∂M/∂start - ∂M/∂end.
+    let axes = [V::new(1.0, 0.0), V::new(0.0, 1.0)];
+    let d_start = axes.map(|e| {
+        let d_w = d_unit(u_hat, r, e).perp_cw() * -1.0;
+        m_hat * u_hat.dot(e) + d_unit(m_hat, len_w, d_w) * r
+    });
+    let d_end = axes.map(|e| {
+        let d_w = d_unit(v_hat, len_v, e).perp_cw();
+        d_unit(m_hat, len_w, d_w) * r
+    });
+    let d_center = [0, 1].map(|i| axes[i] - d_start[i] - d_end[i]);
+    Some(ArcMidpointPds {
+        midpoint: center + m_hat * r,
+        d_center,
+        d_start,
+        d_end,
+    })
+}
+
+/// The residual and partial derivatives from [`arc_segment_area`].
+#[derive(Clone, Copy)]
+struct SegmentAreaPds {
+    residual: f64,
+    d_center: V,
+    d_start: V,
+    d_end: V,
+}
+
+/// How far is the arc's circular segment from having this area?
+/// With `r = |start - center|` and θ the counterclockwise sweep from start to end,
+/// the area is `r²(θ - sin θ)/2`. The residual is that area's error divided by `r`,
+/// so it's in length units like every other residual.
+/// Returns `None` if the start or end are on the center, where there's no sweep.
+fn arc_segment_area(center: V, start: V, end: V, target: f64) -> Option<SegmentAreaPds> {
+    let u = start - center;
+    let v = end - center;
+    let r = u.magnitude();
+    let v_sq = v.magnitude_squared();
+    if r < EPSILON || v_sq < EPSILON * EPSILON {
+        return None;
+    }
+    let theta = u.signed_angle(v).rem_euclid(2.0 * PI);
+    let (sin, cos) = (libm::sin(theta), libm::cos(theta));
+    // With R = r(θ - sin θ)/2 - target/r:
+    // ∂R/∂r = (θ - sin θ)/2 + target/r², and ∂r/∂start = u/r.
+    // ∂R/∂θ = r(1 - cos θ)/2, and ∂θ/∂start = -perp_ccw(u)/r², ∂θ/∂end = perp_ccw(v)/|v|².
+    // A zero sweep just flattens the θ terms, it doesn't blow up.
+    let d_radius = 0.5 * (theta - sin) + target / (r * r);
+    let d_theta = 0.5 * r * (1.0 - cos);
+    let d_start = u * (d_radius / r) - u.perp_ccw() * (d_theta / (r * r));
+    let d_end = v.perp_ccw() * (d_theta / v_sq);
+    Some(SegmentAreaPds {
+        residual: 0.5 * r * (theta - sin) - target / r,
+        d_center: (d_start + d_end) * -1.0,
+        d_start,
+        d_end,
+    })
+}
+
+/// The residual and partial derivatives from [`bisector_distance`].
+#[derive(Clone, Copy)]
+struct BisectorPds {
+    residual: f64,
+    d_o: V,
+    d_a: V,
+    d_b: V,
+}
+
+/// How far is `o` from being equidistant to `a` and `b`?
+/// This is `(|o - a|² - |o - b|²) / 2|a - b|`, the smooth squared-distance difference scaled
+/// so that it's in length units, which also makes it the signed distance from `o` to the
+/// perpendicular bisector of `ab`.
+/// Returns `None` if `a` and `b` coincide, as every point is equidistant from them.
+fn bisector_distance(o: V, a: V, b: V) -> Option<BisectorPds> {
+    let len = a.euclidean_distance(b);
+    if len < EPSILON {
+        return None;
+    }
+    let n = (o - a).magnitude_squared() - (o - b).magnitude_squared();
+    // With R = N / 2L:
+    // ∂R/∂o = (b - a) / L
+    // ∂R/∂a = -(o - a) / L - N (a - b) / 2L³
+    // ∂R/∂b = (o - b) / L - N (b - a) / 2L³
+    let correction = n / (2.0 * len * len * len);
+    Some(BisectorPds {
+        residual: n / (2.0 * len),
+        d_o: (b - a) * len.recip(),
+        d_a: (a - o) * len.recip() - (a - b) * correction,
+        d_b: (o - b) * len.recip() - (b - a) * correction,
+    })
+}
+
+/// [`Constraint::Concyclic`] is just coincident centers, plus equal center-to-start lengths.
+fn concyclic_parts(arc0: &DatumCircularArc, arc1: &DatumCircularArc) -> (Constraint, Constraint) {
+    (
+        Constraint::PointsCoincident(arc0.center, arc1.center),
+        Constraint::LinesEqualLength(
+            DatumLineSegment::new(arc0.center, arc0.start),
+            DatumLineSegment::new(arc1.center, arc1.start),
+        ),
+    )
+}
+
+/// [`Constraint::LineCircleIntersection`] is just the point on the line, plus on the circle.
+fn line_circle_intersection_parts(
+    point: DatumPoint,
+    line: &DatumLineSegment,
+    circle: &DatumCircle,
+) -> (Constraint, Constraint) {
+    (
+        Constraint::PointOnLine(point, *line),
+        Constraint::PointOnCircle(point, *circle),
+    )
+}
+
+/// [`Constraint::ArcCentralAngleScalar`] is just [`Constraint::AngleEqualsScalar`]
+/// between the arc's two radii.
+fn arc_central_angle_scalar_part(arc: &DatumCircularArc, scalar: Id) -> Constraint {
+    Constraint::AngleEqualsScalar(
+        DatumLineSegment::new(arc.center, arc.start),
+        DatumLineSegment::new(arc.center, arc.end),
+        scalar,
+    )
+}
+
+/// [`Constraint::EqualLengthToCommon`] is just [`Constraint::Arc`] around the common point.
+fn equal_length_to_common_arc(common: DatumPoint, a: DatumPoint, b: DatumPoint) -> Constraint {
+    Constraint::Arc(DatumCircularArc {
+        center: common,
+        start: a,
+        end: b,
+    })
+}
+
+/// Which side of a line is the reference point on?
+/// +1 or -1, given `Ax + By + C` evaluated at the reference point.
+/// A reference point exactly on the line counts as being on the positive side.
+fn same_side_sign(reference_side: f64) -> f64 {
+    if reference_side >= 0.0 { 1.0 } else { -1.0 }
+}
+
 /// If we represent the line in the form (Ax + By + C),
 /// this returns (A, B, C).
 fn equation_of_line(
@@ -2646,8 +5943,14 @@ fn rotation_for_angle_kind(angle_kind: AngleKind) -> Rotation2 {
     }
 }
 
-#[cfg(test)]
-fn wrap_angle_delta(delta: f64) -> f64 {
+/// How far is `value` past the nearest multiple of `spacing` from `origin`?
+fn grid_offset(value: f64, origin: f64, spacing: f64) -> f64 {
+    let offset = value - origin;
+    offset - spacing * libm::round(offset / spacing)
+}
+
+/// Wraps an angle difference into the interval (-π, π].
+pub(crate) fn wrap_angle_delta(delta: f64) -> f64 {
     if delta > -PI && delta <= PI {
         // If inside our interval, return unchanged.
         delta
@@ -1,6 +1,9 @@
-use crate::datatypes::{
-    AngleKind,
-    inputs::{DatumCircle, DatumCircularArc, DatumLineSegment, DatumPoint},
+use crate::{
+    LineSide,
+    datatypes::{
+        AngleKind,
+        inputs::{DatumCircle, DatumCircularArc, DatumLineSegment, DatumPoint},
+    },
 };
 
 use super::Constraint;
@@ -59,4 +62,56 @@ impl Constraint {
             ),
         ]
     }
+
+    /// Keeps this point at least `distance` away from every one of the obstacles,
+    /// e.g. to maintain clearance between placed components.
+    /// Each obstacle gets its own one-sided [`Constraint::MinDistance`], so obstacles
+    /// which are already far enough away don't affect the solve.
+    pub fn min_distance_to_any(
+        point: DatumPoint,
+        obstacles: &[DatumPoint],
+        distance: f64,
+    ) -> impl Iterator<Item = Self> + '_ {
+        obstacles
+            .iter()
+            .map(move |obstacle| Constraint::MinDistance(point, *obstacle, distance))
+    }
+
+    /// Makes these points cocircular, i.e. all on some circle around `center`.
+    /// The center is an ordinary point, so leave it unconstrained (with a rough initial guess)
+    /// to let the solver find the circle, e.g. when recovering a circle from sampled points.
+    /// Every point after the first gets a [`Constraint::EqualLengthToCommon`] keeping it the
+    /// same distance from the center as the first point. Yields nothing for fewer than 2 points.
+    pub fn cocircular(
+        center: DatumPoint,
+        points: &[DatumPoint],
+    ) -> impl Iterator<Item = Self> + '_ {
+        points
+            .iter()
+            .skip(1)
+            .map(move |point| Constraint::EqualLengthToCommon(center, points[0], *point))
+    }
+
+    /// Keeps every edge of this closed polygon tangent to the circle, i.e. the polygon is
+    /// circumscribed about the circle. Edges run from each vertex to the next, and from the
+    /// last vertex back to the first. Each edge gets a [`Constraint::LineTangentToCircle`]
+    /// whose side is picked from the initial guesses, so start the circle inside the polygon.
+    /// Yields nothing for fewer than 3 vertices.
+    pub fn circumscribed_circle(
+        circle: DatumCircle,
+        vertices: &[DatumPoint],
+    ) -> impl Iterator<Item = Self> + '_ {
+        let num_edges = if vertices.len() < 3 {
+            0
+        } else {
+            vertices.len()
+        };
+        (0..num_edges).map(move |i| {
+            let edge = DatumLineSegment {
+                p0: vertices[i],
+                p1: vertices[(i + 1) % num_edges],
+            };
+            Constraint::LineTangentToCircle(edge, circle, LineSide::Undefined)
+        })
+    }
 }
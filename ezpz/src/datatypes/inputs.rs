@@ -16,6 +16,7 @@ pub(crate) trait Datum {
 /// ```
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DatumDistance {
     /// ID of the variable for this distance.
     pub id: Id,
@@ -51,6 +52,7 @@ impl Datum for DatumDistance {
 /// ```
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DatumPoint {
     /// ID of the variable for this point's X component.
     pub x_id: Id,
@@ -111,6 +113,7 @@ impl Datum for DatumPoint {
 /// can be determined by the constraint solver.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DatumLineSegment {
     /// Point for one end of this line.
     pub p0: DatumPoint,
@@ -145,9 +148,60 @@ impl Datum for DatumLineSegment {
     }
 }
 
+/// Infinite line in normal form, i.e. all points `p` where
+/// `p.x * cos(angle) + p.y * sin(angle) = offset`.
+/// Unlike [`DatumLineSegment`], it isn't attached to any endpoints,
+/// so it can't degenerate when those endpoints coincide.
+/// The normal points to the left of the line, i.e. travelling along the line
+/// means heading in the direction `(sin(angle), -cos(angle))`.
+/// To make a line pass through a segment, constrain both of the segment's
+/// points to be distance 0 from it.
+/// ```
+/// use ezpz::datatypes::inputs::DatumLine;
+/// use ezpz::IdGenerator;
+///
+/// let mut ids = IdGenerator::default();
+/// let line = DatumLine::new(&mut ids);
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DatumLine {
+    /// ID of the variable for the angle of this line's normal, in radians,
+    /// measured CCW from the +X axis.
+    pub angle: Id,
+    /// ID of the variable for this line's signed distance from the origin,
+    /// measured along its normal.
+    pub offset: Id,
+}
+
+impl DatumLine {
+    /// Create a new `DatumLine` from an ID generator.
+    /// ```
+    /// use ezpz::datatypes::inputs::DatumLine;
+    /// use ezpz::IdGenerator;
+    ///
+    /// let mut ids = IdGenerator::default();
+    /// let line = DatumLine::new(&mut ids);
+    /// ```
+    pub fn new(id_generator: &mut IdGenerator) -> Self {
+        Self {
+            angle: id_generator.next_id(),
+            offset: id_generator.next_id(),
+        }
+    }
+}
+
+impl Datum for DatumLine {
+    fn all_variables(&self) -> impl IntoIterator<Item = Id> {
+        [self.angle, self.offset]
+    }
+}
+
 /// A circle, whose radius and position can be determined by the constraint solver.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DatumCircle {
     /// Center of the circle.
     pub center: DatumPoint,
@@ -168,6 +222,7 @@ impl Datum for DatumCircle {
 /// To get a clockwise arc, swap start and end.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DatumCircularArc {
     /// Center of the circle
     pub center: DatumPoint,
@@ -191,3 +246,59 @@ impl Datum for DatumCircularArc {
         ]
     }
 }
+
+/// General conic section, in implicit form `Ax² + Bxy + Cy² + Dx + Ey + F = 0`.
+/// Covers circles, ellipses, parabolas and hyperbolas.
+/// Each coefficient is a variable which can be determined by the constraint solver,
+/// use [`crate::Constraint::Fixed`] to make a coefficient constant.
+/// ```
+/// use ezpz::datatypes::inputs::DatumConic;
+/// use ezpz::IdGenerator;
+///
+/// let mut ids = IdGenerator::default();
+/// let conic = DatumConic::new(&mut ids);
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DatumConic {
+    /// ID of the variable for the `x²` coefficient.
+    pub a: Id,
+    /// ID of the variable for the `xy` coefficient.
+    pub b: Id,
+    /// ID of the variable for the `y²` coefficient.
+    pub c: Id,
+    /// ID of the variable for the `x` coefficient.
+    pub d: Id,
+    /// ID of the variable for the `y` coefficient.
+    pub e: Id,
+    /// ID of the variable for the constant term.
+    pub f: Id,
+}
+
+impl DatumConic {
+    /// Create a new `DatumConic` from an ID generator.
+    /// ```
+    /// use ezpz::datatypes::inputs::DatumConic;
+    /// use ezpz::IdGenerator;
+    ///
+    /// let mut ids = IdGenerator::default();
+    /// let conic = DatumConic::new(&mut ids);
+    /// ```
+    pub fn new(id_generator: &mut IdGenerator) -> Self {
+        Self {
+            a: id_generator.next_id(),
+            b: id_generator.next_id(),
+            c: id_generator.next_id(),
+            d: id_generator.next_id(),
+            e: id_generator.next_id(),
+            f: id_generator.next_id(),
+        }
+    }
+}
+
+impl Datum for DatumConic {
+    fn all_variables(&self) -> impl IntoIterator<Item = Id> {
+        [self.a, self.b, self.c, self.d, self.e, self.f]
+    }
+}
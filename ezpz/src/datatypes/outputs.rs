@@ -2,6 +2,8 @@
 
 /// A 2D point that ezpz solved for, i.e. found values for all its variables.
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     #[allow(missing_docs)]
     pub x: f64,
@@ -45,6 +47,47 @@ pub struct Arc {
     pub center: Point,
 }
 
+/// An infinite 2D line that ezpz solved for, in normal form.
+/// See [`crate::datatypes::inputs::DatumLine`].
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Line {
+    /// Angle of the line's normal, in radians, measured CCW from the +X axis.
+    pub angle: f64,
+    /// Signed distance of the line from the origin, measured along its normal.
+    pub offset: f64,
+}
+
+impl Line {
+    /// The line that travels through `p0` then `p1`.
+    /// Useful for turning a line segment into an initial guess for a
+    /// [`crate::datatypes::inputs::DatumLine`].
+    /// Returns None if the points coincide, because then there's no unique line.
+    /// ```
+    /// use ezpz::datatypes::outputs::{Line, Point};
+    ///
+    /// let line = Line::through_points(Point { x: 0.0, y: 2.0 }, Point { x: 1.0, y: 2.0 }).unwrap();
+    /// assert!((line.signed_distance(Point { x: 5.0, y: 0.0 }) - -2.0).abs() < 1e-9);
+    /// ```
+    pub fn through_points(p0: Point, p1: Point) -> Option<Self> {
+        let dx = p1.x - p0.x;
+        let dy = p1.y - p0.y;
+        if libm::hypot(dx, dy) <= crate::EPSILON {
+            return None;
+        }
+        // The direction is the normal rotated 90° clockwise,
+        // so the normal is the direction rotated 90° counter-clockwise.
+        let angle = libm::atan2(dx, -dy);
+        let offset = p0.x * libm::cos(angle) + p0.y * libm::sin(angle);
+        Some(Self { angle, offset })
+    }
+
+    /// Signed distance from the line to this point.
+    /// Positive on the left of the line (the side its normal points to).
+    pub fn signed_distance(&self, p: Point) -> f64 {
+        p.x * libm::cos(self.angle) + p.y * libm::sin(self.angle) - self.offset
+    }
+}
+
 impl std::fmt::Display for Point {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({},{})", self.x, self.y)
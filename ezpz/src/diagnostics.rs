@@ -0,0 +1,142 @@
+use crate::{
+    Config, ConstraintRequest, FreedomAnalysis, Id, SolveOutcome, SparsityStats, TerminationReason,
+    Warning, evaluate_at, jacobian_sparsity_stats, solve_analysis,
+};
+
+/// Everything about one solve, bundled up for support tickets and automated triage.
+/// Created by [`diagnostic_report`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub struct DiagnosticReport {
+    /// How the solve went.
+    pub status: SolveStatus,
+    /// How many variables were being solved for.
+    pub num_vars: usize,
+    /// How many residuals the enabled constraints have between them.
+    pub num_eqs: usize,
+    /// Structure of the system's Jacobian.
+    /// `None` if the system couldn't even be built, e.g. a constraint used a variable with no guess.
+    pub sparsity: Option<SparsityStats>,
+    /// Warnings about the problem definition or the solve.
+    pub warnings: Vec<Warning>,
+    /// Why the solver stopped. `None` if the solve failed.
+    pub termination_reason: Option<TerminationReason>,
+    /// One per constraint request, in order: the norm of its residuals at the solution,
+    /// or at the initial guesses if the solve failed. Disabled constraints have residual 0.
+    pub constraint_residuals: Vec<f64>,
+    /// The solve's outcome. `None` if the solve failed.
+    pub outcome: Option<SolveOutcome>,
+    /// The degrees of freedom analysis. `None` if the solve failed.
+    pub analysis: Option<FreedomAnalysis>,
+    /// Why the solve failed. `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// How a solve went, see [`DiagnosticReport::status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub enum SolveStatus {
+    /// Every constraint was satisfied.
+    Satisfied,
+    /// The solver converged, but some constraints couldn't be satisfied,
+    /// so the solution only minimizes their (least-squares) residuals.
+    LeastSquares,
+    /// The solver ran out of iterations with some constraints still unsatisfied.
+    NotConverged,
+    /// The system couldn't be solved at all.
+    Failed,
+}
+
+/// Solve the system with [`solve_analysis`], and gather everything known about the solve
+/// into one report, e.g. to attach to a bug report. This never fails: if the solve does,
+/// the report says why.
+/// ```
+/// use ezpz::{Config, Constraint, ConstraintRequest, SolveStatus, diagnostic_report};
+///
+/// // Two constraints which want the same variable in different places.
+/// let requests = [
+///     ConstraintRequest::highest_priority(Constraint::Fixed(0, 1.0)),
+///     ConstraintRequest::highest_priority(Constraint::Fixed(0, 3.0)),
+/// ];
+/// let report = diagnostic_report(&requests, vec![(0, 0.0)], Config::default());
+/// assert_eq!(report.status, SolveStatus::LeastSquares);
+/// assert_eq!(report.constraint_residuals.len(), 2);
+/// ```
+pub fn diagnostic_report(
+    reqs: &[ConstraintRequest],
+    initial_guesses: Vec<(Id, f64)>,
+    config: Config,
+) -> DiagnosticReport {
+    let sparsity = jacobian_sparsity_stats(reqs, &initial_guesses).ok();
+    let mut at = initial_guesses.clone();
+    match solve_analysis(reqs, initial_guesses, config) {
+        Ok(solved) => {
+            let outcome = solved.outcome;
+            // Final values are in the same order as the guesses.
+            for ((_id, value), &solved) in at.iter_mut().zip(outcome.final_values()) {
+                *value = solved;
+            }
+            let status = if outcome.is_satisfied() {
+                SolveStatus::Satisfied
+            } else if outcome.converged() {
+                SolveStatus::LeastSquares
+            } else {
+                SolveStatus::NotConverged
+            };
+            DiagnosticReport {
+                status,
+                num_vars: at.len(),
+                num_eqs: num_eqs(reqs),
+                sparsity,
+                warnings: outcome.warnings().to_vec(),
+                termination_reason: Some(outcome.termination_reason()),
+                constraint_residuals: constraint_residuals(reqs, &at),
+                outcome: Some(outcome),
+                analysis: Some(solved.analysis),
+                error: None,
+            }
+        }
+        Err(failure) => DiagnosticReport {
+            status: SolveStatus::Failed,
+            num_vars: failure.num_vars,
+            num_eqs: failure.num_eqs,
+            sparsity,
+            warnings: failure.warnings,
+            termination_reason: None,
+            constraint_residuals: constraint_residuals(reqs, &at),
+            outcome: None,
+            analysis: None,
+            error: Some(failure.error.to_string()),
+        },
+    }
+}
+
+fn num_eqs(reqs: &[ConstraintRequest]) -> usize {
+    reqs.iter()
+        .filter(|req| req.is_enabled())
+        .map(|req| req.constraint().residual_dim())
+        .sum()
+}
+
+/// Norm of each constraint's residuals at these values, or empty if they can't be evaluated.
+fn constraint_residuals(reqs: &[ConstraintRequest], values: &[(Id, f64)]) -> Vec<f64> {
+    let Ok((residuals, _jacobian)) = evaluate_at(reqs, values) else {
+        return Vec::new();
+    };
+    // The residuals are every enabled constraint's rows, in order.
+    let mut rows = residuals.into_iter();
+    reqs.iter()
+        .map(|req| {
+            if !req.is_enabled() {
+                return 0.0;
+            }
+            rows.by_ref()
+                .take(req.constraint().residual_dim())
+                .map(|r| r * r)
+                .sum::<f64>()
+                .sqrt()
+        })
+        .collect()
+}
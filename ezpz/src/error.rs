@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use faer::{
     linalg::svd::SvdError,
     sparse::{CreationError, FaerError, linalg::LltError},
@@ -27,6 +29,45 @@ pub enum TextualError {
         /// The undefined point.
         label: String,
     },
+    /// The text couldn't be parsed, starting at this token.
+    #[error("Could not parse the problem, unexpected token at line {line}, column {column}")]
+    UnexpectedToken {
+        /// Byte offsets of the unexpected token in the problem text.
+        /// Empty if the text ended unexpectedly.
+        span: Range<usize>,
+        /// Line of the unexpected token, starting from 1.
+        line: usize,
+        /// Column (in characters) of the unexpected token, starting from 1.
+        column: usize,
+    },
+}
+
+/// Errors from building a constraint with [`crate::constraint_from_spec`].
+#[derive(thiserror::Error, Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub enum SpecError {
+    /// There's no constraint with this kind which can be built from points and numbers.
+    #[error("There's no constraint kind {kind} which can be built from points and numbers")]
+    UnknownKind {
+        /// The kind which was given.
+        kind: String,
+    },
+    /// The constraint kind needs a different number of arguments.
+    #[error(
+        "Constraint kind {kind} takes {expected_points} points and {expected_scalars} numbers, but you gave {points} points and {scalars} numbers"
+    )]
+    WrongArity {
+        /// The kind which was given.
+        kind: String,
+        /// How many points this kind takes.
+        expected_points: usize,
+        /// How many numbers this kind takes.
+        expected_scalars: usize,
+        /// How many points were given.
+        points: usize,
+        /// How many numbers were given.
+        scalars: usize,
+    },
 }
 
 /// Errors that could occur when running the core Newton-Gauss solve.
@@ -83,4 +124,71 @@ pub enum NonLinearSystemError {
     /// You provided an empty constraint system.
     #[error("Cannot solve an empty system")]
     EmptySystemNotAllowed,
+    /// The residual grew too much, see [`crate::Config::with_abort_on_divergence`].
+    #[error(
+        "The solve diverged after {iterations} iterations: the residual norm grew to {residual_norm}, from a best of {best_residual_norm}"
+    )]
+    Diverged {
+        /// How many iterations ran, including the one which diverged.
+        iterations: usize,
+        /// Residual norm after the diverging step.
+        residual_norm: f64,
+        /// Smallest residual norm seen before the diverging step.
+        best_residual_norm: f64,
+    },
+    /// The residual stopped improving, see [`crate::Config::with_stall_window`].
+    #[error(
+        "The solve stalled after {iterations} iterations: the residual norm {residual_norm} barely improved over the last {window} iterations"
+    )]
+    Stalled {
+        /// How many iterations ran before the stall was noticed.
+        iterations: usize,
+        /// Residual norm when the stall was noticed.
+        residual_norm: f64,
+        /// How many iterations the residual norm had to improve in.
+        window: usize,
+    },
+    /// The solve ran out of time, see [`crate::Config::with_time_limit`].
+    #[error(
+        "Time budget exceeded: still unsolved after {iterations} iterations and {time_limit:?}"
+    )]
+    TimeLimitExceeded {
+        /// How many iterations ran before the time ran out.
+        iterations: usize,
+        /// The time limit which was exceeded.
+        time_limit: std::time::Duration,
+    },
+}
+
+/// Errors from dumping or replaying a solve, see [`crate::dump_repro`].
+#[cfg(feature = "serde")]
+#[derive(thiserror::Error, Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub enum ReproError {
+    /// The repro couldn't be converted to or from JSON.
+    #[error("Could not convert the repro to or from JSON: {error}")]
+    Json {
+        /// Underlying error.
+        #[from]
+        error: serde_json::Error,
+    },
+    /// The repro was written by an incompatible version of ezpz.
+    #[error(
+        "Repro has format version {found}, but this version of ezpz only replays version {expected}"
+    )]
+    UnsupportedVersion {
+        /// Format version of the repro.
+        found: u32,
+        /// Format version this version of ezpz reads and writes.
+        expected: u32,
+    },
+    /// The config has a setting which is code rather than data, so it can't be serialized,
+    /// and replaying without it would run a different solve.
+    #[error(
+        "The config's `{setting}` is code, so it can't be saved in a repro. Remove it before dumping the repro."
+    )]
+    UnserializableConfig {
+        /// Which setting couldn't be serialized, e.g. `on_iteration`.
+        setting: &'static str,
+    },
 }
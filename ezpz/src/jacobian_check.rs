@@ -0,0 +1,125 @@
+use crate::{Config, Constraint, Id, NonLinearSystemError, solver::Layout};
+
+/// One partial derivative compared by [`check_jacobian`].
+#[derive(Clone, Copy, Debug)]
+pub struct JacobianCheckEntry {
+    /// Which of the constraint's residuals this is the derivative of, starting from 0.
+    pub row: usize,
+    /// Which variable this is the derivative with respect to.
+    pub variable: Id,
+    /// The partial derivative from the constraint's analytic Jacobian.
+    pub analytic: f64,
+    /// The partial derivative estimated by a central finite difference of the residual.
+    pub finite_difference: f64,
+    /// How far apart the two estimates are.
+    pub error: f64,
+}
+
+/// Returned by [`check_jacobian`].
+#[derive(Clone, Debug)]
+pub struct JacobianCheckReport {
+    /// Every structurally nonzero entry of the constraint's Jacobian, row by row.
+    pub entries: Vec<JacobianCheckEntry>,
+    /// The constraint was degenerate at these values (e.g. a zero-length line),
+    /// so its Jacobian is undefined and there are no entries.
+    pub degenerate: bool,
+}
+
+impl JacobianCheckReport {
+    /// The largest error of any entry, or 0 if there are none.
+    pub fn max_error(&self) -> f64 {
+        self.entries
+            .iter()
+            .map(|entry| entry.error)
+            .fold(0.0, libm::fmax)
+    }
+}
+
+/// Compare a constraint's analytic Jacobian against a finite difference of its residual,
+/// at these variable values (indexed by [`Id`]). Useful for validating a new constraint.
+/// Residuals are compared before inequality constraints are clamped, so they're smooth
+/// everywhere except where the constraint itself has a kink (e.g. an absolute value).
+/// Any undefined constraint state (like which side of a line a tangent circle is on)
+/// is inferred from these values, just like a solve infers it from the initial guesses.
+/// ```
+/// use ezpz::{Constraint, IdGenerator, check_jacobian, datatypes::inputs::DatumPoint};
+///
+/// let mut ids = IdGenerator::default();
+/// let p = DatumPoint::new(&mut ids);
+/// let q = DatumPoint::new(&mut ids);
+/// let report = check_jacobian(&Constraint::Distance(p, q, 4.0), &[0.0, 0.0, 3.0, 1.0]).unwrap();
+/// assert_eq!(report.entries.len(), 4);
+/// assert!(report.max_error() < 1e-6);
+/// ```
+pub fn check_jacobian(
+    constraint: &Constraint,
+    values: &[f64],
+) -> Result<JacobianCheckReport, NonLinearSystemError> {
+    let mut ids = Vec::with_capacity(16);
+    constraint.extend_dependent_variable_ids(&mut ids);
+    if let Some(&missing) = ids.iter().find(|&&id| id as usize >= values.len()) {
+        return Err(NonLinearSystemError::NotFound(missing));
+    }
+    let mut constraint = *constraint;
+    constraint.set_from_initial_values(values);
+    let all_variables: Vec<Id> = (0..values.len() as Id).collect();
+    let layout = Layout::new(&all_variables, &[&constraint], &Config::default());
+
+    let mut analytic: [Vec<_>; 3] = Default::default();
+    let mut degenerate = false;
+    let [a0, a1, a2] = &mut analytic;
+    constraint.jacobian_rows(&layout, values, a0, a1, a2, &mut degenerate);
+    if degenerate {
+        return Ok(JacobianCheckReport {
+            entries: Vec::new(),
+            degenerate,
+        });
+    }
+    let mut structure: [Vec<Id>; 3] = Default::default();
+    let [s0, s1, s2] = &mut structure;
+    constraint.nonzeroes(s0, s1, s2);
+
+    let mut entries = Vec::with_capacity(s0.len() + s1.len() + s2.len());
+    let mut perturbed = values.to_vec();
+    let rows = constraint.residual_dim().min(3);
+    for (row, (variables, analytic)) in structure.iter_mut().zip(&analytic).enumerate().take(rows) {
+        variables.sort_unstable();
+        variables.dedup();
+        for &variable in variables.iter() {
+            // A variable can appear in more than one term of a row, and those get summed.
+            let analytic = analytic
+                .iter()
+                .filter(|entry| entry.id == variable)
+                .map(|entry| entry.partial_derivative)
+                .sum();
+            let i = layout.index_of(variable);
+            let step = 1e-6 * (1.0 + values[i].abs());
+            perturbed[i] = values[i] + step;
+            let plus = residual_row(&constraint, &layout, &perturbed, row);
+            perturbed[i] = values[i] - step;
+            let minus = residual_row(&constraint, &layout, &perturbed, row);
+            perturbed[i] = values[i];
+            let finite_difference = (plus - minus) / (2.0 * step);
+            entries.push(JacobianCheckEntry {
+                row,
+                variable,
+                analytic,
+                finite_difference,
+                error: (analytic - finite_difference).abs(),
+            });
+        }
+    }
+    Ok(JacobianCheckReport {
+        entries,
+        degenerate,
+    })
+}
+
+/// One row of the constraint's (unclamped) residual.
+fn residual_row(constraint: &Constraint, layout: &Layout, values: &[f64], row: usize) -> f64 {
+    let mut residuals = [0.0; 3];
+    let [r0, r1, r2] = &mut residuals;
+    let mut degenerate = false;
+    constraint.residual(layout, values, r0, r1, r2, &mut degenerate);
+    residuals[row]
+}
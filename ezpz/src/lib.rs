@@ -2,33 +2,67 @@
 
 use std::collections::HashSet;
 
-pub use crate::analysis::FreedomAnalysis;
 use crate::analysis::{Analysis, NoAnalysis, SolveOutcomeAnalysis};
-pub use crate::constraint_request::ConstraintRequest;
+pub use crate::analysis::{FreedomAnalysis, RANK_PROFILE_TOLERANCES};
+pub use crate::analysis_cache::AnalysisCache;
+pub use crate::conflicts::find_conflicts;
+pub use crate::constraint_request::{ConstraintRequest, enforce_symmetry};
+pub use crate::constraint_spec::constraint_from_spec;
 use crate::constraints::ConstraintEntry;
-pub use crate::constraints::{CircleSide, Constraint, LineSide};
+pub use crate::constraints::{CircleSide, Constraint, Endpoint, LineSide, TangencyKind};
+pub use crate::diagnostics::{DiagnosticReport, SolveStatus, diagnostic_report};
 pub use crate::error::*;
-pub use crate::solver::Config;
+pub use crate::solver::{
+    Clock, Config, InitialLambdaContext, IterationStats, PriorityMode, SparsityStats, StdClock,
+};
 // Only public for now so that I can benchmark it.
 // TODO: Replace this with an end-to-end benchmark,
 // or find a different way to structure modules.
 pub use crate::id::{Id, IdGenerator};
-use crate::solver::Model;
-pub use solve_outcome::{FailureOutcome, SolveOutcome, SolveOutcomeFreedomAnalysis};
+pub use crate::jacobian_check::{JacobianCheckEntry, JacobianCheckReport, check_jacobian};
+pub use crate::multistart::solve_multistart;
+#[cfg(feature = "serde")]
+pub use crate::repro::{dump_repro, replay};
+pub use crate::session::SolverSession;
+use crate::solver::{Model, SymbolicStructure};
+pub use solve_outcome::{
+    FailureOutcome, PriorityCost, SolveOutcome, SolveOutcomeFreedomAnalysis, TerminationReason,
+};
 pub use warnings::{Warning, WarningContent};
 
 mod analysis;
+/// Reusing freedom analysis across solves with the same structure.
+mod analysis_cache;
+/// Grouping the constraints which can't all be satisfied at once.
+mod conflicts;
 mod constraint_request;
+/// Building constraints from a kind and positional arguments, for language bindings.
+mod constraint_spec;
 /// Each kind of constraint we support.
 mod constraints;
 /// Geometric data (lines, points, etc).
 pub mod datatypes;
+/// Everything about a solve in one report, for triage.
+mod diagnostics;
 mod error;
 /// IDs of various entities, points, scalars etc.
 mod id;
+/// Checking constraints' analytic Jacobians against finite differences.
+mod jacobian_check;
+/// Solving from several starting points, to find alternative solutions.
+mod multistart;
+/// Importing geometry from path-based formats, like SVG paths.
+pub mod path;
+/// Serialize and replay whole solves, for reproducing bugs.
+#[cfg(feature = "serde")]
+mod repro;
 /// Residual field visualization (optional).
 #[cfg(feature = "residual-viz")]
 pub mod residual_viz;
+/// Reusing the symbolic analysis across solves of the same system.
+mod session;
+/// Builders for common shapes, with all the constraints they need.
+pub mod shapes;
 mod solve_outcome;
 /// Numeric solver using sparse matrices.
 mod solver;
@@ -42,6 +76,115 @@ mod warnings;
 
 const EPSILON: f64 = 1e-4;
 
+/// Just like [`solve_analysis`] except the freedom analysis only looks at the variables
+/// involved in some of the constraints. The whole system is still solved, but
+/// [`FreedomAnalysis`] is computed from just those variables' columns of the Jacobian,
+/// treating every other variable as fixed. This tells you whether one piece of a sketch
+/// is fully constrained, even if other, unrelated pieces are still free.
+/// `focus` holds indices into `reqs`. Indices past the end of `reqs` are ignored.
+pub fn solve_analysis_subset(
+    reqs: &[ConstraintRequest],
+    initial_guesses: Vec<(Id, f64)>,
+    config: Config,
+    focus: &[usize],
+) -> Result<SolveOutcomeFreedomAnalysis, FailureOutcome> {
+    let num_vars = initial_guesses.len();
+    let mut focus_variables = Vec::with_capacity(focus.len() * 4);
+    for req in focus.iter().filter_map(|&i| reqs.get(i)) {
+        req.constraint()
+            .extend_associated_variable_ids(&mut focus_variables);
+    }
+    focus_variables.sort_unstable();
+    focus_variables.dedup();
+
+    let out = solve_with_priority_inner::<FreedomAnalysis>(
+        reqs,
+        initial_guesses,
+        config,
+        &[],
+        None,
+        None,
+    )?;
+    match out.analysis.restricted_to(&focus_variables) {
+        Ok(analysis) => Ok(SolveOutcomeFreedomAnalysis {
+            analysis,
+            outcome: out.outcome,
+        }),
+        Err(error) => Err(FailureOutcome {
+            error,
+            warnings: out.outcome.warnings,
+            num_vars,
+            num_eqs: reqs
+                .iter()
+                .filter(|c| c.is_enabled())
+                .map(|c| c.constraint().residual_dim())
+                .sum(),
+            priority_progress: out.outcome.priority_progress,
+        }),
+    }
+}
+
+/// Describe the structure of this system's Jacobian, without solving it.
+/// Useful for performance tuning, e.g. spotting constraints that involve unusually many
+/// variables, or deciding whether the system is small and dense enough to solve some other way.
+/// The guesses are only used to know which variables exist, not their values.
+pub fn jacobian_sparsity_stats(
+    reqs: &[ConstraintRequest],
+    initial_guesses: &[(Id, f64)],
+) -> Result<SparsityStats, NonLinearSystemError> {
+    let constraints = constraint_entries(reqs);
+    let (all_variables, initial_values) = initial_guesses.iter().copied().unzip();
+    let model = Model::new(
+        &constraints,
+        all_variables,
+        initial_values,
+        &Config::default(),
+    )?;
+    Ok(model.sparsity_stats())
+}
+
+/// One entry of a Jacobian: `(row, column, partial derivative)`.
+pub type JacobianTriplet = (usize, usize, f64);
+
+/// Evaluate the system at these variable values, without solving it.
+/// Returns each residual (in constraint order, see [`jacobian_sparsity_stats`]) and the
+/// Jacobian, as `(row, column, partial derivative)` triplets for each structurally nonzero entry.
+/// Columns are variable IDs. Useful for debugging constraints, or teaching how the solver works.
+/// Any undefined constraint state (like which side of a line a tangent circle is on)
+/// is inferred from these values, just like a solve infers it from the initial guesses.
+pub fn evaluate_at(
+    reqs: &[ConstraintRequest],
+    values: &[(Id, f64)],
+) -> Result<(Vec<f64>, Vec<JacobianTriplet>), NonLinearSystemError> {
+    let (all_variables, current_values): (Vec<Id>, Vec<f64>) = values.iter().copied().unzip();
+    let mut reqs = reqs.to_vec();
+    for req in &mut reqs {
+        req.set_from_initial_values(&current_values);
+    }
+    let constraints = constraint_entries(&reqs);
+    let mut model = Model::new(
+        &constraints,
+        all_variables,
+        current_values.clone(),
+        &Config::default(),
+    )?;
+    Ok(model.evaluate(&current_values))
+}
+
+/// Number each constraint by its position in `reqs`, leaving out disabled ones.
+fn constraint_entries(reqs: &[ConstraintRequest]) -> Vec<ConstraintEntry<'_>> {
+    reqs.iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_enabled())
+        .map(|(id, c)| ConstraintEntry {
+            constraint: c.constraint(),
+            priority: c.priority(),
+            weight: c.weight(),
+            id,
+        })
+        .collect()
+}
+
 /// Given some initial guesses, constrain them.
 /// Returns the same variables in the same order, but constrained.
 /// ```
@@ -82,10 +225,63 @@ pub fn solve(
     initial_guesses: Vec<(Id, f64)>,
     config: Config,
 ) -> Result<SolveOutcome, FailureOutcome> {
-    let out = solve_with_priority_inner::<NoAnalysis>(reqs, initial_guesses, config)?;
+    let out =
+        solve_with_priority_inner::<NoAnalysis>(reqs, initial_guesses, config, &[], None, None)?;
+    Ok(out.outcome)
+}
+
+/// Just like [`solve`] except some variables are kept within bounds, e.g. so a radius can't go
+/// negative. Each bound is `(variable, lower, upper)`, and either end can be infinite.
+/// Guesses outside their bounds are moved onto them, and any step which would cross a bound
+/// stops at it (projected Gauss-Newton), so the variable stays pinned there until the
+/// constraints pull it back inside. The other variables' steps aren't recomputed to make up
+/// for it, so when a bound is hit the result may not be the best least-squares fit.
+/// Bounds on variables which aren't in the system are ignored.
+/// ```
+/// use ezpz::{Config, Constraint, ConstraintRequest, solve_bounded};
+///
+/// // Variable 0 wants to be -1, but is bounded below by 0.
+/// let requests = [ConstraintRequest::highest_priority(Constraint::Fixed(0, -1.0))];
+/// let outcome = solve_bounded(&requests, vec![(0, 2.0)], &[(0, 0.0, f64::INFINITY)], Config::default())
+///     .unwrap();
+/// assert_eq!(outcome.final_values(), &[0.0]);
+/// assert!(outcome.is_unsatisfied());
+/// ```
+pub fn solve_bounded(
+    reqs: &[ConstraintRequest],
+    initial_guesses: Vec<(Id, f64)>,
+    bounds: &[(Id, f64, f64)],
+    config: Config,
+) -> Result<SolveOutcome, FailureOutcome> {
+    let out =
+        solve_with_priority_inner::<NoAnalysis>(reqs, initial_guesses, config, bounds, None, None)?;
     Ok(out.outcome)
 }
 
+/// Just like [`solve`] except it returns the solved state after each priority level was added,
+/// from highest priority to lowest. E.g. a UI could show the sketch with just the required
+/// constraints, then with the preferences added.
+/// Each stage is a solve of only the constraints at its priority level or higher.
+/// The stages stop at the first level that couldn't be satisfied (which is included),
+/// or that failed to solve (which isn't). Errors if even the highest priority level fails.
+/// With [`PriorityMode::Weighted`] there's only one stage, because every level is solved at once.
+pub fn solve_priority_stages(
+    reqs: &[ConstraintRequest],
+    initial_guesses: Vec<(Id, f64)>,
+    config: Config,
+) -> Result<Vec<(u32, SolveOutcome)>, FailureOutcome> {
+    let mut stages = Vec::new();
+    solve_with_priority_inner::<NoAnalysis>(
+        reqs,
+        initial_guesses,
+        config,
+        &[],
+        None,
+        Some(&mut stages),
+    )?;
+    Ok(stages)
+}
+
 /// Just like [`solve`] except it also does some expensive analysis steps
 /// at the end. This lets it calculate helpful data for the user, like degrees of freedom.
 /// Should not be called on every iteration of a system when you change the initial values!
@@ -136,7 +332,14 @@ pub fn solve_analysis(
     initial_guesses: Vec<(Id, f64)>,
     config: Config,
 ) -> Result<SolveOutcomeFreedomAnalysis, FailureOutcome> {
-    let out = solve_with_priority_inner::<FreedomAnalysis>(reqs, initial_guesses, config)?;
+    let out = solve_with_priority_inner::<FreedomAnalysis>(
+        reqs,
+        initial_guesses,
+        config,
+        &[],
+        None,
+        None,
+    )?;
     Ok(SolveOutcomeFreedomAnalysis {
         analysis: out.analysis,
         outcome: out.outcome,
@@ -145,10 +348,17 @@ pub fn solve_analysis(
 
 /// Given some initial guesses, constrain them.
 /// Returns the same variables in the same order, but constrained.
+/// Variables are kept within `bounds`, see [`solve_bounded`].
+/// If `symbolic` is given, it caches each priority level's symbolic structure, reusing it
+/// if it's there already (see [`SolverSession`]).
+/// If `stages` is given, each priority level's outcome is pushed onto it as it's solved.
 pub(crate) fn solve_with_priority_inner<A: Analysis>(
     reqs: &[ConstraintRequest],
-    initial_guesses: Vec<(Id, f64)>,
+    mut initial_guesses: Vec<(Id, f64)>,
     config: Config,
+    bounds: &[(Id, f64, f64)],
+    mut symbolic: Option<&mut Vec<Option<SymbolicStructure>>>,
+    mut stages: Option<&mut Vec<(u32, SolveOutcome)>>,
 ) -> Result<SolveOutcomeAnalysis<A>, FailureOutcome> {
     // When there's no constraints, return early.
     // Use the initial guesses as the final values.
@@ -157,6 +367,7 @@ pub(crate) fn solve_with_priority_inner<A: Analysis>(
             analysis: A::no_constraints(),
             outcome: SolveOutcome {
                 unsatisfied: Vec::new(),
+                residuals: Vec::new(),
                 final_values: initial_guesses
                     .into_iter()
                     .map(|(_id, guess)| guess)
@@ -164,11 +375,19 @@ pub(crate) fn solve_with_priority_inner<A: Analysis>(
                 iterations: 0,
                 warnings: Vec::new(),
                 priority_solved: 0,
-                converged: true,
+                termination_reason: TerminationReason::ResidualTolerance,
+                priority_progress: Vec::new(),
+                per_priority_cost: Vec::new(),
             },
+            iterates: Vec::new(),
         });
     }
 
+    if config.seeds_scalars_from_constraints() {
+        seed_scalars(reqs, &mut initial_guesses);
+    }
+    let config = config.start_time_limit();
+
     let max_id = initial_guesses
         .iter()
         .map(|(id, _)| *id as usize)
@@ -180,27 +399,38 @@ pub(crate) fn solve_with_priority_inner<A: Analysis>(
     }
 
     // Infer any undefined constraint state from initial values
+    let num_requests = reqs.len();
     let mut reqs = reqs.to_vec();
     for req in &mut reqs {
         req.set_from_initial_values(&initial_values);
     }
 
-    let reqs: Vec<_> = reqs
-        .iter()
-        .enumerate()
-        .map(|(id, c)| ConstraintEntry {
-            constraint: c.constraint(),
-            priority: c.priority(),
-            weight: c.weight(),
-            id,
-        })
-        .collect();
+    let mut reqs = constraint_entries(&reqs);
+
+    if let PriorityMode::Weighted { base } = config.priority_mode()
+        && !reqs.is_empty()
+    {
+        // Every priority level in one solve, with lower priorities weighted down.
+        for req in &mut reqs {
+            req.weight *= libm::pow(base, -f64::from(req.priority));
+        }
+        let level = symbolic.map(|cache| symbolic_slot(cache, 0));
+        let outcome = solve_inner(&reqs, num_requests, initial_guesses, &config, bounds, level)?;
+        if let Some(stages) = stages {
+            stages.push((outcome.outcome.priority_solved, outcome.outcome.clone()));
+        }
+        return Ok(outcome);
+    }
 
     // Find all the priority levels, and put them into order from highest to lowest priority.
     let priorities: HashSet<_> = reqs.iter().map(|c| c.priority).collect();
     let mut priorities: Vec<_> = priorities.into_iter().collect();
     let lowest_priority = priorities.iter().min().copied().unwrap_or(0);
     priorities.sort();
+    // The residual reached at each priority level, reported whether or not the solve succeeds.
+    let mut priority_progress = Vec::with_capacity(priorities.len());
+    // The work done at each priority level, reported alongside the residuals above.
+    let mut per_priority_cost = Vec::with_capacity(priorities.len());
 
     // Handle the case with 0 constraints.
     // (this gets used below, if the per-constraint loop never returns).
@@ -212,7 +442,7 @@ pub(crate) fn solve_with_priority_inner<A: Analysis>(
     // or cannot find a solution that satisfies all of them.
     let mut constraint_subset: Vec<ConstraintEntry<'_>> = Vec::with_capacity(total_constraints);
 
-    for curr_max_priority in priorities {
+    for (level, curr_max_priority) in priorities.into_iter().enumerate() {
         constraint_subset.clear();
         for req in &reqs {
             if req.priority <= curr_max_priority {
@@ -221,35 +451,67 @@ pub(crate) fn solve_with_priority_inner<A: Analysis>(
         }
         let solve_res = solve_inner(
             constraint_subset.as_slice(),
+            num_requests,
             initial_guesses.clone(),
-            config,
+            &config,
+            bounds,
+            symbolic
+                .as_deref_mut()
+                .map(|cache| symbolic_slot(cache, level)),
         );
 
         match solve_res {
-            Ok(outcome) => {
+            Ok(mut outcome) => {
+                if let Some(stages) = stages.as_deref_mut() {
+                    stages.push((curr_max_priority, outcome.outcome.clone()));
+                }
+                priority_progress.append(&mut outcome.outcome.priority_progress);
+                per_priority_cost.append(&mut outcome.outcome.per_priority_cost);
                 // If there were unsatisfied constraints, then there's no point trying to add more lower-priority constraints,
                 // just return now.
                 if outcome.outcome.is_unsatisfied() {
-                    return Ok(res.unwrap_or(outcome));
+                    let mut outcome = res.unwrap_or(outcome);
+                    outcome
+                        .outcome
+                        .set_priority_history(priority_progress, per_priority_cost);
+                    return Ok(outcome);
                 }
                 // Otherwise, continue the loop again, adding higher-priority constraints.
                 res = Some(outcome);
             }
             // If this constraint couldn't be solved,
-            Err(e) => {
+            Err(mut e) => {
+                priority_progress.append(&mut e.priority_progress);
                 // then return a previous solved system with fewer (higher-priority) constraints,
                 // or if there was no such previous system, then this was the first run,
                 // and we should just return the error.
-                return res.ok_or(e);
+                return match res {
+                    Some(mut outcome) => {
+                        outcome
+                            .outcome
+                            .set_priority_history(priority_progress, per_priority_cost);
+                        Ok(outcome)
+                    }
+                    None => {
+                        e.priority_progress = priority_progress;
+                        Err(e)
+                    }
+                };
             }
         }
     }
+    if let Some(outcome) = &mut res {
+        outcome
+            .outcome
+            .set_priority_history(priority_progress, per_priority_cost);
+    }
     // The unwrap default value is used when
     // there were 0 constraints.
     Ok(res.unwrap_or(SolveOutcomeAnalysis {
         analysis: A::no_constraints(),
         outcome: SolveOutcome {
             unsatisfied: Vec::new(),
+            residuals: vec![f64::NAN; num_requests],
             final_values: initial_guesses
                 .into_iter()
                 .map(|(_id, guess)| guess)
@@ -257,16 +519,58 @@ pub(crate) fn solve_with_priority_inner<A: Analysis>(
             iterations: 0,
             warnings: Vec::new(),
             priority_solved: lowest_priority,
-            converged: true,
+            termination_reason: TerminationReason::ResidualTolerance,
+            priority_progress: Vec::new(),
+            per_priority_cost: Vec::new(),
         },
+        iterates: Vec::new(),
     }))
 }
 
+/// The cached symbolic structure for this priority level, which is empty if it wasn't
+/// cached yet.
+fn symbolic_slot(
+    cache: &mut Vec<Option<SymbolicStructure>>,
+    level: usize,
+) -> &mut Option<SymbolicStructure> {
+    if cache.len() <= level {
+        cache.resize_with(level + 1, || None);
+    }
+    &mut cache[level]
+}
+
+/// Replace zero guesses with the values their constraints imply,
+/// see [`Config::with_seed_scalars_from_constraints`].
+fn seed_scalars(reqs: &[ConstraintRequest], initial_guesses: &mut [(Id, f64)]) {
+    let seeds: Vec<_> = reqs
+        .iter()
+        .filter(|req| req.is_enabled())
+        .filter_map(|req| req.constraint().scalar_seed())
+        .collect();
+    if seeds.is_empty() {
+        return;
+    }
+    for (id, guess) in initial_guesses {
+        if *guess != 0.0 {
+            continue;
+        }
+        if let Some(&(_, seed)) = seeds.iter().find(|(seed_id, _)| seed_id == id) {
+            *guess = seed;
+        }
+    }
+}
+
+/// `num_requests` is how many constraint requests `constraints` came from,
+/// so each outcome has a residual for every one of them.
 fn solve_inner<A: Analysis>(
     constraints: &[ConstraintEntry<'_>],
+    num_requests: usize,
     initial_guesses: Vec<(Id, f64)>,
-    config: Config,
+    config: &Config,
+    bounds: &[(Id, f64, f64)],
+    symbolic: Option<&mut Option<SymbolicStructure>>,
 ) -> Result<SolveOutcomeAnalysis<A>, FailureOutcome> {
+    let start = start_timer();
     let num_vars = initial_guesses.len();
     let num_eqs = constraints
         .iter()
@@ -275,8 +579,16 @@ fn solve_inner<A: Analysis>(
     let (all_variables, mut values): (Vec<Id>, Vec<f64>) = initial_guesses.into_iter().unzip();
     let mut warnings = warnings::lint(constraints);
     let initial_values = values.clone();
+    let lowest_priority = constraints
+        .iter()
+        .map(|c| c.priority)
+        .max()
+        .unwrap_or_default();
 
-    let mut model = match Model::new(constraints, all_variables, initial_values, config) {
+    let cached = symbolic.as_ref().and_then(|slot| slot.as_ref());
+    let model =
+        Model::new_with_symbolic(constraints, all_variables, initial_values, config, cached);
+    let mut model = match model {
         Ok(o) => o,
         Err(error) => {
             return Err(FailureOutcome {
@@ -284,13 +596,36 @@ fn solve_inner<A: Analysis>(
                 warnings,
                 num_vars,
                 num_eqs,
+                priority_progress: Vec::new(),
             });
         }
     };
 
+    if let Some(slot) = symbolic
+        && slot.is_none()
+    {
+        *slot = Some(model.symbolic_structure());
+    }
+    model.set_bounds(bounds);
+    model.clamp_to_bounds(&mut values);
+
     let mut unsatisfied: Vec<usize> = Vec::new();
-    let outcome = model.solve_levenberg_marquardt(&mut values, config);
+    let mut residuals = vec![f64::NAN; num_requests];
+    let outcome = if constraints.iter().all(|c| c.constraint.is_linear()) {
+        model.solve_linear(&mut values, config)
+    } else {
+        model.solve_levenberg_marquardt(&mut values, config)
+    };
     warnings.extend(model.warnings.lock().unwrap().drain(..));
+    model.extend_zero_row_warnings(&mut warnings);
+    let residual_norm = evaluate_residuals(
+        constraints,
+        &values,
+        config,
+        &mut unsatisfied,
+        &mut residuals,
+    );
+    let priority_progress = vec![(lowest_priority, residual_norm)];
     let success = match outcome {
         Ok(o) => o,
         Err(error) => {
@@ -299,32 +634,11 @@ fn solve_inner<A: Analysis>(
                 warnings,
                 num_vars,
                 num_eqs,
+                priority_progress,
             });
         }
     };
-    let cs: Vec<_> = constraints.iter().map(|c| c.constraint).collect();
-    let layout = solver::Layout::new(&Vec::new(), cs.as_slice(), config);
-    for constraint in constraints {
-        let mut residual0 = 0.0;
-        let mut residual1 = 0.0;
-        let mut residual2 = 0.0;
-        let mut degenerate = false;
-        constraint.constraint.residual(
-            &layout,
-            &values,
-            &mut residual0,
-            &mut residual1,
-            &mut residual2,
-            &mut degenerate,
-        );
-        let satisfied = is_satisfied(
-            constraint.constraint.residual_dim(),
-            [residual0, residual1, residual2],
-        );
-        if !satisfied {
-            unsatisfied.push(constraint.id);
-        }
-    }
+    let mut iterates = std::mem::take(&mut model.iterates);
     let analysis = match A::analyze(model) {
         Ok(o) => o,
         Err(error) => {
@@ -333,28 +647,115 @@ fn solve_inner<A: Analysis>(
                 warnings,
                 num_vars,
                 num_eqs,
+                priority_progress,
             });
         }
     };
 
-    let lowest_priority = constraints
-        .iter()
-        .map(|c| c.priority)
-        .max()
-        .unwrap_or_default();
+    if config.normalizes_angles() {
+        normalize_angles(constraints, &mut values);
+    }
+    if config.records_iterates() {
+        // One iterate per iteration, and the last one is where the solve finished.
+        iterates.resize_with(success.iterations, Vec::new);
+        if let Some(last) = iterates.last_mut() {
+            last.clone_from(&values);
+        }
+    }
+
+    let per_priority_cost = vec![PriorityCost {
+        priority: lowest_priority,
+        iterations: success.iterations,
+        duration: start.map(|start| start.elapsed()),
+    }];
+
     Ok(SolveOutcomeAnalysis {
         outcome: SolveOutcome {
             priority_solved: lowest_priority,
             unsatisfied,
+            residuals,
             final_values: values,
             iterations: success.iterations,
             warnings,
-            converged: success.converged,
+            termination_reason: success.reason,
+            priority_progress,
+            per_priority_cost,
         },
         analysis,
+        iterates,
     })
 }
 
+/// Wrap every variable which these constraints treat as an angle into (-π, π].
+fn normalize_angles(constraints: &[ConstraintEntry<'_>], values: &mut [f64]) {
+    let mut angles = Vec::new();
+    for c in constraints {
+        c.constraint.extend_angle_variable_ids(&mut angles);
+    }
+    for id in angles {
+        if let Some(value) = values.get_mut(id as usize) {
+            *value = constraints::wrap_angle_delta(*value);
+        }
+    }
+}
+
+/// Start timing a solve, on platforms which have a clock.
+fn start_timer() -> Option<std::time::Instant> {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    {
+        Some(std::time::Instant::now())
+    }
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    {
+        None
+    }
+}
+
+/// Evaluate every constraint at `values`, pushing the IDs of unsatisfied constraints into
+/// `unsatisfied`, and writing the norm of each constraint's residual into `residual_norms`
+/// at its ID. Returns the Euclidean norm of all residuals.
+fn evaluate_residuals(
+    constraints: &[ConstraintEntry<'_>],
+    values: &[f64],
+    config: &Config,
+    unsatisfied: &mut Vec<usize>,
+    residual_norms: &mut [f64],
+) -> f64 {
+    let cs: Vec<_> = constraints.iter().map(|c| c.constraint).collect();
+    let layout = solver::Layout::new(&Vec::new(), cs.as_slice(), config);
+    let mut residual_sq = 0.0;
+    for constraint in constraints {
+        let mut residual0 = 0.0;
+        let mut residual1 = 0.0;
+        let mut residual2 = 0.0;
+        let mut degenerate = false;
+        constraint.constraint.residual(
+            &layout,
+            values,
+            &mut residual0,
+            &mut residual1,
+            &mut residual2,
+            &mut degenerate,
+        );
+        let residual_dim = constraint.constraint.residual_dim();
+        let kinds = constraint.constraint.residual_kinds();
+        let residuals = [
+            kinds[0].clamp(residual0),
+            kinds[1].clamp(residual1),
+            kinds[2].clamp(residual2),
+        ];
+        let constraint_sq = residuals[..residual_dim].iter().map(|r| r * r).sum::<f64>();
+        residual_sq += constraint_sq;
+        if let Some(norm) = residual_norms.get_mut(constraint.id) {
+            *norm = libm::sqrt(constraint_sq);
+        }
+        if !is_satisfied(residual_dim, residuals) {
+            unsatisfied.push(constraint.id);
+        }
+    }
+    libm::sqrt(residual_sq)
+}
+
 fn is_satisfied(residual_dim: usize, residuals: [f64; 3]) -> bool {
     let sat0 = residuals[0].abs() < EPSILON;
     let sat1 = residuals[1].abs() < EPSILON;
@@ -0,0 +1,102 @@
+use crate::{Config, ConstraintRequest, Id, SolveOutcome, solve};
+
+/// Seed for the perturbations, so that multistart solves are reproducible.
+const SEED: u64 = 0x5EED_E2E2;
+
+/// Two solutions whose final values are closer than this (relative to the size of the
+/// initial guesses) are considered the same solution.
+const DISTINCT_TOLERANCE: f64 = 1e-6;
+
+/// Solve the system from several different starting points, and return each distinct solution.
+/// Many geometric problems have more than one valid solution (e.g. which side of a line a
+/// tangent circle sits on), and the solver only finds whichever is closest to the initial guesses.
+/// This is useful for exploring the alternatives.
+///
+/// The first start uses the initial guesses as given. Each later start perturbs every guess by
+/// up to the size of the largest guess (or 1, if that's smaller), using a fixed seed, so the
+/// results are the same every time. Only solves which converged and satisfied every constraint
+/// are returned, in the order they were first found. So the result is empty if no start solved
+/// the system.
+/// ```
+/// use ezpz::{Config, Constraint, ConstraintRequest, IdGenerator, solve_multistart};
+///
+/// // x² = 4 has two roots, which can be found by fixing |(x, 0) - (0, 0)| = 2.
+/// let mut ids = IdGenerator::default();
+/// let origin = ezpz::datatypes::inputs::DatumPoint::new(&mut ids);
+/// let p = ezpz::datatypes::inputs::DatumPoint::new(&mut ids);
+/// let requests = [
+///     ConstraintRequest::highest_priority(Constraint::Fixed(origin.id_x(), 0.0)),
+///     ConstraintRequest::highest_priority(Constraint::Fixed(origin.id_y(), 0.0)),
+///     ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+///     ConstraintRequest::highest_priority(Constraint::Distance(origin, p, 2.0)),
+/// ];
+/// let initial_guesses = vec![
+///     (origin.id_x(), 0.0),
+///     (origin.id_y(), 0.0),
+///     (p.id_x(), 1.0),
+///     (p.id_y(), 0.0),
+/// ];
+/// let solutions = solve_multistart(&requests, initial_guesses, Config::default(), 10);
+/// assert_eq!(solutions.len(), 2);
+/// ```
+pub fn solve_multistart(
+    reqs: &[ConstraintRequest],
+    initial_guesses: Vec<(Id, f64)>,
+    config: Config,
+    n_starts: usize,
+) -> Vec<SolveOutcome> {
+    let scale = initial_guesses
+        .iter()
+        .map(|(_id, guess)| guess.abs())
+        .fold(1.0, libm::fmax);
+    let tolerance = DISTINCT_TOLERANCE * scale;
+    let mut rng = SplitMix64(SEED);
+    let mut solutions: Vec<SolveOutcome> = Vec::new();
+    let mut guesses = initial_guesses.clone();
+    for start in 0..n_starts {
+        if start > 0 {
+            for ((_id, guess), (_, initial)) in guesses.iter_mut().zip(&initial_guesses) {
+                *guess = initial + scale * rng.next_signed_unit();
+            }
+        }
+        let Ok(outcome) = solve(reqs, guesses.clone(), config.clone()) else {
+            continue;
+        };
+        if !outcome.converged() || outcome.is_unsatisfied() {
+            continue;
+        }
+        let is_new = solutions
+            .iter()
+            .all(|seen| distance(seen.final_values(), outcome.final_values()) > tolerance);
+        if is_new {
+            solutions.push(outcome);
+        }
+    }
+    solutions
+}
+
+/// Euclidean distance between two solutions' final values.
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    libm::sqrt(a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum())
+}
+
+/// Small, seedable pseudo-random number generator.
+/// See <https://prng.di.unimi.it/splitmix64.c>.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in [-1, 1).
+    fn next_signed_unit(&mut self) -> f64 {
+        // The top 53 bits fill an f64's mantissa exactly.
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        2.0 * unit - 1.0
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    Constraint, ConstraintRequest, EPSILON, Id, IdGenerator,
+    datatypes::{
+        inputs::{DatumCircularArc, DatumLineSegment, DatumPoint},
+        outputs::Point,
+    },
+    vector::V,
+};
+
+/// Builds geometry and constraints from drawing commands, like `moveTo`, `lineTo` and `arcTo`
+/// in vector-path formats. Each segment gets its own points, which are joined to the previous
+/// segment's end with [`Constraint::PointsCoincident`], so later constraints can still refer to
+/// each segment on its own. Arcs are also constrained to their radius.
+/// ```
+/// use ezpz::{Config, IdGenerator, datatypes::outputs::Point, path::PathBuilder, solve};
+///
+/// // A D shape: a straight side, closed by a semicircle.
+/// let mut ids = IdGenerator::default();
+/// let (geometry, requests) = PathBuilder::new()
+///     .move_to(Point { x: 0.0, y: -2.0 })
+///     .line_to(Point { x: 0.0, y: 2.0 })
+///     .arc_to(Point { x: 0.0, y: -2.0 }, -2.0)
+///     .close()
+///     .build(&mut ids);
+/// assert_eq!(geometry.segments.len(), 2);
+/// let solved = solve(&requests, geometry.initial_guesses(), Config::default()).unwrap();
+/// assert!(solved.is_satisfied());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PathBuilder {
+    commands: Vec<Command>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Command {
+    MoveTo(Point),
+    LineTo(Point),
+    ArcTo(Point, f64),
+    Close,
+}
+
+/// One segment of a path, see [`PathGeometry::segments`].
+#[derive(Clone, Copy, Debug)]
+pub enum PathSegment {
+    /// A straight line, from `p0` to `p1` in the path's direction.
+    Line(DatumLineSegment),
+    /// An arc. Arcs always go counterclockwise from their start to their end,
+    /// so a clockwise arc in the path goes from the arc's end to its start.
+    Arc(DatumCircularArc),
+}
+
+/// Handles to the geometry built by [`PathBuilder::build`], so you can constrain it further.
+#[derive(Clone, Debug)]
+pub struct PathGeometry {
+    /// Every segment, in the order they were drawn.
+    pub segments: Vec<PathSegment>,
+    initial_guesses: Vec<(Id, f64)>,
+}
+
+impl PathGeometry {
+    /// Initial guesses for every variable in the path, from the coordinates
+    /// it was drawn with, in the order their IDs were made.
+    pub fn initial_guesses(&self) -> Vec<(Id, f64)> {
+        self.initial_guesses.clone()
+    }
+}
+
+impl PathBuilder {
+    /// An empty path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new subpath at this point, without joining it to the previous one.
+    pub fn move_to(mut self, point: Point) -> Self {
+        self.commands.push(Command::MoveTo(point));
+        self
+    }
+
+    /// Draw a straight line from the current point to this one.
+    /// If nothing was drawn yet, the path starts at the origin.
+    pub fn line_to(mut self, point: Point) -> Self {
+        self.commands.push(Command::LineTo(point));
+        self
+    }
+
+    /// Draw the shorter arc with this radius from the current point to this one.
+    /// A positive radius goes counterclockwise, a negative one goes clockwise.
+    /// If the points are more than a diameter apart, the arc is a semicircle
+    /// between them, i.e. its radius is as small as it can be.
+    pub fn arc_to(mut self, point: Point, radius: f64) -> Self {
+        self.commands.push(Command::ArcTo(point, radius));
+        self
+    }
+
+    /// Finish the current subpath where it started, drawing a line back to its start
+    /// if it isn't already there.
+    pub fn close(mut self) -> Self {
+        self.commands.push(Command::Close);
+        self
+    }
+
+    /// Make the points and arcs for every segment, and the constraints joining them.
+    pub fn build(&self, id_generator: &mut IdGenerator) -> (PathGeometry, Vec<ConstraintRequest>) {
+        let mut segments = Vec::with_capacity(self.commands.len());
+        let mut guesses = Vec::with_capacity(6 * self.commands.len());
+        let mut constraints = Vec::with_capacity(2 * self.commands.len());
+        let mut new_point = |guess: V| {
+            let point = DatumPoint::new(id_generator);
+            guesses.push((point.id_x(), guess.x));
+            guesses.push((point.id_y(), guess.y));
+            point
+        };
+
+        let mut current = V::new(0.0, 0.0);
+        let mut subpath_start = current;
+        // The first and last points of the current subpath's segments so far.
+        let mut first_point: Option<DatumPoint> = None;
+        let mut last_point: Option<DatumPoint> = None;
+        for &command in &self.commands {
+            let (start, end, segment) = match command {
+                Command::MoveTo(point) => {
+                    current = V::new(point.x, point.y);
+                    subpath_start = current;
+                    first_point = None;
+                    last_point = None;
+                    continue;
+                }
+                Command::LineTo(point) => {
+                    let to = V::new(point.x, point.y);
+                    let line = DatumLineSegment::new(new_point(current), new_point(to));
+                    current = to;
+                    (line.p0, line.p1, PathSegment::Line(line))
+                }
+                Command::ArcTo(point, radius) => {
+                    let (from_xy, to) = (current, V::new(point.x, point.y));
+                    let center = new_point(arc_center(from_xy, to, radius));
+                    let (from, to_point) = (new_point(from_xy), new_point(to));
+                    // Arcs go counterclockwise, so a clockwise arc runs from its end to its start.
+                    let arc = if radius >= 0.0 {
+                        DatumCircularArc {
+                            center,
+                            start: from,
+                            end: to_point,
+                        }
+                    } else {
+                        DatumCircularArc {
+                            center,
+                            start: to_point,
+                            end: from,
+                        }
+                    };
+                    constraints.push(Constraint::Arc(arc));
+                    // Too short a radius can't reach, so it becomes a semicircle.
+                    let radius = libm::fmax(radius.abs(), to.euclidean_distance(from_xy) / 2.0);
+                    constraints.push(Constraint::ArcRadius(arc, radius));
+                    current = to;
+                    (from, to_point, PathSegment::Arc(arc))
+                }
+                Command::Close => {
+                    let Some(first) = first_point else {
+                        continue;
+                    };
+                    let end = if current.euclidean_distance(subpath_start) > EPSILON {
+                        let line =
+                            DatumLineSegment::new(new_point(current), new_point(subpath_start));
+                        if let Some(last) = last_point {
+                            constraints.push(Constraint::PointsCoincident(last, line.p0));
+                        }
+                        segments.push(PathSegment::Line(line));
+                        line.p1
+                    } else {
+                        // Already back at the start.
+                        let Some(last) = last_point else {
+                            continue;
+                        };
+                        last
+                    };
+                    constraints.push(Constraint::PointsCoincident(end, first));
+                    current = subpath_start;
+                    first_point = None;
+                    last_point = None;
+                    continue;
+                }
+            };
+            if let Some(last) = last_point {
+                constraints.push(Constraint::PointsCoincident(last, start));
+            }
+            first_point.get_or_insert(start);
+            last_point = Some(end);
+            segments.push(segment);
+        }
+
+        let geometry = PathGeometry {
+            segments,
+            initial_guesses: guesses,
+        };
+        let requests = constraints
+            .into_iter()
+            .map(ConstraintRequest::highest_priority)
+            .collect();
+        (geometry, requests)
+    }
+}
+
+/// Center of the shorter arc with this (signed) radius from `from` to `to`.
+fn arc_center(from: V, to: V, radius: f64) -> V {
+    let chord = to - from;
+    let half_chord = chord.magnitude() / 2.0;
+    let midpoint = from + chord * 0.5;
+    if half_chord < EPSILON {
+        return midpoint;
+    }
+    // The center is to the left of the chord for a counterclockwise arc, right for clockwise.
+    let offset = libm::sqrt(libm::fmax(radius * radius - half_chord * half_chord, 0.0));
+    let left = chord.perp_ccw() * (half_chord * 2.0).recip();
+    midpoint + left * offset.copysign(radius)
+}
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Config, ConstraintRequest, FailureOutcome, Id, ReproError, SolveOutcome};
+
+/// Bump this whenever the repro format changes incompatibly.
+const REPRO_VERSION: u32 = 1;
+
+/// Everything the solver needs to reproduce a solve.
+/// Borrows its contents, so dumping doesn't need to clone the problem.
+#[derive(Serialize)]
+struct ReproRef<'a> {
+    version: u32,
+    constraints: &'a [ConstraintRequest],
+    initial_guesses: &'a [(Id, f64)],
+    config: Config,
+}
+
+/// Owned version of [`ReproRef`], for replaying.
+#[derive(Deserialize)]
+struct Repro {
+    version: u32,
+    constraints: Vec<ConstraintRequest>,
+    initial_guesses: Vec<(Id, f64)>,
+    config: Config,
+}
+
+/// Serialize a solve's inputs into self-contained JSON.
+/// Pass the output to [`replay`] to reproduce the exact same solve,
+/// e.g. when attaching a problem to a bug report.
+/// Errors with [`ReproError::UnserializableConfig`] if the config has any setting which is
+/// code rather than data (e.g. [`Config::with_on_iteration`]), because the replay couldn't
+/// run the same solve without it.
+pub fn dump_repro(
+    reqs: &[ConstraintRequest],
+    initial_guesses: &[(Id, f64)],
+    config: Config,
+) -> Result<String, ReproError> {
+    if let Some(setting) = config.unserializable_setting() {
+        return Err(ReproError::UnserializableConfig { setting });
+    }
+    let repro = ReproRef {
+        version: REPRO_VERSION,
+        constraints: reqs,
+        initial_guesses,
+        config,
+    };
+    Ok(serde_json::to_string_pretty(&repro)?)
+}
+
+/// Solve a problem previously serialized by [`dump_repro`].
+/// Errors if the repro couldn't be read, otherwise returns the result of the solve.
+pub fn replay(repro: &str) -> Result<Result<SolveOutcome, FailureOutcome>, ReproError> {
+    let Repro {
+        version,
+        constraints,
+        initial_guesses,
+        config,
+    } = serde_json::from_str(repro)?;
+    if version != REPRO_VERSION {
+        return Err(ReproError::UnsupportedVersion {
+            found: version,
+            expected: REPRO_VERSION,
+        });
+    }
+    Ok(crate::solve(&constraints, initial_guesses, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Constraint, IdGenerator, LineSide, PriorityMode,
+        datatypes::inputs::{DatumCircle, DatumDistance, DatumLineSegment, DatumPoint},
+    };
+
+    #[test]
+    fn replay_matches_original_solve() {
+        let mut ids = IdGenerator::default();
+        let p = DatumPoint::new(&mut ids);
+        let q = DatumPoint::new(&mut ids);
+        let circle = DatumCircle {
+            center: DatumPoint::new(&mut ids),
+            radius: DatumDistance::new(ids.next_id()),
+        };
+        let reqs = [
+            ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Distance(p, q, 4.0)),
+            ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.0)),
+            ConstraintRequest::new(
+                Constraint::LineTangentToCircle(
+                    DatumLineSegment::new(p, q),
+                    circle,
+                    LineSide::Undefined,
+                ),
+                1,
+            )
+            .with_weight(2.5),
+        ];
+        let initial_guesses = vec![
+            (p.id_x(), 0.1),
+            (p.id_y(), -0.2),
+            (q.id_x(), 3.3),
+            (q.id_y(), 1.7),
+            (circle.center.id_x(), 1.0),
+            (circle.center.id_y(), 2.0),
+            (circle.radius.id, 0.5),
+        ];
+        let config = Config::default().with_max_iterations(50);
+
+        let repro = dump_repro(&reqs, &initial_guesses, config.clone()).unwrap();
+        let mut original = crate::solve(&reqs, initial_guesses, config).unwrap();
+        let mut replayed = replay(&repro).unwrap().unwrap();
+        // Wall-clock time is the one thing a replay can't reproduce.
+        for cost in original
+            .per_priority_cost
+            .iter_mut()
+            .chain(replayed.per_priority_cost.iter_mut())
+        {
+            cost.duration = None;
+        }
+        // Debug output includes every other field, with floats printed exactly.
+        assert_eq!(format!("{original:?}"), format!("{replayed:?}"));
+    }
+
+    #[test]
+    fn repro_round_trips_config() {
+        let mut ids = IdGenerator::default();
+        let x = ids.next_id();
+        let reqs = [
+            ConstraintRequest::new(Constraint::Fixed(x, 0.0), 0),
+            ConstraintRequest::new(Constraint::Fixed(x, 1.0), 1),
+        ];
+        let initial_guesses = vec![(x, 0.5)];
+        let config = Config::default()
+            .with_priority_mode(PriorityMode::Weighted { base: 10.0 })
+            .with_convergence_tolerance(1e-10)
+            .with_length_tolerance(1e-6)
+            .with_stall_window(4)
+            .with_regularization_weights([(x, 1e-3)].into())
+            .with_max_iterations(12);
+
+        let repro = dump_repro(&reqs, &initial_guesses, config.clone()).unwrap();
+        let Repro {
+            config: replayed_config,
+            ..
+        } = serde_json::from_str(&repro).unwrap();
+        assert_eq!(format!("{config:?}"), format!("{replayed_config:?}"));
+        // The weighted mode lets the lower priority pull X away from 0.
+        let original = crate::solve(&reqs, initial_guesses, config).unwrap();
+        let replayed = replay(&repro).unwrap().unwrap();
+        assert_eq!(original.final_values(), replayed.final_values());
+        assert!(replayed.final_values()[0] > 0.0);
+    }
+
+    #[test]
+    fn dump_repro_rejects_code_in_config() {
+        let config = Config::default().with_on_iteration(|_stats| {});
+        let err = dump_repro(&[], &[], config).unwrap_err();
+        assert!(matches!(
+            err,
+            ReproError::UnserializableConfig {
+                setting: "on_iteration"
+            }
+        ));
+    }
+
+    #[test]
+    fn replay_rejects_other_versions() {
+        let repro = dump_repro(&[], &[], Config::default()).unwrap();
+        let repro = repro.replace("\"version\": 1", "\"version\": 999");
+        let err = replay(&repro).unwrap_err();
+        assert!(matches!(
+            err,
+            ReproError::UnsupportedVersion { found: 999, .. }
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{
+    Config, ConstraintRequest, FailureOutcome, Id, SolveOutcome, analysis::NoAnalysis,
+    solve_with_priority_inner, solver::SymbolicStructure,
+};
+
+/// Solves the same constraints over and over from different initial guesses, e.g. on every
+/// frame while the user drags a point, reusing the symbolic analysis of the sparse system
+/// between solves. The Jacobian's sparsity pattern and the symbolic factorization of `JᵀJ + λI`
+/// only depend on the constraints and which variables are solved for, so after the first solve
+/// each [`SolverSession::resolve`] only refreshes values and factors them numerically.
+/// ```
+/// use ezpz::{Config, Constraint, ConstraintRequest, IdGenerator, SolverSession};
+/// use ezpz::datatypes::inputs::DatumPoint;
+///
+/// let mut ids = IdGenerator::default();
+/// let p = DatumPoint::new(&mut ids);
+/// let q = DatumPoint::new(&mut ids);
+/// let requests = [
+///     ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+///     ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+///     ConstraintRequest::highest_priority(Constraint::Distance(p, q, 4.0)),
+/// ];
+/// let mut session = SolverSession::new(&requests, Config::default());
+/// // Drag Q around the circle.
+/// for y in [0.0, 1.0, 2.0] {
+///     let guesses = vec![(p.id_x(), 0.0), (p.id_y(), 0.0), (q.id_x(), 3.0), (q.id_y(), y)];
+///     assert!(session.resolve(guesses).unwrap().is_satisfied());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SolverSession {
+    reqs: Vec<ConstraintRequest>,
+    config: Config,
+    /// Which variables the cached structure was built for, in order.
+    variables: Vec<Id>,
+    /// The symbolic structure of each priority level's system, once it's been built.
+    symbolic: Vec<Option<SymbolicStructure>>,
+}
+
+impl SolverSession {
+    /// Start a session for solving these constraints.
+    /// Nothing is computed until the first [`SolverSession::resolve`].
+    pub fn new(reqs: &[ConstraintRequest], config: Config) -> Self {
+        Self {
+            reqs: reqs.to_vec(),
+            config,
+            variables: Vec::new(),
+            symbolic: Vec::new(),
+        }
+    }
+
+    /// Just like [`crate::solve`] with this session's constraints and config.
+    /// If the guesses are for different variables (or in a different order) than the last
+    /// solve, the symbolic analysis is rebuilt for them.
+    pub fn resolve(
+        &mut self,
+        initial_guesses: Vec<(Id, f64)>,
+    ) -> Result<SolveOutcome, FailureOutcome> {
+        let same_variables = self.variables.len() == initial_guesses.len()
+            && self
+                .variables
+                .iter()
+                .zip(&initial_guesses)
+                .all(|(&cached, &(id, _guess))| cached == id);
+        if !same_variables {
+            self.variables.clear();
+            self.variables
+                .extend(initial_guesses.iter().map(|&(id, _guess)| id));
+            self.symbolic.clear();
+        }
+        let out = solve_with_priority_inner::<NoAnalysis>(
+            &self.reqs,
+            initial_guesses,
+            self.config.clone(),
+            &[],
+            Some(&mut self.symbolic),
+            None,
+        )?;
+        Ok(out.outcome)
+    }
+
+    /// The constraints this session solves.
+    pub fn requests(&self) -> &[ConstraintRequest] {
+        &self.reqs
+    }
+}
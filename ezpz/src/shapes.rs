@@ -0,0 +1,106 @@
+use crate::{
+    Constraint, ConstraintRequest, Id, IdGenerator,
+    datatypes::inputs::{DatumCircularArc, DatumLineSegment, DatumPoint},
+};
+
+/// Handles to the geometry of a slot from [`slot`], so you can constrain it further.
+/// The outline goes counter-clockwise: along the bottom side, around the right end,
+/// back along the top side, then around the left end.
+#[derive(Clone, Copy, Debug)]
+pub struct SlotGeometry {
+    /// The bottom side, from left to right.
+    pub bottom: DatumLineSegment,
+    /// The top side, from right to left.
+    pub top: DatumLineSegment,
+    /// The left end, from the top side's end to the bottom side's start.
+    pub left: DatumCircularArc,
+    /// The right end, from the bottom side's end to the top side's start.
+    pub right: DatumCircularArc,
+    length: f64,
+    width: f64,
+}
+
+impl SlotGeometry {
+    /// Initial guesses for every variable in the slot, in the order their IDs were made.
+    /// The guesses are already a slot of the right size, lying along the X axis
+    /// with the left end's center at the origin.
+    pub fn initial_guesses(&self) -> Vec<(Id, f64)> {
+        let radius = self.width / 2.0;
+        let points = [
+            (self.left.center, 0.0, 0.0),
+            (self.right.center, self.length, 0.0),
+            (self.bottom.p0, 0.0, -radius),
+            (self.bottom.p1, self.length, -radius),
+            (self.top.p0, self.length, radius),
+            (self.top.p1, 0.0, radius),
+        ];
+        let mut guesses = Vec::with_capacity(2 * points.len());
+        for (point, x, y) in points {
+            guesses.push((point.id_x(), x));
+            guesses.push((point.id_y(), y));
+        }
+        guesses
+    }
+}
+
+/// A slot: two parallel sides of the given length, joined at each end by a semicircle
+/// tangent to both sides. `width` is the distance between the sides, i.e. twice each end's radius.
+/// The constraints fix the slot's size and shape, but not where it is or which way it points.
+/// Solve it with [`SlotGeometry::initial_guesses`].
+/// ```
+/// use ezpz::{Config, IdGenerator, shapes::slot, solve};
+///
+/// let mut ids = IdGenerator::default();
+/// let (geometry, requests) = slot(&mut ids, 10.0, 4.0);
+/// let solved = solve(&requests, geometry.initial_guesses(), Config::default()).unwrap();
+/// assert!(solved.is_satisfied());
+/// ```
+pub fn slot(
+    id_generator: &mut IdGenerator,
+    length: f64,
+    width: f64,
+) -> (SlotGeometry, Vec<ConstraintRequest>) {
+    let left_center = DatumPoint::new(id_generator);
+    let right_center = DatumPoint::new(id_generator);
+    let bottom =
+        DatumLineSegment::new(DatumPoint::new(id_generator), DatumPoint::new(id_generator));
+    let top = DatumLineSegment::new(DatumPoint::new(id_generator), DatumPoint::new(id_generator));
+    let left = DatumCircularArc {
+        center: left_center,
+        start: top.p1,
+        end: bottom.p0,
+    };
+    let right = DatumCircularArc {
+        center: right_center,
+        start: bottom.p1,
+        end: top.p0,
+    };
+    let left_diameter = DatumLineSegment::new(left.start, left.end);
+    let right_diameter = DatumLineSegment::new(right.start, right.end);
+
+    let constraints = [
+        Constraint::lines_parallel([bottom, top]),
+        // Each end is a semicircle, because its center is the midpoint between its ends.
+        Constraint::Midpoint(left_diameter, left.center),
+        Constraint::Midpoint(right_diameter, right.center),
+        // A side is tangent to an end where it's perpendicular to the end's radius.
+        Constraint::lines_perpendicular([bottom, right_diameter]),
+        Constraint::lines_perpendicular([top, left_diameter]),
+        // Dimensions.
+        Constraint::Distance(bottom.p0, bottom.p1, length),
+        Constraint::Distance(left.center, left.start, width / 2.0),
+    ];
+    let geometry = SlotGeometry {
+        bottom,
+        top,
+        left,
+        right,
+        length,
+        width,
+    };
+    let requests = constraints
+        .into_iter()
+        .map(ConstraintRequest::highest_priority)
+        .collect();
+    (geometry, requests)
+}
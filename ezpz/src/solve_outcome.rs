@@ -1,28 +1,38 @@
+use std::time::Duration;
+
 use crate::{
-    FreedomAnalysis, Id, NonLinearSystemError, Warning,
+    ConstraintRequest, FreedomAnalysis, Id, NonLinearSystemError, Warning,
     datatypes::{
-        inputs::{DatumCircle, DatumCircularArc, DatumDistance, DatumPoint},
-        outputs::{Arc, Circle, Point},
+        inputs::{DatumCircle, DatumCircularArc, DatumDistance, DatumLine, DatumPoint},
+        outputs::{Arc, Circle, Line, Point},
     },
 };
 
 /// Data from a successful solved system.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
 pub struct SolveOutcome {
     /// Which constraints couldn't be satisfied
     pub(crate) unsatisfied: Vec<usize>,
-    /// Did the solver converge on a solution?
-    pub(crate) converged: bool,
+    /// The norm of each constraint's residual at the final values.
+    pub(crate) residuals: Vec<f64>,
+    /// Why did the solver stop?
+    pub(crate) termination_reason: TerminationReason,
     /// Each variable's final value.
     pub(crate) final_values: Vec<f64>,
-    /// How many iterations of Newton's method were required?
+    /// How many iterations of Newton's method were required,
+    /// summed over every priority level the solver attempted?
     pub(crate) iterations: usize,
     /// Anything that went wrong either in problem definition or during solving it.
     pub(crate) warnings: Vec<Warning>,
     /// What is the lowest priority that got solved?
     /// 0 is the highest priority. Larger numbers are lower priority.
     pub(crate) priority_solved: u32,
+    /// The residual norm reached at each priority level the solver attempted.
+    pub(crate) priority_progress: Vec<(u32, f64)>,
+    /// How much work the solver did at each priority level it attempted.
+    pub(crate) per_priority_cost: Vec<PriorityCost>,
 }
 
 impl SolveOutcome {
@@ -31,9 +41,27 @@ impl SolveOutcome {
         &self.unsatisfied
     }
 
+    /// How far off each constraint is at the final values, e.g. how many units a distance
+    /// constraint missed by. Each entry is the norm of that constraint's residual
+    /// (constraints like [`crate::Constraint::PointsCoincident`] have one residual per axis),
+    /// indexed like [`SolveOutcome::unsatisfied`], i.e. by the constraint's index in the
+    /// requests this was solved from.
+    /// Constraints which weren't part of this solution, because they were disabled or
+    /// lower priority than [`SolveOutcome::priority_solved`], are NaN.
+    pub fn residuals(&self) -> &[f64] {
+        &self.residuals
+    }
+
     /// Did the solver converge on a solution?
     pub fn converged(&self) -> bool {
-        self.converged
+        self.termination_reason.converged()
+    }
+
+    /// Why did the solver stop?
+    /// With several priority levels, this is for the level this outcome came from
+    /// (see [`SolveOutcome::priority_solved`]).
+    pub fn termination_reason(&self) -> TerminationReason {
+        self.termination_reason
     }
 
     /// Each variable's final value.
@@ -41,7 +69,9 @@ impl SolveOutcome {
         &self.final_values
     }
 
-    /// How many iterations of Newton's method were required?
+    /// How many iterations of Newton's method were required,
+    /// summed over every priority level the solver attempted?
+    /// See [`SolveOutcome::per_priority_cost`] for the breakdown.
     pub fn iterations(&self) -> usize {
         self.iterations
     }
@@ -57,6 +87,18 @@ impl SolveOutcome {
         self.priority_solved
     }
 
+    /// The residual norm reached at each priority level the solver attempted,
+    /// from highest priority to lowest.
+    pub fn priority_progress(&self) -> &[(u32, f64)] {
+        &self.priority_progress
+    }
+
+    /// How much work the solver did at each priority level it attempted,
+    /// from highest priority to lowest. Useful for finding which priority level is expensive.
+    pub fn per_priority_cost(&self) -> &[PriorityCost] {
+        &self.per_priority_cost
+    }
+
     /// Look up the solved value for this distance.
     fn final_value_scalar(&self, id: Id) -> f64 {
         self.final_values[id as usize]
@@ -89,6 +131,75 @@ impl SolveOutcome {
         Circle { center, radius }
     }
 
+    /// Look up the solved values for this infinite line.
+    pub fn final_value_line(&self, line: &DatumLine) -> Line {
+        let angle = self.final_value_scalar(line.angle);
+        let offset = self.final_value_scalar(line.offset);
+        Line { angle, offset }
+    }
+
+    /// Which of these variables finished within `epsilon` of their initial guess?
+    /// The solver leaves unconstrained variables where they started, so combined with
+    /// [`FreedomAnalysis`] this is a good way to highlight free geometry.
+    /// Variables which aren't part of this system are ignored.
+    pub fn unmoved_variables(&self, initial: &[(Id, f64)], epsilon: f64) -> Vec<Id> {
+        let mut unmoved = Vec::with_capacity(initial.len());
+        for &(id, guess) in initial {
+            let Some(&final_value) = self.final_values.get(id as usize) else {
+                continue;
+            };
+            if (final_value - guess).abs() <= epsilon {
+                unmoved.push(id);
+            }
+        }
+        unmoved
+    }
+
+    /// The gradient of the least-squares objective (`Jᵀr`) at the final values, one entry per
+    /// variable, in the same order as [`SolveOutcome::final_values`]. It's near zero at any
+    /// minimum, even one which doesn't satisfy every constraint, so it's useful for checking
+    /// stationarity or for optimization loops which wrap the solver.
+    /// `reqs` should be the constraints this was solved from. Only those at
+    /// [`SolveOutcome::priority_solved`] or higher are included, as they're the ones this solution is for.
+    pub fn objective_gradient(
+        &self,
+        reqs: &[ConstraintRequest],
+    ) -> Result<Vec<f64>, NonLinearSystemError> {
+        let mut gradient = vec![0.0; self.final_values.len()];
+        let solved: Vec<_> = reqs
+            .iter()
+            .filter(|req| req.priority() <= self.priority_solved)
+            .copied()
+            .collect();
+        if solved.is_empty() {
+            return Ok(gradient);
+        }
+        let values: Vec<_> = self
+            .final_values
+            .iter()
+            .enumerate()
+            .map(|(id, &value)| (id as Id, value))
+            .collect();
+        let (residuals, jacobian) = crate::evaluate_at(&solved, &values)?;
+        // Jacobian columns are variable IDs, which index into the final values.
+        for (row, col, partial_derivative) in jacobian {
+            gradient[col] += partial_derivative * residuals[row];
+        }
+        Ok(gradient)
+    }
+
+    /// Record what happened at every priority level the solver attempted,
+    /// not just the one this outcome came from.
+    pub(crate) fn set_priority_history(
+        &mut self,
+        priority_progress: Vec<(u32, f64)>,
+        per_priority_cost: Vec<PriorityCost>,
+    ) {
+        self.iterations = per_priority_cost.iter().map(|cost| cost.iterations).sum();
+        self.priority_progress = priority_progress;
+        self.per_priority_cost = per_priority_cost;
+    }
+
     /// Were all constraints satisfied?
     pub fn is_satisfied(&self) -> bool {
         self.unsatisfied.is_empty()
@@ -100,12 +211,72 @@ impl SolveOutcome {
     }
 }
 
+/// Why the solver stopped iterating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub enum TerminationReason {
+    /// Every residual got within the convergence tolerance, so the system is solved.
+    ResidualTolerance,
+    /// The step got smaller than the step tolerance, so the solver is at a (least-squares)
+    /// solution, which might not satisfy every constraint.
+    StepTolerance,
+    /// The gradient of the squared residual got flatter than the gradient tolerance,
+    /// so the solver is at a (least-squares) solution, which might not satisfy every constraint.
+    GradientTolerance,
+    /// The custom stopping criterion from [`crate::Config::with_stop_when`] said to stop.
+    StopCriterion,
+    /// The solver ran out of iterations before converging.
+    MaxIterations,
+}
+
+impl TerminationReason {
+    /// Did the solver converge, or give up?
+    pub fn converged(self) -> bool {
+        self != Self::MaxIterations
+    }
+}
+
+/// How much work the solver did solving one priority level.
+/// See [`SolveOutcome::per_priority_cost`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub struct PriorityCost {
+    /// The lowest priority included in this level's solve.
+    pub(crate) priority: u32,
+    /// How many iterations of Newton's method this level took.
+    pub(crate) iterations: usize,
+    /// How long this level took to solve and analyze.
+    /// `None` on platforms without a clock, like `wasm32-unknown-unknown`.
+    pub(crate) duration: Option<Duration>,
+}
+
+impl PriorityCost {
+    /// The lowest priority included in this level's solve.
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// How many iterations of Newton's method this level took.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// How long this level took to solve and analyze.
+    /// `None` on platforms without a clock, like `wasm32-unknown-unknown`.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+}
+
 /// Just like [`SolveOutcome`] except it also contains the result of
 /// expensive numeric analysis on the final solved system.
 /// Created from [`crate::solve_analysis`].
 // This is just like `SolveOutcomeAnalysis<FreedomAnalysis>`,
 // except it doesn't leak the private trait `Analysis`.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SolveOutcomeFreedomAnalysis {
     /// Extra analysis for the system,
     /// which is probably expensive to compute.
@@ -133,6 +304,10 @@ pub struct FailureOutcome {
     pub num_vars: usize,
     /// Size of the system.
     pub num_eqs: usize,
+    /// The residual norm reached at each priority level the solver attempted,
+    /// from highest priority to lowest. Useful for seeing how far the solve got
+    /// before it failed.
+    pub priority_progress: Vec<(u32, f64)>,
 }
 
 impl FailureOutcome {
@@ -156,6 +331,12 @@ impl FailureOutcome {
     pub fn num_eqs(&self) -> usize {
         self.num_eqs
     }
+
+    /// The residual norm reached at each priority level the solver attempted,
+    /// from highest priority to lowest.
+    pub fn priority_progress(&self) -> &[(u32, f64)] {
+        &self.priority_progress
+    }
 }
 
 #[cfg(test)]
@@ -168,11 +349,14 @@ mod tests {
         // do what we expect.
         let so = SolveOutcome {
             unsatisfied: vec![0],
+            residuals: vec![1.0],
             final_values: vec![0.3],
             iterations: 1,
             warnings: Vec::new(),
             priority_solved: 0,
-            converged: Default::default(),
+            termination_reason: TerminationReason::ResidualTolerance,
+            priority_progress: Vec::new(),
+            per_priority_cost: Vec::new(),
         };
 
         assert!(so.is_unsatisfied());
@@ -1,15 +1,22 @@
-use std::sync::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use faer::Side;
 use faer::sparse::{Pair, SparseColMatRef, SymbolicSparseColMat, linalg::solvers::SymbolicLlt};
+use faer::{ColMut, ColRef, Side};
 
 use crate::{
     Constraint, ConstraintEntry, NonLinearSystemError, Warning, WarningContent,
-    constraints::JacobianVar, id::Id,
+    constraints::{JacobianVar, ResidualKind, ResidualUnit},
+    id::Id,
 };
 
 mod find_dof;
 mod newton;
+mod sparsity;
+
+pub(crate) use find_dof::underconstrained_columns;
+pub use sparsity::SparsityStats;
 
 // Roughly. Most constraints will only involve roughly 4 variables.
 // May as well round up to the nearest power of 2.
@@ -22,13 +29,56 @@ const NONZEROES_PER_ROW: usize = 8;
 // Ref: https://people.csail.mit.edu/jsolomon/share/book/numerical_book.pdf, 4.1.3
 const DEFAULT_INITIAL_LAMBDA: f64 = 1e-9;
 
+/// See [`Config::with_stop_when`].
+type StopWhenFn = dyn FnMut(&[f64], &[f64]) -> bool + Send;
+
+/// See [`Config::with_on_iteration`].
+type OnIterationFn = dyn FnMut(&IterationStats) + Send;
+
+/// A closure set on a [`Config`], shared by all its clones.
+struct Hook<F: ?Sized>(Arc<Mutex<F>>);
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> std::fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+/// Tells the time, for [`Config::with_time_limit`].
+/// Implement this to use a platform's own clock, e.g. `performance.now()` in the browser,
+/// where [`std::time::Instant`] isn't available.
+pub trait Clock: Sync + std::fmt::Debug {
+    /// How much time has passed since some fixed point in the past.
+    fn now(&self) -> Duration;
+}
+
+/// The standard library's monotonic clock, [`std::time::Instant`].
+/// This is the default [`Clock`], except on `wasm32-unknown-unknown`, which has no clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdClock;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Clock for StdClock {
+    fn now(&self) -> Duration {
+        static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        EPOCH.get_or_init(std::time::Instant::now).elapsed()
+    }
+}
+
 /// Configuration for how to solve a system.
 /// ```
 /// let config = ezpz::Config::default()
 ///     .with_max_iterations(200)
 ///     .with_convergence_tolerance(1e-10);
 /// ```
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct Config {
     /// How many iteration rounds before the solver gives up?
@@ -36,10 +86,64 @@ pub struct Config {
     /// How close can the residual be to 0 before we declare the system is solved?
     /// Smaller number means more precise solves.
     residual_tolerance: f64,
+    /// Largest length residual that counts as converged, overriding `residual_tolerance`.
+    length_tolerance: Option<f64>,
+    /// Largest angular error (in radians) that counts as converged, overriding `residual_tolerance`.
+    angle_tolerance: Option<f64>,
     /// Stop iterating if the step size becomes negligible (relative infinity norm).
     step_tolerance: f64,
+    /// Stop iterating if the gradient of the squared residual becomes negligible (infinity norm).
+    gradient_tolerance: f64,
     /// Initial value of the Levenberg-Marquardt damping parameter λ.
     initial_lambda: f64,
+    /// Scale the damping parameter λ by the current residual norm.
+    adaptive_regularization: bool,
+    /// Weight of the Tikhonov term pulling every variable towards its initial guess.
+    regularization_lambda: f64,
+    /// Fail as soon as a step grows the residual norm past `divergence_ratio` times the best seen.
+    abort_on_divergence: bool,
+    /// How much the residual norm may grow before the solve counts as diverging.
+    divergence_ratio: f64,
+    /// How many iterations the residual norm gets to improve by `stall_tolerance`
+    /// before the solve counts as stalled. 0 never stalls.
+    stall_window: usize,
+    /// Smallest relative improvement in the residual norm, over `stall_window` iterations,
+    /// which isn't a stall.
+    stall_tolerance: f64,
+    /// Fall back to gradient descent if the damped Gauss-Newton system can't be factored.
+    gradient_descent_fallback: bool,
+    /// Wrap solved angle variables into (-π, π].
+    normalize_angles: bool,
+    /// Replace zero guesses for scalars with values implied by their constraints.
+    seed_scalars_from_constraints: bool,
+    /// How constraints of different priorities are traded off against each other.
+    priority_mode: PriorityMode,
+    /// Computes the initial λ from the problem, overriding `initial_lambda`.
+    /// Not serialized, because it's code rather than data.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    initial_lambda_fn: Option<fn(&InitialLambdaContext<'_>) -> f64>,
+    /// Custom stopping criterion, given each variable's value and each residual.
+    /// Not serialized, because it's code rather than data.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stop_when: Option<Hook<StopWhenFn>>,
+    /// Called with the details of every step the solver takes.
+    /// Not serialized, because it's code rather than data.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    on_iteration: Option<Hook<OnIterationFn>>,
+    /// Tikhonov regularization weights for some variables, overriding `regularization_lambda`.
+    regularization_weights: Option<HashMap<Id, f64>>,
+    /// Fail if the solve takes longer than this.
+    time_limit: Option<Duration>,
+    /// Tells the time for `time_limit`, overriding [`StdClock`].
+    /// Not serialized, because it's code rather than data.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock: Option<&'static dyn Clock>,
+    /// When the current solve runs out of time, according to `clock`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    deadline: Option<Duration>,
+    /// Keep a copy of the variables after every iteration.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    record_iterates: bool,
 }
 
 impl Config {
@@ -56,17 +160,228 @@ impl Config {
         self
     }
 
+    /// Count the solve as converged once every length residual (like a distance's error) is
+    /// within this many units of 0, instead of using [`Config::with_convergence_tolerance`].
+    /// Useful for solving to a physical precision, e.g. 0.01mm.
+    /// ```
+    /// // Solve lengths to within 0.01 and angles to within 0.001 radians.
+    /// let config = ezpz::Config::default()
+    ///     .with_length_tolerance(0.01)
+    ///     .with_angle_tolerance(0.001);
+    /// ```
+    pub fn with_length_tolerance(mut self, value: f64) -> Self {
+        self.length_tolerance = Some(value);
+        self
+    }
+
+    /// Count the solve as converged once every angular error (like a [`crate::Constraint::LinesAtAngle`]'s)
+    /// is within this many radians of 0, instead of using [`Config::with_convergence_tolerance`].
+    /// Angle residuals are scaled by a length to keep them in length units, so this is
+    /// converted back to an angle using the geometry's size at each iteration.
+    pub fn with_angle_tolerance(mut self, value: f64) -> Self {
+        self.angle_tolerance = Some(value);
+        self
+    }
+
     /// Stop iterating if the step size becomes negligible (relative infinity norm).
     pub fn with_step_tolerance(mut self, value: f64) -> Self {
         self.step_tolerance = value;
         self
     }
 
+    /// Stop iterating if the gradient of the squared residual (`Jᵀr`) becomes negligible
+    /// (infinity norm). This catches least-squares minima of inconsistent systems, whose residual
+    /// never reaches the convergence tolerance. 0 by default, i.e. only an exactly flat gradient
+    /// stops the solve.
+    pub fn with_gradient_tolerance(mut self, value: f64) -> Self {
+        self.gradient_tolerance = value;
+        self
+    }
+
     /// Initial value of the Levenberg-Marquardt damping parameter λ.
     pub fn with_initial_lambda(mut self, value: f64) -> Self {
         self.initial_lambda = value;
         self
     }
+
+    /// Compute the initial Levenberg-Marquardt damping parameter λ from the problem,
+    /// instead of using [`Config::with_initial_lambda`]. Useful for picking stronger
+    /// initial damping for stiff problems, based on cheap features like which kinds of
+    /// constraints are present or how large the coordinates are.
+    /// ```
+    /// use ezpz::{Config, Constraint};
+    /// let config = Config::default().with_initial_lambda_fn(|problem| {
+    ///     let has_tangents = problem
+    ///         .constraints()
+    ///         .any(|c| matches!(c, Constraint::LineTangentToCircle(..)));
+    ///     if has_tangents { 1e-3 } else { 1e-9 }
+    /// });
+    /// ```
+    pub fn with_initial_lambda_fn(mut self, f: fn(&InitialLambdaContext<'_>) -> f64) -> Self {
+        self.initial_lambda_fn = Some(f);
+        self
+    }
+
+    /// Stop solving as soon as this returns true, e.g. once some distance you care about is
+    /// close enough, even if other residuals are still above the convergence tolerance.
+    /// It's checked every iteration, and given each variable's value (indexed by [`crate::Id`])
+    /// and each residual. The solve counts as converged, with
+    /// [`crate::TerminationReason::StopCriterion`].
+    /// ```
+    /// // Stop once some variable is within 0.01 of 10.
+    /// let id = 3;
+    /// let config = ezpz::Config::default()
+    ///     .with_stop_when(move |values, _residuals| (values[id] - 10.0).abs() < 0.01);
+    /// ```
+    pub fn with_stop_when(
+        mut self,
+        f: impl FnMut(&[f64], &[f64]) -> bool + Send + 'static,
+    ) -> Self {
+        self.stop_when = Some(Hook(Arc::new(Mutex::new(f))));
+        self
+    }
+
+    /// If the damped Gauss-Newton system `JᵀJ + λI` can't be factored, e.g. because λ is 0 and
+    /// the Jacobian is rank-deficient, spend the remaining iterations on gradient descent
+    /// instead. Each gradient descent step backtracks until it reduces the residual, so
+    /// it always makes progress, but slowly, so the result may not satisfy every constraint.
+    /// Off by default.
+    pub fn with_gradient_descent_fallback(mut self, value: bool) -> Self {
+        self.gradient_descent_fallback = value;
+        self
+    }
+
+    /// After solving, wrap every variable which the constraints treat as an angle
+    /// (like a [`crate::datatypes::inputs::DatumLine`]'s angle) into (-π, π].
+    /// The solver can drift angles by multiples of 2π, which doesn't change the geometry
+    /// but leaves them numerically far from their guesses. Off by default.
+    pub fn with_normalize_angles(mut self, value: bool) -> Self {
+        self.normalize_angles = value;
+        self
+    }
+
+    /// Before solving, replace any zero guess for a scalar with a value its constraints imply,
+    /// e.g. a circle's radius guess with its [`crate::Constraint::CircleRadius`] target.
+    /// A radius guessed as 0 is degenerate, so this helps when there's no natural guess.
+    /// Off by default.
+    pub fn with_seed_scalars_from_constraints(mut self, value: bool) -> Self {
+        self.seed_scalars_from_constraints = value;
+        self
+    }
+
+    /// How constraints of different priorities are traded off against each other.
+    /// [`PriorityMode::Lexicographic`] by default.
+    pub fn with_priority_mode(mut self, value: PriorityMode) -> Self {
+        self.priority_mode = value;
+        self
+    }
+
+    /// Call this after every step the solver takes, with the details of that step,
+    /// e.g. to study how the solver behaves on some problem.
+    /// Clones of this config share the closure, so solves using them take turns calling it.
+    /// ```
+    /// let config = ezpz::Config::default().with_on_iteration(|stats| {
+    ///     if !stats.accepted() {
+    ///         eprintln!("iteration {} was rejected", stats.iteration());
+    ///     }
+    /// });
+    /// ```
+    pub fn with_on_iteration(mut self, f: impl FnMut(&IterationStats) + Send + 'static) -> Self {
+        self.on_iteration = Some(Hook(Arc::new(Mutex::new(f))));
+        self
+    }
+
+    /// Scale the damping parameter λ by the current residual norm, so damping is strong
+    /// while the solve is far from a solution and fades away as it converges.
+    /// Off by default.
+    pub fn with_adaptive_regularization(mut self, value: bool) -> Self {
+        self.adaptive_regularization = value;
+        self
+    }
+
+    /// Add a Tikhonov regularization term `μ·|x - x₀|²` to the least-squares objective,
+    /// pulling every variable towards its initial guess `x₀`. Unlike [`Config::with_initial_lambda`],
+    /// which only damps each step, this changes where the solve ends up.
+    ///
+    /// A larger μ pins down the directions an underconstrained system leaves free more firmly,
+    /// so they stay near their guesses. But it also competes with the constraints: the solution
+    /// is pulled off the exact solution towards the guesses, by more as μ grows, so a large μ
+    /// can leave well-constrained parts of the system unsatisfied.
+    /// 0 (off) by default.
+    pub fn with_regularization_lambda(mut self, value: f64) -> Self {
+        self.regularization_lambda = value;
+        self
+    }
+
+    /// Like [`Config::with_regularization_lambda`], but with a separate μ for each variable
+    /// in `weights`, keyed by its ID. Variables missing from `weights` use the uniform μ.
+    /// Give already-placed reference geometry a large weight to anchor it near its guesses,
+    /// and leave out the variables which should move freely to satisfy the constraints.
+    /// ```
+    /// // Anchor the variables with ID 0 and 1, leave the rest free.
+    /// let config = ezpz::Config::default()
+    ///     .with_regularization_weights([(0, 1.0), (1, 1.0)].into());
+    /// ```
+    pub fn with_regularization_weights(mut self, weights: HashMap<Id, f64>) -> Self {
+        self.regularization_weights = Some(weights);
+        self
+    }
+
+    /// Fail with [`crate::NonLinearSystemError::Diverged`] as soon as a step grows the residual
+    /// norm past [`Config::with_divergence_ratio`] times the smallest residual norm seen so far,
+    /// instead of spending the rest of the iterations trying to recover.
+    /// Useful for interactive solves, where failing fast beats a slow failure.
+    /// Off by default.
+    pub fn with_abort_on_divergence(mut self, value: bool) -> Self {
+        self.abort_on_divergence = value;
+        self
+    }
+
+    /// How many times larger than the best residual norm seen so far a step's residual norm
+    /// can get before the solve counts as diverging. Only used with
+    /// [`Config::with_abort_on_divergence`]. 100 by default.
+    pub fn with_divergence_ratio(mut self, value: f64) -> Self {
+        self.divergence_ratio = value;
+        self
+    }
+
+    /// Count the solve as stalled if the best residual norm over this many iterations improves
+    /// by less than [`Config::with_stall_tolerance`] on the one from before them. This catches solves which
+    /// alternate between accepted and rejected steps without getting anywhere, that would
+    /// otherwise run until [`Config::with_max_iterations`]. A stalled solve switches to
+    /// gradient descent if [`Config::with_gradient_descent_fallback`] is on, and otherwise
+    /// fails with [`crate::NonLinearSystemError::Stalled`]. 0 (the default) never stalls.
+    pub fn with_stall_window(mut self, value: usize) -> Self {
+        self.stall_window = value;
+        self
+    }
+
+    /// The smallest relative improvement in the residual norm, over
+    /// [`Config::with_stall_window`] iterations, which doesn't count as a stall.
+    /// 1e-3 by default, i.e. the residual norm has to drop by 0.1%.
+    pub fn with_stall_tolerance(mut self, value: f64) -> Self {
+        self.stall_tolerance = value;
+        self
+    }
+
+    /// Fail with [`crate::NonLinearSystemError::TimeLimitExceeded`] if the solve is still
+    /// iterating after this long, counting every priority level. Useful when solving on a UI
+    /// thread, which a pathological system shouldn't freeze. No limit by default.
+    /// On `wasm32-unknown-unknown` this needs a [`Config::with_clock`] to take effect.
+    /// ```
+    /// let config = ezpz::Config::default()
+    ///     .with_time_limit(std::time::Duration::from_millis(10));
+    /// ```
+    pub fn with_time_limit(mut self, value: Duration) -> Self {
+        self.time_limit = Some(value);
+        self
+    }
+
+    /// Tell the time for [`Config::with_time_limit`] with this clock, instead of [`StdClock`].
+    pub fn with_clock(mut self, clock: &'static dyn Clock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
 }
 
 impl Default for Config {
@@ -74,10 +389,290 @@ impl Default for Config {
         Self {
             max_iterations: 35,
             residual_tolerance: 1e-8,
+            length_tolerance: None,
+            angle_tolerance: None,
             step_tolerance: 1e-12,
+            gradient_tolerance: 0.0,
             initial_lambda: DEFAULT_INITIAL_LAMBDA,
+            adaptive_regularization: false,
+            regularization_lambda: 0.0,
+            abort_on_divergence: false,
+            divergence_ratio: 100.0,
+            stall_window: 0,
+            stall_tolerance: 1e-3,
+            gradient_descent_fallback: false,
+            normalize_angles: false,
+            seed_scalars_from_constraints: false,
+            priority_mode: PriorityMode::Lexicographic,
+            initial_lambda_fn: None,
+            stop_when: None,
+            on_iteration: None,
+            regularization_weights: None,
+            time_limit: None,
+            clock: None,
+            deadline: None,
+            record_iterates: false,
+        }
+    }
+}
+
+impl Config {
+    /// The initial λ to use for this problem.
+    pub(crate) fn initial_lambda_for(&self, problem: &InitialLambdaContext<'_>) -> f64 {
+        match self.initial_lambda_fn {
+            Some(f) => f(problem),
+            None => self.initial_lambda,
+        }
+    }
+
+    /// The first setting which is code rather than data, so it isn't serialized, if any.
+    #[cfg(feature = "serde")]
+    pub(crate) fn unserializable_setting(&self) -> Option<&'static str> {
+        let Self {
+            initial_lambda_fn,
+            stop_when,
+            on_iteration,
+            clock,
+            ..
+        } = self;
+        [
+            ("initial_lambda_fn", initial_lambda_fn.is_some()),
+            ("stop_when", stop_when.is_some()),
+            ("on_iteration", on_iteration.is_some()),
+            ("clock", clock.is_some()),
+        ]
+        .into_iter()
+        .find_map(|(setting, is_set)| is_set.then_some(setting))
+    }
+
+    /// Are residuals held to tolerances in physical units, rather than one tolerance for all?
+    pub(crate) fn has_unit_tolerances(&self) -> bool {
+        self.length_tolerance.is_some() || self.angle_tolerance.is_some()
+    }
+
+    /// Largest residual which counts as converged, for a row measuring this.
+    pub(crate) fn tolerance_for(&self, unit: ResidualUnit) -> f64 {
+        match unit {
+            ResidualUnit::Length => self.length_tolerance.unwrap_or(self.residual_tolerance),
+            ResidualUnit::Angle { scale } => self
+                .angle_tolerance
+                .map_or(self.residual_tolerance, |tolerance| tolerance * scale),
+            ResidualUnit::Other => self.residual_tolerance,
+        }
+    }
+
+    /// Should solved angle variables be wrapped into (-π, π]?
+    pub(crate) fn normalizes_angles(&self) -> bool {
+        self.normalize_angles
+    }
+
+    /// Should zero scalar guesses be seeded from their constraints?
+    pub(crate) fn seeds_scalars_from_constraints(&self) -> bool {
+        self.seed_scalars_from_constraints
+    }
+
+    /// How constraints of different priorities are traded off against each other.
+    pub(crate) fn priority_mode(&self) -> PriorityMode {
+        self.priority_mode
+    }
+
+    /// Is there an `on_iteration` closure to report steps to?
+    pub(crate) fn reports_iterations(&self) -> bool {
+        self.on_iteration.is_some()
+    }
+
+    /// Pass the details of a step to the `on_iteration` closure, if any.
+    pub(crate) fn report_iteration(&self, stats: &IterationStats) {
+        if let Some(Hook(f)) = &self.on_iteration
+            && let Ok(mut f) = f.lock()
+        {
+            f(stats);
         }
     }
+
+    /// Does the custom stopping criterion (if any) say to stop here?
+    pub(crate) fn should_stop(&self, values: &[f64], residuals: &[f64]) -> bool {
+        match &self.stop_when {
+            Some(Hook(f)) => f.lock().is_ok_and(|mut f| f(values, residuals)),
+            None => false,
+        }
+    }
+
+    /// Keep a copy of the variables after every iteration, see
+    /// [`crate::textual::ConstraintSystem::solve_recording`].
+    pub(crate) fn with_recorded_iterates(mut self) -> Self {
+        self.record_iterates = true;
+        self
+    }
+
+    /// Does the solve keep a copy of the variables after every iteration?
+    pub(crate) fn records_iterates(&self) -> bool {
+        self.record_iterates
+    }
+
+    /// The current time, if there's a clock to tell it.
+    fn now(&self) -> Option<Duration> {
+        if let Some(clock) = self.clock {
+            return Some(clock.now());
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        {
+            Some(StdClock.now())
+        }
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        {
+            None
+        }
+    }
+
+    /// Start the clock on the time limit (if any), for a solve starting now.
+    pub(crate) fn start_time_limit(mut self) -> Self {
+        self.deadline = match self.time_limit {
+            Some(limit) => self.now().map(|now| now + limit),
+            None => None,
+        };
+        self
+    }
+
+    /// If the solve has run past its time limit, that limit.
+    /// Only ever set after [`Config::start_time_limit`].
+    pub(crate) fn time_limit_exceeded(&self) -> Option<Duration> {
+        let deadline = self.deadline?;
+        let limit = self.time_limit?;
+        self.now().filter(|&now| now > deadline).map(|_| limit)
+    }
+
+    /// Should the solve abort, because the squared residual norm grew this much
+    /// from the best one seen so far?
+    pub(crate) fn has_diverged(&self, residual_sq: f64, best_residual_sq: f64) -> bool {
+        self.abort_on_divergence
+            && residual_sq > self.divergence_ratio * self.divergence_ratio * best_residual_sq
+    }
+
+    /// Has the solve stalled, because the best squared residual norm over the last
+    /// [`Config::with_stall_window`] iterations isn't enough of an improvement on the one
+    /// from before them? `history` is the squared residual norm at the start of each of the
+    /// last `stall_window` iterations, see [`Config::record_stall_history`].
+    pub(crate) fn has_stalled(&self, history: &VecDeque<f64>, residual_sq: f64) -> bool {
+        let window = self.stall_window;
+        if window == 0 || history.len() < window {
+            return false;
+        }
+        let Some(&earlier_sq) = history.front() else {
+            return false;
+        };
+        let best_sq = history
+            .iter()
+            .skip(1)
+            .copied()
+            .fold(residual_sq, libm::fmin);
+        let improved = 1.0 - self.stall_tolerance;
+        best_sq > improved * improved * earlier_sq
+    }
+
+    /// Remember this iteration's squared residual norm for [`Config::has_stalled`], keeping
+    /// only the last [`Config::with_stall_window`] of them. Does nothing if stalls are off,
+    /// so `history` never allocates then.
+    pub(crate) fn record_stall_history(&self, history: &mut VecDeque<f64>, residual_sq: f64) {
+        if self.stall_window == 0 {
+            return;
+        }
+        if history.len() == self.stall_window {
+            history.pop_front();
+        }
+        history.push_back(residual_sq);
+    }
+}
+
+/// How a solve trades off constraints of different priorities, see [`Config::with_priority_mode`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub enum PriorityMode {
+    /// Solve the highest priority constraints first, then add each lower priority level
+    /// only while every constraint so far can still be satisfied. A lower priority constraint
+    /// never moves the solution away from satisfying a higher priority one.
+    Lexicographic,
+    /// Solve all constraints together in one least-squares solve, where a constraint with
+    /// priority `p` has its weight multiplied by `base^(-p)`. Higher priorities win conflicts,
+    /// but lower priorities still pull on the solution a little, which changes smoothly as
+    /// the geometry moves. `base` should be greater than 1.
+    /// The weights only affect the trade-off: convergence is still judged on each
+    /// constraint's unweighted residual.
+    Weighted {
+        /// How many times more a priority level weighs than the next lower one.
+        base: f64,
+    },
+}
+
+/// The problem being solved, passed to [`Config::with_initial_lambda_fn`].
+#[derive(Debug)]
+pub struct InitialLambdaContext<'a> {
+    pub(crate) constraints: &'a [ConstraintEntry<'a>],
+    pub(crate) initial_values: &'a [f64],
+}
+
+impl InitialLambdaContext<'_> {
+    /// Every constraint being solved.
+    pub fn constraints(&self) -> impl Iterator<Item = &Constraint> {
+        self.constraints.iter().map(|c| c.constraint)
+    }
+
+    /// The value of every variable at the start of the solve.
+    pub fn initial_values(&self) -> &[f64] {
+        self.initial_values
+    }
+}
+
+/// What happened during one step of the solve, passed to [`Config::with_on_iteration`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub struct IterationStats {
+    pub(crate) iteration: usize,
+    pub(crate) residual_norm: f64,
+    pub(crate) gradient_norm: f64,
+    pub(crate) damping: f64,
+    pub(crate) step_norm: f64,
+    pub(crate) next_residual_norm: f64,
+    pub(crate) accepted: bool,
+}
+
+impl IterationStats {
+    /// Which iteration took this step, starting from 0.
+    pub fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    /// The residual norm before this step.
+    pub fn residual_norm(&self) -> f64 {
+        self.residual_norm
+    }
+
+    /// The gradient of the squared residual (`Jᵀr`) before this step (infinity norm).
+    pub fn gradient_norm(&self) -> f64 {
+        self.gradient_norm
+    }
+
+    /// The λ put on the diagonal of `JᵀJ + λI` to damp this step.
+    pub fn damping(&self) -> f64 {
+        self.damping
+    }
+
+    /// Size of the damped Gauss-Newton step (infinity norm).
+    pub fn step_norm(&self) -> f64 {
+        self.step_norm
+    }
+
+    /// The residual norm after taking the step, whether or not it was accepted.
+    pub fn next_residual_norm(&self) -> f64 {
+        self.next_residual_norm
+    }
+
+    /// Did the solver keep this step? Steps that don't reduce the residual are undone,
+    /// and the solver retries with more damping on the next iteration.
+    pub fn accepted(&self) -> bool {
+        self.accepted
+    }
 }
 
 #[derive(Debug)]
@@ -89,7 +684,7 @@ pub(crate) struct Layout {
 }
 
 impl Layout {
-    pub(crate) fn new(all_variables: &[Id], constraints: &[&Constraint], _config: Config) -> Self {
+    pub(crate) fn new(all_variables: &[Id], constraints: &[&Constraint], _config: &Config) -> Self {
         // We'll have different numbers of rows in the system depending on whether
         // or not regularization is enabled.
         let num_residuals_constraints: usize = constraints.iter().map(|c| c.residual_dim()).sum();
@@ -137,6 +732,19 @@ pub(crate) struct Model<'c> {
     pub(crate) warnings: Mutex<Vec<Warning>>,
     lambda_i: faer::sparse::SparseColMat<usize, f64>,
     llt_symbolic: SymbolicLlt<usize>,
+    /// Each variable's Tikhonov regularization weight, see [`Config::with_regularization_lambda`].
+    /// Empty if there's no regularization.
+    regularization: Vec<f64>,
+    /// The initial values which regularization pulls towards. Empty if there's no regularization.
+    anchor: Vec<f64>,
+    /// Each variable's `(lower, upper)` bounds, see [`crate::solve_bounded`].
+    /// Empty if nothing is bounded.
+    bounds: Vec<(f64, f64)>,
+    /// The variables after each iteration, if the config records them.
+    pub(crate) iterates: Vec<Vec<f64>>,
+    /// Which rows of the latest Jacobian were all zeros, despite being active.
+    /// The solver can't make progress on them, so they're warned about after the solve.
+    zero_rows: Vec<bool>,
 }
 
 fn validate_variables(
@@ -188,14 +796,105 @@ fn validate_variables(
     Ok(())
 }
 
+/// The parts of a [`Model`] which only depend on the constraints' structure and which variables
+/// are solved for, not on any values. They're expensive to compute, but can be reused by later
+/// models of the same system, see [`crate::SolverSession`].
+#[derive(Clone, Debug)]
+pub(crate) struct SymbolicStructure {
+    /// Where the Jacobian's nonzeroes are.
+    jacobian: SymbolicSparseColMat<usize>,
+    /// Symbolic Cholesky factorization of `JᵀJ + λI`.
+    llt: SymbolicLlt<usize>,
+}
+
 impl<'c> Model<'c> {
     pub(crate) fn new(
         constraints: &'c [ConstraintEntry<'c>],
         all_variables: Vec<Id>,
         initial_values: Vec<f64>,
-        config: Config,
+        config: &Config,
     ) -> Result<Self, NonLinearSystemError> {
-        validate_variables(constraints, &all_variables, &initial_values)?;
+        Self::new_with_symbolic(constraints, all_variables, initial_values, config, None)
+    }
+
+    /// Like [`Model::new`], but reusing the symbolic structure of an earlier model
+    /// of exactly the same constraints and variables, if there is one.
+    /// The variables aren't validated again, because that model already did.
+    pub(crate) fn new_with_symbolic(
+        constraints: &'c [ConstraintEntry<'c>],
+        all_variables: Vec<Id>,
+        initial_values: Vec<f64>,
+        config: &Config,
+        symbolic: Option<&SymbolicStructure>,
+    ) -> Result<Self, NonLinearSystemError> {
+        let num_cols = all_variables.len();
+        let cs: Vec<_> = constraints.iter().map(|c| c.constraint).collect();
+        let layout = Layout::new(&all_variables, cs.as_slice(), config);
+        let lambda_i = build_lambda_i(layout.num_variables, config.initial_lambda);
+        let (sym, llt_symbolic) = match symbolic {
+            Some(symbolic) => (symbolic.jacobian.clone(), symbolic.llt.clone()),
+            None => {
+                validate_variables(constraints, &all_variables, &initial_values)?;
+                let sym = Self::jacobian_structure(constraints, &layout, num_cols)?;
+                // Precompute the symbolic Cholesky factorization of A = JᵀJ + λI so we can
+                // reuse it inside the Newton loop
+                let llt_symbolic = Self::precompute_symbolic_cholesky(&sym, &lambda_i)?;
+                (sym, llt_symbolic)
+            }
+        };
+
+        let jc = JacobianCache {
+            vals: vec![0.0; sym.compute_nnz()], // We have a nonzero count util.
+            sym,
+        };
+        let (regularization, anchor) = if let Some(weights) = &config.regularization_weights {
+            let mut regularization = vec![config.regularization_lambda; num_cols];
+            for (&id, &weight) in weights {
+                // Columns are indexed by variable ID.
+                if let Some(col) = regularization.get_mut(id as usize) {
+                    *col = weight;
+                }
+            }
+            (regularization, initial_values)
+        } else if config.regularization_lambda > 0.0 {
+            (vec![config.regularization_lambda; num_cols], initial_values)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        // All done.
+        Ok(Self {
+            warnings: Default::default(),
+            zero_rows: vec![false; layout.num_rows()],
+            layout,
+            jacobian_cache: jc,
+            constraints,
+            row0_scratch: Vec::with_capacity(NONZEROES_PER_ROW),
+            row1_scratch: Vec::with_capacity(NONZEROES_PER_ROW),
+            row2_scratch: Vec::with_capacity(NONZEROES_PER_ROW),
+            lambda_i,
+            llt_symbolic,
+            regularization,
+            anchor,
+            bounds: Vec::new(),
+            iterates: Vec::new(),
+        })
+    }
+
+    /// The symbolic structure of this model, for reusing in later models of the same system.
+    pub(crate) fn symbolic_structure(&self) -> SymbolicStructure {
+        SymbolicStructure {
+            jacobian: self.jacobian_cache.sym.clone(),
+            llt: self.llt_symbolic.clone(),
+        }
+    }
+
+    /// Where the Jacobian's nonzeroes are.
+    fn jacobian_structure(
+        constraints: &[ConstraintEntry<'_>],
+        layout: &Layout,
+        num_cols: usize,
+    ) -> Result<SymbolicSparseColMat<usize>, NonLinearSystemError> {
         /*
         Firstly, find the size of the relevant matrices.
         Each constraint yields 1 or more residual function f.
@@ -214,10 +913,6 @@ impl<'c> Model<'c> {
                        which is = total number of "involved primitive IDs"
         */
 
-        let num_cols = all_variables.len();
-        let cs: Vec<_> = constraints.iter().map(|c| c.constraint).collect();
-        let layout = Layout::new(&all_variables, cs.as_slice(), config);
-
         // Generate the Jacobian matrix structure.
         // This is the nonzeroes of `J`.
         // It's MxN.
@@ -258,29 +953,27 @@ impl<'c> Model<'c> {
             num_cols,
             &nonzero_cells_j,
         )?;
+        Ok(sym)
+    }
 
-        let jc = JacobianCache {
-            vals: vec![0.0; sym.compute_nnz()], // We have a nonzero count util.
-            sym,
-        };
-
-        // Precompute the symbolic Cholesky factorization of A = JᵀJ + λI so we can reuse it inside
-        // the Newton loop
-        let lambda_i = build_lambda_i(layout.num_variables, config.initial_lambda);
-        let llt_symbolic = Self::precompute_symbolic_cholesky(&jc.sym, &lambda_i)?;
+    /// Keep these variables within their `(variable, lower, upper)` bounds while solving.
+    pub(crate) fn set_bounds(&mut self, bounds: &[(Id, f64, f64)]) {
+        if bounds.is_empty() {
+            return;
+        }
+        self.bounds = vec![(f64::NEG_INFINITY, f64::INFINITY); self.layout.num_variables];
+        for &(id, lower, upper) in bounds {
+            if let Some(bound) = self.bounds.get_mut(self.layout.index_of(id)) {
+                *bound = (lower, upper);
+            }
+        }
+    }
 
-        // All done.
-        Ok(Self {
-            warnings: Default::default(),
-            layout,
-            jacobian_cache: jc,
-            constraints,
-            row0_scratch: Vec::with_capacity(NONZEROES_PER_ROW),
-            row1_scratch: Vec::with_capacity(NONZEROES_PER_ROW),
-            row2_scratch: Vec::with_capacity(NONZEROES_PER_ROW),
-            lambda_i,
-            llt_symbolic,
-        })
+    /// Move any values outside their bounds onto them.
+    pub(crate) fn clamp_to_bounds(&self, current_assignments: &mut [f64]) {
+        for (x, &(lower, upper)) in current_assignments.iter_mut().zip(&self.bounds) {
+            *x = libm::fmin(libm::fmax(*x, lower), upper);
+        }
     }
 
     /// This is used in the core Newton solving, but it can be calculated entirely from
@@ -300,6 +993,15 @@ impl<'c> Model<'c> {
     }
 }
 
+/// Keep a copy of the variables after an iteration, if the config says to.
+/// Takes the model's iterates rather than the model, so it can be called while
+/// the Jacobian is borrowed.
+fn record_iterate(iterates: &mut Vec<Vec<f64>>, config: &Config, values: &[f64]) {
+    if config.records_iterates() {
+        iterates.push(values.to_vec());
+    }
+}
+
 fn build_lambda_i(num_variables: usize, lambda: f64) -> faer::sparse::SparseColMat<usize, f64> {
     faer::sparse::SparseColMat::<usize, f64>::try_new_from_triplets(
         num_variables,
@@ -311,8 +1013,61 @@ fn build_lambda_i(num_variables: usize, lambda: f64) -> faer::sparse::SparseColM
     .unwrap()
 }
 
+/// Put this damping, plus each variable's regularization weight (if any), on the diagonal of `λI`.
+fn fill_lambda_i(
+    lambda_i: &mut faer::sparse::SparseColMat<usize, f64>,
+    regularization: &[f64],
+    damping: f64,
+) {
+    let diagonal = lambda_i.val_mut();
+    diagonal.fill(damping);
+    for (entry, weight) in diagonal.iter_mut().zip(regularization) {
+        *entry += weight;
+    }
+}
+
 /// Connect the model to the Newton-Gauss numeric solver.
 impl Model<'_> {
+    /// The Tikhonov regularization term of the objective, `Σ μᵢ·(xᵢ - x₀ᵢ)²`.
+    fn regularization_cost(&self, current_assignments: &[f64]) -> f64 {
+        self.regularization
+            .iter()
+            .zip(current_assignments.iter().zip(&self.anchor))
+            .map(|(weight, (x, x0))| weight * (x - x0) * (x - x0))
+            .sum()
+    }
+
+    /// Add the regularization term's share of the descent direction, `-μᵢ·(xᵢ - x₀ᵢ)`,
+    /// to `-Jᵀr`.
+    fn regularize_gradient(&self, current_assignments: &[f64], mut gradient: ColMut<'_, f64>) {
+        for (i, (weight, (x, x0))) in self
+            .regularization
+            .iter()
+            .zip(current_assignments.iter().zip(&self.anchor))
+            .enumerate()
+        {
+            gradient[i] -= weight * (x - x0);
+        }
+    }
+
+    /// `Σ μᵢ·vᵢ²`, how much the regularization term curves along `v`.
+    fn regularization_curvature(&self, v: ColRef<'_, f64>) -> f64 {
+        self.regularization
+            .iter()
+            .zip(v.iter())
+            .map(|(weight, v)| weight * v * v)
+            .sum()
+    }
+
+    /// Shorten each component of `step` so that `x + scale·step` stays within its bounds.
+    /// So a variable already at a bound doesn't move any further past it.
+    fn project_step(&self, current_assignments: &[f64], mut step: ColMut<'_, f64>, scale: f64) {
+        for (i, (x, &(lower, upper))) in current_assignments.iter().zip(&self.bounds).enumerate() {
+            let target = libm::fmin(libm::fmax(x + scale * step[i], lower), upper);
+            step[i] = (target - x) / scale;
+        }
+    }
+
     /// Compute the residual F, figuring out how close the problem is to being solved.
     /// `out` is the global residual vector.
     fn residual(&self, current_assignments: &[f64], out: &mut [f64]) {
@@ -344,13 +1099,58 @@ impl Model<'_> {
                     content: WarningContent::Degenerate,
                 });
             }
-            for row in [&residuals0, &residuals1, &residuals2]
-                .iter()
+            for (row, kind) in [residuals0, residuals1, residuals2]
+                .into_iter()
+                .zip(constraint.constraint.residual_kinds())
                 .take(constraint.constraint.residual_dim())
             {
                 let this_row = row_num;
                 row_num += 1;
-                out[this_row] = constraint.weight * **row;
+                out[this_row] = constraint.weight * kind.clamp(row);
+            }
+        }
+    }
+
+    /// Evaluate the residual and Jacobian at `current_assignments`, without solving.
+    /// The Jacobian is returned as `(row, column, value)` triplets in column-major order,
+    /// one for every structurally nonzero entry (even if its value happens to be 0).
+    pub(crate) fn evaluate(
+        &mut self,
+        current_assignments: &[f64],
+    ) -> (Vec<f64>, Vec<crate::JacobianTriplet>) {
+        let mut residuals = vec![0.0; self.layout.num_rows()];
+        self.residual(current_assignments, &mut residuals);
+        self.refresh_jacobian(current_assignments);
+        let sym = &self.jacobian_cache.sym;
+        let mut triplets = Vec::with_capacity(self.jacobian_cache.vals.len());
+        for col in 0..sym.ncols() {
+            let entries = sym.col_range(col);
+            triplets.extend(
+                sym.row_idx()[entries.clone()]
+                    .iter()
+                    .zip(&self.jacobian_cache.vals[entries])
+                    .map(|(&row, &val)| (row, col, val)),
+            );
+        }
+        (residuals, triplets)
+    }
+
+    /// Extend `warnings` with one about each row which was all zeros in the latest Jacobian,
+    /// see [`WarningContent::DegenerateJacobianRow`].
+    pub(crate) fn extend_zero_row_warnings(&self, warnings: &mut Vec<Warning>) {
+        let mut zero_rows = self.zero_rows.iter();
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            let dim = constraint.constraint.residual_dim();
+            for (row, _) in zero_rows
+                .by_ref()
+                .take(dim)
+                .enumerate()
+                .filter(|(_, z)| **z)
+            {
+                warnings.push(Warning {
+                    about_constraint: Some(i),
+                    content: WarningContent::DegenerateJacobianRow { row },
+                });
             }
         }
     }
@@ -390,15 +1190,47 @@ impl Model<'_> {
                 });
             }
 
+            // Inequality rows only contribute while they're violated,
+            // so check their residuals to see which rows are active.
+            let kinds = constraint.constraint.residual_kinds();
+            let mut active = [true; 3];
+            if kinds.contains(&ResidualKind::Inequality) {
+                let mut residuals = [0.0; 3];
+                let [r0, r1, r2] = &mut residuals;
+                // Degenerate residuals get reported by `Model::residual`, so ignore them here.
+                let mut residual_degenerate = false;
+                constraint.constraint.residual(
+                    &self.layout,
+                    current_assignments,
+                    r0,
+                    r1,
+                    r2,
+                    &mut residual_degenerate,
+                );
+                for ((is_active, kind), residual) in active.iter_mut().zip(kinds).zip(residuals) {
+                    *is_active = kind.is_active(residual);
+                }
+            }
+
             // For each variable in this constraint's set of partial derivatives (Jacobian slice).
-            for row in [&self.row0_scratch, &self.row1_scratch, &self.row2_scratch]
+            for (row, is_active) in [&self.row0_scratch, &self.row1_scratch, &self.row2_scratch]
                 .into_iter()
+                .zip(active)
                 .take(constraint.constraint.residual_dim())
             {
                 let this_row = row_num;
                 row_num += 1;
                 #[cfg(feature = "dbg-jac")]
                 dbg_matrix.push(vec![0.0; self.layout.num_variables]);
+                if !is_active {
+                    // Leave this row's entries zeroed.
+                    self.zero_rows[this_row] = false;
+                    continue;
+                }
+                // An all-zero row adds nothing to JᵀJ or Jᵀr, so it's dropped from the step, and the
+                // damping keeps the normal equations well-posed. But its residual can't be reduced
+                // either, so remember it to warn about after the solve.
+                self.zero_rows[this_row] = row.iter().all(|var| var.partial_derivative == 0.0);
                 for jacobian_var in row {
                     let weighted_partial = constraint.weight * jacobian_var.partial_derivative;
                     #[cfg(feature = "dbg-jac")]
@@ -460,7 +1292,7 @@ mod tests {
         let all_variables = vec![0, 2]; // Only X components, missing Y components.
         let initial_values = vec![0.0, 0.0];
 
-        let Err(err) = Model::new(&[entry], all_variables, initial_values, Config::default())
+        let Err(err) = Model::new(&[entry], all_variables, initial_values, &Config::default())
         else {
             panic!("expected missing guess error");
         };
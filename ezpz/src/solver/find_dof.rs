@@ -7,7 +7,7 @@ use faer::{
     sparse::SparseColMatRef,
 };
 
-use crate::{FreedomAnalysis, NonLinearSystemError, solver::Model};
+use crate::{FreedomAnalysis, Id, NonLinearSystemError, analysis::JacobianColumns, solver::Model};
 
 const TOLERANCE_BASE: f64 = 1E-8;
 
@@ -23,16 +23,134 @@ impl Model<'_> {
             "Jacobian was malformed, Adam messed something up here."
         );
 
-        let nullspace = orthonormal_nullspace(j_dense.as_mat_ref(), nvars)?;
+        let (nullspace, relative_pivots) = orthonormal_nullspace(j_dense.as_mat_ref(), nvars)?;
         let underconstrained = underconstrained_variables(nullspace.as_mat_ref(), nvars);
-        Ok(FreedomAnalysis::new(underconstrained))
+        let remaining_dof = nullspace.ncols();
+        let condition_number = condition_number(j_dense.as_mat_ref())?;
+        // Each Jacobian row belongs to a constraint, in the same order as the residual.
+        let row_owners: Vec<usize> = self
+            .constraints
+            .iter()
+            .flat_map(|c| std::iter::repeat_n(c.id, c.constraint.residual_dim()))
+            .collect();
+        let mut redundant_constraints: Vec<usize> = redundant_rows(j_dense.as_mat_ref())
+            .into_iter()
+            .map(|row| row_owners[row])
+            .collect();
+        redundant_constraints.sort_unstable();
+        redundant_constraints.dedup();
+        let jacobian = JacobianColumns {
+            col_ptr: self.jacobian_cache.sym.col_ptr().to_vec(),
+            row_idx: self.jacobian_cache.sym.row_idx().to_vec(),
+            vals: self.jacobian_cache.vals.clone(),
+        };
+        Ok(FreedomAnalysis::new(
+            underconstrained,
+            remaining_dof,
+            relative_pivots,
+            condition_number,
+            redundant_constraints,
+            jacobian,
+        ))
     }
 }
 
+/// Which rows of the Jacobian are linearly dependent on other rows?
+/// Uses a column-pivoted QR of the transpose, so each row is a column there, and the
+/// columns which get pivoted past the rank are the dependent ones. Ties are pivoted in
+/// their original order, so of two identical rows, the later one is the dependent one.
+/// All-zero rows are skipped, as they're inactive inequalities or degenerate constraints,
+/// which don't constrain anything right now, rather than duplicating other constraints.
+fn redundant_rows(jacobian: MatRef<'_, f64>) -> Vec<usize> {
+    let nonzero_rows: Vec<usize> = (0..jacobian.nrows())
+        .filter(|&row| (0..jacobian.ncols()).any(|col| jacobian[(row, col)] != 0.0))
+        .collect();
+    if nonzero_rows.is_empty() {
+        return Vec::new();
+    }
+    let active = Mat::from_fn(nonzero_rows.len(), jacobian.ncols(), |row, col| {
+        jacobian[(nonzero_rows[row], col)]
+    });
+    let qr = ColPivQr::new(active.transpose());
+    let r = qr.R();
+    let ndiag = r.nrows().min(r.ncols());
+    let largest_diagonal = (0..ndiag).map(|i| r.get(i, i).abs()).fold(0.0, libm::fmax);
+    let tolerance = TOLERANCE_BASE * largest_diagonal;
+    let rank = (0..ndiag)
+        .take_while(|&i| r.get(i, i).abs() > tolerance)
+        .count();
+    let (pivots, _) = qr.P().arrays();
+    let mut redundant: Vec<usize> = pivots[rank..].iter().map(|&i| nonzero_rows[i]).collect();
+    redundant.sort_unstable();
+    redundant
+}
+
+/// Ratio of the Jacobian's largest singular value to its smallest.
+fn condition_number(jacobian: MatRef<'_, f64>) -> Result<f64, NonLinearSystemError> {
+    if jacobian.nrows() == 0 {
+        return Ok(1.0);
+    }
+    // The SVD only returns min(rows, columns) singular values, so with fewer rows than
+    // variables, the structurally zero ones are missing. There the rank must be below the
+    // number of variables, so the Jacobian is singular.
+    if jacobian.nrows() < jacobian.ncols() {
+        return Ok(f64::INFINITY);
+    }
+    // Singular values come sorted from largest to smallest.
+    let singular_values = jacobian
+        .singular_values()
+        .map_err(NonLinearSystemError::FaerSvd)?;
+    let (Some(&largest), Some(&smallest)) = (singular_values.first(), singular_values.last())
+    else {
+        return Ok(1.0);
+    };
+    if smallest <= 0.0 {
+        return Ok(f64::INFINITY);
+    }
+    Ok(largest / smallest)
+}
+
+/// Which of these variables are underconstrained, considering only their columns of the Jacobian?
+/// Variables outside `columns` are treated as fixed, so this answers whether the given
+/// variables are pinned down by the constraints, regardless of how free everything else is.
+/// Also returns how many degrees of freedom those variables have left between them,
+/// and the relative pivots from [`orthonormal_nullspace`].
+/// `columns` must be sorted and deduplicated.
+pub(crate) fn underconstrained_columns(
+    jacobian: &JacobianColumns,
+    columns: &[Id],
+) -> Result<(Vec<Id>, usize, Vec<f64>), NonLinearSystemError> {
+    if columns.is_empty() {
+        return Ok((Vec::new(), 0, Vec::new()));
+    }
+    let nrows = jacobian
+        .row_idx
+        .iter()
+        .max()
+        .map_or(0, |max_row| max_row + 1);
+    let mut j_dense = Mat::zeros(nrows, columns.len());
+    for (col, &var) in columns.iter().enumerate() {
+        for (row, val) in jacobian.column(var) {
+            j_dense[(row, col)] = val;
+        }
+    }
+    let nvars = columns.len();
+    let (nullspace, relative_pivots) = orthonormal_nullspace(j_dense.as_mat_ref(), nvars)?;
+    let underconstrained = underconstrained_variables(nullspace.as_mat_ref(), nvars);
+    let underconstrained = underconstrained
+        .into_iter()
+        .map(|col| columns[col as usize])
+        .collect();
+    Ok((underconstrained, nullspace.ncols(), relative_pivots))
+}
+
+/// Find an orthonormal basis for the Jacobian's nullspace, using a column-pivoted QR.
+/// Also returns the size of each pivot (diagonal of R) relative to the largest one,
+/// which is what the rank is estimated from.
 fn orthonormal_nullspace(
     jacobian: MatRef<'_, f64>,
     nvars: usize,
-) -> Result<Mat<f64>, NonLinearSystemError> {
+) -> Result<(Mat<f64>, Vec<f64>), NonLinearSystemError> {
     let qr = ColPivQr::new(jacobian);
     let r = qr.R();
     let ndiag = r.nrows().min(r.ncols());
@@ -42,6 +160,9 @@ fn orthonormal_nullspace(
         .reduce(libm::fmax)
         .ok_or(NonLinearSystemError::EmptySystemNotAllowed)?;
     let tolerance = TOLERANCE_BASE * largest_diagonal;
+    let relative_pivots = (0..ndiag)
+        .map(|i| r.get(i, i).abs() / largest_diagonal)
+        .collect();
     let rank = (0..ndiag)
         .take_while(|&i| r.get(i, i).abs() > tolerance)
         .count();
@@ -75,13 +196,16 @@ fn orthonormal_nullspace(
         qr.P().inverse(),
     );
 
-    Ok(Qr::new(nullspace.as_mat_ref()).compute_thin_Q())
+    Ok((
+        Qr::new(nullspace.as_mat_ref()).compute_thin_Q(),
+        relative_pivots,
+    ))
 }
 
 fn underconstrained_variables(
     nullspace: faer::mat::generic::Mat<faer::mat::Ref<'_, f64>>,
     nvars: usize,
-) -> Vec<crate::Id> {
+) -> Vec<Id> {
     debug_assert_eq!(nvars, nullspace.nrows());
 
     // Compute participation norm for each variable.
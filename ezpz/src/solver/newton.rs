@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use faer::{
     ColRef, Side,
     prelude::Solve,
@@ -7,29 +9,92 @@ use faer::{
     },
 };
 
-use crate::{Config, NonLinearSystemError};
+use crate::{Config, NonLinearSystemError, TerminationReason, constraints::ResidualUnit};
 
-use super::Model;
+use super::{InitialLambdaContext, IterationStats, Model, fill_lambda_i, record_iterate};
 
 // Levenberg-Marquardt adaptive damping params
 const LM_LAMBDA_INCR: f64 = 10.0;
 const LM_LAMBDA_DECR: f64 = 0.1;
 
+/// How many times a gradient descent step can be halved before giving up on it.
+const MAX_BACKTRACKS: usize = 32;
+
+/// The λ to put on the diagonal of `JᵀJ + λI`.
+/// With [`Config::with_adaptive_regularization`] this shrinks with the residual norm,
+/// so the damping fades away (and stops biasing the solution) as the solver converges.
+fn damping(lambda: f64, residual_sq: f64, config: &Config) -> f64 {
+    if config.adaptive_regularization {
+        lambda * libm::sqrt(residual_sq)
+    } else {
+        lambda
+    }
+}
+
+/// Largest absolute entry of the gradient `-Jᵀr`.
+fn gradient_inf_norm(gradient: ColRef<'_, f64>) -> f64 {
+    gradient
+        .iter()
+        .map(|x| x.abs())
+        .reduce(libm::fmax)
+        .unwrap_or(0.0)
+}
+
+/// Euclidean norm of the residual.
+fn l2_norm(residual: &[f64]) -> f64 {
+    libm::sqrt(residual.iter().map(|x| x * x).sum())
+}
+
 #[derive(Debug)]
 pub struct SuccessfulSolve {
     /// How many iterations did the solver run for?
     pub iterations: usize,
-    /// Did it ultimately converge, or not?
-    pub converged: bool,
+    /// Why did the solver stop?
+    pub reason: TerminationReason,
 }
 
 impl Model<'_> {
+    /// Is every residual row within its tolerance, i.e. is the system solved?
+    /// Rows are held to [`Config::with_convergence_tolerance`], unless the config has
+    /// tolerances in physical units.
+    fn within_tolerance(
+        &self,
+        current_values: &[f64],
+        residual: &[f64],
+        config: &Config,
+    ) -> Result<bool, NonLinearSystemError> {
+        if residual.is_empty() {
+            return Err(NonLinearSystemError::EmptySystemNotAllowed);
+        }
+        let unit_tolerances = config.has_unit_tolerances();
+        // The rows are every constraint's residuals in order, each multiplied by its weight,
+        // e.g. from a weighted priority mode. Scaling the tolerance by the weight holds the
+        // unweighted residual to it.
+        let mut rows = residual.iter();
+        for entry in self.constraints {
+            let dim = entry.constraint.residual_dim();
+            let units = if unit_tolerances {
+                entry
+                    .constraint
+                    .residual_units(&self.layout, current_values)
+            } else {
+                [ResidualUnit::Other; 3]
+            };
+            for (unit, row) in units.into_iter().take(dim).zip(rows.by_ref()) {
+                if row.abs() > entry.weight.abs() * config.tolerance_for(unit) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
     /// Solve via Levenberg-Marquardt algorithm (Gauss-Newton with adaptive damping)
     #[inline(never)]
     pub(crate) fn solve_levenberg_marquardt(
         &mut self,
         current_values: &mut [f64],
-        config: Config,
+        config: &Config,
     ) -> Result<SuccessfulSolve, NonLinearSystemError> {
         let m = self.layout.total_num_residuals;
         let n = current_values.len();
@@ -41,23 +106,57 @@ impl Model<'_> {
         // damping parameter λ is scaled down on accepted steps and up on rejected ones. A step is
         // rejected if it doesn't reduce the squared norm of the residual, which biases toward
         // gradient descent near singular configurations where Gauss-Newton tends to overshoot.
-        let mut lambda = config.initial_lambda;
+        let mut lambda = config.initial_lambda_for(&InitialLambdaContext {
+            constraints: self.constraints,
+            initial_values: current_values,
+        });
         let mut residual_sq = self.eval(current_values, &mut global_residual);
+        // The objective being minimized, i.e. the squared residual plus any regularization.
+        let mut cost = residual_sq + self.regularization_cost(current_values);
+        // The squared residual at the start of the last few iterations, for noticing stalls.
+        let mut residual_history = VecDeque::with_capacity(config.stall_window);
 
         for this_iteration in 0..config.max_iterations {
+            // Every iteration starts where the previous one left off.
+            if this_iteration > 0 {
+                record_iterate(&mut self.iterates, config, current_values);
+            }
             // Convergence check: if the residual is within our tolerance,
             // then the system is totally solved and we can return.
-            let largest_absolute_elem = global_residual
-                .iter()
-                .map(|x| x.abs())
-                .reduce(libm::fmax)
-                .ok_or(NonLinearSystemError::EmptySystemNotAllowed)?;
-            if largest_absolute_elem <= config.residual_tolerance {
+            if self.within_tolerance(current_values, &global_residual, config)? {
                 return Ok(SuccessfulSolve {
                     iterations: this_iteration,
-                    converged: true,
+                    reason: TerminationReason::ResidualTolerance,
                 });
             }
+            if config.should_stop(current_values, &global_residual) {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::StopCriterion,
+                });
+            }
+            if let Some(time_limit) = config.time_limit_exceeded() {
+                return Err(NonLinearSystemError::TimeLimitExceeded {
+                    iterations: this_iteration,
+                    time_limit,
+                });
+            }
+            if config.has_stalled(&residual_history, residual_sq) {
+                if config.gradient_descent_fallback {
+                    return self.solve_gradient_descent(
+                        current_values,
+                        config,
+                        this_iteration,
+                        &mut global_residual,
+                    );
+                }
+                return Err(NonLinearSystemError::Stalled {
+                    iterations: this_iteration,
+                    residual_norm: libm::sqrt(residual_sq),
+                    window: config.stall_window,
+                });
+            }
+            config.record_stall_history(&mut residual_history, residual_sq);
 
             /*
                 NOTE(dr): We solve the following linear system to get the damped Gauss-Newton step d
@@ -77,11 +176,23 @@ impl Model<'_> {
             let jtj = j.transpose().to_col_major()? * j;
 
             // Update λI with current damping value
-            self.lambda_i.val_mut().fill(lambda);
+            let damping = damping(lambda, residual_sq, config);
+            fill_lambda_i(&mut self.lambda_i, &self.regularization, damping);
 
             // Solve linear system
             let a = jtj + &self.lambda_i;
-            let b = j.transpose() * -ColRef::from_slice(&global_residual);
+            let mut b = j.transpose() * -ColRef::from_slice(&global_residual);
+            self.regularize_gradient(current_values, b.as_mut());
+
+            // If the gradient of the squared residual is flat enough, we're at a
+            // (least-squares) minimum, even if the residual isn't 0.
+            let gradient_norm = gradient_inf_norm(b.as_ref());
+            if gradient_norm <= config.gradient_tolerance {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::GradientTolerance,
+                });
+            }
 
             // Solve the linear system for the step `d`
             let factored = match Llt::try_new_with_symbolic(
@@ -90,6 +201,14 @@ impl Model<'_> {
                 Side::Lower,
             ) {
                 Ok(factored) => factored,
+                Err(LltError::Numeric(_)) if config.gradient_descent_fallback => {
+                    return self.solve_gradient_descent(
+                        current_values,
+                        config,
+                        this_iteration,
+                        &mut global_residual,
+                    );
+                }
                 // A is SPD for λ > 0, so a numeric failure means λ has decayed enough that A is no
                 // longer numerically positive-definite. Treat it like a rejected step: increase λ
                 // and retry next iteration.
@@ -99,12 +218,14 @@ impl Model<'_> {
                 }
                 Err(e) => return Err(e.into()),
             };
-            let d = factored.solve(&b);
+            let mut d = factored.solve(&b);
             assert_eq!(
                 d.nrows(),
                 n,
                 "the `d` column must be the same size as the number of variables."
             );
+            // Stop at any bounds the step would cross.
+            self.project_step(current_values, d.as_mut(), 1.0);
             let step_inf_norm = d.iter().map(|x| x.abs()).reduce(libm::fmax).unwrap_or(0.0);
 
             // Take the tentative step and evaluate the residual at the new position
@@ -115,11 +236,35 @@ impl Model<'_> {
             self.residual(current_values, &mut next_residual);
             let next_residual_sq: f64 = next_residual.iter().map(|x| x * x).sum();
 
-            if next_residual_sq < residual_sq {
+            // Only accepted steps change the residual, so the current one is the best seen.
+            if config.has_diverged(next_residual_sq, residual_sq) {
+                return Err(NonLinearSystemError::Diverged {
+                    iterations: this_iteration + 1,
+                    residual_norm: libm::sqrt(next_residual_sq),
+                    best_residual_norm: libm::sqrt(residual_sq),
+                });
+            }
+
+            let next_cost = next_residual_sq + self.regularization_cost(current_values);
+            let accepted = next_cost < cost;
+            if config.reports_iterations() {
+                config.report_iteration(&IterationStats {
+                    iteration: this_iteration,
+                    residual_norm: libm::sqrt(residual_sq),
+                    gradient_norm,
+                    damping,
+                    step_norm: step_inf_norm,
+                    next_residual_norm: libm::sqrt(next_residual_sq),
+                    accepted,
+                });
+            }
+
+            if accepted {
                 // Step reduced the residual: accept it and decrease λ.
                 std::mem::swap(&mut global_residual, &mut next_residual);
                 self.refresh_jacobian(current_values);
                 residual_sq = next_residual_sq;
+                cost = next_cost;
                 lambda *= LM_LAMBDA_DECR;
             } else {
                 // Step didn't reduce the residual: revert it and increase λ.
@@ -134,13 +279,247 @@ impl Model<'_> {
             if step_inf_norm <= config.step_tolerance {
                 return Ok(SuccessfulSolve {
                     iterations: this_iteration,
-                    converged: true,
+                    reason: TerminationReason::StepTolerance,
                 });
             }
         }
         Ok(SuccessfulSolve {
             iterations: config.max_iterations,
-            converged: false,
+            reason: TerminationReason::MaxIterations,
+        })
+    }
+
+    /// Solve via gradient descent with backtracking, starting at `first_iteration`.
+    /// `global_residual` must hold the residual at `current_values`, and the Jacobian cache
+    /// must be up to date. Used when the damped Gauss-Newton system can't be factored,
+    /// see [`Config::with_gradient_descent_fallback`].
+    fn solve_gradient_descent(
+        &mut self,
+        current_values: &mut [f64],
+        config: &Config,
+        first_iteration: usize,
+        global_residual: &mut Vec<f64>,
+    ) -> Result<SuccessfulSolve, NonLinearSystemError> {
+        let mut next_residual = vec![0.0; global_residual.len()];
+        let mut residual_sq: f64 = global_residual.iter().map(|x| x * x).sum();
+        let mut cost = residual_sq + self.regularization_cost(current_values);
+
+        for this_iteration in first_iteration..config.max_iterations {
+            // Every iteration starts where the previous one left off.
+            if this_iteration > first_iteration {
+                record_iterate(&mut self.iterates, config, current_values);
+            }
+            if self.within_tolerance(current_values, global_residual, config)? {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::ResidualTolerance,
+                });
+            }
+            if config.should_stop(current_values, global_residual) {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::StopCriterion,
+                });
+            }
+            if let Some(time_limit) = config.time_limit_exceeded() {
+                return Err(NonLinearSystemError::TimeLimitExceeded {
+                    iterations: this_iteration,
+                    time_limit,
+                });
+            }
+
+            let j =
+                SparseColMatRef::new(self.jacobian_cache.sym.as_ref(), &self.jacobian_cache.vals);
+            let mut b = j.transpose() * -ColRef::from_slice(global_residual);
+            self.regularize_gradient(current_values, b.as_mut());
+            let gradient_norm = gradient_inf_norm(b.as_ref());
+            if gradient_norm <= config.gradient_tolerance {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::GradientTolerance,
+                });
+            }
+
+            // Start from the Cauchy step, which minimizes the linearized residual along -Jᵀr,
+            // i.e. α = |Jᵀr|² / |JJᵀr|². Then halve it until the step reduces the residual.
+            let jb = j * &b;
+            let b_sq = b.squared_norm_l2();
+            let jb_sq = jb.squared_norm_l2() + self.regularization_curvature(b.as_ref());
+            let mut alpha = if jb_sq > 0.0 { b_sq / jb_sq } else { 1.0 };
+            let mut accepted = false;
+            let mut next_residual_sq = residual_sq;
+            let mut next_cost = cost;
+            for _ in 0..MAX_BACKTRACKS {
+                // Shrinking a projected step keeps it within the bounds,
+                // so projecting again only shortens the bounded components further.
+                self.project_step(current_values, b.as_mut(), alpha);
+                current_values
+                    .iter_mut()
+                    .zip(b.iter())
+                    .for_each(|(curr_val, step)| *curr_val += alpha * step);
+                self.residual(current_values, &mut next_residual);
+                next_residual_sq = next_residual.iter().map(|x| x * x).sum();
+                next_cost = next_residual_sq + self.regularization_cost(current_values);
+                if next_cost < cost {
+                    accepted = true;
+                    break;
+                }
+                current_values
+                    .iter_mut()
+                    .zip(b.iter())
+                    .for_each(|(curr_val, step)| *curr_val -= alpha * step);
+                alpha *= 0.5;
+            }
+            let step_inf_norm = alpha * b.iter().map(|x| x.abs()).reduce(libm::fmax).unwrap_or(0.0);
+
+            if config.reports_iterations() {
+                config.report_iteration(&IterationStats {
+                    iteration: this_iteration,
+                    residual_norm: libm::sqrt(residual_sq),
+                    gradient_norm,
+                    // A gradient descent step of size α is the λ → ∞ limit of the damped
+                    // Gauss-Newton step with λ = 1/α.
+                    damping: alpha.recip(),
+                    step_norm: step_inf_norm,
+                    next_residual_norm: libm::sqrt(next_residual_sq),
+                    accepted,
+                });
+            }
+
+            // No step along the gradient reduces the residual, so this is as good as it gets.
+            if !accepted {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration + 1,
+                    reason: TerminationReason::StepTolerance,
+                });
+            }
+            std::mem::swap(global_residual, &mut next_residual);
+            self.refresh_jacobian(current_values);
+            residual_sq = next_residual_sq;
+            cost = next_cost;
+
+            if step_inf_norm <= config.step_tolerance {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration + 1,
+                    reason: TerminationReason::StepTolerance,
+                });
+            }
+        }
+        Ok(SuccessfulSolve {
+            iterations: config.max_iterations,
+            reason: TerminationReason::MaxIterations,
+        })
+    }
+
+    /// Solve a system where every constraint is linear (see [`crate::Constraint::is_linear`]).
+    /// The Jacobian is constant, so it's built and factored once, and a single (slightly damped)
+    /// step lands on the solution. Following steps reuse the same factorization, and only
+    /// mop up the error that damping left behind.
+    #[inline(never)]
+    pub(crate) fn solve_linear(
+        &mut self,
+        current_values: &mut [f64],
+        config: &Config,
+    ) -> Result<SuccessfulSolve, NonLinearSystemError> {
+        let m = self.layout.total_num_residuals;
+        let n = current_values.len();
+
+        let mut global_residual = vec![0.0; m];
+        let lambda = config.initial_lambda_for(&InitialLambdaContext {
+            constraints: self.constraints,
+            initial_values: current_values,
+        });
+        self.eval(current_values, &mut global_residual);
+
+        let j = SparseColMatRef::new(self.jacobian_cache.sym.as_ref(), &self.jacobian_cache.vals);
+        let jtj = j.transpose().to_col_major()? * j;
+        fill_lambda_i(&mut self.lambda_i, &self.regularization, lambda);
+        let a = jtj + &self.lambda_i;
+        let factored =
+            match Llt::try_new_with_symbolic(self.llt_symbolic.clone(), a.as_ref(), Side::Lower) {
+                Ok(factored) => factored,
+                // Too ill-conditioned to factor with this little damping, so
+                // let Levenberg-Marquardt work out how much damping it needs.
+                Err(LltError::Numeric(_)) => {
+                    return self.solve_levenberg_marquardt(current_values, config);
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+        for this_iteration in 0..config.max_iterations {
+            // Every iteration starts where the previous one left off.
+            if this_iteration > 0 {
+                record_iterate(&mut self.iterates, config, current_values);
+            }
+            if self.within_tolerance(current_values, &global_residual, config)? {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::ResidualTolerance,
+                });
+            }
+            if config.should_stop(current_values, &global_residual) {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::StopCriterion,
+                });
+            }
+            if let Some(time_limit) = config.time_limit_exceeded() {
+                return Err(NonLinearSystemError::TimeLimitExceeded {
+                    iterations: this_iteration,
+                    time_limit,
+                });
+            }
+
+            let mut b = j.transpose() * -ColRef::from_slice(&global_residual);
+            self.regularize_gradient(current_values, b.as_mut());
+            let gradient_norm = gradient_inf_norm(b.as_ref());
+            if gradient_norm <= config.gradient_tolerance {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::GradientTolerance,
+                });
+            }
+            let mut d = factored.solve(&b);
+            assert_eq!(
+                d.nrows(),
+                n,
+                "the `d` column must be the same size as the number of variables."
+            );
+            self.project_step(current_values, d.as_mut(), 1.0);
+            let step_inf_norm = d.iter().map(|x| x.abs()).reduce(libm::fmax).unwrap_or(0.0);
+            current_values
+                .iter_mut()
+                .zip(d.iter())
+                .for_each(|(curr_val, step)| *curr_val += step);
+            if config.reports_iterations() {
+                let residual_norm = l2_norm(&global_residual);
+                self.residual(current_values, &mut global_residual);
+                config.report_iteration(&IterationStats {
+                    iteration: this_iteration,
+                    residual_norm,
+                    gradient_norm,
+                    damping: lambda,
+                    step_norm: step_inf_norm,
+                    next_residual_norm: l2_norm(&global_residual),
+                    // There's no λ schedule to fall back on, so every step is kept.
+                    accepted: true,
+                });
+            } else {
+                self.residual(current_values, &mut global_residual);
+            }
+
+            // An inconsistent system never gets its residual to 0,
+            // so stop once we're at the least-squares solution.
+            if step_inf_norm <= config.step_tolerance {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration + 1,
+                    reason: TerminationReason::StepTolerance,
+                });
+            }
+        }
+        Ok(SuccessfulSolve {
+            iterations: config.max_iterations,
+            reason: TerminationReason::MaxIterations,
         })
     }
 
@@ -150,31 +529,56 @@ impl Model<'_> {
     pub(crate) fn solve_gauss_newton(
         &mut self,
         current_values: &mut [f64],
-        config: Config,
+        config: &Config,
     ) -> Result<SuccessfulSolve, NonLinearSystemError> {
         let m = self.layout.total_num_residuals;
         let n = current_values.len();
 
         let mut global_residual = vec![0.0; m];
+        let mut best_residual_sq = f64::INFINITY;
+        let lambda = config.initial_lambda_for(&InitialLambdaContext {
+            constraints: self.constraints,
+            initial_values: current_values,
+        });
 
         for this_iteration in 0..config.max_iterations {
+            // Every iteration starts where the previous one left off.
+            if this_iteration > 0 {
+                record_iterate(&mut self.iterates, config, current_values);
+            }
             // Assemble global residual and Jacobian
             // Re-evaluate the global residual.
             self.residual(current_values, &mut global_residual);
+            let residual_sq: f64 = global_residual.iter().map(|x| x * x).sum();
+            if config.has_diverged(residual_sq, best_residual_sq) {
+                return Err(NonLinearSystemError::Diverged {
+                    iterations: this_iteration,
+                    residual_norm: libm::sqrt(residual_sq),
+                    best_residual_norm: libm::sqrt(best_residual_sq),
+                });
+            }
+            best_residual_sq = best_residual_sq.min(residual_sq);
             // Re-evaluate the global jacobian, write it into self.jc
             self.refresh_jacobian(current_values);
 
             // Convergence check: if the residual is within our tolerance,
             // then the system is totally solved and we can return.
-            let largest_absolute_elem = global_residual
-                .iter()
-                .map(|x| x.abs())
-                .reduce(libm::fmax)
-                .ok_or(NonLinearSystemError::EmptySystemNotAllowed)?;
-            if largest_absolute_elem <= config.residual_tolerance {
+            if self.within_tolerance(current_values, &global_residual, config)? {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::ResidualTolerance,
+                });
+            }
+            if config.should_stop(current_values, &global_residual) {
                 return Ok(SuccessfulSolve {
                     iterations: this_iteration,
-                    converged: true,
+                    reason: TerminationReason::StopCriterion,
+                });
+            }
+            if let Some(time_limit) = config.time_limit_exceeded() {
+                return Err(NonLinearSystemError::TimeLimitExceeded {
+                    iterations: this_iteration,
+                    time_limit,
                 });
             }
 
@@ -190,18 +594,28 @@ impl Model<'_> {
             // TODO: Is there any way to transpose `j` and keep it in column-major?
             // Converting from row- to column-major might not be necessary.
             let jtj = j.transpose().to_col_major()? * j;
+            self.lambda_i
+                .val_mut()
+                .fill(damping(lambda, residual_sq, config));
             let a = jtj + &self.lambda_i;
             let b = j.transpose() * -ColRef::from_slice(&global_residual);
+            if gradient_inf_norm(b.as_ref()) <= config.gradient_tolerance {
+                return Ok(SuccessfulSolve {
+                    iterations: this_iteration,
+                    reason: TerminationReason::GradientTolerance,
+                });
+            }
 
             // Solve linear system
             let factored =
                 Llt::try_new_with_symbolic(self.llt_symbolic.clone(), a.as_ref(), Side::Lower)?;
-            let d = factored.solve(&b);
+            let mut d = factored.solve(&b);
             assert_eq!(
                 d.nrows(),
                 n,
                 "the `d` column must be the same size as the number of variables."
             );
+            self.project_step(current_values, d.as_mut(), 1.0);
             let step_inf_norm = d.iter().map(|d| d.abs()).reduce(libm::fmax).unwrap_or(0.0);
             current_values
                 .iter_mut()
@@ -217,13 +631,13 @@ impl Model<'_> {
             if step_inf_norm <= config.step_tolerance {
                 return Ok(SuccessfulSolve {
                     iterations: this_iteration,
-                    converged: true,
+                    reason: TerminationReason::StepTolerance,
                 });
             }
         }
         Ok(SuccessfulSolve {
             iterations: config.max_iterations,
-            converged: false,
+            reason: TerminationReason::MaxIterations,
         })
     }
 
@@ -0,0 +1,91 @@
+//! Measuring the structure of the Jacobian, for performance tuning.
+use crate::solver::Model;
+
+/// How the nonzero entries of a system's Jacobian are laid out.
+/// Created from [`crate::jacobian_sparsity_stats`].
+/// Rows are residuals (in constraint order) and columns are variables (in ID order).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
+pub struct SparsityStats {
+    /// Number of rows, i.e. residuals.
+    pub(crate) rows: usize,
+    /// Number of columns, i.e. variables.
+    pub(crate) columns: usize,
+    /// Number of structurally nonzero entries.
+    pub(crate) nonzeros: usize,
+    /// Most nonzero entries in any one row.
+    pub(crate) max_row_nonzeros: usize,
+    /// Most nonzero entries in any one column.
+    pub(crate) max_column_nonzeros: usize,
+    /// Furthest any nonzero entry is from the diagonal.
+    pub(crate) bandwidth: usize,
+}
+
+impl SparsityStats {
+    /// Number of rows, i.e. residuals.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of columns, i.e. variables.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Number of structurally nonzero entries.
+    pub fn nonzeros(&self) -> usize {
+        self.nonzeros
+    }
+
+    /// What fraction of the entries are nonzero? Between 0 and 1.
+    /// 0 for an empty matrix.
+    pub fn density(&self) -> f64 {
+        let cells = self.rows * self.columns;
+        if cells == 0 {
+            return 0.0;
+        }
+        self.nonzeros as f64 / cells as f64
+    }
+
+    /// Most nonzero entries in any one row, i.e. the most variables any residual depends on.
+    pub fn max_row_nonzeros(&self) -> usize {
+        self.max_row_nonzeros
+    }
+
+    /// Most nonzero entries in any one column, i.e. the most residuals any variable appears in.
+    pub fn max_column_nonzeros(&self) -> usize {
+        self.max_column_nonzeros
+    }
+
+    /// Furthest any nonzero entry is from the diagonal, i.e. the largest `|row - column|`.
+    /// Small bandwidth means each constraint only involves variables with nearby IDs.
+    pub fn bandwidth(&self) -> usize {
+        self.bandwidth
+    }
+}
+
+impl Model<'_> {
+    pub(crate) fn sparsity_stats(&self) -> SparsityStats {
+        let sym = &self.jacobian_cache.sym;
+        let mut row_nonzeros = vec![0; sym.nrows()];
+        let mut max_column_nonzeros = 0;
+        let mut bandwidth = 0;
+        for col in 0..sym.ncols() {
+            let rows = sym.row_idx_of_col_raw(col);
+            max_column_nonzeros = max_column_nonzeros.max(rows.len());
+            for &row in rows {
+                row_nonzeros[row] += 1;
+                bandwidth = bandwidth.max(row.abs_diff(col));
+            }
+        }
+        SparsityStats {
+            rows: sym.nrows(),
+            columns: sym.ncols(),
+            nonzeros: sym.compute_nnz(),
+            max_row_nonzeros: row_nonzeros.into_iter().max().unwrap_or_default(),
+            max_column_nonzeros,
+            bandwidth,
+        }
+    }
+}
@@ -1,11 +1,19 @@
-use std::{f64::consts::PI, str::FromStr};
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
 use super::*;
 use crate::{
-    CircleSide, LineSide,
+    CircleSide, Endpoint, LineSide, TangencyKind,
     datatypes::{
         Angle, AngleKind,
-        inputs::{DatumCircle, DatumCircularArc, DatumDistance, DatumLineSegment, DatumPoint},
+        inputs::{
+            DatumCircle, DatumCircularArc, DatumConic, DatumDistance, DatumLine, DatumLineSegment,
+            DatumPoint,
+        },
         outputs::Point,
     },
     textual::{OutcomeAnalysis, Problem},
@@ -35,6 +43,59 @@ fn parse_problem(txt: &str) -> Problem {
     }
 }
 
+#[test]
+fn textual_round_trip() {
+    // Parsing a problem, writing it back out, then parsing that again should give the same
+    // constraint system for every test case.
+    let mut cases: Vec<_> = std::fs::read_dir("../test_cases")
+        .unwrap()
+        .map(|entry| entry.unwrap().path().join("problem.md"))
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty());
+    for case in cases {
+        let txt = std::fs::read_to_string(&case).unwrap();
+        let original = parse_problem(&txt);
+        let serialized = original.to_string();
+        let reparsed = parse_problem(&serialized);
+        assert_eq!(
+            serialized,
+            reparsed.to_string(),
+            "{} did not serialize stably",
+            case.display()
+        );
+
+        let original = original.to_constraint_system().unwrap();
+        let reparsed = reparsed.to_constraint_system().unwrap();
+        assert_eq!(
+            format!("{:?}", original.constraints),
+            format!("{:?}", reparsed.constraints),
+            "{} changed constraints after a round trip",
+            case.display()
+        );
+    }
+}
+
+#[test]
+fn parse_error_span() {
+    let txt = "# constraints
+point p
+point q
+distance(p, q, 4)
+tangent_ish(p, q)
+
+# guesses
+p roughly (0, 0)
+q roughly (3, 0)
+";
+    let Err(TextualError::UnexpectedToken { span, line, column }) = Problem::from_str(txt) else {
+        panic!("Expected a parse error");
+    };
+    assert_eq!(&txt[span.clone()], "tangent_ish");
+    assert_eq!(span.start, txt.find("tangent_ish").unwrap());
+    assert_eq!((line, column), (5, 1));
+}
+
 #[test]
 fn empty() {
     // This constraint references variable 0.
@@ -67,6 +128,365 @@ fn it_returns_best_satisfied_solution() {
     assert_eq!(solved.as_ref().priority_solved, high_priority);
 }
 
+#[test]
+fn priority_progress_reports_each_level() {
+    // The first two priority levels are solvable, but the last one contradicts the first.
+    let mut ids = IdGenerator::default();
+    let x = ids.next_id();
+    let y = ids.next_id();
+
+    let constraints = vec![
+        ConstraintRequest::new(Constraint::Fixed(x, 0.0), 0),
+        ConstraintRequest::new(Constraint::Fixed(y, 3.0), 1),
+        ConstraintRequest::new(Constraint::Fixed(x, 1.0), 2),
+    ];
+    let initial_guesses = vec![(x, 5.0), (y, 5.0)];
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert_eq!(solved.priority_solved(), 1);
+
+    let progress = solved.priority_progress();
+    let priorities: Vec<_> = progress.iter().map(|(priority, _)| *priority).collect();
+    assert_eq!(priorities, vec![0, 1, 2]);
+    // Every level up to the failing one was solved, so its residual should be ~0.
+    assert!(progress[0].1 < EPSILON);
+    assert!(progress[1].1 < EPSILON);
+    // The best the solver can do on the last level is split the difference.
+    assert!((progress[2].1 - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-3);
+}
+
+#[test]
+fn per_priority_cost_reports_each_level() {
+    // Three priority levels, each adding another constraint on the points.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::new(Constraint::Fixed(p.id_x(), 0.0), 0),
+        ConstraintRequest::new(Constraint::Fixed(p.id_y(), 0.0), 0),
+        ConstraintRequest::new(Constraint::Distance(p, q, 4.0), 1),
+        ConstraintRequest::new(Constraint::Horizontal(DatumLineSegment::new(p, q)), 2),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 1.0),
+        (p.id_y(), -1.0),
+        (q.id_x(), 3.0),
+        (q.id_y(), 2.0),
+    ];
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+
+    let costs = solved.per_priority_cost();
+    let priorities: Vec<_> = costs.iter().map(|cost| cost.priority()).collect();
+    assert_eq!(priorities, vec![0, 1, 2]);
+    assert!(costs.iter().all(|cost| cost.iterations() > 0));
+    assert!(costs.iter().all(|cost| cost.duration().is_some()));
+    let total_iterations: usize = costs.iter().map(|cost| cost.iterations()).sum();
+    assert_eq!(total_iterations, solved.iterations());
+}
+
+#[test]
+fn priority_stages() {
+    // Each priority level adds another constraint on Q, so each stage pins it down further.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::new(Constraint::Fixed(p.id_x(), 0.0), 0),
+        ConstraintRequest::new(Constraint::Fixed(p.id_y(), 0.0), 0),
+        ConstraintRequest::new(Constraint::Distance(p, q, 4.0), 1),
+        ConstraintRequest::new(Constraint::Horizontal(DatumLineSegment::new(p, q)), 2),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 1.0),
+        (p.id_y(), -1.0),
+        (q.id_x(), 3.0),
+        (q.id_y(), 2.0),
+    ];
+    let stages = solve_priority_stages(&constraints, initial_guesses, Config::default()).unwrap();
+    let priorities: Vec<_> = stages.iter().map(|(priority, _)| *priority).collect();
+    assert_eq!(priorities, vec![0, 1, 2]);
+    assert!(stages.iter().all(|(_, outcome)| outcome.is_satisfied()));
+    let point = |outcome: &SolveOutcome, i: usize| Point {
+        x: outcome.final_values()[i],
+        y: outcome.final_values()[i + 1],
+    };
+    let origin = Point { x: 0.0, y: 0.0 };
+
+    // Only P is constrained at first, so Q stays where it was guessed.
+    assert_points_eq(point(&stages[0].1, 0), origin);
+    assert_points_eq(point(&stages[0].1, 2), Point { x: 3.0, y: 2.0 });
+    // Then Q is somewhere 4 away from P.
+    assert_points_eq(point(&stages[1].1, 0), origin);
+    assert_nearly_eq(point(&stages[1].1, 2).euclidean_distance(origin), 4.0);
+    // Finally, it's also level with P.
+    assert_points_eq(point(&stages[2].1, 2), Point { x: 4.0, y: 0.0 });
+}
+
+#[test]
+fn weighted_priority_mode() {
+    // The two priorities want X in different places.
+    let mut ids = IdGenerator::default();
+    let x = ids.next_id();
+    let constraints = vec![
+        ConstraintRequest::new(Constraint::Fixed(x, 0.0), 0),
+        ConstraintRequest::new(Constraint::Fixed(x, 1.0), 1),
+    ];
+
+    // Lexicographically, the higher priority wins outright.
+    let lexicographic = solve(&constraints, vec![(x, 0.5)], Config::default()).unwrap();
+    assert_nearly_eq(lexicographic.final_values()[0], 0.0);
+    assert_eq!(lexicographic.priority_solved, 0);
+
+    // Weighted, the lower priority pulls X a little way towards it:
+    // minimizing x² + (0.1(x - 1))² gives x = 0.01/1.01.
+    let config = Config::default().with_priority_mode(PriorityMode::Weighted { base: 10.0 });
+    let weighted = solve(&constraints, vec![(x, 0.5)], config.clone()).unwrap();
+    assert_nearly_eq(weighted.final_values()[0], 0.01 / 1.01);
+    let stages = solve_priority_stages(&constraints, vec![(x, 0.5)], config).unwrap();
+    assert_eq!(stages.len(), 1);
+}
+
+#[test]
+fn weighted_priority_mode_tolerance() {
+    // The lower priority's row is weighted by 0.1, but its unweighted residual should still
+    // reach the tolerance, not just the weighted one.
+    let mut ids = IdGenerator::default();
+    let [x, y] = std::array::from_fn(|_| ids.next_id());
+    let constraints = vec![
+        ConstraintRequest::new(Constraint::Fixed(x, 0.0), 0),
+        ConstraintRequest::new(Constraint::Fixed(y, 1.0), 1),
+    ];
+    let tolerance = 1e-8;
+    let config = Config::default()
+        .with_convergence_tolerance(tolerance)
+        .with_priority_mode(PriorityMode::Weighted { base: 10.0 });
+    // Y's weighted residual starts within the tolerance, but its actual residual doesn't.
+    let guesses = vec![(x, 0.0), (y, 1.0 - 5.0 * tolerance)];
+    let solved = solve(&constraints, guesses, config).unwrap();
+    assert!(solved.iterations() > 0);
+    assert!((solved.final_values()[1] - 1.0).abs() <= tolerance);
+    assert!(solved.is_satisfied());
+}
+
+#[test]
+fn unit_tolerances() {
+    // Q should be 1000 away from the origin P, at 30° from the X axis (the line PR).
+    // Its guess is 0.0005 too far away, and 0.0005 radians off.
+    let mut ids = IdGenerator::default();
+    let [p, q, r] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+    let constraints: Vec<_> = [
+        Constraint::Fixed(p.id_x(), 0.0),
+        Constraint::Fixed(p.id_y(), 0.0),
+        Constraint::Fixed(r.id_x(), 1.0),
+        Constraint::Fixed(r.id_y(), 0.0),
+        Constraint::Distance(p, q, 1000.0),
+        Constraint::LinesAtAngle(
+            DatumLineSegment::new(p, r),
+            DatumLineSegment::new(p, q),
+            AngleKind::Other(Angle::from_degrees(30.0)),
+        ),
+    ]
+    .into_iter()
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+    let angle = PI / 6.0 + 0.0005;
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 1000.0005 * libm::cos(angle)),
+        (q.id_y(), 1000.0005 * libm::sin(angle)),
+        (r.id_x(), 1.0),
+        (r.id_y(), 0.0),
+    ];
+    let solve_with = |config: Config| {
+        solve(
+            &constraints,
+            initial_guesses.clone(),
+            config.with_max_iterations(1),
+        )
+        .unwrap()
+    };
+
+    // Both errors are within 0.001 of their units, so the guesses already count as converged,
+    // even though the angle's residual is about 0.5 in length units.
+    let config = Config::default()
+        .with_length_tolerance(0.001)
+        .with_angle_tolerance(0.001);
+    let solved = solve_with(config);
+    assert_eq!(
+        solved.termination_reason(),
+        TerminationReason::ResidualTolerance
+    );
+    assert_eq!(solved.iterations(), 0);
+
+    // Tighten either tolerance, and they don't.
+    for config in [
+        Config::default().with_length_tolerance(0.001),
+        Config::default()
+            .with_length_tolerance(0.0001)
+            .with_angle_tolerance(0.001),
+        Config::default()
+            .with_length_tolerance(0.001)
+            .with_angle_tolerance(0.0001),
+    ] {
+        let solved = solve_with(config);
+        assert_ne!(
+            solved.termination_reason(),
+            TerminationReason::ResidualTolerance
+        );
+    }
+}
+
+#[test]
+fn parallel_offset_unit_tolerances() {
+    // Line B should be parallel to the 1000 long line A, 10 away from it.
+    // Its guess is the right distance away, but 0.0005 radians off parallel.
+    let mut ids = IdGenerator::default();
+    let [p, q, r, s] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+    let line_a = DatumLineSegment::new(p, q);
+    let line_b = DatumLineSegment::new(r, s);
+    let constraints: Vec<_> = [
+        Constraint::Fixed(p.id_x(), 0.0),
+        Constraint::Fixed(p.id_y(), 0.0),
+        Constraint::Fixed(q.id_x(), 1000.0),
+        Constraint::Fixed(q.id_y(), 0.0),
+        Constraint::Fixed(r.id_x(), 0.0),
+        Constraint::Fixed(r.id_y(), 10.0),
+        Constraint::ParallelOffset(line_a, line_b, 10.0),
+    ]
+    .into_iter()
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 1000.0),
+        (q.id_y(), 0.0),
+        (r.id_x(), 0.0),
+        (r.id_y(), 10.0),
+        (s.id_x(), 1000.0 * libm::cos(0.0005)),
+        (s.id_y(), 10.0 + 1000.0 * libm::sin(0.0005)),
+    ];
+    let solve_with = |config: Config| {
+        solve(
+            &constraints,
+            initial_guesses.clone(),
+            config.with_max_iterations(1),
+        )
+        .unwrap()
+    };
+
+    // The parallelism residual is about 0.5 in length units, but it's an angular error,
+    // so it's held to the angle tolerance.
+    let solved = solve_with(
+        Config::default()
+            .with_length_tolerance(0.001)
+            .with_angle_tolerance(0.001),
+    );
+    assert_eq!(
+        solved.termination_reason(),
+        TerminationReason::ResidualTolerance
+    );
+    assert_eq!(solved.iterations(), 0);
+
+    let solved = solve_with(
+        Config::default()
+            .with_length_tolerance(0.001)
+            .with_angle_tolerance(0.0001),
+    );
+    assert_ne!(
+        solved.termination_reason(),
+        TerminationReason::ResidualTolerance
+    );
+}
+
+#[test]
+fn collinear() {
+    // A and C are fixed, and B has to be on the line through them.
+    let mut ids = IdGenerator::default();
+    let [a, b, c] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+    let mut constraints: Vec<_> = [(a, 0.0, 0.0), (c, 4.0, 2.0)]
+        .into_iter()
+        .flat_map(|(p, x, y)| {
+            [
+                Constraint::Fixed(p.id_x(), x),
+                Constraint::Fixed(p.id_y(), y),
+            ]
+        })
+        .map(ConstraintRequest::highest_priority)
+        .collect();
+    constraints.push(ConstraintRequest::highest_priority(Constraint::Collinear(
+        a, b, c,
+    )));
+    let initial_guesses = vec![
+        (a.id_x(), 0.0),
+        (a.id_y(), 0.0),
+        (b.id_x(), 1.0),
+        (b.id_y(), 2.0),
+        (c.id_x(), 4.0),
+        (c.id_y(), 2.0),
+    ];
+    let solved = solve_analysis(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.outcome.is_satisfied());
+    let values = solved.outcome.final_values();
+    assert_nearly_eq(values[3], values[2] / 2.0);
+    // B can still slide along the line, and that's the only freedom left.
+    assert_eq!(solved.analysis.remaining_dof(), 1);
+    assert_eq!(solved.analysis.underconstrained(), &[b.id_x(), b.id_y()]);
+}
+
+#[test]
+fn disable_constraint() {
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let mut constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(p, q, 4.0)),
+        ConstraintRequest::highest_priority(Constraint::Horizontal(DatumLineSegment::new(p, q))),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 3.0),
+        (q.id_y(), 1.0),
+    ];
+    let solve_q = |constraints: &[ConstraintRequest]| {
+        let solved =
+            solve_analysis(constraints, initial_guesses.clone(), Config::default()).unwrap();
+        assert!(solved.outcome.is_satisfied());
+        let values = solved.outcome.final_values();
+        let q = Point {
+            x: values[2],
+            y: values[3],
+        };
+        (q, solved.analysis.remaining_dof())
+    };
+    let (original, original_dof) = solve_q(&constraints);
+    assert_points_eq(original, Point { x: 4.0, y: 0.0 });
+    assert_eq!(original_dof, 0);
+
+    // Without the horizontal constraint, Q is free to go around P,
+    // so it only moves far enough to be 4 away from P.
+    constraints[3].set_enabled(false);
+    let (free, free_dof) = solve_q(&constraints);
+    assert_eq!(free_dof, 1);
+    let scale = 4.0 / 10f64.sqrt();
+    assert_points_eq(
+        free,
+        Point {
+            x: 3.0 * scale,
+            y: scale,
+        },
+    );
+
+    constraints[3].set_enabled(true);
+    let (restored, restored_dof) = solve_q(&constraints);
+    assert_points_eq(restored, original);
+    assert_eq!(restored_dof, original_dof);
+}
+
 #[test]
 fn initials_become_finals_if_no_constraints() {
     // If a lower-priority constraint causes the higher-priority constraints to be unsatisfied,
@@ -137,6 +557,32 @@ fn coincident() {
     assert_points_eq(solved.get_point("q").unwrap(), Point { x: 3.0, y: 3.0 });
 }
 
+#[test]
+fn fixed_point() {
+    // Assigning a point compiles to one constraint, which fixes both of its coordinates.
+    let txt = "# constraints
+point p
+p = (3, 4)
+
+# guesses
+p roughly (-1, 7)
+";
+    let problem = parse_problem(txt);
+    let system = problem.to_constraint_system().unwrap();
+    assert_eq!(system.constraints.len(), 1);
+    assert!(matches!(
+        system.constraints[0].constraint(),
+        Constraint::FixedPoint(_, 3.0, 4.0)
+    ));
+    let solved = system
+        .solve_with_config_analysis(Config::default())
+        .unwrap();
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_eq!(solved.outcome.num_eqs, 2);
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 3.0, y: 4.0 });
+}
+
 // #[test]
 // fn massive() {
 //     let solved = run("massive_parallel_system");
@@ -144,6 +590,124 @@ fn coincident() {
 //     assert!(!solved.analysis.is_underconstrained());
 // }
 
+#[test]
+fn massive_condition_number() {
+    // The same system as test_cases/massive_parallel_system, from its generator script,
+    // but with fewer lines so the SVD stays quick in debug builds.
+    let problem = |overconstrain: bool| {
+        let num_lines = 25;
+        let mut txt = String::from("# constraints\n");
+        for line in 0..num_lines {
+            let (a, b) = (line * 2, line * 2 + 1);
+            txt.push_str(&format!(
+                "point p{a}\npoint p{b}\nvertical(p{a}, p{b})\np{a}.x={line}\np{a}.y=0\np{b}.y=4\n"
+            ));
+            if overconstrain {
+                txt.push_str(&format!("distance(p{a}, p{b}, 4)\n"));
+            }
+        }
+        txt.push_str("\n# guesses\n");
+        for line in 0..num_lines {
+            let (a, b) = (line * 2, line * 2 + 1);
+            txt.push_str(&format!("p{a} roughly ({a},{a})\np{b} roughly ({b},{b})\n"));
+        }
+        txt
+    };
+    for overconstrain in [false, true] {
+        let txt = problem(overconstrain);
+        let problem = parse_problem(&txt);
+        let system = problem.to_constraint_system().unwrap();
+        let solved = system
+            .solve_with_config_analysis(Config::default())
+            .unwrap();
+        assert!(solved.is_satisfied());
+        // Independent, axis-aligned lines, so the Jacobian is well conditioned,
+        // even with redundant distance constraints.
+        let condition_number = solved.analysis.condition_number();
+        assert!(condition_number.is_finite());
+        assert!(
+            (1.0..100.0).contains(&condition_number),
+            "condition number was {condition_number}"
+        );
+    }
+}
+
+#[test]
+fn underconstrained_condition_number() {
+    // One distance between two free points leaves 3 degrees of freedom, so the Jacobian
+    // is singular, even though it has fewer rows than columns.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = [ConstraintRequest::highest_priority(Constraint::Distance(
+        p, q, 5.0,
+    ))];
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 3.0),
+        (q.id_y(), 4.0),
+    ];
+    let solved = solve_analysis(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.outcome.is_satisfied());
+    assert!(solved.analysis.is_underconstrained());
+    assert!(solved.analysis.condition_number().is_infinite());
+}
+
+#[test]
+fn redundant_constraints() {
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints: Vec<_> = [
+        Constraint::Fixed(p.id_x(), 0.0),
+        Constraint::Fixed(p.id_y(), 0.0),
+        Constraint::Distance(p, q, 5.0),
+        Constraint::Fixed(q.id_y(), 3.0),
+        // The same distance again, which pins down nothing new.
+        Constraint::Distance(p, q, 5.0),
+    ]
+    .into_iter()
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 3.0),
+        (q.id_y(), 2.0),
+    ];
+    let solved = solve_analysis(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.outcome.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_eq!(solved.analysis.redundant_constraints(), &[4]);
+
+    // Without the duplicate, nothing is redundant.
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 3.0),
+        (q.id_y(), 2.0),
+    ];
+    let solved = solve_analysis(&constraints[..4], initial_guesses, Config::default()).unwrap();
+    assert!(solved.analysis.redundant_constraints().is_empty());
+
+    // A satisfied inequality is inactive, so its Jacobian row is all zeroes,
+    // but that doesn't make it redundant.
+    let mut constraints = constraints[..4].to_vec();
+    constraints.push(ConstraintRequest::highest_priority(
+        Constraint::MinDistance(p, q, 1.0),
+    ));
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 3.0),
+        (q.id_y(), 2.0),
+    ];
+    let solved = solve_analysis(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.outcome.is_satisfied());
+    assert!(solved.analysis.redundant_constraints().is_empty());
+}
+
 #[test]
 fn symmetric() {
     let solved = run("symmetric");
@@ -218,1700 +782,4316 @@ fn midpoint() {
 }
 
 #[test]
-fn underconstrained() {
-    let solved = run("underconstrained");
-    assert!(solved.analysis.is_underconstrained());
+fn polar() {
+    let solved = run("polar");
     assert!(solved.is_satisfied());
-    assert_eq!(solved.analysis.underconstrained(), vec![0, 1]);
-    // p should be whatever the user's initial guess was.
-    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 1.0, y: 1.0 });
-    // q should be what it was constrained to be.
-    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert!(!solved.analysis.is_underconstrained());
+    // P should sit 5 units away from the origin, at 45 degrees.
+    let offset = 5.0 * std::f64::consts::FRAC_1_SQRT_2;
+    assert_points_eq(solved.get_point("o").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_points_eq(
+        solved.get_point("p").unwrap(),
+        Point {
+            x: offset,
+            y: offset,
+        },
+    );
 }
 
 #[test]
-fn tiny() {
-    let solved = run("tiny");
+fn scaled_copy() {
+    let solved = run("scaled_copy");
     assert!(solved.is_satisfied());
     assert!(!solved.analysis.is_underconstrained());
-    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 0.0 });
-    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert!((solved.outcome.get_scalar("s").unwrap() - 2.0).abs() < EPSILON);
+
+    // Changing the scale should resize the whole copied triangle about the pivot.
+    let txt = std::fs::read_to_string("../test_cases/scaled_copy/problem.md").unwrap();
+    let pivot = Point { x: 1.0, y: 1.0 };
+    for scale in [2.0, 0.5, -1.5] {
+        let txt = txt.replace("s = 2", &format!("s = {scale}"));
+        let problem = parse_problem(&txt);
+        let solved = problem.to_constraint_system().unwrap().solve().unwrap();
+        assert!(solved.unsatisfied.is_empty());
+        for (original, copy) in [("a", "a2"), ("b", "b2"), ("c", "c2")] {
+            let original = solved.get_point(original).unwrap();
+            let expected = Point {
+                x: pivot.x + scale * (original.x - pivot.x),
+                y: pivot.y + scale * (original.y - pivot.y),
+            };
+            assert_points_eq(solved.get_point(copy).unwrap(), expected);
+        }
+    }
 }
 
 #[test]
-fn inconsistent() {
-    // This has inconsistent requirements:
-    // p should be (1,4) and it should ALSO be (4,1).
-    // Because they can't be simultaneously satisfied, we should find a
-    // solution which minimizes the squared error instead.
-    let solved = run("inconsistent");
-    assert!(!solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained()); // If anything it's overconstrained not under.
-    assert_points_eq(solved.get_point("o").unwrap(), Point { x: 0.0, y: 0.0 });
-    // (2.5, 2.5) is midway between the two inconsistent requirement points.
-    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 2.5, y: 2.5 });
+fn circumcenter() {
+    // ABC is a right triangle, so its circumcenter is the midpoint of the hypotenuse BC.
+    let solved = run("circumcenter");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("o").unwrap(), Point { x: 2.0, y: 1.0 });
 }
 
 #[test]
-fn weight_biases_inconsistent_solution() {
-    // Two competing Fixed constraints on the same variable at the same priority:
-    // target=0 with default weight 1, and target=100 with weight 100. With equal
-    // weights the least-squares minimum sits at the midpoint (50). With 100x on
-    // the second target the solver should pull almost all the way to 100.
-    let mut ids = IdGenerator::default();
-    let var_id = ids.next_id();
-
-    let constraints = vec![
-        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 0.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 100.0)).with_weight(100.0),
-    ];
-    let initial_guesses = vec![(var_id, 50.0)];
-
-    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
-    let final_value = solved.final_values()[0];
-    assert!(
-        final_value > 99.0,
-        "weighted target should dominate; got {final_value}",
-    );
-
-    // Equal weights for comparison: should settle at the midpoint.
-    let baseline = vec![
-        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 0.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 100.0)),
-    ];
-    let baseline_solved = solve(&baseline, vec![(var_id, 50.0)], Config::default()).unwrap();
-    assert_nearly_eq(baseline_solved.final_values()[0], 50.0);
+fn sagitta() {
+    // The arc's center and radius are fixed, so its radius is 5.
+    // A sagitta of 1 puts the chord 4 from the center, so the chord is 6 long.
+    let solved = run("sagitta");
+    assert!(solved.is_satisfied());
+    let arc = solved.get_arc("a").unwrap();
+    assert_points_eq(arc.center, Point { x: 0.0, y: 0.0 });
+    assert_nearly_eq(arc.a.euclidean_distance(arc.center), 5.0);
+    assert_nearly_eq(arc.b.euclidean_distance(arc.center), 5.0);
+    assert_nearly_eq(arc.a.euclidean_distance(arc.b), 6.0);
 }
 
 #[test]
-fn circle() {
-    let solved = run("circle");
+fn arc_segment_area() {
+    // The arc's center and radius are fixed. A segment area of π - 2 is r²(θ - sin θ)/2
+    // for a quarter turn, so the end should sit a quarter turn counterclockwise of the start.
+    let solved = run("arc_segment_area");
     assert!(solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained());
-    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 5.0, y: 5.0 });
-    let circle_a = solved.get_circle("a").unwrap();
-    // From the problem:
-    // circle a
-    // radius(a, 3.4)
-    // a.center = (0.1, 0.2)
-    assert_nearly_eq(circle_a.radius, 3.4);
-    assert_points_eq(circle_a.center, Point { x: 0.1, y: 0.2 });
+    let arc = solved.get_arc("a").unwrap();
+    assert_points_eq(arc.center, Point { x: 0.0, y: 0.0 });
+    assert_nearly_eq(arc.a.euclidean_distance(arc.center), 2.0);
+    assert_nearly_eq(arc.b.euclidean_distance(arc.center), 2.0);
+    assert_nearly_eq(arc.a.x * arc.b.x + arc.a.y * arc.b.y, 0.0);
+    assert_nearly_eq(arc.a.x * arc.b.y - arc.a.y * arc.b.x, 4.0);
 }
 
 #[test]
-fn circle_center() {
-    // Very similar to test `circle` above,
-    // except it gives each constraint on the center separately.
-    let solved = run("circle_center");
-    assert!(!solved.analysis.is_underconstrained());
+fn equal_length_to() {
+    // O and A are fixed 3 apart, and B can only slide vertically until it is 3 from O too.
+    let solved = run("equal_length_to");
     assert!(solved.is_satisfied());
-    let circle_a = solved.get_circle("a").unwrap();
-    assert_nearly_eq(circle_a.radius, 1.0);
-    assert_points_eq(circle_a.center, Point { x: 0.0, y: 0.0 });
+    let o = solved.get_point("o").unwrap();
+    let a = solved.get_point("a").unwrap();
+    let b = solved.get_point("b").unwrap();
+    assert_points_eq(o, Point { x: 1.0, y: 1.0 });
+    assert_points_eq(a, Point { x: 4.0, y: 1.0 });
+    assert_nearly_eq(b.x, 1.0);
+    assert_nearly_eq(o.euclidean_distance(b), 3.0);
 }
 
 #[test]
-fn circle_tangent() {
-    // `tangent(...)` now starts with `LineSide::Undefined`, so the side is inferred
-    // from the initial geometry. This fixture starts the circle below the line.
-    let solved = run("circle_tangent");
+fn bisector_line() {
+    // OA and OB are fixed at a right angle, and OP is 2 long but otherwise free,
+    // so it should swing round to 45 degrees.
+    let solved = run("bisector_line");
     assert!(solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained());
-    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 3.0 });
-    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 5.0, y: 3.0 });
-    let circle_a = solved.get_circle("a").unwrap();
-    assert_nearly_eq(circle_a.center.y, 1.5);
-    assert_nearly_eq(circle_a.radius, 1.5);
+    let p = solved.get_point("p").unwrap();
+    let expected = std::f64::consts::SQRT_2;
+    assert_points_eq(
+        p,
+        Point {
+            x: expected,
+            y: expected,
+        },
+    );
 }
 
 #[test]
-fn circle_tangent_other_dir() {
-    // Just like `circle_tangent` but using line QP instead of PQ. Reversing the
-    // line direction reverses the preferred side of the oriented tangent.
-    let solved = run("circle_tangent_other_dir");
+fn radius_ratio() {
+    // The scalar is fixed at 1.5, and the radius should be twice that.
+    let solved = run("radius_ratio");
     assert!(solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained());
-    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 3.0 });
-    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 5.0, y: 3.0 });
-    let circle_a = solved.get_circle("a").unwrap();
-    assert_nearly_eq(circle_a.center.y, 1.5);
-    assert_nearly_eq(circle_a.radius, 1.5);
+    let circle = solved.get_circle("c").unwrap();
+    let m = solved.outcome.get_scalar("m").unwrap();
+    assert_nearly_eq(m, 1.5);
+    assert_nearly_eq(circle.radius, 2.0 * m);
 }
 
 #[test]
-fn line_tangent_left_explicit() {
-    let mut ids = IdGenerator::default();
-    let p0 = DatumPoint::new(&mut ids);
-    let p1 = DatumPoint::new(&mut ids);
-    let center = DatumPoint::new(&mut ids);
-    let radius = DatumDistance::new(ids.next_id());
-    let line = DatumLineSegment::new(p0, p1);
-    let circle = DatumCircle { center, radius };
+fn linear_combo() {
+    // The total length is driven by its two parts.
+    let solved = run("linear_combo");
+    assert!(solved.is_satisfied());
+    let total = solved.outcome.get_scalar("total").unwrap();
+    assert_nearly_eq(total, 7.0);
+    assert_nearly_eq(
+        total,
+        solved.outcome.get_scalar("a").unwrap() + solved.outcome.get_scalar("b").unwrap(),
+    );
+}
 
-    let constraints = vec![
-        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
-        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
-            line,
-            circle,
-            LineSide::Left,
-        )),
-    ];
-    let initial_guesses = vec![
-        (p0.id_x(), 0.0),
-        (p0.id_y(), 3.0),
-        (p1.id_x(), 5.0),
-        (p1.id_y(), 3.0),
-        (center.id_x(), 2.0),
-        (center.id_y(), 1.5),
-        (radius.id, 1.5),
-    ];
+#[test]
+fn equidistant_line_point() {
+    // The directrix y = -1 and focus (0, 1) define the parabola y = x²/4,
+    // so with x fixed at 2, the point should land at y = 1.
+    let solved = run("equidistant_line_point");
+    assert!(solved.is_satisfied());
+    let p = solved.get_point("p").unwrap();
+    assert_points_eq(p, Point { x: 2.0, y: 1.0 });
+}
 
-    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+#[test]
+fn tangent_at_angle() {
+    // The line must point straight up with the unit circle at the origin on its left,
+    // so it runs along x = 1.
+    let solved = run("tangent_at_angle");
     assert!(solved.is_satisfied());
-    let solved_circle = solved.final_value_circle(&circle);
-    assert_nearly_eq(solved_circle.center.y, 4.5);
-    assert_nearly_eq(solved_circle.radius, 1.5);
+    let p = solved.get_point("p").unwrap();
+    let q = solved.get_point("q").unwrap();
+    assert_points_eq(p, Point { x: 1.0, y: 0.0 });
+    assert_points_eq(q, Point { x: 1.0, y: 2.0 });
 }
 
 #[test]
-fn line_tangent_right_explicit() {
-    let mut ids = IdGenerator::default();
-    let p0 = DatumPoint::new(&mut ids);
-    let p1 = DatumPoint::new(&mut ids);
-    let center = DatumPoint::new(&mut ids);
-    let radius = DatumDistance::new(ids.next_id());
-    let line = DatumLineSegment::new(p0, p1);
-    let circle = DatumCircle { center, radius };
+fn circumscribed() {
+    // Every edge of the fixed square is tangent to the circle,
+    // so it must be the square's incircle.
+    let solved = run("circumscribed");
+    assert!(solved.is_satisfied());
+    let c = solved.get_circle("c").unwrap();
+    assert_points_eq(c.center, Point { x: 3.0, y: 3.0 });
+    assert_nearly_eq(c.radius, 2.0);
+}
 
-    let constraints = vec![
-        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
-        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
-            line,
-            circle,
-            LineSide::Right,
-        )),
-    ];
-    let initial_guesses = vec![
-        (p0.id_x(), 0.0),
-        (p0.id_y(), 3.0),
-        (p1.id_x(), 5.0),
-        (p1.id_y(), 3.0),
-        (center.id_x(), 2.0),
-        (center.id_y(), 4.5),
-        (radius.id, 1.5),
-    ];
+#[test]
+fn parallel_offset() {
+    // B has to be parallel to the X axis, 2 above it (on A's left) and 3 long.
+    let solved = run("parallel_offset");
+    assert!(solved.is_satisfied());
+    assert_points_eq(solved.get_point("b0").unwrap(), Point { x: 1.0, y: 2.0 });
+    assert_points_eq(solved.get_point("b1").unwrap(), Point { x: 4.0, y: 2.0 });
+}
 
-    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+#[test]
+fn angle_equals_scalar() {
+    // Fixing the driving scalar at 30° should rotate the crank B to 30° from A.
+    let solved = run("angle_equals_scalar");
     assert!(solved.is_satisfied());
-    let solved_circle = solved.final_value_circle(&circle);
-    assert_nearly_eq(solved_circle.center.y, 1.5);
-    assert_nearly_eq(solved_circle.radius, 1.5);
+    let theta = 30f64.to_radians();
+    assert_nearly_eq(solved.outcome.get_scalar("theta").unwrap(), theta);
+    assert_points_eq(
+        solved.get_point("b1").unwrap(),
+        Point {
+            x: 2.0 * libm::cos(theta),
+            y: 2.0 * libm::sin(theta),
+        },
+    );
 }
 
 #[test]
-fn line_tangent_left_inferred() {
-    let mut ids = IdGenerator::default();
-    let p0 = DatumPoint::new(&mut ids);
-    let p1 = DatumPoint::new(&mut ids);
-    let center = DatumPoint::new(&mut ids);
-    let radius = DatumDistance::new(ids.next_id());
-    let line = DatumLineSegment::new(p0, p1);
-    let circle = DatumCircle { center, radius };
+fn arc_central_angle_scalar() {
+    // The arc has radius 2 and starts on the +X axis. Fixing the driving scalar at 120°
+    // should open the arc to end 120° counterclockwise from its start.
+    let solved = run("arc_central_angle_scalar");
+    assert!(solved.is_satisfied());
+    let theta = 120f64.to_radians();
+    assert_nearly_eq(solved.outcome.get_scalar("theta").unwrap(), theta);
+    let arc = solved.get_arc("a").unwrap();
+    assert_points_eq(
+        arc.b,
+        Point {
+            x: 2.0 * libm::cos(theta),
+            y: 2.0 * libm::sin(theta),
+        },
+    );
+}
 
-    let constraints = vec![
-        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
-        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
-            line,
-            circle,
-            LineSide::Undefined,
-        )),
-    ];
-    let initial_guesses = vec![
-        (p0.id_x(), 0.0),
-        (p0.id_y(), 3.0),
-        (p1.id_x(), 5.0),
-        (p1.id_y(), 3.0),
-        (center.id_x(), 2.0),
-        (center.id_y(), 4.5),
-        (radius.id, 1.5),
-    ];
+#[test]
+fn subtends() {
+    // Turning CCW from B to A, AB subtends 45° from points on an arc below it.
+    // On AB's perpendicular bisector, that's where tan(22.5°) = 1 / depth.
+    let solved = run("subtends");
+    assert!(solved.is_satisfied());
+    let depth = 1.0 / libm::tan(22.5f64.to_radians());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 1.0, y: -depth });
+}
 
-    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+#[test]
+fn homothety() {
+    // A ratio of -1 reflects O through C.
+    let solved = run("homothety");
     assert!(solved.is_satisfied());
-    let solved_circle = solved.final_value_circle(&circle);
-    assert_nearly_eq(solved_circle.center.y, 4.5);
-    assert_nearly_eq(solved_circle.radius, 1.5);
+    assert_points_eq(solved.get_point("i").unwrap(), Point { x: -2.0, y: 1.0 });
 }
 
 #[test]
-fn line_tangent_right_inferred() {
-    let mut ids = IdGenerator::default();
-    let p0 = DatumPoint::new(&mut ids);
-    let p1 = DatumPoint::new(&mut ids);
-    let center = DatumPoint::new(&mut ids);
-    let radius = DatumDistance::new(ids.next_id());
-    let line = DatumLineSegment::new(p0, p1);
-    let circle = DatumCircle { center, radius };
+fn symmetric_angle() {
+    // Both arms start at the origin and end on x = 2, 2 apart,
+    // so they can only be symmetric about the X axis as (2, ±1).
+    let solved = run("symmetric_angle");
+    assert!(solved.is_satisfied());
+    assert_points_eq(solved.get_point("a1").unwrap(), Point { x: 2.0, y: 1.0 });
+    assert_points_eq(solved.get_point("b1").unwrap(), Point { x: 2.0, y: -1.0 });
+}
 
-    let constraints = vec![
-        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
-        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
-        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
-            line,
-            circle,
-            LineSide::Undefined,
-        )),
-    ];
-    let initial_guesses = vec![
-        (p0.id_x(), 0.0),
-        (p0.id_y(), 3.0),
-        (p1.id_x(), 5.0),
-        (p1.id_y(), 3.0),
-        (center.id_x(), 2.0),
-        (center.id_y(), 1.5),
-        (radius.id, 1.5),
-    ];
+#[test]
+fn length_ratio_bounds() {
+    // CD starts 3 times as long as AB, so it's pulled back to the upper bound of 2.
+    // EF starts 1.5 times as long, which is already within the bounds, so it stays put.
+    let solved = run("length_ratio_bounds");
+    assert!(solved.is_satisfied());
+    assert_points_eq(solved.get_point("d").unwrap(), Point { x: 2.0, y: 1.0 });
+    assert_points_eq(solved.get_point("f").unwrap(), Point { x: 1.5, y: 2.0 });
+}
 
-    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+#[test]
+fn parallel_offset_scalar() {
+    // The thickness scalar drives how far B is offset from A.
+    let solved = run("parallel_offset_scalar");
     assert!(solved.is_satisfied());
-    let solved_circle = solved.final_value_circle(&circle);
-    assert_nearly_eq(solved_circle.center.y, 1.5);
-    assert_nearly_eq(solved_circle.radius, 1.5);
+    assert_points_eq(solved.get_point("b0").unwrap(), Point { x: 0.0, y: 1.5 });
+    assert_points_eq(solved.get_point("b1").unwrap(), Point { x: 4.0, y: 1.5 });
 }
 
 #[test]
-fn circle_tangent_external_inferred() {
-    let mut ids = IdGenerator::default();
-    let circle_a = DatumCircle {
-        center: DatumPoint::new(&mut ids),
-        radius: DatumDistance::new(ids.next_id()),
-    };
-    let circle_b = DatumCircle {
-        center: DatumPoint::new(&mut ids),
-        radius: DatumDistance::new(ids.next_id()),
-    };
+fn on_grid() {
+    // P snaps to the nearest intersection of the grid, Q is already on it so it stays put.
+    let solved = run("on_grid");
+    assert!(solved.is_satisfied());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 1.1, y: 1.0 });
+    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 0.6, y: 1.5 });
+}
 
-    let initial_guesses = vec![
-        (circle_a.center.id_x(), 0.0),
-        (circle_a.center.id_y(), 0.0),
-        (circle_a.radius.id, 2.0),
-        (circle_b.center.id_x(), 4.0),
-        (circle_b.center.id_y(), 0.0),
-        (circle_b.radius.id, 3.0),
-    ];
-    let constraints = [
-        ConstraintRequest::highest_priority(Constraint::Fixed(circle_a.radius.id, 2.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(circle_b.radius.id, 3.0)),
-        ConstraintRequest::highest_priority(Constraint::CircleTangentToCircle(
-            circle_a,
-            circle_b,
-            CircleSide::Undefined,
-        )),
-    ];
+#[test]
+fn distance_sum() {
+    // P moves onto the ellipse with foci F and G whose distances to P add up to 10.
+    let solved = run("distance_sum");
+    assert!(solved.is_satisfied());
+    let p = solved.get_point("p").unwrap();
+    let f = Point { x: -3.0, y: 0.0 };
+    let g = Point { x: 3.0, y: 0.0 };
+    assert_nearly_eq(p.euclidean_distance(f) + p.euclidean_distance(g), 10.0);
+    // That's the ellipse x²/25 + y²/16 = 1.
+    assert_nearly_eq(p.x * p.x / 25.0 + p.y * p.y / 16.0, 1.0);
+}
 
-    let outcome = solve(&constraints, initial_guesses, Config::default()).unwrap();
-    assert!(outcome.is_satisfied());
-    let center_a = outcome.final_value_point(&circle_a.center);
-    let center_b = outcome.final_value_point(&circle_b.center);
-    assert_nearly_eq(center_a.euclidean_distance(center_b), 5.0);
+#[test]
+fn tangent_circles() {
+    // B slides along the X axis until it touches A from outside.
+    let solved = run("tangent_circles");
+    assert!(solved.is_satisfied());
+    let b = solved.get_circle("b").unwrap();
+    assert_points_eq(b.center, Point { x: 3.0, y: 0.0 });
 }
 
 #[test]
-fn circle_tangent_internal_inferred() {
-    let mut ids = IdGenerator::default();
-    let circle_a = DatumCircle {
-        center: DatumPoint::new(&mut ids),
-        radius: DatumDistance::new(ids.next_id()),
-    };
-    let circle_b = DatumCircle {
-        center: DatumPoint::new(&mut ids),
-        radius: DatumDistance::new(ids.next_id()),
+fn fillet() {
+    // The lines meet at the origin, along the X axis and the line y = x.
+    // The circle settles into the corner between them, tangent to both.
+    let solved = run("fillet");
+    assert!(solved.is_satisfied());
+    let c = solved.get_circle("c").unwrap();
+    assert_nearly_eq(c.radius, 1.0);
+    assert_nearly_eq(c.center.y, 1.0);
+    let diagonal_distance = (c.center.x - c.center.y).abs() / 2f64.sqrt();
+    assert_nearly_eq(diagonal_distance, 1.0);
+    // So the center is on the corner's bisector, at x = 1/tan(22.5°).
+    let expected = Point {
+        x: 1.0 / libm::tan(PI / 8.0),
+        y: 1.0,
     };
+    assert_points_eq(c.center, expected);
+}
 
-    let initial_guesses = vec![
-        (circle_a.center.id_x(), 0.0),
-        (circle_a.center.id_y(), 0.0),
-        (circle_a.radius.id, 5.0),
-        (circle_b.center.id_x(), 1.0),
-        (circle_b.center.id_y(), 0.0),
-        (circle_b.radius.id, 2.0),
-    ];
-    let constraints = [
-        ConstraintRequest::highest_priority(Constraint::Fixed(circle_a.radius.id, 5.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(circle_b.radius.id, 2.0)),
-        ConstraintRequest::highest_priority(Constraint::CircleTangentToCircle(
-            circle_a,
-            circle_b,
-            CircleSide::Undefined,
-        )),
-    ];
-
-    let outcome = solve(&constraints, initial_guesses, Config::default()).unwrap();
-    assert!(outcome.is_satisfied());
-    let center_a = outcome.final_value_point(&circle_a.center);
-    let center_b = outcome.final_value_point(&circle_b.center);
-    assert_nearly_eq(center_a.euclidean_distance(center_b), 3.0);
+#[test]
+fn point_on_circle() {
+    // The circle's radius isn't a literal in point_on_circle, it comes from radius(c, 2).
+    let solved = run("point_on_circle");
+    assert!(solved.is_satisfied());
+    let c = solved.get_circle("c").unwrap();
+    assert_nearly_eq(c.radius, 2.0);
+    let p = solved.get_point("p").unwrap();
+    assert_nearly_eq(p.euclidean_distance(c.center), 2.0);
 }
 
 #[test]
-fn two_rectangles() {
-    let solved = run("two_rectangles");
+fn line_circle_intersect() {
+    // The line y = 3 crosses the circle of radius 5 around the origin at (-4, 3) and (4, 3).
+    // P starts nearer the right-hand one, so that's where it should end up.
+    let solved = run("line_circle_intersect");
     assert!(solved.is_satisfied());
     assert!(!solved.analysis.is_underconstrained());
-    // This forms two rectangles.
-    assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 1.0, y: 1.0 });
-    assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 5.0, y: 1.0 });
-    assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 5.0, y: 4.0 });
-    assert_points_eq(solved.get_point("p3").unwrap(), Point { x: 1.0, y: 4.0 });
-    // Second rectangle
-    assert_points_eq(solved.get_point("p4").unwrap(), Point { x: 2.0, y: 2.0 });
-    assert_points_eq(solved.get_point("p5").unwrap(), Point { x: 6.0, y: 2.0 });
-    assert_points_eq(solved.get_point("p6").unwrap(), Point { x: 6.0, y: 6.0 });
-    assert_points_eq(solved.get_point("p7").unwrap(), Point { x: 2.0, y: 6.0 });
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 4.0, y: 3.0 });
 }
 
 #[test]
-fn angle_constraints() {
-    for file in ["angle_parallel", "angle_parallel_manual"] {
-        let solved = run(file);
-        assert!(solved.is_satisfied());
-        assert!(!solved.analysis.is_underconstrained());
-        assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 0.0, y: 0.0 });
-        assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 4.0, y: 4.0 });
-        assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 0.0, y: 0.0 });
-        assert_points_eq(solved.get_point("p3").unwrap(), Point { x: 4.0, y: 4.0 });
-    }
+fn circle_through_points() {
+    // The points are fixed, so it's the circle's center and radius which move,
+    // to the circumcircle of the right triangle, centered on its hypotenuse.
+    let solved = run("circle_through_points");
+    assert!(solved.is_satisfied());
+    let c = solved.get_circle("c").unwrap();
+    assert_points_eq(c.center, Point { x: 2.0, y: 1.5 });
+    assert_nearly_eq(c.radius, 2.5);
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 3.0 });
 }
 
 #[test]
-fn perpendicular() {
-    let solved = run("perpendicular");
+fn point_on_line() {
+    // P snaps onto the line through A and B, which is at an angle.
+    let solved = run("point_on_line");
     assert!(solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained());
-    assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 0.0, y: 0.0 });
-    assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 0.0, y: 4.0 });
-    assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 0.0, y: 0.0 });
-    assert_points_eq(solved.get_point("p3").unwrap(), Point { x: 4.0, y: 0.0 });
+    let p = solved.get_point("p").unwrap();
+    let a = solved.get_point("a").unwrap();
+    let b = solved.get_point("b").unwrap();
+    let ab = V::new(b.x - a.x, b.y - a.y);
+    let ap = V::new(p.x - a.x, p.y - a.y);
+    assert_nearly_eq(ab.cross_2d(ap), 0.0);
 }
 
 #[test]
-fn nonsquare() {
-    let solved = run("nonsquare");
+fn common_tangent() {
+    // The circles have different radii, so the belt between their tops slopes down.
+    let solved = run("common_tangent");
     assert!(solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained());
-    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 0.0 });
-    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 0.0, y: 0.0 });
+    let a = solved.get_point("a").unwrap();
+    let b = solved.get_point("b").unwrap();
+    let c0 = solved.get_circle("c0").unwrap();
+    let c1 = solved.get_circle("c1").unwrap();
+    let ab = V::new(b.x - a.x, b.y - a.y);
+    let signed_distance = |p: Point| ab.cross_2d(V::new(p.x - a.x, p.y - a.y)) / ab.magnitude();
+    // Both centers are one radius away on the same side, below the line.
+    assert_nearly_eq(signed_distance(c0.center), -3.0);
+    assert_nearly_eq(signed_distance(c1.center), -1.0);
+    // The line meets the X axis at the circles' external homothetic center, (15, 0).
+    let t = a.y / (a.y - b.y);
+    assert_nearly_eq(a.x + t * (b.x - a.x), 15.0);
 }
 
 #[test]
-fn square() {
-    let solved = run("square");
+fn arc_midpoint() {
+    // The arc is centered on the origin, and its apex is fixed at (0, 2).
+    // So its radius is 2, and its ends are mirror images across the Y axis.
+    let solved = run("arc_midpoint");
     assert!(solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained());
-    assert_nearly_eq(
-        solved.get_point("a").unwrap().y - solved.get_point("c").unwrap().y,
-        solved.get_point("b").unwrap().y - solved.get_point("d").unwrap().y,
-    );
-    assert_nearly_eq(
-        solved.get_point("a").unwrap().x - solved.get_point("c").unwrap().x,
-        solved.get_point("d").unwrap().x - solved.get_point("b").unwrap().x,
+    let arc = solved.get_arc("a").unwrap();
+    assert_nearly_eq(arc.a.euclidean_distance(arc.center), 2.0);
+    assert_points_eq(
+        arc.b,
+        Point {
+            x: -arc.a.x,
+            y: arc.a.y,
+        },
     );
 }
 
 #[test]
-fn parallelogram() {
-    let solved = run("parallelogram");
-    // The paralallelogram has two vertical lines AB and CD.
-    // A and B are fully determined, but C and D are free.
-    assert!(solved.analysis.is_underconstrained());
-    // A = 0 and 1
-    // B = 2 and 3
-    // CD are 4, 5, 6 and 7, and aren't constrained.
-    assert_eq!(solved.analysis.underconstrained(), vec![4, 5, 6, 7]);
-    assert_nearly_eq(
-        solved.get_point("a").unwrap().y - solved.get_point("c").unwrap().y,
-        solved.get_point("b").unwrap().y - solved.get_point("d").unwrap().y,
-    );
-    assert_nearly_eq(
-        solved.get_point("a").unwrap().x - solved.get_point("c").unwrap().x,
-        solved.get_point("b").unwrap().x - solved.get_point("d").unwrap().x,
+fn line_angle() {
+    // A is fixed at the origin, so B has to be on the ray at 30° from the X axis,
+    // 2 away from A.
+    let solved = run("line_angle");
+    assert!(solved.is_satisfied());
+    let b = solved.get_point("b").unwrap();
+    assert_points_eq(
+        b,
+        Point {
+            x: 3f64.sqrt(),
+            y: 1.0,
+        },
     );
 }
 
 #[test]
-fn underdetermined_lines() {
-    // This should solve for a horizontal line from (0,0) to (4,0), then
-    // a vertical line from (4,0) to (4,4). Note that the length of the second
-    // line is not specified; we're relying on regularisation to push our solution
-    // towards its start point.
-    let solved = run("underdetermined_lines");
-    assert!(solved.analysis.is_underconstrained());
-    assert_eq!(
-        solved.analysis.underconstrained(),
-        vec![5],
-        "P0 and P1 are constrained, but P2 is only fixed in the X direction, not Y"
-    );
+fn equal_angle() {
+    // OA to OB is a quarter turn, so CD (pointing up) to CE has to be too.
+    let solved = run("equal_angle");
     assert!(solved.is_satisfied());
-    assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 0.0, y: 0.0 });
-    assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 4.0, y: 0.0 });
-    assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 4.0, y: 4.0 });
+    let e = solved.get_point("e").unwrap();
+    assert_points_eq(e, Point { x: 7.0, y: 0.0 });
 }
 
 #[test]
-fn arc_radius() {
-    let solved = run("arc_radius");
-    assert!(solved.is_satisfied());
-    assert!(solved.analysis.is_underconstrained());
-    assert_eq!(
-        solved.analysis.underconstrained(),
-        vec![
-            // P is vars 0,1, and P is totally unconstrained.
-            0, 1,
-            // The arc's endpoint A (2, 3) and B (4, 5) are unconstrained, they can be anywhere
-            // as long as they're the right distance from the arc's center.
-            // But the center (6, 7) is fully constrained.
-            2, 3, 4, 5
-        ],
-        "Center of arc is fixed, but the other 2 points can vary."
+fn line_arc_tangent() {
+    // A quarter circle from (2, 0) to (0, 2), and a line running into its start.
+    let mut ids = IdGenerator::default();
+    let arc = DatumCircularArc {
+        center: DatumPoint::new(&mut ids),
+        start: DatumPoint::new(&mut ids),
+        end: DatumPoint::new(&mut ids),
+    };
+    let p = DatumPoint::new(&mut ids);
+    let line = DatumLineSegment::new(p, arc.start);
+    let mut constraints: Vec<_> = [
+        (arc.center, 0.0, 0.0),
+        (arc.start, 2.0, 0.0),
+        (arc.end, 0.0, 2.0),
+    ]
+    .into_iter()
+    .flat_map(|(point, x, y)| {
+        [
+            Constraint::Fixed(point.id_x(), x),
+            Constraint::Fixed(point.id_y(), y),
+        ]
+    })
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+    constraints.extend(
+        [
+            Constraint::Distance(p, arc.start, 3.0),
+            Constraint::LineArcTangent(line, arc, Endpoint::Start),
+        ]
+        .map(ConstraintRequest::highest_priority),
     );
+    let initial_guesses = vec![
+        (arc.center.id_x(), 0.0),
+        (arc.center.id_y(), 0.0),
+        (arc.start.id_x(), 2.0),
+        (arc.start.id_y(), 0.0),
+        (arc.end.id_x(), 0.0),
+        (arc.end.id_y(), 2.0),
+        (p.id_x(), 2.8),
+        (p.id_y(), -2.5),
+    ];
+    let outcome = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(outcome.is_satisfied());
+    // The line has to come straight up into the arc, which starts heading up too.
+    assert_points_eq(outcome.final_value_point(&p), Point { x: 2.0, y: -3.0 });
+}
+
+#[test]
+fn arc_chord_angle() {
+    // The quarter circle from (2, 0) to (0, 2) has its chord at 135°, so it has to turn.
+    let solved = run("arc_chord_angle");
+    assert!(solved.is_satisfied());
     let arc = solved.get_arc("a").unwrap();
     assert_points_eq(arc.center, Point { x: 0.0, y: 0.0 });
-    assert_nearly_eq(5.0, arc.a.euclidean_distance(Default::default()));
-    assert_nearly_eq(5.0, arc.b.euclidean_distance(Default::default()));
+    assert_nearly_eq(arc.center.euclidean_distance(arc.a), 2.0);
+    assert_nearly_eq(arc.center.euclidean_distance(arc.b), 2.0);
+    let chord_angle = libm::atan2(arc.b.y - arc.a.y, arc.b.x - arc.a.x);
+    assert_nearly_eq(chord_angle, 120f64.to_radians());
 }
 
-/// Point-Arc coincident constraint.
 #[test]
-fn parc_coincident() {
-    let solved = run("parc_coincident");
+fn reverse_tangent_arcs() {
+    // An S-curve: A's lower semicircle from (-1, 0) to (1, 0), then B's upper semicircle
+    // on to (3, 0). B is drawn backwards, so the arcs meet at both their ends.
+    let mut ids = IdGenerator::default();
+    let [a, b] = std::array::from_fn(|_| DatumCircularArc {
+        center: DatumPoint::new(&mut ids),
+        start: DatumPoint::new(&mut ids),
+        end: DatumPoint::new(&mut ids),
+    });
+    let mut constraints: Vec<_> = [
+        (a.center, 0.0, 0.0),
+        (a.start, -1.0, 0.0),
+        (a.end, 1.0, 0.0),
+    ]
+    .into_iter()
+    .flat_map(|(point, x, y)| {
+        [
+            Constraint::Fixed(point.id_x(), x),
+            Constraint::Fixed(point.id_y(), y),
+        ]
+    })
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+    constraints.extend(
+        [
+            Constraint::PointsCoincident(a.end, b.end),
+            Constraint::ArcRadius(b, 1.0),
+            Constraint::Fixed(b.start.id_y(), 0.0),
+            Constraint::ReverseTangentArcs(a, b),
+        ]
+        .map(ConstraintRequest::highest_priority),
+    );
+    let guesses = [
+        (a.center, 0.0, 0.0),
+        (a.start, -1.0, 0.0),
+        (a.end, 1.0, 0.0),
+        (b.center, 1.4, 0.5),
+        (b.start, 2.5, 0.4),
+        (b.end, 1.0, 0.0),
+    ];
+    let initial_guesses = guesses
+        .into_iter()
+        .flat_map(|(point, x, y)| [(point.id_x(), x), (point.id_y(), y)])
+        .collect();
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
     assert!(solved.is_satisfied());
-    assert!(solved.analysis.is_underconstrained());
-    let arc = solved.get_arc("a").unwrap();
-    let origin = Point { x: 0.0, y: 0.0 };
-    assert_points_eq(arc.center, origin);
-    assert_nearly_eq(5.0, arc.a.euclidean_distance(origin));
-    assert_nearly_eq(5.0, arc.b.euclidean_distance(origin));
-    let point = solved.get_point("p").unwrap();
-    assert_nearly_eq(5.0, arc.center.euclidean_distance(point));
+    let b = solved.final_value_arc(&b);
+    assert_points_eq(b.center, Point { x: 2.0, y: 0.0 });
+    assert_points_eq(b.a, Point { x: 3.0, y: 0.0 });
+
+    // The textual version leaves the join free, but the centers still end up on opposite
+    // sides of the shared tangent, i.e. either side of the join along the radius.
+    let solved = run("reverse_tangent_arcs");
+    assert!(solved.is_satisfied());
+    let a = solved.get_arc("a").unwrap();
+    let b = solved.get_arc("b").unwrap();
+    let ra = V::new(a.b.x - a.center.x, a.b.y - a.center.y);
+    let rb = V::new(b.b.x - b.center.x, b.b.y - b.center.y);
+    assert!(ra.dot(rb) < 0.0);
 }
 
 #[test]
-fn arc_equidistant() {
-    let solved = run("arc_equidistant");
+fn same_side() {
+    // P starts below the line, but the reference point R is above it,
+    // so P should get pushed up onto (and no further than) the line.
+    let solved = run("same_side");
     assert!(solved.is_satisfied());
-    assert!(solved.analysis.is_underconstrained());
-    assert_eq!(
-        solved.analysis.underconstrained(),
-        vec![
-            // P is vars 0,1, and P is totally unconstrained.
-            0, 1,
-            // The arc's endpoint A (2, 3) and B (4, 5) are unconstrained, they can be anywhere
-            // as long as they're the right distance from the arc's center.
-            // But the center (6, 7) is fully constrained.
-            2, 3, 4, 5
-        ],
-        "Center of arc is fixed, but the other 2 points can vary."
-    );
-    let arc = solved.get_arc("a").unwrap();
-    assert_points_eq(arc.center, Point { x: 0.0, y: 0.0 });
-    assert_nearly_eq(
-        arc.a.euclidean_distance(arc.center),
-        arc.b.euclidean_distance(arc.center),
+    let p = solved.get_point("p").unwrap();
+    assert!((p.x - 2.0).abs() < EPSILON);
+    assert!(
+        p.y > -1e-6,
+        "P should be back on R's side of the line, got {p:?}"
     );
+
+    // If P already starts on the right side, the constraint leaves it alone.
+    let txt = std::fs::read_to_string("../test_cases/same_side/problem.md").unwrap();
+    let txt = txt.replace("p roughly (2, -3)", "p roughly (2, 3)");
+    let solved = parse_problem(&txt)
+        .to_constraint_system()
+        .unwrap()
+        .solve()
+        .unwrap();
+    assert!(solved.unsatisfied.is_empty());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 2.0, y: 3.0 });
 }
 
 #[test]
-fn chamfer_square() {
-    let solved = run("chamfer_square");
+fn underconstrained() {
+    let solved = run("underconstrained");
+    assert!(solved.analysis.is_underconstrained());
     assert!(solved.is_satisfied());
-    assert!(!solved.analysis.is_underconstrained());
-    assert_points_eq(solved.get_point("a").unwrap(), Point { x: 0.0, y: 40.0 });
-    assert_points_eq(solved.get_point("b").unwrap(), Point { x: 30.0, y: 40.0 });
-    assert_points_eq(solved.get_point("c").unwrap(), Point { x: 40.0, y: 30.0 });
-    assert_points_eq(solved.get_point("d").unwrap(), Point { x: 40.0, y: 0.0 });
-    assert_points_eq(solved.get_point("e").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_eq!(solved.analysis.underconstrained(), vec![0, 1]);
+    // p should be whatever the user's initial guess was.
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 1.0, y: 1.0 });
+    // q should be what it was constrained to be.
+    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 0.0, y: 0.0 });
 }
 
 #[test]
-fn arc_length() {
-    let solved = run("arc_length");
+fn underconstrained_unmoved_variables() {
+    // Same system as the `underconstrained` test case: q is fixed, p is free.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_y(), 0.0)),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 1.0),
+        (p.id_y(), 1.0),
+        (q.id_x(), 4.0),
+        (q.id_y(), -2.0),
+    ];
+    let solved = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
     assert!(solved.is_satisfied());
+    // The solver never had a reason to move p, but q had to move to the origin.
+    assert_eq!(
+        solved.unmoved_variables(&initial_guesses, EPSILON),
+        vec![p.id_x(), p.id_y()]
+    );
 }
 
-/// Test that mirrors the `trim_arc2_left_side` scenario from modeling-app.
-///
-/// This test represents a trim operation where:
-/// - Two arcs intersect
-/// - arc1 should remain unchanged (its parameters shouldn't change when `PointArcCoincident` is added)
-/// - arc2 should be trimmed to end at the intersection point
-/// - When `PointArcCoincident` is added for the intersection point on arc2, arc1's parameters should remain unchanged
-///
-/// This test mirrors the KCL code from the modeling-app test:
-/// ```
-/// sketch(on = YZ) {
-///   arc1 = sketch2::arc(start = [var 0mm, var 5mm], end = [var 0mm, var -5mm], center = [var 30mm, var 0mm])
-///   arc2 = sketch2::arc(start = [var 5mm, var 0mm], end = [var -5mm, var 0mm], center = [var 0mm, var -30mm])
-/// }
-/// ```
 #[test]
-fn test_trim_arc2_left_side_arc1_should_remain_fixed() {
+fn point_stiffness() {
+    // q is fixed, r can slide horizontally along a line through q, and p is totally free.
     let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let r = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Horizontal(DatumLineSegment::new(q, r))),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 1.0),
+        (p.id_y(), 1.0),
+        (q.id_x(), 4.0),
+        (q.id_y(), -2.0),
+        (r.id_x(), 3.0),
+        (r.id_y(), 2.0),
+    ];
+    let solved = solve_analysis(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.outcome.is_satisfied());
+    assert!(solved.analysis.point_stiffness(&q) > 0.5);
+    assert!(solved.analysis.point_stiffness(&r) < EPSILON);
+    assert!(solved.analysis.point_stiffness(&p) < EPSILON);
+}
 
-    // Create two arcs matching the trim test scenario:
-    // arc1: start = (0, 5), end = (0, -5), center = (30, 0)
-    // arc2: start = (5, 0), end = (-5, 0), center = (0, -30)
-    let arc1_center = DatumPoint::new(&mut ids);
-    let arc1_start = DatumPoint::new(&mut ids);
-    let arc1_end = DatumPoint::new(&mut ids);
-    let arc1 = DatumCircularArc {
-        center: arc1_center,
-        start: arc1_start,
-        end: arc1_end,
-    };
+#[test]
+fn linear_fast_path() {
+    // A rectangle laid out with only linear constraints, with Q at the midpoint of its diagonal.
+    let mut ids = IdGenerator::default();
+    let [a, b, c, d, q] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_x(), 1.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_y(), 2.0)),
+        ConstraintRequest::highest_priority(Constraint::Horizontal(DatumLineSegment::new(a, b))),
+        ConstraintRequest::highest_priority(Constraint::HorizontalDistance(b, a, 4.0)),
+        ConstraintRequest::highest_priority(Constraint::Vertical(DatumLineSegment::new(b, c))),
+        ConstraintRequest::highest_priority(Constraint::VerticalDistance(c, b, 3.0)),
+        ConstraintRequest::highest_priority(Constraint::Horizontal(DatumLineSegment::new(c, d))),
+        ConstraintRequest::highest_priority(Constraint::Vertical(DatumLineSegment::new(d, a))),
+        ConstraintRequest::highest_priority(Constraint::Midpoint(DatumLineSegment::new(a, c), q)),
+    ];
+    assert!(constraints.iter().all(|c| c.constraint().is_linear()));
+    let initial_guesses: Vec<_> = [a, b, c, d, q]
+        .iter()
+        .flat_map(|p| [(p.id_x(), 0.3), (p.id_y(), -0.7)])
+        .collect();
 
-    let arc2_center = DatumPoint::new(&mut ids);
-    let arc2_start = DatumPoint::new(&mut ids);
-    let arc2_end = DatumPoint::new(&mut ids);
-    let arc2 = DatumCircularArc {
-        center: arc2_center,
-        start: arc2_start,
-        end: arc2_end,
-    };
+    let solved = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    // One step solves the system, up to the tiny bias from damping,
+    // which one more step with the same factorization cleans up.
+    assert!(solved.iterations() <= 2);
+    assert_points_eq(solved.final_value_point(&c), Point { x: 5.0, y: 5.0 });
+    assert_points_eq(solved.final_value_point(&d), Point { x: 1.0, y: 5.0 });
+    assert_points_eq(solved.final_value_point(&q), Point { x: 3.0, y: 3.5 });
+
+    // Should match what the usual Levenberg-Marquardt solve finds.
+    let entries: Vec<_> = constraints
+        .iter()
+        .enumerate()
+        .map(|(id, c)| ConstraintEntry {
+            constraint: c.constraint(),
+            priority: c.priority(),
+            weight: c.weight(),
+            id,
+        })
+        .collect();
+    let (all_variables, mut values): (Vec<_>, Vec<_>) = initial_guesses.into_iter().unzip();
+    let mut model =
+        Model::new(&entries, all_variables, values.clone(), &Config::default()).unwrap();
+    let newton = model
+        .solve_levenberg_marquardt(&mut values, &Config::default())
+        .unwrap();
+    assert!(solved.iterations() <= newton.iterations);
+    for (fast, newton) in solved.final_values().iter().zip(values) {
+        assert_nearly_eq(*fast, newton);
+    }
+}
 
-    // Expected values for arc1 (matching the modeling-app test)
-    // These should remain unchanged when PointArcCoincident is added
-    let arc1_center_x_expected = 30.0;
-    let arc1_center_y_expected = 0.0;
-    let arc1_start_x_expected = 0.0;
-    let arc1_start_y_expected = 5.0;
-    let arc1_end_x_expected = 0.0;
-    let arc1_end_y_expected = -5.0;
+#[test]
+fn tiny() {
+    let solved = run("tiny");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 0.0, y: 0.0 });
+}
 
-    // Initial guesses matching the trim test scenario
-    let initial_guesses = vec![
-        // arc1: start = (0, 5), end = (0, -5), center = (30, 0)
-        (arc1_center.id_x(), 30.0),
-        (arc1_center.id_y(), 0.0),
-        (arc1_start.id_x(), 0.0),
-        (arc1_start.id_y(), 5.0),
-        (arc1_end.id_x(), 0.0),
-        (arc1_end.id_y(), -5.0),
-        // arc2: start = (5, 0), end = (-5, 0), center = (0, -30)
-        (arc2_center.id_x(), 0.0),
-        (arc2_center.id_y(), -30.0),
-        (arc2_start.id_x(), 5.0),
-        (arc2_start.id_y(), 0.0),
-        // arc2.end will become the intersection point after trimming
-        (arc2_end.id_x(), -5.0),
-        (arc2_end.id_y(), 0.0),
-    ];
+#[test]
+fn priorities() {
+    // P is fixed and Q is 5 away from it. Q should also be level with P, at a lower priority,
+    // and at X = 3 at an even lower priority, which can't be done as well.
+    let txt = std::fs::read_to_string("../test_cases/priorities/problem.md").unwrap();
+    let problem = parse_problem(&txt);
+    let system = problem.to_constraint_system().unwrap();
+    let priorities: Vec<_> = system.constraints.iter().map(|c| c.priority()).collect();
+    assert_eq!(priorities, vec![0, 0, 1, 2]);
+    let solved = system.solve().unwrap();
+    assert!(solved.unsatisfied.is_empty());
+    // Priority 2 couldn't be satisfied, so the solution is from priority 1.
+    assert_eq!(solved.priority_solved, 1);
+    assert_points_eq(solved.points["q"], Point { x: 5.0, y: 0.0 });
+}
 
-    // Now solve with PointArcCoincident added (this is what the trim operation does)
-    // The bug is that adding PointArcCoincident causes arc1's parameters to change
-    // even though they should remain at the expected values
-    let constraints_with_trim = vec![
-        // Define both arcs
-        ConstraintRequest::highest_priority(Constraint::Arc(arc1)),
-        ConstraintRequest::highest_priority(Constraint::Arc(arc2)),
-        // Fix arc1's parameters to the expected values (matching the modeling-app test)
-        // These should NOT change when PointArcCoincident is added
-        ConstraintRequest::highest_priority(Constraint::Fixed(
-            arc1_center.id_x(),
-            arc1_center_x_expected,
-        )),
-        ConstraintRequest::highest_priority(Constraint::Fixed(
-            arc1_center.id_y(),
-            arc1_center_y_expected,
-        )),
-        ConstraintRequest::highest_priority(Constraint::Fixed(
-            arc1_start.id_x(),
-            arc1_start_x_expected,
-        )),
-        ConstraintRequest::highest_priority(Constraint::Fixed(
-            arc1_start.id_y(),
-            arc1_start_y_expected,
-        )),
-        ConstraintRequest::highest_priority(Constraint::Fixed(
-            arc1_end.id_x(),
-            arc1_end_x_expected,
-        )),
-        ConstraintRequest::highest_priority(Constraint::Fixed(
-            arc1_end.id_y(),
-            arc1_end_y_expected,
-        )),
-        // Fix arc2's start and center
-        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_center.id_x(), 0.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_center.id_y(), -30.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_start.id_x(), 5.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_start.id_y(), 0.0)),
-        // The intersection point (arc2.end) should be on arc2 (this is what causes the bug)
-        // In the trim scenario, arc2.end becomes the intersection point
-        ConstraintRequest::highest_priority(Constraint::PointArcCoincident(arc2, arc2_end)),
-        // Also add that arc2.end is on arc1 (matching the coincident constraint
-        // in the modeling-app test: sketch2::coincident([arc2.end, arc1]))
-        ConstraintRequest::highest_priority(Constraint::PointArcCoincident(arc1, arc2_end)),
-    ];
+#[test]
+fn inconsistent() {
+    // This has inconsistent requirements:
+    // p should be (1,4) and it should ALSO be (4,1).
+    // Because they can't be simultaneously satisfied, we should find a
+    // solution which minimizes the squared error instead.
+    let solved = run("inconsistent");
+    assert!(!solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained()); // If anything it's overconstrained not under.
+    assert_points_eq(solved.get_point("o").unwrap(), Point { x: 0.0, y: 0.0 });
+    // (2.5, 2.5) is midway between the two inconsistent requirement points.
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 2.5, y: 2.5 });
+}
 
-    let trim_outcome = solve(&constraints_with_trim, initial_guesses, Config::default())
-        .expect("trim constraint system should converge");
+#[test]
+fn objective_gradient_at_least_squares_minimum() {
+    // The inconsistent constraints can't both be satisfied, but the solution should still
+    // be a stationary point of the least-squares objective.
+    let txt = std::fs::read_to_string("../test_cases/inconsistent/problem.md").unwrap();
+    let problem = parse_problem(&txt);
+    let system = problem.to_constraint_system().unwrap();
+    let solved = system.solve_no_metadata(Config::default()).unwrap();
+    assert!(!solved.is_satisfied());
+    let gradient = solved.objective_gradient(&system.constraints).unwrap();
+    assert_eq!(gradient.len(), solved.final_values().len());
+    for partial in gradient {
+        assert!(
+            partial.abs() < EPSILON,
+            "gradient should be ~0, got {partial}"
+        );
+    }
+}
 
-    assert!(
-        trim_outcome.is_satisfied(),
-        "the constraint should be satisfied"
-    );
+#[test]
+fn residuals_for_inconsistent() {
+    let txt = std::fs::read_to_string("../test_cases/inconsistent/problem.md").unwrap();
+    let problem = parse_problem(&txt);
+    let system = problem.to_constraint_system().unwrap();
+    let solved = system.solve_no_metadata(Config::default()).unwrap();
+    let residuals = solved.residuals();
+    assert_eq!(residuals.len(), system.constraints.len());
+    for (i, residual) in residuals.iter().enumerate() {
+        if solved.unsatisfied().contains(&i) {
+            // P ends up midway between (1, 4) and (4, 1), so each of its coordinates is 1.5 off
+            // from both fixed points.
+            assert_nearly_eq(*residual, libm::hypot(1.5, 1.5));
+        } else {
+            assert!(
+                residual.abs() < EPSILON,
+                "residual {i} should be ~0, got {residual}"
+            );
+        }
+    }
+}
 
-    // Verify that arc1's parameters remain at their fixed values
-    // The bug on kurt-point-on-arc is that these fixed values are violated
-    // when PointArcCoincident is added, even though they're explicitly fixed
-    assert_nearly_eq(
-        trim_outcome.final_values[arc1_center.id_x() as usize],
-        arc1_center_x_expected,
-    );
-    assert_nearly_eq(
-        trim_outcome.final_values[arc1_center.id_y() as usize],
-        arc1_center_y_expected,
-    );
-    assert_nearly_eq(
-        trim_outcome.final_values[arc1_start.id_x() as usize],
-        arc1_start_x_expected,
-    );
-    assert_nearly_eq(
-        trim_outcome.final_values[arc1_start.id_y() as usize],
-        arc1_start_y_expected,
-    );
-    assert_nearly_eq(
-        trim_outcome.final_values[arc1_end.id_x() as usize],
-        arc1_end_x_expected,
-    );
-    assert_nearly_eq(
-        trim_outcome.final_values[arc1_end.id_y() as usize],
-        arc1_end_y_expected,
-    );
+#[test]
+fn diagnostic_report_for_inconsistent() {
+    let txt = std::fs::read_to_string("../test_cases/inconsistent/problem.md").unwrap();
+    let problem = parse_problem(&txt);
+    let system = problem.to_constraint_system().unwrap();
+    let report = system.diagnostic_report(Config::default());
+    assert_eq!(report.status, SolveStatus::LeastSquares);
+    assert!(report.error.is_none());
+    assert_eq!(report.num_vars, 4);
+    // Each point is fixed by one constraint, with a residual for each coordinate.
+    assert_eq!(report.num_eqs, 2 * system.constraints.len());
+    assert_eq!(report.sparsity.unwrap().rows(), report.num_eqs);
+    assert!(report.termination_reason.unwrap().converged());
+    assert!(!report.outcome.unwrap().is_satisfied());
+    assert_eq!(report.analysis.unwrap().remaining_dof(), 0);
+    // P ends up midway between its two fixed positions, so each of its coordinates is off by 1.5
+    // from both of them, while O is satisfied.
+    assert_eq!(report.constraint_residuals.len(), system.constraints.len());
+    let off: Vec<_> = report
+        .constraint_residuals
+        .iter()
+        .filter(|&&r| r > EPSILON)
+        .collect();
+    assert_eq!(off.len(), 2);
+    for r in off {
+        assert_nearly_eq(*r, libm::hypot(1.5, 1.5));
+    }
 }
 
-fn solve_arc_length_case(
-    arc_center_x: f64,
-    arc_center_y: f64,
-    arc_radius: f64,
-    arc_start_radians: f64,
-    desired_arc_length: f64,
-    arc_end_guess: Point,
-) -> (SolveOutcome, DatumCircularArc) {
+#[test]
+fn weight_biases_inconsistent_solution() {
+    // Two competing Fixed constraints on the same variable at the same priority:
+    // target=0 with default weight 1, and target=100 with weight 100. With equal
+    // weights the least-squares minimum sits at the midpoint (50). With 100x on
+    // the second target the solver should pull almost all the way to 100.
     let mut ids = IdGenerator::default();
-    let center = DatumPoint::new(&mut ids);
-    let start = DatumPoint::new(&mut ids);
-    let end = DatumPoint::new(&mut ids);
-    let arc = DatumCircularArc { center, start, end };
-
-    let arc_start = Point {
-        x: arc_center_x + libm::cos(arc_start_radians) * arc_radius,
-        y: arc_center_y + libm::sin(arc_start_radians) * arc_radius,
-    };
+    let var_id = ids.next_id();
 
-    let initial_guesses = vec![
-        (arc.center.id_x(), arc_center_x),
-        (arc.center.id_y(), arc_center_y),
-        (arc.start.id_x(), arc_start.x),
-        (arc.start.id_y(), arc_start.y),
-        (arc.end.id_x(), arc_end_guess.x),
-        (arc.end.id_y(), arc_end_guess.y),
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 100.0)).with_weight(100.0),
     ];
+    let initial_guesses = vec![(var_id, 50.0)];
 
-    let requests: Vec<_> = vec![
-        Constraint::Arc(arc),
-        Constraint::Fixed(arc.center.id_x(), arc_center_x),
-        Constraint::Fixed(arc.center.id_y(), arc_center_y),
-        Constraint::Fixed(arc.start.id_x(), arc_start.x),
-        Constraint::Fixed(arc.start.id_y(), arc_start.y),
-        Constraint::ArcLength(arc, desired_arc_length),
-    ]
-    .into_iter()
-    .map(ConstraintRequest::highest_priority)
-    .collect();
-
-    let outcome =
-        solve(&requests, initial_guesses, Config::default()).expect("arc length case should solve");
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    let final_value = solved.final_values()[0];
+    assert!(
+        final_value > 99.0,
+        "weighted target should dominate; got {final_value}",
+    );
 
-    (outcome, arc)
+    // Equal weights for comparison: should settle at the midpoint.
+    let baseline = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(var_id, 100.0)),
+    ];
+    let baseline_solved = solve(&baseline, vec![(var_id, 50.0)], Config::default()).unwrap();
+    assert_nearly_eq(baseline_solved.final_values()[0], 50.0);
 }
 
 #[test]
-fn arc_length_ccw_over_pi() {
-    let arc_center_x = 0.0;
-    let arc_center_y = 0.0;
-    let arc_radius = 1.0;
-    let arc_start_radians = 0.0;
-    let desired_arc_length = 1.5 * PI;
-    let arc_end_guess = Point { x: 0.0, y: -1.0 };
-
-    let (outcome, arc) = solve_arc_length_case(
-        arc_center_x,
-        arc_center_y,
-        arc_radius,
-        arc_start_radians,
-        desired_arc_length,
-        arc_end_guess,
-    );
-
-    assert!(outcome.is_satisfied());
-
-    let solved_end_x = outcome.final_values[arc.end.id_x() as usize];
-    let solved_end_y = outcome.final_values[arc.end.id_y() as usize];
-    let solved_end = Point {
-        x: solved_end_x,
-        y: solved_end_y,
-    };
-    let center_point = Point {
-        x: arc_center_x,
-        y: arc_center_y,
-    };
-    let end_distance = solved_end.euclidean_distance(center_point);
-    assert_nearly_eq(end_distance, arc_radius);
-
-    let two_pi = 2.0 * PI;
-    let end_radians =
-        libm::atan2(solved_end_y - arc_center_y, solved_end_x - arc_center_x).rem_euclid(two_pi);
-    let ccw_delta = (end_radians - arc_start_radians).rem_euclid(two_pi);
-    let actual_arc_length = arc_radius * ccw_delta;
-    assert_nearly_eq(actual_arc_length, desired_arc_length);
+fn circle() {
+    let solved = run("circle");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 5.0, y: 5.0 });
+    let circle_a = solved.get_circle("a").unwrap();
+    // From the problem:
+    // circle a
+    // radius(a, 3.4)
+    // a.center = (0.1, 0.2)
+    assert_nearly_eq(circle_a.radius, 3.4);
+    assert_points_eq(circle_a.center, Point { x: 0.1, y: 0.2 });
 }
 
 #[test]
-fn arc_length_near_zero() {
-    let arc_center_x = -2.0;
-    let arc_center_y = 3.0;
-    let arc_radius = 5.0;
-    let arc_start_radians = 0.25 * PI;
-    let desired_arc_length = 1.0e-3;
-    let arc_end_guess = Point {
-        x: arc_center_x + libm::cos(arc_start_radians + 1.0e-2) * arc_radius,
-        y: arc_center_y + libm::sin(arc_start_radians + 1.0e-2) * arc_radius,
-    };
+fn circle_center() {
+    // Very similar to test `circle` above,
+    // except it gives each constraint on the center separately.
+    let solved = run("circle_center");
+    assert!(!solved.analysis.is_underconstrained());
+    assert!(solved.is_satisfied());
+    let circle_a = solved.get_circle("a").unwrap();
+    assert_nearly_eq(circle_a.radius, 1.0);
+    assert_points_eq(circle_a.center, Point { x: 0.0, y: 0.0 });
+}
 
-    let (outcome, arc) = solve_arc_length_case(
-        arc_center_x,
-        arc_center_y,
-        arc_radius,
-        arc_start_radians,
-        desired_arc_length,
-        arc_end_guess,
-    );
+#[test]
+fn circle_tangent() {
+    // `tangent(...)` now starts with `LineSide::Undefined`, so the side is inferred
+    // from the initial geometry. This fixture starts the circle below the line.
+    let solved = run("circle_tangent");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 3.0 });
+    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 5.0, y: 3.0 });
+    let circle_a = solved.get_circle("a").unwrap();
+    assert_nearly_eq(circle_a.center.y, 1.5);
+    assert_nearly_eq(circle_a.radius, 1.5);
+}
 
-    assert!(outcome.is_satisfied());
+#[test]
+fn circle_tangent_other_dir() {
+    // Just like `circle_tangent` but using line QP instead of PQ. Reversing the
+    // line direction reverses the preferred side of the oriented tangent.
+    let solved = run("circle_tangent_other_dir");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 3.0 });
+    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 5.0, y: 3.0 });
+    let circle_a = solved.get_circle("a").unwrap();
+    assert_nearly_eq(circle_a.center.y, 1.5);
+    assert_nearly_eq(circle_a.radius, 1.5);
+}
 
-    let solved_end_x = outcome.final_values[arc.end.id_x() as usize];
-    let solved_end_y = outcome.final_values[arc.end.id_y() as usize];
-    let end_radians =
-        libm::atan2(solved_end_y - arc_center_y, solved_end_x - arc_center_x).rem_euclid(2.0 * PI);
-    let ccw_delta = (end_radians - arc_start_radians).rem_euclid(2.0 * PI);
-    let actual_arc_length = arc_radius * ccw_delta;
-    assert_nearly_eq(actual_arc_length, desired_arc_length);
+#[test]
+fn multistart_finds_both_tangent_sides() {
+    // The `circle_tangent` problem: a fixed horizontal line at y = 3, and a circle of radius 1.5
+    // tangent to it. The circle can sit on either side of the line, so its center is at
+    // y = 1.5 or y = 4.5. The initial guesses only lead to the first one.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let circle = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    };
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_x(), 5.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
+            DatumLineSegment::new(p, q),
+            circle,
+            LineSide::Undefined,
+        )),
+        ConstraintRequest::highest_priority(Constraint::Fixed(circle.center.id_x(), 2.5)),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 0.1),
+        (p.id_y(), 3.1),
+        (q.id_x(), 4.9),
+        (q.id_y(), 2.9),
+        (circle.center.id_x(), 2.5),
+        (circle.center.id_y(), 1.4),
+        (circle.radius.id, 2.0),
+    ];
+
+    let solutions = solve_multistart(&constraints, initial_guesses, Config::default(), 10);
+    let mut center_heights: Vec<_> = solutions
+        .iter()
+        .map(|solution| solution.final_value_circle(&circle).center.y)
+        .collect();
+    center_heights.sort_by(f64::total_cmp);
+    assert_eq!(center_heights.len(), 2);
+    assert_nearly_eq(center_heights[0], 1.5);
+    assert_nearly_eq(center_heights[1], 4.5);
+    // The unperturbed start comes first.
+    assert_nearly_eq(solutions[0].final_value_circle(&circle).center.y, 1.5);
 }
 
 #[test]
-fn arc_length_near_full_circle() {
-    let arc_center_x = 1.0;
-    let arc_center_y = -1.0;
-    let arc_radius = 2.5;
-    let arc_start_radians = 0.0;
-    let desired_arc_length = 2.0 * PI * arc_radius - 1.0e-3;
-    let arc_end_guess = Point {
-        x: arc_center_x + libm::cos(-1.0e-2) * arc_radius,
-        y: arc_center_y + libm::sin(-1.0e-2) * arc_radius,
+fn find_conflicting_distances() {
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let r = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        // These two can't both hold, whatever their priorities.
+        ConstraintRequest::new(Constraint::Distance(p, q, 3.0), 1),
+        ConstraintRequest::new(Constraint::Distance(p, q, 5.0), 2),
+        // An unrelated point, which can be placed without any conflict.
+        ConstraintRequest::new(Constraint::Distance(p, r, 1.0), 2),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 4.0),
+        (q.id_y(), 0.1),
+        (r.id_x(), 0.0),
+        (r.id_y(), 1.2),
+    ];
+    let conflicts = find_conflicts(&constraints, initial_guesses, Config::default());
+    assert_eq!(conflicts, vec![vec![2, 3]]);
+}
+
+#[test]
+fn bounded_circle_radius_stays_positive() {
+    // The radius is tied to a parameter which is driven negative. Without bounds the radius
+    // follows it, but bounded it stops at its lower bound.
+    let mut ids = IdGenerator::default();
+    let circle = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
     };
+    let parameter = ids.next_id();
+    let p = DatumPoint::new(&mut ids);
+    let linear = [
+        Constraint::Fixed(circle.center.id_x(), 0.0),
+        Constraint::Fixed(circle.center.id_y(), 0.0),
+        Constraint::ScalarEqual(circle.radius.id, parameter),
+        Constraint::Fixed(parameter, -1.0),
+    ];
+    let initial_guesses = vec![
+        (circle.center.id_x(), 0.0),
+        (circle.center.id_y(), 0.0),
+        (circle.radius.id, 2.0),
+        (parameter, 2.0),
+        (p.id_x(), 1.0),
+        (p.id_y(), 1.0),
+    ];
+    let bounds = [(circle.radius.id, 0.1, f64::INFINITY)];
 
-    let (outcome, arc) = solve_arc_length_case(
-        arc_center_x,
-        arc_center_y,
-        arc_radius,
-        arc_start_radians,
-        desired_arc_length,
-        arc_end_guess,
-    );
+    // Once on its own, which is solved as a linear system, then with an unrelated
+    // nonlinear constraint too, so it's solved with Levenberg-Marquardt.
+    let nonlinear = Constraint::Distance(circle.center, p, 3.0);
+    for constraints in [linear.to_vec(), [linear.as_slice(), &[nonlinear]].concat()] {
+        let constraints: Vec<_> = constraints
+            .into_iter()
+            .map(ConstraintRequest::highest_priority)
+            .collect();
+        let unbounded = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+        assert_nearly_eq(unbounded.final_value_circle(&circle).radius, -1.0);
 
-    assert!(outcome.is_satisfied());
+        let bounded = solve_bounded(
+            &constraints,
+            initial_guesses.clone(),
+            &bounds,
+            Config::default(),
+        )
+        .unwrap();
+        assert!(bounded.is_unsatisfied());
+        assert_nearly_eq(bounded.final_value_circle(&circle).radius, 0.1);
+    }
+}
 
-    let solved_end_x = outcome.final_values[arc.end.id_x() as usize];
-    let solved_end_y = outcome.final_values[arc.end.id_y() as usize];
-    let end_radians =
-        libm::atan2(solved_end_y - arc_center_y, solved_end_x - arc_center_x).rem_euclid(2.0 * PI);
-    let ccw_delta = (end_radians - arc_start_radians).rem_euclid(2.0 * PI);
-    let actual_arc_length = arc_radius * ccw_delta;
-    assert_nearly_eq(actual_arc_length, desired_arc_length);
+#[test]
+fn time_limit_exceeded() {
+    /// Every time it's asked, a second has passed.
+    #[derive(Debug)]
+    struct FakeClock(std::sync::atomic::AtomicU64);
+    impl Clock for FakeClock {
+        fn now(&self) -> std::time::Duration {
+            let ticks = self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            std::time::Duration::from_secs(ticks)
+        }
+    }
+    static CLOCK: FakeClock = FakeClock(std::sync::atomic::AtomicU64::new(0));
+
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let r = DatumPoint::new(&mut ids);
+    let constraints: Vec<_> = [
+        Constraint::Fixed(p.id_x(), 0.0),
+        Constraint::Fixed(p.id_y(), 0.0),
+        Constraint::Distance(p, q, 5.0),
+        Constraint::Distance(q, r, 5.0),
+        Constraint::Distance(p, r, 8.0),
+    ]
+    .into_iter()
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 100.0),
+        (q.id_y(), 1.0),
+        (r.id_x(), -30.0),
+        (r.id_y(), 60.0),
+    ];
+
+    // Without a time limit, this takes more than two iterations.
+    let solved = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    assert!(solved.iterations() > 2);
+
+    // The clock starts at 0s, then iterations 0 and 1 start at 1s and 2s, within the limit.
+    let config = Config::default()
+        .with_time_limit(std::time::Duration::from_millis(2500))
+        .with_clock(&CLOCK);
+    let err = solve(&constraints, initial_guesses, config).unwrap_err();
+    match err.error {
+        NonLinearSystemError::TimeLimitExceeded {
+            iterations,
+            time_limit,
+        } => {
+            assert_eq!(iterations, 2);
+            assert_eq!(time_limit, std::time::Duration::from_millis(2500));
+        }
+        other => panic!("expected the time limit to be exceeded, got {other}"),
+    }
 }
 
 #[test]
-fn arc_length_degenerate_warns() {
+fn session_matches_solve() {
+    // Two priority levels, so the session caches a structure for each.
     let mut ids = IdGenerator::default();
-    let center = DatumPoint::new(&mut ids);
-    let start = DatumPoint::new(&mut ids);
-    let end = DatumPoint::new(&mut ids);
-    let arc = DatumCircularArc { center, start, end };
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(p, q, 5.0)),
+        ConstraintRequest::new(Constraint::Fixed(q.id_x(), 3.0), 1),
+    ];
+    let mut session = SolverSession::new(&constraints, Config::default());
+    for (x, y) in [(1.0, 1.0), (4.0, 2.0), (-2.0, -6.0), (1.0, 1.0)] {
+        let guesses = vec![
+            (p.id_x(), 0.0),
+            (p.id_y(), 0.0),
+            (q.id_x(), x),
+            (q.id_y(), y),
+        ];
+        let expected = solve(&constraints, guesses.clone(), Config::default()).unwrap();
+        let actual = session.resolve(guesses).unwrap();
+        assert!(actual.is_satisfied());
+        assert_eq!(actual.iterations(), expected.iterations());
+        assert_eq!(actual.priority_solved(), expected.priority_solved());
+        for (a, e) in actual.final_values().iter().zip(expected.final_values()) {
+            assert_nearly_eq(*a, *e);
+        }
+    }
 
-    let initial_guesses = vec![
-        (arc.center.id_x(), 0.0),
-        (arc.center.id_y(), 0.0),
-        (arc.start.id_x(), 0.0),
-        (arc.start.id_y(), 0.0),
-        (arc.end.id_x(), 1.0),
-        (arc.end.id_y(), 0.0),
+    // Guessing the variables in another order rebuilds the structure.
+    let guesses = vec![
+        (q.id_y(), 1.0),
+        (q.id_x(), 1.0),
+        (p.id_y(), 0.0),
+        (p.id_x(), 0.0),
     ];
+    let expected = solve(&constraints, guesses.clone(), Config::default()).unwrap();
+    let actual = session.resolve(guesses).unwrap();
+    assert_points_eq(actual.final_value_point(&q), expected.final_value_point(&q));
+}
 
-    let requests: Vec<_> = vec![
-        Constraint::Fixed(arc.center.id_x(), 0.0),
-        Constraint::Fixed(arc.center.id_y(), 0.0),
-        Constraint::Fixed(arc.start.id_x(), 0.0),
-        Constraint::Fixed(arc.start.id_y(), 0.0),
-        Constraint::ArcLength(arc, 1.0),
+#[test]
+fn seed_scalars_from_constraints() {
+    // B's radius is constrained to 4, but guessed as 0. Which way B is tangent to A is inferred
+    // from the guesses, and with a zero radius B looks just as close to touching A from outside
+    // as from inside, so it's put outside. Seeding B's radius from its constraint shows B
+    // already encloses A, touching it from inside.
+    let mut ids = IdGenerator::default();
+    let [a, b] = std::array::from_fn(|_| DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    });
+    let constraints: Vec<_> = [
+        Constraint::Fixed(a.center.id_x(), 0.0),
+        Constraint::Fixed(a.center.id_y(), 0.0),
+        Constraint::CircleRadius(a, 1.0),
+        Constraint::Fixed(b.center.id_y(), 0.0),
+        Constraint::CircleRadius(b, 4.0),
+        Constraint::CircleTangentToCircle(a, b, CircleSide::Undefined),
     ]
     .into_iter()
     .map(ConstraintRequest::highest_priority)
     .collect();
+    let initial_guesses = vec![
+        (a.center.id_x(), 0.0),
+        (a.center.id_y(), 0.0),
+        (a.radius.id, 1.0),
+        (b.center.id_x(), 3.0),
+        (b.center.id_y(), 0.0),
+        (b.radius.id, 0.0),
+    ];
 
-    let outcome = solve(&requests, initial_guesses, Config::default())
-        .expect("degenerate arc length case should solve");
+    let unseeded = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    assert!(unseeded.is_satisfied());
+    assert_nearly_eq(unseeded.final_value_circle(&b).center.x, 5.0);
 
-    assert!(
-        outcome
-            .warnings
-            .iter()
-            .any(|warning| matches!(warning.content, WarningContent::Degenerate))
-    );
+    let config = Config::default().with_seed_scalars_from_constraints(true);
+    let seeded = solve(&constraints, initial_guesses, config).unwrap();
+    assert!(seeded.is_satisfied());
+    let b = seeded.final_value_circle(&b);
+    assert_nearly_eq(b.radius, 4.0);
+    assert_points_eq(b.center, Point { x: 3.0, y: 0.0 });
 }
 
 #[test]
-fn strange_nonconvergence() {
-    use crate::datatypes::inputs::DatumPoint;
-    let p = DatumPoint { x_id: 0, y_id: 1 };
-    let q = DatumPoint { x_id: 2, y_id: 3 };
-    let r = DatumPoint { x_id: 4, y_id: 5 };
-    let s = DatumPoint { x_id: 6, y_id: 7 };
-    let t = DatumPoint { x_id: 8, y_id: 9 };
+fn line_tangent_left_explicit() {
+    let mut ids = IdGenerator::default();
+    let p0 = DatumPoint::new(&mut ids);
+    let p1 = DatumPoint::new(&mut ids);
+    let center = DatumPoint::new(&mut ids);
+    let radius = DatumDistance::new(ids.next_id());
+    let line = DatumLineSegment::new(p0, p1);
+    let circle = DatumCircle { center, radius };
 
-    let requests = [
-        ConstraintRequest::highest_priority(Constraint::Fixed(0, 0.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(1, 0.0)),
-        ConstraintRequest::highest_priority(Constraint::PointsCoincident(r, s)),
-        ConstraintRequest::highest_priority(Constraint::PointsCoincident(q, p)),
-        ConstraintRequest::highest_priority(Constraint::LinesEqualLength(
-            DatumLineSegment { p0: q, p1: r },
-            DatumLineSegment { p0: s, p1: t },
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
+        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
+            line,
+            circle,
+            LineSide::Left,
         )),
     ];
     let initial_guesses = vec![
-        (0, 0.0),
-        (1, -0.02),
-        (2, -3.39),
-        (3, -0.38),
-        (4, -2.76),
-        (5, 4.83),
-        (6, -1.54),
-        (7, 5.21),
-        (8, -1.15),
-        (9, 2.75),
+        (p0.id_x(), 0.0),
+        (p0.id_y(), 3.0),
+        (p1.id_x(), 5.0),
+        (p1.id_y(), 3.0),
+        (center.id_x(), 2.0),
+        (center.id_y(), 1.5),
+        (radius.id, 1.5),
     ];
-    let outcome = solve(
-        &requests,
-        initial_guesses,
-        Config::default().with_max_iterations(31),
-    );
-    let iterations = outcome.unwrap().iterations;
-    assert_eq!(iterations, 2);
+
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let solved_circle = solved.final_value_circle(&circle);
+    assert_nearly_eq(solved_circle.center.y, 4.5);
+    assert_nearly_eq(solved_circle.radius, 1.5);
 }
 
 #[test]
-fn warnings() {
-    let txt = "# constraints
-point p
-point q
-p.x = 0
-p.y = 0
-q.y = 0
-vertical(p, q)
-point r
-point s
-r.x = 0
-s.x = 0
-s.y = 0
-lines_at_angle(p, q, r, s, 0rad)
+fn line_tangent_right_explicit() {
+    let mut ids = IdGenerator::default();
+    let p0 = DatumPoint::new(&mut ids);
+    let p1 = DatumPoint::new(&mut ids);
+    let center = DatumPoint::new(&mut ids);
+    let radius = DatumDistance::new(ids.next_id());
+    let line = DatumLineSegment::new(p0, p1);
+    let circle = DatumCircle { center, radius };
 
-# guesses
-p roughly (3, 4)
-q roughly (5, 6)
-r roughly (3, 4)
-s roughly (5, 6)
-";
-    let problem = Problem::from_str(txt).unwrap();
-    let solved = problem.to_constraint_system().unwrap().solve().unwrap();
-    assert!(!solved.warnings.is_empty());
-    assert!(solved.warnings.contains(&Warning {
-        about_constraint: Some(7),
-        content: WarningContent::ShouldBeParallel(Angle::from_radians(0.0))
-    }));
-}
-
-#[track_caller]
-fn assert_points_eq(l: Point, r: Point) {
-    let dist = l.euclidean_distance(r);
-    assert!(dist < EPSILON, "LHS was {l}, RHS was {r}, dist was {dist}");
-}
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
+        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
+            line,
+            circle,
+            LineSide::Right,
+        )),
+    ];
+    let initial_guesses = vec![
+        (p0.id_x(), 0.0),
+        (p0.id_y(), 3.0),
+        (p1.id_x(), 5.0),
+        (p1.id_y(), 3.0),
+        (center.id_x(), 2.0),
+        (center.id_y(), 4.5),
+        (radius.id, 1.5),
+    ];
 
-#[track_caller]
-pub fn assert_nearly_eq(l: f64, r: f64) {
-    let diff = (l - r).abs();
-    assert!(
-        diff < EPSILON,
-        "LHS was {l}, RHS was {r}, difference was {diff}"
-    );
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let solved_circle = solved.final_value_circle(&circle);
+    assert_nearly_eq(solved_circle.center.y, 1.5);
+    assert_nearly_eq(solved_circle.radius, 1.5);
 }
 
-#[track_caller]
-fn assert_point_on_arc_ccw(point: Point, center: Point, start: Point, end: Point) {
-    let radius = start.euclidean_distance(center);
-    assert_nearly_eq(point.euclidean_distance(center), radius);
-
-    let s = V {
-        x: start.x - center.x,
-        y: start.y - center.y,
-    };
-    let e = V {
-        x: end.x - center.x,
-        y: end.y - center.y,
-    };
-    let p = V {
-        x: point.x - center.x,
-        y: point.y - center.y,
-    };
+#[test]
+fn line_tangent_left_inferred() {
+    let mut ids = IdGenerator::default();
+    let p0 = DatumPoint::new(&mut ids);
+    let p1 = DatumPoint::new(&mut ids);
+    let center = DatumPoint::new(&mut ids);
+    let radius = DatumDistance::new(ids.next_id());
+    let line = DatumLineSegment::new(p0, p1);
+    let circle = DatumCircle { center, radius };
 
-    let two_pi = 2.0 * PI;
-    let a_sp = s.signed_angle(p).rem_euclid(two_pi);
-    let a_se = s.signed_angle(e).rem_euclid(two_pi);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
+        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
+            line,
+            circle,
+            LineSide::Undefined,
+        )),
+    ];
+    let initial_guesses = vec![
+        (p0.id_x(), 0.0),
+        (p0.id_y(), 3.0),
+        (p1.id_x(), 5.0),
+        (p1.id_y(), 3.0),
+        (center.id_x(), 2.0),
+        (center.id_y(), 4.5),
+        (radius.id, 1.5),
+    ];
 
-    let a_sp = if a_sp > two_pi - EPSILON { 0.0 } else { a_sp };
-    assert!(
-        a_sp <= a_se + EPSILON,
-        "point {point} is not on the CCW arc from {start} to {end} around {center}"
-    );
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let solved_circle = solved.final_value_circle(&circle);
+    assert_nearly_eq(solved_circle.center.y, 4.5);
+    assert_nearly_eq(solved_circle.radius, 1.5);
 }
 
-fn solve_point_arc_coincident_with_fixed_arc(
-    center_point: Point,
-    start_point: Point,
-    end_point: Point,
-    initial_point: Point,
-) -> Point {
+#[test]
+fn line_tangent_right_inferred() {
     let mut ids = IdGenerator::default();
+    let p0 = DatumPoint::new(&mut ids);
+    let p1 = DatumPoint::new(&mut ids);
     let center = DatumPoint::new(&mut ids);
-    let start = DatumPoint::new(&mut ids);
-    let end = DatumPoint::new(&mut ids);
-    let point = DatumPoint::new(&mut ids);
-    let arc = DatumCircularArc { center, start, end };
+    let radius = DatumDistance::new(ids.next_id());
+    let line = DatumLineSegment::new(p0, p1);
+    let circle = DatumCircle { center, radius };
 
     let constraints = vec![
-        ConstraintRequest::highest_priority(Constraint::PointArcCoincident(arc, point)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(center.id_x(), center_point.x)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(center.id_y(), center_point.y)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(start.id_x(), start_point.x)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(start.id_y(), start_point.y)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(end.id_x(), end_point.x)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(end.id_y(), end_point.y)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 3.0)),
+        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 1.5)),
+        ConstraintRequest::highest_priority(Constraint::LineTangentToCircle(
+            line,
+            circle,
+            LineSide::Undefined,
+        )),
     ];
     let initial_guesses = vec![
-        (center.id_x(), center_point.x),
-        (center.id_y(), center_point.y),
-        (start.id_x(), start_point.x),
-        (start.id_y(), start_point.y),
-        (end.id_x(), end_point.x),
-        (end.id_y(), end_point.y),
-        (point.id_x(), initial_point.x),
-        (point.id_y(), initial_point.y),
+        (p0.id_x(), 0.0),
+        (p0.id_y(), 3.0),
+        (p1.id_x(), 5.0),
+        (p1.id_y(), 3.0),
+        (center.id_x(), 2.0),
+        (center.id_y(), 1.5),
+        (radius.id, 1.5),
     ];
 
-    let outcome = solve(&constraints, initial_guesses, Config::default())
-        .expect("fixed arc point-arc coincident case should solve");
-    assert!(outcome.is_satisfied(), "constraint should be satisfied");
-
-    Point {
-        x: outcome.final_values[point.id_x() as usize],
-        y: outcome.final_values[point.id_y() as usize],
-    }
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let solved_circle = solved.final_value_circle(&circle);
+    assert_nearly_eq(solved_circle.center.y, 1.5);
+    assert_nearly_eq(solved_circle.radius, 1.5);
 }
 
-/// Regression test for old point-on-arc behavior where the angular residuals were zeroed out once
-/// the point was already on the circle containing the arc.
-///
-/// In this setup the initial point starts on the containing circle but outside the arc's angular
-/// interval. The previous implementation could therefore converge immediately without moving the
-/// point onto the arc itself.
 #[test]
-fn point_arc_coincident_old_incorrect_convergence_1() {
-    let center = Point { x: 0.0, y: 0.0 };
-    let start = Point { x: 1.0, y: 0.0 };
-    let end = Point { x: 0.0, y: 1.0 };
-    let solved_point =
-        solve_point_arc_coincident_with_fixed_arc(center, start, end, Point { x: 0.0, y: -1.0 });
-    assert_point_on_arc_ccw(solved_point, center, start, end);
-    assert_points_eq(solved_point, start);
-}
+fn circle_tangent_external_inferred() {
+    let mut ids = IdGenerator::default();
+    let circle_a = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    };
+    let circle_b = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    };
 
-/// Regression test for old point-on-arc behavior where competing angular residuals could cancel
-/// each other out.
-///
-/// The initial point starts well away from the arc in a configuration where the previous residual
-/// formulation could settle onto the containing circle while still leaving the point outside the
-/// arc interval.
-#[test]
-fn point_arc_coincident_old_incorrect_convergence_2() {
-    let center = Point { x: 0.0, y: 0.0 };
-    let start = Point { x: 1.0, y: 0.0 };
-    let end = Point { x: 0.0, y: 1.0 };
-    let solved_point =
-        solve_point_arc_coincident_with_fixed_arc(center, start, end, Point { x: -3.0, y: -3.0 });
-    assert_point_on_arc_ccw(solved_point, center, start, end);
-    assert_points_eq(solved_point, start);
+    let initial_guesses = vec![
+        (circle_a.center.id_x(), 0.0),
+        (circle_a.center.id_y(), 0.0),
+        (circle_a.radius.id, 2.0),
+        (circle_b.center.id_x(), 4.0),
+        (circle_b.center.id_y(), 0.0),
+        (circle_b.radius.id, 3.0),
+    ];
+    let constraints = [
+        ConstraintRequest::highest_priority(Constraint::Fixed(circle_a.radius.id, 2.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(circle_b.radius.id, 3.0)),
+        ConstraintRequest::highest_priority(Constraint::CircleTangentToCircle(
+            circle_a,
+            circle_b,
+            CircleSide::Undefined,
+        )),
+    ];
+
+    let outcome = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(outcome.is_satisfied());
+    let center_a = outcome.final_value_point(&circle_a.center);
+    let center_b = outcome.final_value_point(&circle_b.center);
+    assert_nearly_eq(center_a.euclidean_distance(center_b), 5.0);
 }
 
-/// Regression test for the bug that motivated this fix.
-///
-/// The bug report was: adding `point_arc_coincident(point, arc)` can cause the solver to
-/// "jump" to a very different configuration even when the point is already basically on the arc.
-///
-/// This test reproduces that bug by:
-/// - Solving a baseline problem without the `point_arc_coincident` constraint
-/// - Solving the same problem with the constraint added
-/// - Verifying that the initial guess is already close to the arc (distance-from-radius < 0.5)
-/// - Asserting that adding the constraint doesn't cause dramatic changes
-///
-/// This test encodes the stability goal: "don't move much if already almost satisfied".
 #[test]
-fn point_basically_already_on_arc_should_not_cause_much_change_in_sketch() {
-    // First, solve without the point_arc_coincident constraint to get a baseline
-    let txt_without = std::fs::read_to_string(
-        "../test_cases/arc_line_coincident_bug/problem_without_arc_constraint.md",
-    )
-    .unwrap();
-    let problem_without = parse_problem(&txt_without);
-    let system_without = problem_without.to_constraint_system().unwrap();
-    let solved_without = system_without
-        .solve_with_config_analysis(Default::default())
-        .unwrap();
-
-    // Now solve with the point_arc_coincident constraint
-    let solved_with = run("arc_line_coincident_bug");
+fn circle_tangent_internal_inferred() {
+    let mut ids = IdGenerator::default();
+    let circle_a = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    };
+    let circle_b = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    };
 
-    // Initial guesses
-    let initial_line3_start = Point { x: 4.32, y: 3.72 };
-    let initial_line3_end = Point { x: 1.06, y: -3.26 };
-    let initial_line4_start = Point { x: -2.32, y: -2.96 };
-    let initial_line4_end = Point { x: -7.01, y: -2.77 };
-    let initial_arc_center = Point { x: 1.06, y: -3.26 };
-    let initial_arc_a = Point { x: -1.44, y: -0.99 };
-    let initial_arc_b = Point { x: 2.49, y: -0.2 };
+    let initial_guesses = vec![
+        (circle_a.center.id_x(), 0.0),
+        (circle_a.center.id_y(), 0.0),
+        (circle_a.radius.id, 5.0),
+        (circle_b.center.id_x(), 1.0),
+        (circle_b.center.id_y(), 0.0),
+        (circle_b.radius.id, 2.0),
+    ];
+    let constraints = [
+        ConstraintRequest::highest_priority(Constraint::Fixed(circle_a.radius.id, 5.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(circle_b.radius.id, 2.0)),
+        ConstraintRequest::highest_priority(Constraint::CircleTangentToCircle(
+            circle_a,
+            circle_b,
+            CircleSide::Undefined,
+        )),
+    ];
 
-    // Get the solved values
-    let solved_line3_start = solved_with.get_point("line3start").unwrap();
-    let solved_line3_end = solved_with.get_point("line3end").unwrap();
-    let solved_line4_start = solved_with.get_point("line4start").unwrap();
-    let solved_line4_end = solved_with.get_point("line4end").unwrap();
-    let solved_arc = solved_with.get_arc("arc1").unwrap();
+    let outcome = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(outcome.is_satisfied());
+    let center_a = outcome.final_value_point(&circle_a.center);
+    let center_b = outcome.final_value_point(&circle_b.center);
+    assert_nearly_eq(center_a.euclidean_distance(center_b), 3.0);
+}
 
-    // Calculate how far line4_start is from the arc in the initial guess
-    // The arc center is at (1.06, -3.26) and arc.a is at (-1.44, -0.99)
-    // So the radius is the distance from center to arc.a
-    let initial_arc_radius = initial_arc_center.euclidean_distance(initial_arc_a);
-    let initial_line4_start_to_center = initial_line4_start.euclidean_distance(initial_arc_center);
-    let initial_distance_from_arc = (initial_line4_start_to_center - initial_arc_radius).abs();
+#[test]
+fn two_rectangles() {
+    let solved = run("two_rectangles");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    // This forms two rectangles.
+    assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 1.0, y: 1.0 });
+    assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 5.0, y: 1.0 });
+    assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 5.0, y: 4.0 });
+    assert_points_eq(solved.get_point("p3").unwrap(), Point { x: 1.0, y: 4.0 });
+    // Second rectangle
+    assert_points_eq(solved.get_point("p4").unwrap(), Point { x: 2.0, y: 2.0 });
+    assert_points_eq(solved.get_point("p5").unwrap(), Point { x: 6.0, y: 2.0 });
+    assert_points_eq(solved.get_point("p6").unwrap(), Point { x: 6.0, y: 6.0 });
+    assert_points_eq(solved.get_point("p7").unwrap(), Point { x: 2.0, y: 6.0 });
+}
 
-    // Verify that line4_start is already very close to the arc (within a reasonable tolerance)
-    // This should be a small value, showing the point is already basically on the arc
-    assert!(
-        initial_distance_from_arc < 0.5,
-        "line4_start should be close to the arc initially. Distance from arc: {}",
-        initial_distance_from_arc
+#[test]
+fn two_rectangles_recording() {
+    let txt = std::fs::read_to_string("../test_cases/two_rectangles/problem.md").unwrap();
+    let problem = parse_problem(&txt);
+    let system = problem.to_constraint_system().unwrap();
+    let (outcome, frames) = system.solve_recording(Config::default()).unwrap();
+    assert!(outcome.converged && outcome.unsatisfied.is_empty());
+    assert!(outcome.iterations > 0);
+    assert_eq!(frames.len(), outcome.iterations);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.iterations, i + 1);
+    }
+    // The last frame is where the solve finished.
+    let last = frames.last().unwrap();
+    assert_eq!(last.points, outcome.points);
+    assert_eq!(last.circles, outcome.circles);
+    assert_eq!(last.arcs, outcome.arcs);
+    assert_eq!(last.scalars, outcome.scalars);
+    // The first frame is somewhere between the initial guesses and the solution.
+    assert_ne!(frames[0].points, outcome.points);
+}
+
+#[test]
+fn analysis_subset() {
+    // Two rectangles, like the `two_rectangles` test case,
+    // except the second one is missing its height so it's underconstrained.
+    let mut ids = IdGenerator::default();
+    let [p0, p1, p2, p3, p4, p5, p6, p7] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+    let rectangle = |[a, b, c, d]: [DatumPoint; 4], origin: (f64, f64)| {
+        [
+            Constraint::Fixed(a.id_x(), origin.0),
+            Constraint::Fixed(a.id_y(), origin.1),
+            Constraint::Horizontal(DatumLineSegment::new(a, b)),
+            Constraint::Horizontal(DatumLineSegment::new(c, d)),
+            Constraint::Vertical(DatumLineSegment::new(b, c)),
+            Constraint::Vertical(DatumLineSegment::new(d, a)),
+            Constraint::Distance(a, b, 4.0),
+        ]
+    };
+    let mut constraints: Vec<_> = rectangle([p0, p1, p2, p3], (1.0, 1.0))
+        .into_iter()
+        .chain([Constraint::Distance(p0, p3, 3.0)])
+        .map(ConstraintRequest::highest_priority)
+        .collect();
+    let first_rectangle: Vec<_> = (0..constraints.len()).collect();
+    constraints.extend(
+        rectangle([p4, p5, p6, p7], (2.0, 2.0))
+            .into_iter()
+            .map(ConstraintRequest::highest_priority),
     );
+    let second_rectangle: Vec<_> = (first_rectangle.len()..constraints.len()).collect();
+    let initial_guesses: Vec<_> = [p0, p1, p2, p3, p4, p5, p6, p7]
+        .iter()
+        .enumerate()
+        .flat_map(|(i, p)| [(p.id_x(), i as f64), (p.id_y(), 0.5 * i as f64)])
+        .collect();
+
+    let whole = solve_analysis(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    assert!(whole.outcome.is_satisfied());
+    assert!(whole.analysis.is_underconstrained());
+
+    // On its own, the first rectangle is fully constrained.
+    let first = solve_analysis_subset(
+        &constraints,
+        initial_guesses.clone(),
+        Config::default(),
+        &first_rectangle,
+    )
+    .unwrap();
+    assert!(!first.analysis.is_underconstrained());
+    // The whole system still gets solved.
+    assert_eq!(first.outcome.final_values(), whole.outcome.final_values());
 
-    // Calculate how much the solution changed from the initial guesses
-    let _change_line3_start = solved_line3_start.euclidean_distance(initial_line3_start);
-    let _change_line3_end = solved_line3_end.euclidean_distance(initial_line3_end);
-    let change_line4_start = solved_line4_start.euclidean_distance(initial_line4_start);
-    let _change_line4_end = solved_line4_end.euclidean_distance(initial_line4_end);
-    let _change_arc_center = solved_arc.center.euclidean_distance(initial_arc_center);
-    let _change_arc_a = solved_arc.a.euclidean_distance(initial_arc_a);
-    let _change_arc_b = solved_arc.b.euclidean_distance(initial_arc_b);
+    // But the second one isn't: its far side can slide up and down.
+    let second = solve_analysis_subset(
+        &constraints,
+        initial_guesses,
+        Config::default(),
+        &second_rectangle,
+    )
+    .unwrap();
+    let mut underconstrained = second.analysis.underconstrained().to_vec();
+    underconstrained.sort_unstable();
+    assert_eq!(underconstrained, vec![p6.id_y(), p7.id_y()]);
+}
+
+#[test]
+fn analysis_cache() {
+    // Q has to be some distance from a fixed P, leaving it one degree of freedom.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let requests = |distance| {
+        vec![
+            ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Distance(p, q, distance)),
+        ]
+    };
+    let guesses = |qx| {
+        vec![
+            (p.id_x(), 0.0),
+            (p.id_y(), 0.0),
+            (q.id_x(), qx),
+            (q.id_y(), 1.0),
+        ]
+    };
+    let mut cache = AnalysisCache::default();
+    assert!(cache.analysis().is_none());
+
+    let solved = cache
+        .solve(&requests(4.0), guesses(3.0), Config::default())
+        .unwrap();
+    assert!(solved.is_satisfied());
+    assert_eq!(cache.analyses_computed(), 1);
+    assert_eq!(cache.analysis().unwrap().remaining_dof(), 1);
+
+    // Only values changed, like dragging Q around, so the analysis is reused.
+    let solved = cache
+        .solve(&requests(5.0), guesses(-2.0), Config::default())
+        .unwrap();
+    assert!(solved.is_satisfied());
+    assert_eq!(cache.analyses_computed(), 1);
+
+    // Fixing Q's X is a structural change, so the analysis is recomputed.
+    let mut fixed_x = requests(5.0);
+    fixed_x.push(ConstraintRequest::highest_priority(Constraint::Fixed(
+        q.id_x(),
+        3.0,
+    )));
+    let solved = cache
+        .solve(&fixed_x, guesses(-2.0), Config::default())
+        .unwrap();
+    assert!(solved.is_satisfied());
+    assert_eq!(cache.analyses_computed(), 2);
+    assert_eq!(cache.analysis().unwrap().remaining_dof(), 0);
+
+    // Invalidating forces a recompute, even for the same structure.
+    cache.invalidate();
+    cache
+        .solve(&fixed_x, guesses(3.0), Config::default())
+        .unwrap();
+    assert_eq!(cache.analyses_computed(), 3);
+}
+
+#[test]
+fn rank_profile() {
+    // Q has to lie on two lines through C. When the lines are perpendicular, Q is pinned
+    // firmly to C. When they're only 1e-7 radians apart, the second line is nearly redundant.
+    let analyze = |e: (f64, f64)| {
+        let mut ids = IdGenerator::default();
+        let [c, d, e_point, q] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+        let fixed = [(c, (1.0, 1.0)), (d, (2.0, 1.0)), (e_point, e)];
+        let constraints: Vec<_> = fixed
+            .iter()
+            .flat_map(|&(p, (x, y))| {
+                [
+                    Constraint::Fixed(p.id_x(), x),
+                    Constraint::Fixed(p.id_y(), y),
+                ]
+            })
+            .chain([
+                Constraint::PointLineDistance(q, DatumLineSegment::new(c, d), 0.0),
+                Constraint::PointLineDistance(q, DatumLineSegment::new(c, e_point), 0.0),
+            ])
+            .map(ConstraintRequest::highest_priority)
+            .collect();
+        let initial_guesses: Vec<_> = fixed
+            .iter()
+            .flat_map(|&(p, (x, y))| [(p.id_x(), x), (p.id_y(), y)])
+            .chain([(q.id_x(), 1.0), (q.id_y(), 1.0)])
+            .collect();
+        solve_analysis(&constraints, initial_guesses, Config::default())
+            .unwrap()
+            .analysis
+    };
+
+    let well_conditioned = analyze((1.0, 2.0)).rank_profile();
+    assert_eq!(well_conditioned.len(), RANK_PROFILE_TOLERANCES.len());
+    assert!(well_conditioned.iter().all(|&(_tolerance, rank)| rank == 8));
+
+    let near_redundant = analyze((2.0, 1.0 + 1e-7)).rank_profile();
+    let tolerances: Vec<_> = near_redundant
+        .iter()
+        .map(|&(tolerance, _rank)| tolerance)
+        .collect();
+    assert_eq!(tolerances, RANK_PROFILE_TOLERANCES);
+    // Loose tolerances count the second line as redundant, tight ones don't.
+    assert_eq!(near_redundant.first(), Some(&(1e-4, 7)));
+    assert_eq!(near_redundant.last(), Some(&(1e-12, 8)));
+}
+
+#[test]
+fn check_jacobian_on_builtin_constraints() {
+    // Every kind which can be built from points and numbers, plus the ones which need circles or
+    // scalar variables, at a generic point where nothing is degenerate or on a kink.
+    let kinds = [
+        "Distance",
+        "VerticalDistance",
+        "HorizontalDistance",
+        "Vertical",
+        "Horizontal",
+        "LinesAtAngle",
+        "FixedPoint",
+        "PointsCoincident",
+        "LinesEqualLength",
+        "ArcRadius",
+        "Arc",
+        "Midpoint",
+        "PointLineDistance",
+        "VerticalPointLineDistance",
+        "HorizontalPointLineDistance",
+        "Symmetric",
+        "PointArcCoincident",
+        "ArcLength",
+        "ArcAngle",
+        "PointsAtAngle",
+        "SubtendsAngle",
+        "PolarOffset",
+        "Homothety",
+        "MinDistance",
+        "LengthRatioBounds",
+        "SameSideOfLine",
+        "Concyclic",
+        "Circumcenter",
+        "ArcSagitta",
+        "ArcSegmentArea",
+        "EqualLengthToCommon",
+        "AnchorNear",
+        "OnGrid",
+        "DistanceSum",
+        "PointOnLine",
+        "Collinear",
+        "ArcMidpoint",
+        "LineAngle",
+        "ArcChordAngle",
+        "ReverseTangentArcs",
+        "AngleBisectorLine",
+        "SymmetricAngle",
+        "EquidistantLinePoint",
+        "ParallelOffset",
+    ];
+    let mut ids = IdGenerator::default();
+    let points: Vec<_> = (0..6).map(|_| DatumPoint::new(&mut ids)).collect();
+    let circle = DatumCircle {
+        center: points[0],
+        radius: DatumDistance::new(ids.next_id()),
+    };
+    let [scalar, circle_center, circle_radius] = std::array::from_fn(|_| ids.next_id());
+    let other_circle = DatumCircle {
+        center: DatumPoint::new_xy(circle_center, ids.next_id()),
+        radius: DatumDistance::new(circle_radius),
+    };
+    let line = DatumLineSegment::new(points[1], points[2]);
+    let other_line = DatumLineSegment::new(points[3], points[4]);
+    let infinite_line = DatumLine::new(&mut ids);
+    let conic = DatumConic::new(&mut ids);
+    let mut constraints: Vec<_> = kinds
+        .iter()
+        .map(|kind| {
+            let SpecError::WrongArity {
+                expected_points,
+                expected_scalars,
+                ..
+            } = constraint_from_spec(kind, &[], &[5.0; 9]).unwrap_err()
+            else {
+                panic!("{kind} should be buildable from a spec");
+            };
+            let scalars: Vec<_> = (0..expected_scalars)
+                .map(|i| 1.3 + 0.4 * i as f64)
+                .collect();
+            constraint_from_spec(kind, &points[..expected_points], &scalars).unwrap()
+        })
+        .collect();
+    constraints.extend([
+        Constraint::LineTangentToCircle(line, circle, LineSide::Undefined),
+        // Takes eight points, more than the specs above get.
+        Constraint::EqualAngle(
+            line,
+            other_line,
+            DatumLineSegment::new(points[0], points[5]),
+            DatumLineSegment::new(points[5], points[2]),
+        ),
+        Constraint::CircleTangentToCircle(circle, other_circle, CircleSide::Undefined),
+        Constraint::CircleRadius(circle, 2.0),
+        Constraint::DistanceVar(points[1], points[2], DatumDistance::new(scalar)),
+        Constraint::PointOnConic(points[3], conic),
+        Constraint::ArcCircleEqualRadius(
+            DatumCircularArc {
+                center: points[0],
+                start: points[1],
+                end: points[2],
+            },
+            circle,
+        ),
+        Constraint::Fixed(scalar, 1.0),
+        Constraint::ScalarEqual(scalar, circle.radius.id),
+        Constraint::DistanceLinearCombo(
+            scalar,
+            [(circle.radius.id, 1.0), (circle_radius, -2.0)],
+            0.5,
+        ),
+        Constraint::ScaledCopy(points[0], scalar, points[1], points[2]),
+        Constraint::RadiusRatio(circle, scalar, 1.5),
+        Constraint::TangentAtAngle(line, circle, Angle::from_degrees(30.0)),
+        Constraint::ParallelOffsetScalar(line, other_line, scalar),
+        Constraint::FilletRadius(line, other_line, circle),
+        Constraint::PointOnCircle(points[3], circle),
+        Constraint::LineCircleIntersection(points[3], line, circle),
+        Constraint::AngleEqualsScalar(line, other_line, scalar),
+        Constraint::ArcCentralAngleScalar(
+            DatumCircularArc {
+                center: points[0],
+                start: points[1],
+                end: points[2],
+            },
+            scalar,
+        ),
+        Constraint::PointInfiniteLineDistance(points[0], infinite_line, 1.0),
+        Constraint::InfiniteLineTangentToCircle(infinite_line, circle, LineSide::Undefined),
+        Constraint::CommonTangent(line, circle, other_circle, TangencyKind::External),
+        Constraint::CommonTangent(line, circle, other_circle, TangencyKind::Internal),
+        // The arc's end is one of the line's points, like a real line-arc join.
+        Constraint::LineArcTangent(
+            line,
+            DatumCircularArc {
+                center: points[0],
+                start: points[5],
+                end: line.p1,
+            },
+            Endpoint::End,
+        ),
+        Constraint::LineArcTangent(
+            line,
+            DatumCircularArc {
+                center: points[0],
+                start: points[5],
+                end: line.p1,
+            },
+            Endpoint::Start,
+        ),
+    ]);
+    // Every variant should be checked. This match has no wildcard arm, so a new variant won't
+    // compile until it has an index here, and then this fails until it's checked above.
+    fn variant_index(constraint: &Constraint) -> usize {
+        match constraint {
+            Constraint::LineTangentToCircle(..) => 0,
+            Constraint::CircleTangentToCircle(..) => 1,
+            Constraint::Distance(..) => 2,
+            Constraint::DistanceVar(..) => 3,
+            Constraint::VerticalDistance(..) => 4,
+            Constraint::HorizontalDistance(..) => 5,
+            Constraint::Vertical(..) => 6,
+            Constraint::Horizontal(..) => 7,
+            Constraint::Fixed(..) => 8,
+            Constraint::FixedPoint(..) => 9,
+            Constraint::LinesAtAngle(..) => 10,
+            Constraint::PointsCoincident(..) => 11,
+            Constraint::CircleRadius(..) => 12,
+            Constraint::LinesEqualLength(..) => 13,
+            Constraint::ArcRadius(..) => 14,
+            Constraint::Arc(..) => 15,
+            Constraint::Midpoint(..) => 16,
+            Constraint::PointLineDistance(..) => 17,
+            Constraint::VerticalPointLineDistance(..) => 18,
+            Constraint::HorizontalPointLineDistance(..) => 19,
+            Constraint::Symmetric(..) => 20,
+            Constraint::ScalarEqual(..) => 21,
+            Constraint::PointArcCoincident(..) => 22,
+            Constraint::ArcLength(..) => 23,
+            Constraint::ArcAngle(..) => 24,
+            Constraint::PointsAtAngle(..) => 25,
+            Constraint::PolarOffset(..) => 26,
+            Constraint::PointOnConic(..) => 27,
+            Constraint::ScaledCopy(..) => 28,
+            Constraint::Homothety(..) => 29,
+            Constraint::PointInfiniteLineDistance(..) => 30,
+            Constraint::InfiniteLineTangentToCircle(..) => 31,
+            Constraint::MinDistance(..) => 32,
+            Constraint::LengthRatioBounds(..) => 33,
+            Constraint::SameSideOfLine(..) => 34,
+            Constraint::ArcCircleEqualRadius(..) => 35,
+            Constraint::Concyclic(..) => 36,
+            Constraint::Circumcenter(..) => 37,
+            Constraint::ArcSagitta(..) => 38,
+            Constraint::ArcSegmentArea(..) => 39,
+            Constraint::EqualLengthToCommon(..) => 40,
+            Constraint::AnchorNear(..) => 41,
+            Constraint::OnGrid(..) => 42,
+            Constraint::DistanceSum(..) => 43,
+            Constraint::FilletRadius(..) => 44,
+            Constraint::PointOnCircle(..) => 45,
+            Constraint::PointOnLine(..) => 46,
+            Constraint::LineCircleIntersection(..) => 47,
+            Constraint::Collinear(..) => 48,
+            Constraint::CommonTangent(..) => 49,
+            Constraint::ArcMidpoint(..) => 50,
+            Constraint::LineAngle(..) => 51,
+            Constraint::EqualAngle(..) => 52,
+            Constraint::LineArcTangent(..) => 53,
+            Constraint::ArcChordAngle(..) => 54,
+            Constraint::ReverseTangentArcs(..) => 55,
+            Constraint::DistanceLinearCombo(..) => 56,
+            Constraint::AngleBisectorLine(..) => 57,
+            Constraint::SymmetricAngle(..) => 58,
+            Constraint::RadiusRatio(..) => 59,
+            Constraint::EquidistantLinePoint(..) => 60,
+            Constraint::TangentAtAngle(..) => 61,
+            Constraint::ParallelOffset(..) => 62,
+            Constraint::ParallelOffsetScalar(..) => 63,
+            Constraint::AngleEqualsScalar(..) => 64,
+            Constraint::SubtendsAngle(..) => 65,
+            Constraint::ArcCentralAngleScalar(..) => 66,
+        }
+    }
+    let mut checked = [false; 67];
+    for constraint in &constraints {
+        checked[variant_index(constraint)] = true;
+    }
+    for (index, checked) in checked.iter().enumerate() {
+        assert!(checked, "variant {index} of `variant_index` isn't checked");
+    }
+
+    // Scatter the values, so no points coincide and no lines are parallel.
+    let values: Vec<_> = (0..ids.next_id())
+        .map(|i| 3.0 * libm::sin(1.7 * i as f64 + 0.3))
+        .collect();
+
+    for constraint in constraints {
+        let report = check_jacobian(&constraint, &values).unwrap();
+        let kind = constraint.constraint_kind();
+        assert!(!report.degenerate, "{kind} was degenerate");
+        assert!(!report.entries.is_empty(), "{kind} had no entries");
+        for entry in report.entries {
+            let tolerance = 1e-5 * (1.0 + entry.analytic.abs());
+            assert!(
+                entry.error < tolerance,
+                "{kind} ∂r{}/∂id{}: analytic {}, finite difference {}",
+                entry.row,
+                entry.variable,
+                entry.analytic,
+                entry.finite_difference,
+            );
+        }
+    }
+}
+
+#[test]
+fn constraint_from_spec_matches_variants() {
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+
+    // Constraint doesn't implement PartialEq, so compare the debug output instead.
+    let distance = constraint_from_spec("Distance", &[p, q], &[4.0]).unwrap();
+    assert_eq!(
+        format!("{distance:?}"),
+        format!("{:?}", Constraint::Distance(p, q, 4.0))
+    );
+    assert_eq!(distance.constraint_kind(), "Distance");
+    let coincident = constraint_from_spec("PointsCoincident", &[p, q], &[]).unwrap();
+    assert_eq!(
+        format!("{coincident:?}"),
+        format!("{:?}", Constraint::PointsCoincident(p, q))
+    );
+
+    // Wrong number of arguments.
+    let err = constraint_from_spec("PointsCoincident", &[p], &[1.0]).unwrap_err();
+    assert!(matches!(
+        err,
+        SpecError::WrongArity {
+            expected_points: 2,
+            expected_scalars: 0,
+            points: 1,
+            scalars: 1,
+            ..
+        }
+    ));
+    // Kinds which need more than points and numbers can't be built from a spec.
+    let err = constraint_from_spec("CircleRadius", &[p], &[1.0]).unwrap_err();
+    assert!(matches!(err, SpecError::UnknownKind { .. }));
+}
+
+#[test]
+fn enforce_symmetry_about_axis() {
+    // The axis is the diagonal y = x, and each pair has one fixed and one free point,
+    // so the free points should end up at the fixed ones' mirror images.
+    let mut ids = IdGenerator::default();
+    let [axis0, axis1, a, a_mirror, b, b_mirror] =
+        std::array::from_fn(|_| DatumPoint::new(&mut ids));
+    let fixed = [
+        (axis0, (0.0, 0.0)),
+        (axis1, (1.0, 1.0)),
+        (a, (2.0, 0.0)),
+        (b, (3.0, 1.0)),
+    ];
+    let reqs: Vec<_> = fixed
+        .iter()
+        .flat_map(|&(p, (x, y))| {
+            [
+                Constraint::Fixed(p.id_x(), x),
+                Constraint::Fixed(p.id_y(), y),
+            ]
+        })
+        .map(ConstraintRequest::highest_priority)
+        .collect();
+    let reqs = enforce_symmetry(
+        &reqs,
+        &[(a, a_mirror), (b, b_mirror)],
+        DatumLineSegment::new(axis0, axis1),
+    );
+    assert_eq!(reqs.len(), 10);
+    let initial_guesses: Vec<_> = fixed
+        .iter()
+        .flat_map(|&(p, (x, y))| [(p.id_x(), x), (p.id_y(), y)])
+        .chain([
+            (a_mirror.id_x(), 0.5),
+            (a_mirror.id_y(), 1.5),
+            (b_mirror.id_x(), 1.5),
+            (b_mirror.id_y(), 2.5),
+        ])
+        .collect();
+    let solved = solve(&reqs, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    assert_points_eq(
+        solved.final_value_point(&a_mirror),
+        Point { x: 0.0, y: 2.0 },
+    );
+    assert_points_eq(
+        solved.final_value_point(&b_mirror),
+        Point { x: 1.0, y: 3.0 },
+    );
+}
+
+#[test]
+fn jacobian_sparsity() {
+    // Both rows depend on both points, so every entry is nonzero.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let dense = [
+        ConstraintRequest::highest_priority(Constraint::Distance(p, q, 3.0)),
+        ConstraintRequest::highest_priority(Constraint::MinDistance(p, q, 1.0)),
+    ];
+    let guesses: Vec<_> = [p.id_x(), p.id_y(), q.id_x(), q.id_y()]
+        .into_iter()
+        .map(|id| (id, 0.0))
+        .collect();
+    let stats = jacobian_sparsity_stats(&dense, &guesses).unwrap();
+    assert_eq!((stats.rows(), stats.columns()), (2, 4));
+    assert_eq!(stats.nonzeros(), 8);
+    assert_nearly_eq(stats.density(), 1.0);
+    assert_eq!(stats.max_row_nonzeros(), 4);
+    assert_eq!(stats.max_column_nonzeros(), 2);
+
+    // A chain of scalars, each equal to the next, only has nonzeroes next to the diagonal.
+    let mut ids = IdGenerator::default();
+    let scalars: Vec<_> = (0..20).map(|_| ids.next_id()).collect();
+    let banded: Vec<_> = scalars
+        .windows(2)
+        .map(|pair| ConstraintRequest::highest_priority(Constraint::ScalarEqual(pair[0], pair[1])))
+        .collect();
+    let guesses: Vec<_> = scalars.iter().map(|&id| (id, 0.0)).collect();
+    let stats = jacobian_sparsity_stats(&banded, &guesses).unwrap();
+    assert_eq!((stats.rows(), stats.columns()), (19, 20));
+    assert_eq!(stats.nonzeros(), 38);
+    assert!(stats.density() < 0.15);
+    assert_eq!(stats.max_row_nonzeros(), 2);
+    assert_eq!(stats.max_column_nonzeros(), 2);
+    assert_eq!(stats.bandwidth(), 1);
+}
+
+#[test]
+fn evaluate_at_known_point() {
+    // A circle of radius 2.5 centered at (1, 3), and the line from (0, 0) to (4, 0).
+    // The circle should have radius 2, and its center should be 2 from the line.
+    let mut ids = IdGenerator::default();
+    let circle = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    };
+    let line = DatumLineSegment::new(DatumPoint::new(&mut ids), DatumPoint::new(&mut ids));
+    let constraints = [
+        ConstraintRequest::highest_priority(Constraint::CircleRadius(circle, 2.0)),
+        ConstraintRequest::highest_priority(Constraint::PointLineDistance(
+            circle.center,
+            line,
+            2.0,
+        )),
+    ];
+    let values = [
+        (circle.center.id_x(), 1.0),
+        (circle.center.id_y(), 3.0),
+        (circle.radius.id, 2.5),
+        (line.p0.id_x(), 0.0),
+        (line.p0.id_y(), 0.0),
+        (line.p1.id_x(), 4.0),
+        (line.p1.id_y(), 0.0),
+    ];
+
+    let (residuals, jacobian) = evaluate_at(&constraints, &values).unwrap();
+    // The radius is 0.5 too big, and the center is 3 from the line instead of 2.
+    assert_eq!(residuals.len(), 2);
+    assert_nearly_eq(residuals[0], 0.5);
+    assert_nearly_eq(residuals[1], 1.0);
+
+    let partial = |row: usize, id: Id| {
+        jacobian
+            .iter()
+            .find(|&&(r, c, _)| r == row && c == id as usize)
+            .map_or(0.0, |&(_, _, val)| val)
+    };
+    assert_eq!(jacobian.len(), 1 + 6);
+    assert_nearly_eq(partial(0, circle.radius.id), 1.0);
+    // Moving the center up moves it away from the line, and sideways doesn't matter.
+    assert_nearly_eq(partial(1, circle.center.id_x()), 0.0);
+    assert_nearly_eq(partial(1, circle.center.id_y()), 1.0);
+    // Raising the line's ends moves it towards the center, weighted by how close
+    // each end is to the center's projection onto the line (1/4 of the way along).
+    assert_nearly_eq(partial(1, line.p0.id_x()), 0.0);
+    assert_nearly_eq(partial(1, line.p0.id_y()), -0.75);
+    assert_nearly_eq(partial(1, line.p1.id_x()), 0.0);
+    assert_nearly_eq(partial(1, line.p1.id_y()), -0.25);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_diagnostic_report() {
+    let requests = [
+        ConstraintRequest::highest_priority(Constraint::Fixed(0, 1.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(0, 3.0)),
+    ];
+    let report = diagnostic_report(&requests, vec![(0, 0.0)], Config::default());
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["status"], "LeastSquares");
+    assert_eq!(json["num_eqs"], 2);
+    assert_eq!(json["constraint_residuals"].as_array().unwrap().len(), 2);
+    assert_eq!(json["analysis"]["remaining_dof"], 0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_outcome_with_analysis() {
+    // Only the point's X is fixed, so it has one degree of freedom left.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let constraints = [ConstraintRequest::highest_priority(Constraint::Fixed(
+        p.id_x(),
+        1.0,
+    ))];
+    let initial_guesses = vec![(p.id_x(), 0.0), (p.id_y(), 2.0)];
+    let solved = solve_analysis(&constraints, initial_guesses, Config::default()).unwrap();
+    assert_eq!(solved.analysis.remaining_dof(), 1);
+
+    let json = serde_json::to_value(&solved).unwrap();
+    assert_eq!(json["analysis"]["remaining_dof"], 1);
+    assert_eq!(json["analysis"]["is_underconstrained"], true);
+    assert_eq!(
+        json["analysis"]["underconstrained"],
+        serde_json::json!([p.id_y()])
+    );
+    // The free Y never moved from its guess.
+    assert_eq!(json["outcome"]["final_values"][1], 2.0);
+}
+
+#[test]
+fn angle_constraints() {
+    for file in ["angle_parallel", "angle_parallel_manual"] {
+        let solved = run(file);
+        assert!(solved.is_satisfied());
+        assert!(!solved.analysis.is_underconstrained());
+        assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 0.0, y: 0.0 });
+        assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 4.0, y: 4.0 });
+        assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 0.0, y: 0.0 });
+        assert_points_eq(solved.get_point("p3").unwrap(), Point { x: 4.0, y: 4.0 });
+    }
+}
+
+#[test]
+fn perpendicular() {
+    let solved = run("perpendicular");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 0.0, y: 4.0 });
+    assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_points_eq(solved.get_point("p3").unwrap(), Point { x: 4.0, y: 0.0 });
+}
+
+#[test]
+fn nonsquare() {
+    let solved = run("nonsquare");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("p").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_points_eq(solved.get_point("q").unwrap(), Point { x: 0.0, y: 0.0 });
+}
+
+#[test]
+fn square() {
+    let solved = run("square");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_nearly_eq(
+        solved.get_point("a").unwrap().y - solved.get_point("c").unwrap().y,
+        solved.get_point("b").unwrap().y - solved.get_point("d").unwrap().y,
+    );
+    assert_nearly_eq(
+        solved.get_point("a").unwrap().x - solved.get_point("c").unwrap().x,
+        solved.get_point("d").unwrap().x - solved.get_point("b").unwrap().x,
+    );
+}
+
+#[test]
+fn parallelogram() {
+    let solved = run("parallelogram");
+    // The paralallelogram has two vertical lines AB and CD.
+    // A and B are fully determined, but C and D are free.
+    assert!(solved.analysis.is_underconstrained());
+    // A = 0 and 1
+    // B = 2 and 3
+    // CD are 4, 5, 6 and 7, and aren't constrained.
+    assert_eq!(solved.analysis.underconstrained(), vec![4, 5, 6, 7]);
+    assert_nearly_eq(
+        solved.get_point("a").unwrap().y - solved.get_point("c").unwrap().y,
+        solved.get_point("b").unwrap().y - solved.get_point("d").unwrap().y,
+    );
+    assert_nearly_eq(
+        solved.get_point("a").unwrap().x - solved.get_point("c").unwrap().x,
+        solved.get_point("b").unwrap().x - solved.get_point("d").unwrap().x,
+    );
+}
+
+#[test]
+fn underdetermined_lines() {
+    // This should solve for a horizontal line from (0,0) to (4,0), then
+    // a vertical line from (4,0) to (4,4). Note that the length of the second
+    // line is not specified; we're relying on regularisation to push our solution
+    // towards its start point.
+    let solved = run("underdetermined_lines");
+    assert!(solved.analysis.is_underconstrained());
+    assert_eq!(
+        solved.analysis.underconstrained(),
+        vec![5],
+        "P0 and P1 are constrained, but P2 is only fixed in the X direction, not Y"
+    );
+    assert!(solved.is_satisfied());
+    assert_points_eq(solved.get_point("p0").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_points_eq(solved.get_point("p1").unwrap(), Point { x: 4.0, y: 0.0 });
+    assert_points_eq(solved.get_point("p2").unwrap(), Point { x: 4.0, y: 4.0 });
+}
+
+#[test]
+fn arc_radius() {
+    let solved = run("arc_radius");
+    assert!(solved.is_satisfied());
+    assert!(solved.analysis.is_underconstrained());
+    assert_eq!(
+        solved.analysis.underconstrained(),
+        vec![
+            // P is vars 0,1, and P is totally unconstrained.
+            0, 1,
+            // The arc's endpoint A (2, 3) and B (4, 5) are unconstrained, they can be anywhere
+            // as long as they're the right distance from the arc's center.
+            // But the center (6, 7) is fully constrained.
+            2, 3, 4, 5
+        ],
+        "Center of arc is fixed, but the other 2 points can vary."
+    );
+    let arc = solved.get_arc("a").unwrap();
+    assert_points_eq(arc.center, Point { x: 0.0, y: 0.0 });
+    assert_nearly_eq(5.0, arc.a.euclidean_distance(Default::default()));
+    assert_nearly_eq(5.0, arc.b.euclidean_distance(Default::default()));
+}
+
+#[test]
+fn arc_circle_equal_radius() {
+    // The arc starts out with radius 5, but should shrink to match the fixed circle's radius.
+    let solved = run("arc_circle_equal_radius");
+    assert!(solved.is_satisfied());
+    let circle = solved.get_circle("c").unwrap();
+    assert_nearly_eq(3.0, circle.radius);
+    let arc = solved.get_arc("a").unwrap();
+    assert_points_eq(arc.center, Point { x: 0.0, y: 0.0 });
+    assert_nearly_eq(circle.radius, arc.a.euclidean_distance(arc.center));
+    assert_nearly_eq(circle.radius, arc.b.euclidean_distance(arc.center));
+}
+
+#[test]
+fn concyclic() {
+    // B starts on a different circle to A, but should end up sharing A's circle.
+    let solved = run("concyclic");
+    assert!(solved.is_satisfied());
+    let a = solved.get_arc("a").unwrap();
+    let b = solved.get_arc("b").unwrap();
+    assert_points_eq(a.center, Point { x: 0.0, y: 0.0 });
+    assert_points_eq(b.center, a.center);
+    for p in [a.a, a.b, b.a, b.b] {
+        assert_nearly_eq(2.0, p.euclidean_distance(a.center));
+    }
+}
+
+/// Point-Arc coincident constraint.
+#[test]
+fn parc_coincident() {
+    let solved = run("parc_coincident");
+    assert!(solved.is_satisfied());
+    assert!(solved.analysis.is_underconstrained());
+    let arc = solved.get_arc("a").unwrap();
+    let origin = Point { x: 0.0, y: 0.0 };
+    assert_points_eq(arc.center, origin);
+    assert_nearly_eq(5.0, arc.a.euclidean_distance(origin));
+    assert_nearly_eq(5.0, arc.b.euclidean_distance(origin));
+    let point = solved.get_point("p").unwrap();
+    assert_nearly_eq(5.0, arc.center.euclidean_distance(point));
+}
+
+#[test]
+fn arc_equidistant() {
+    let solved = run("arc_equidistant");
+    assert!(solved.is_satisfied());
+    assert!(solved.analysis.is_underconstrained());
+    assert_eq!(
+        solved.analysis.underconstrained(),
+        vec![
+            // P is vars 0,1, and P is totally unconstrained.
+            0, 1,
+            // The arc's endpoint A (2, 3) and B (4, 5) are unconstrained, they can be anywhere
+            // as long as they're the right distance from the arc's center.
+            // But the center (6, 7) is fully constrained.
+            2, 3, 4, 5
+        ],
+        "Center of arc is fixed, but the other 2 points can vary."
+    );
+    let arc = solved.get_arc("a").unwrap();
+    assert_points_eq(arc.center, Point { x: 0.0, y: 0.0 });
+    assert_nearly_eq(
+        arc.a.euclidean_distance(arc.center),
+        arc.b.euclidean_distance(arc.center),
+    );
+}
+
+#[test]
+fn chamfer_square() {
+    let solved = run("chamfer_square");
+    assert!(solved.is_satisfied());
+    assert!(!solved.analysis.is_underconstrained());
+    assert_points_eq(solved.get_point("a").unwrap(), Point { x: 0.0, y: 40.0 });
+    assert_points_eq(solved.get_point("b").unwrap(), Point { x: 30.0, y: 40.0 });
+    assert_points_eq(solved.get_point("c").unwrap(), Point { x: 40.0, y: 30.0 });
+    assert_points_eq(solved.get_point("d").unwrap(), Point { x: 40.0, y: 0.0 });
+    assert_points_eq(solved.get_point("e").unwrap(), Point { x: 0.0, y: 0.0 });
+}
+
+#[test]
+fn arc_length() {
+    let solved = run("arc_length");
+    assert!(solved.is_satisfied());
+}
+
+/// Test that mirrors the `trim_arc2_left_side` scenario from modeling-app.
+///
+/// This test represents a trim operation where:
+/// - Two arcs intersect
+/// - arc1 should remain unchanged (its parameters shouldn't change when `PointArcCoincident` is added)
+/// - arc2 should be trimmed to end at the intersection point
+/// - When `PointArcCoincident` is added for the intersection point on arc2, arc1's parameters should remain unchanged
+///
+/// This test mirrors the KCL code from the modeling-app test:
+/// ```
+/// sketch(on = YZ) {
+///   arc1 = sketch2::arc(start = [var 0mm, var 5mm], end = [var 0mm, var -5mm], center = [var 30mm, var 0mm])
+///   arc2 = sketch2::arc(start = [var 5mm, var 0mm], end = [var -5mm, var 0mm], center = [var 0mm, var -30mm])
+/// }
+/// ```
+#[test]
+fn test_trim_arc2_left_side_arc1_should_remain_fixed() {
+    let mut ids = IdGenerator::default();
+
+    // Create two arcs matching the trim test scenario:
+    // arc1: start = (0, 5), end = (0, -5), center = (30, 0)
+    // arc2: start = (5, 0), end = (-5, 0), center = (0, -30)
+    let arc1_center = DatumPoint::new(&mut ids);
+    let arc1_start = DatumPoint::new(&mut ids);
+    let arc1_end = DatumPoint::new(&mut ids);
+    let arc1 = DatumCircularArc {
+        center: arc1_center,
+        start: arc1_start,
+        end: arc1_end,
+    };
+
+    let arc2_center = DatumPoint::new(&mut ids);
+    let arc2_start = DatumPoint::new(&mut ids);
+    let arc2_end = DatumPoint::new(&mut ids);
+    let arc2 = DatumCircularArc {
+        center: arc2_center,
+        start: arc2_start,
+        end: arc2_end,
+    };
+
+    // Expected values for arc1 (matching the modeling-app test)
+    // These should remain unchanged when PointArcCoincident is added
+    let arc1_center_x_expected = 30.0;
+    let arc1_center_y_expected = 0.0;
+    let arc1_start_x_expected = 0.0;
+    let arc1_start_y_expected = 5.0;
+    let arc1_end_x_expected = 0.0;
+    let arc1_end_y_expected = -5.0;
+
+    // Initial guesses matching the trim test scenario
+    let initial_guesses = vec![
+        // arc1: start = (0, 5), end = (0, -5), center = (30, 0)
+        (arc1_center.id_x(), 30.0),
+        (arc1_center.id_y(), 0.0),
+        (arc1_start.id_x(), 0.0),
+        (arc1_start.id_y(), 5.0),
+        (arc1_end.id_x(), 0.0),
+        (arc1_end.id_y(), -5.0),
+        // arc2: start = (5, 0), end = (-5, 0), center = (0, -30)
+        (arc2_center.id_x(), 0.0),
+        (arc2_center.id_y(), -30.0),
+        (arc2_start.id_x(), 5.0),
+        (arc2_start.id_y(), 0.0),
+        // arc2.end will become the intersection point after trimming
+        (arc2_end.id_x(), -5.0),
+        (arc2_end.id_y(), 0.0),
+    ];
+
+    // Now solve with PointArcCoincident added (this is what the trim operation does)
+    // The bug is that adding PointArcCoincident causes arc1's parameters to change
+    // even though they should remain at the expected values
+    let constraints_with_trim = vec![
+        // Define both arcs
+        ConstraintRequest::highest_priority(Constraint::Arc(arc1)),
+        ConstraintRequest::highest_priority(Constraint::Arc(arc2)),
+        // Fix arc1's parameters to the expected values (matching the modeling-app test)
+        // These should NOT change when PointArcCoincident is added
+        ConstraintRequest::highest_priority(Constraint::Fixed(
+            arc1_center.id_x(),
+            arc1_center_x_expected,
+        )),
+        ConstraintRequest::highest_priority(Constraint::Fixed(
+            arc1_center.id_y(),
+            arc1_center_y_expected,
+        )),
+        ConstraintRequest::highest_priority(Constraint::Fixed(
+            arc1_start.id_x(),
+            arc1_start_x_expected,
+        )),
+        ConstraintRequest::highest_priority(Constraint::Fixed(
+            arc1_start.id_y(),
+            arc1_start_y_expected,
+        )),
+        ConstraintRequest::highest_priority(Constraint::Fixed(
+            arc1_end.id_x(),
+            arc1_end_x_expected,
+        )),
+        ConstraintRequest::highest_priority(Constraint::Fixed(
+            arc1_end.id_y(),
+            arc1_end_y_expected,
+        )),
+        // Fix arc2's start and center
+        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_center.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_center.id_y(), -30.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_start.id_x(), 5.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(arc2_start.id_y(), 0.0)),
+        // The intersection point (arc2.end) should be on arc2 (this is what causes the bug)
+        // In the trim scenario, arc2.end becomes the intersection point
+        ConstraintRequest::highest_priority(Constraint::PointArcCoincident(arc2, arc2_end)),
+        // Also add that arc2.end is on arc1 (matching the coincident constraint
+        // in the modeling-app test: sketch2::coincident([arc2.end, arc1]))
+        ConstraintRequest::highest_priority(Constraint::PointArcCoincident(arc1, arc2_end)),
+    ];
+
+    let trim_outcome = solve(&constraints_with_trim, initial_guesses, Config::default())
+        .expect("trim constraint system should converge");
+
+    assert!(
+        trim_outcome.is_satisfied(),
+        "the constraint should be satisfied"
+    );
+
+    // Verify that arc1's parameters remain at their fixed values
+    // The bug on kurt-point-on-arc is that these fixed values are violated
+    // when PointArcCoincident is added, even though they're explicitly fixed
+    assert_nearly_eq(
+        trim_outcome.final_values[arc1_center.id_x() as usize],
+        arc1_center_x_expected,
+    );
+    assert_nearly_eq(
+        trim_outcome.final_values[arc1_center.id_y() as usize],
+        arc1_center_y_expected,
+    );
+    assert_nearly_eq(
+        trim_outcome.final_values[arc1_start.id_x() as usize],
+        arc1_start_x_expected,
+    );
+    assert_nearly_eq(
+        trim_outcome.final_values[arc1_start.id_y() as usize],
+        arc1_start_y_expected,
+    );
+    assert_nearly_eq(
+        trim_outcome.final_values[arc1_end.id_x() as usize],
+        arc1_end_x_expected,
+    );
+    assert_nearly_eq(
+        trim_outcome.final_values[arc1_end.id_y() as usize],
+        arc1_end_y_expected,
+    );
+}
+
+fn solve_arc_length_case(
+    arc_center_x: f64,
+    arc_center_y: f64,
+    arc_radius: f64,
+    arc_start_radians: f64,
+    desired_arc_length: f64,
+    arc_end_guess: Point,
+) -> (SolveOutcome, DatumCircularArc) {
+    let mut ids = IdGenerator::default();
+    let center = DatumPoint::new(&mut ids);
+    let start = DatumPoint::new(&mut ids);
+    let end = DatumPoint::new(&mut ids);
+    let arc = DatumCircularArc { center, start, end };
+
+    let arc_start = Point {
+        x: arc_center_x + libm::cos(arc_start_radians) * arc_radius,
+        y: arc_center_y + libm::sin(arc_start_radians) * arc_radius,
+    };
+
+    let initial_guesses = vec![
+        (arc.center.id_x(), arc_center_x),
+        (arc.center.id_y(), arc_center_y),
+        (arc.start.id_x(), arc_start.x),
+        (arc.start.id_y(), arc_start.y),
+        (arc.end.id_x(), arc_end_guess.x),
+        (arc.end.id_y(), arc_end_guess.y),
+    ];
+
+    let requests: Vec<_> = vec![
+        Constraint::Arc(arc),
+        Constraint::Fixed(arc.center.id_x(), arc_center_x),
+        Constraint::Fixed(arc.center.id_y(), arc_center_y),
+        Constraint::Fixed(arc.start.id_x(), arc_start.x),
+        Constraint::Fixed(arc.start.id_y(), arc_start.y),
+        Constraint::ArcLength(arc, desired_arc_length),
+    ]
+    .into_iter()
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+
+    let outcome =
+        solve(&requests, initial_guesses, Config::default()).expect("arc length case should solve");
+
+    (outcome, arc)
+}
+
+#[test]
+fn arc_length_ccw_over_pi() {
+    let arc_center_x = 0.0;
+    let arc_center_y = 0.0;
+    let arc_radius = 1.0;
+    let arc_start_radians = 0.0;
+    let desired_arc_length = 1.5 * PI;
+    let arc_end_guess = Point { x: 0.0, y: -1.0 };
+
+    let (outcome, arc) = solve_arc_length_case(
+        arc_center_x,
+        arc_center_y,
+        arc_radius,
+        arc_start_radians,
+        desired_arc_length,
+        arc_end_guess,
+    );
+
+    assert!(outcome.is_satisfied());
+
+    let solved_end_x = outcome.final_values[arc.end.id_x() as usize];
+    let solved_end_y = outcome.final_values[arc.end.id_y() as usize];
+    let solved_end = Point {
+        x: solved_end_x,
+        y: solved_end_y,
+    };
+    let center_point = Point {
+        x: arc_center_x,
+        y: arc_center_y,
+    };
+    let end_distance = solved_end.euclidean_distance(center_point);
+    assert_nearly_eq(end_distance, arc_radius);
+
+    let two_pi = 2.0 * PI;
+    let end_radians =
+        libm::atan2(solved_end_y - arc_center_y, solved_end_x - arc_center_x).rem_euclid(two_pi);
+    let ccw_delta = (end_radians - arc_start_radians).rem_euclid(two_pi);
+    let actual_arc_length = arc_radius * ccw_delta;
+    assert_nearly_eq(actual_arc_length, desired_arc_length);
+}
+
+#[test]
+fn arc_length_near_zero() {
+    let arc_center_x = -2.0;
+    let arc_center_y = 3.0;
+    let arc_radius = 5.0;
+    let arc_start_radians = 0.25 * PI;
+    let desired_arc_length = 1.0e-3;
+    let arc_end_guess = Point {
+        x: arc_center_x + libm::cos(arc_start_radians + 1.0e-2) * arc_radius,
+        y: arc_center_y + libm::sin(arc_start_radians + 1.0e-2) * arc_radius,
+    };
+
+    let (outcome, arc) = solve_arc_length_case(
+        arc_center_x,
+        arc_center_y,
+        arc_radius,
+        arc_start_radians,
+        desired_arc_length,
+        arc_end_guess,
+    );
+
+    assert!(outcome.is_satisfied());
+
+    let solved_end_x = outcome.final_values[arc.end.id_x() as usize];
+    let solved_end_y = outcome.final_values[arc.end.id_y() as usize];
+    let end_radians =
+        libm::atan2(solved_end_y - arc_center_y, solved_end_x - arc_center_x).rem_euclid(2.0 * PI);
+    let ccw_delta = (end_radians - arc_start_radians).rem_euclid(2.0 * PI);
+    let actual_arc_length = arc_radius * ccw_delta;
+    assert_nearly_eq(actual_arc_length, desired_arc_length);
+}
+
+#[test]
+fn arc_length_near_full_circle() {
+    let arc_center_x = 1.0;
+    let arc_center_y = -1.0;
+    let arc_radius = 2.5;
+    let arc_start_radians = 0.0;
+    let desired_arc_length = 2.0 * PI * arc_radius - 1.0e-3;
+    let arc_end_guess = Point {
+        x: arc_center_x + libm::cos(-1.0e-2) * arc_radius,
+        y: arc_center_y + libm::sin(-1.0e-2) * arc_radius,
+    };
+
+    let (outcome, arc) = solve_arc_length_case(
+        arc_center_x,
+        arc_center_y,
+        arc_radius,
+        arc_start_radians,
+        desired_arc_length,
+        arc_end_guess,
+    );
+
+    assert!(outcome.is_satisfied());
+
+    let solved_end_x = outcome.final_values[arc.end.id_x() as usize];
+    let solved_end_y = outcome.final_values[arc.end.id_y() as usize];
+    let end_radians =
+        libm::atan2(solved_end_y - arc_center_y, solved_end_x - arc_center_x).rem_euclid(2.0 * PI);
+    let ccw_delta = (end_radians - arc_start_radians).rem_euclid(2.0 * PI);
+    let actual_arc_length = arc_radius * ccw_delta;
+    assert_nearly_eq(actual_arc_length, desired_arc_length);
+}
+
+#[test]
+fn arc_length_degenerate_warns() {
+    let mut ids = IdGenerator::default();
+    let center = DatumPoint::new(&mut ids);
+    let start = DatumPoint::new(&mut ids);
+    let end = DatumPoint::new(&mut ids);
+    let arc = DatumCircularArc { center, start, end };
+
+    let initial_guesses = vec![
+        (arc.center.id_x(), 0.0),
+        (arc.center.id_y(), 0.0),
+        (arc.start.id_x(), 0.0),
+        (arc.start.id_y(), 0.0),
+        (arc.end.id_x(), 1.0),
+        (arc.end.id_y(), 0.0),
+    ];
+
+    let requests: Vec<_> = vec![
+        Constraint::Fixed(arc.center.id_x(), 0.0),
+        Constraint::Fixed(arc.center.id_y(), 0.0),
+        Constraint::Fixed(arc.start.id_x(), 0.0),
+        Constraint::Fixed(arc.start.id_y(), 0.0),
+        Constraint::ArcLength(arc, 1.0),
+    ]
+    .into_iter()
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+
+    let outcome = solve(&requests, initial_guesses, Config::default())
+        .expect("degenerate arc length case should solve");
+
+    assert!(
+        outcome
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning.content, WarningContent::Degenerate))
+    );
+}
+
+#[test]
+fn degenerate_jacobian_row() {
+    // P and Q are both fixed at the origin, so the distance between them can't be changed,
+    // and its Jacobian row is all zeros. R and S are unrelated, and should still be solved.
+    let mut ids = IdGenerator::default();
+    let [p, q, r, s] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+    let requests: Vec<_> = [p, q, r]
+        .iter()
+        .flat_map(|point| {
+            [
+                Constraint::Fixed(point.id_x(), 0.0),
+                Constraint::Fixed(point.id_y(), 0.0),
+            ]
+        })
+        .chain([
+            Constraint::Distance(p, q, 1.0),
+            Constraint::Distance(r, s, 2.0),
+            Constraint::Fixed(s.id_y(), 0.0),
+        ])
+        .map(ConstraintRequest::highest_priority)
+        .collect();
+    let initial_guesses: Vec<_> = [p, q, r, s]
+        .iter()
+        .flat_map(|point| [(point.id_x(), 0.0), (point.id_y(), 0.0)])
+        .map(|(id, guess)| {
+            if id == s.id_x() {
+                (id, 1.5)
+            } else {
+                (id, guess)
+            }
+        })
+        .collect();
+    let outcome = solve(&requests, initial_guesses, Config::default()).unwrap();
+
+    let degenerate_distance = 6;
+    let degenerate_rows: Vec<_> = outcome
+        .warnings()
+        .iter()
+        .filter(|warning| {
+            matches!(
+                warning.content,
+                WarningContent::DegenerateJacobianRow { row: 0 }
+            )
+        })
+        .collect();
+    // Only warned once, even though every iteration had the same zero row.
+    assert_eq!(degenerate_rows.len(), 1);
+    // The zero row's residual can't be reduced, so the solve stops once the rest of the
+    // system is solved and the steps vanish, rather than claiming every residual converged.
+    assert_eq!(
+        outcome.termination_reason(),
+        TerminationReason::StepTolerance
+    );
+    assert_eq!(
+        degenerate_rows[0].about_constraint,
+        Some(degenerate_distance)
+    );
+    assert_eq!(outcome.unsatisfied(), &[degenerate_distance]);
+    assert_points_eq(outcome.final_value_point(&s), Point { x: 2.0, y: 0.0 });
+}
+
+#[test]
+fn strange_nonconvergence() {
+    use crate::datatypes::inputs::DatumPoint;
+    let p = DatumPoint { x_id: 0, y_id: 1 };
+    let q = DatumPoint { x_id: 2, y_id: 3 };
+    let r = DatumPoint { x_id: 4, y_id: 5 };
+    let s = DatumPoint { x_id: 6, y_id: 7 };
+    let t = DatumPoint { x_id: 8, y_id: 9 };
+
+    let requests = [
+        ConstraintRequest::highest_priority(Constraint::Fixed(0, 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(1, 0.0)),
+        ConstraintRequest::highest_priority(Constraint::PointsCoincident(r, s)),
+        ConstraintRequest::highest_priority(Constraint::PointsCoincident(q, p)),
+        ConstraintRequest::highest_priority(Constraint::LinesEqualLength(
+            DatumLineSegment { p0: q, p1: r },
+            DatumLineSegment { p0: s, p1: t },
+        )),
+    ];
+    let initial_guesses = vec![
+        (0, 0.0),
+        (1, -0.02),
+        (2, -3.39),
+        (3, -0.38),
+        (4, -2.76),
+        (5, 4.83),
+        (6, -1.54),
+        (7, 5.21),
+        (8, -1.15),
+        (9, 2.75),
+    ];
+    let outcome = solve(
+        &requests,
+        initial_guesses,
+        Config::default().with_max_iterations(31),
+    );
+    let iterations = outcome.unwrap().iterations;
+    assert_eq!(iterations, 2);
+}
+
+#[test]
+fn warnings() {
+    let txt = "# constraints
+point p
+point q
+p.x = 0
+p.y = 0
+q.y = 0
+vertical(p, q)
+point r
+point s
+r.x = 0
+s.x = 0
+s.y = 0
+lines_at_angle(p, q, r, s, 0rad)
+
+# guesses
+p roughly (3, 4)
+q roughly (5, 6)
+r roughly (3, 4)
+s roughly (5, 6)
+";
+    let problem = Problem::from_str(txt).unwrap();
+    let solved = problem.to_constraint_system().unwrap().solve().unwrap();
+    assert!(!solved.warnings.is_empty());
+    assert!(solved.warnings.contains(&Warning {
+        about_constraint: Some(7),
+        content: WarningContent::ShouldBeParallel(Angle::from_radians(0.0))
+    }));
+}
+
+#[track_caller]
+fn assert_points_eq(l: Point, r: Point) {
+    let dist = l.euclidean_distance(r);
+    assert!(dist < EPSILON, "LHS was {l}, RHS was {r}, dist was {dist}");
+}
+
+#[track_caller]
+pub fn assert_nearly_eq(l: f64, r: f64) {
+    let diff = (l - r).abs();
+    assert!(
+        diff < EPSILON,
+        "LHS was {l}, RHS was {r}, difference was {diff}"
+    );
+}
+
+#[track_caller]
+fn assert_point_on_arc_ccw(point: Point, center: Point, start: Point, end: Point) {
+    let radius = start.euclidean_distance(center);
+    assert_nearly_eq(point.euclidean_distance(center), radius);
+
+    let s = V {
+        x: start.x - center.x,
+        y: start.y - center.y,
+    };
+    let e = V {
+        x: end.x - center.x,
+        y: end.y - center.y,
+    };
+    let p = V {
+        x: point.x - center.x,
+        y: point.y - center.y,
+    };
+
+    let two_pi = 2.0 * PI;
+    let a_sp = s.signed_angle(p).rem_euclid(two_pi);
+    let a_se = s.signed_angle(e).rem_euclid(two_pi);
+
+    let a_sp = if a_sp > two_pi - EPSILON { 0.0 } else { a_sp };
+    assert!(
+        a_sp <= a_se + EPSILON,
+        "point {point} is not on the CCW arc from {start} to {end} around {center}"
+    );
+}
+
+fn solve_point_arc_coincident_with_fixed_arc(
+    center_point: Point,
+    start_point: Point,
+    end_point: Point,
+    initial_point: Point,
+) -> Point {
+    let mut ids = IdGenerator::default();
+    let center = DatumPoint::new(&mut ids);
+    let start = DatumPoint::new(&mut ids);
+    let end = DatumPoint::new(&mut ids);
+    let point = DatumPoint::new(&mut ids);
+    let arc = DatumCircularArc { center, start, end };
+
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::PointArcCoincident(arc, point)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(center.id_x(), center_point.x)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(center.id_y(), center_point.y)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(start.id_x(), start_point.x)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(start.id_y(), start_point.y)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(end.id_x(), end_point.x)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(end.id_y(), end_point.y)),
+    ];
+    let initial_guesses = vec![
+        (center.id_x(), center_point.x),
+        (center.id_y(), center_point.y),
+        (start.id_x(), start_point.x),
+        (start.id_y(), start_point.y),
+        (end.id_x(), end_point.x),
+        (end.id_y(), end_point.y),
+        (point.id_x(), initial_point.x),
+        (point.id_y(), initial_point.y),
+    ];
+
+    let outcome = solve(&constraints, initial_guesses, Config::default())
+        .expect("fixed arc point-arc coincident case should solve");
+    assert!(outcome.is_satisfied(), "constraint should be satisfied");
+
+    Point {
+        x: outcome.final_values[point.id_x() as usize],
+        y: outcome.final_values[point.id_y() as usize],
+    }
+}
+
+/// Regression test for old point-on-arc behavior where the angular residuals were zeroed out once
+/// the point was already on the circle containing the arc.
+///
+/// In this setup the initial point starts on the containing circle but outside the arc's angular
+/// interval. The previous implementation could therefore converge immediately without moving the
+/// point onto the arc itself.
+#[test]
+fn point_arc_coincident_old_incorrect_convergence_1() {
+    let center = Point { x: 0.0, y: 0.0 };
+    let start = Point { x: 1.0, y: 0.0 };
+    let end = Point { x: 0.0, y: 1.0 };
+    let solved_point =
+        solve_point_arc_coincident_with_fixed_arc(center, start, end, Point { x: 0.0, y: -1.0 });
+    assert_point_on_arc_ccw(solved_point, center, start, end);
+    assert_points_eq(solved_point, start);
+}
+
+/// Regression test for old point-on-arc behavior where competing angular residuals could cancel
+/// each other out.
+///
+/// The initial point starts well away from the arc in a configuration where the previous residual
+/// formulation could settle onto the containing circle while still leaving the point outside the
+/// arc interval.
+#[test]
+fn point_arc_coincident_old_incorrect_convergence_2() {
+    let center = Point { x: 0.0, y: 0.0 };
+    let start = Point { x: 1.0, y: 0.0 };
+    let end = Point { x: 0.0, y: 1.0 };
+    let solved_point =
+        solve_point_arc_coincident_with_fixed_arc(center, start, end, Point { x: -3.0, y: -3.0 });
+    assert_point_on_arc_ccw(solved_point, center, start, end);
+    assert_points_eq(solved_point, start);
+}
+
+/// Regression test for the bug that motivated this fix.
+///
+/// The bug report was: adding `point_arc_coincident(point, arc)` can cause the solver to
+/// "jump" to a very different configuration even when the point is already basically on the arc.
+///
+/// This test reproduces that bug by:
+/// - Solving a baseline problem without the `point_arc_coincident` constraint
+/// - Solving the same problem with the constraint added
+/// - Verifying that the initial guess is already close to the arc (distance-from-radius < 0.5)
+/// - Asserting that adding the constraint doesn't cause dramatic changes
+///
+/// This test encodes the stability goal: "don't move much if already almost satisfied".
+#[test]
+fn point_basically_already_on_arc_should_not_cause_much_change_in_sketch() {
+    // First, solve without the point_arc_coincident constraint to get a baseline
+    let txt_without = std::fs::read_to_string(
+        "../test_cases/arc_line_coincident_bug/problem_without_arc_constraint.md",
+    )
+    .unwrap();
+    let problem_without = parse_problem(&txt_without);
+    let system_without = problem_without.to_constraint_system().unwrap();
+    let solved_without = system_without
+        .solve_with_config_analysis(Default::default())
+        .unwrap();
+
+    // Now solve with the point_arc_coincident constraint
+    let solved_with = run("arc_line_coincident_bug");
+
+    // Initial guesses
+    let initial_line3_start = Point { x: 4.32, y: 3.72 };
+    let initial_line3_end = Point { x: 1.06, y: -3.26 };
+    let initial_line4_start = Point { x: -2.32, y: -2.96 };
+    let initial_line4_end = Point { x: -7.01, y: -2.77 };
+    let initial_arc_center = Point { x: 1.06, y: -3.26 };
+    let initial_arc_a = Point { x: -1.44, y: -0.99 };
+    let initial_arc_b = Point { x: 2.49, y: -0.2 };
+
+    // Get the solved values
+    let solved_line3_start = solved_with.get_point("line3start").unwrap();
+    let solved_line3_end = solved_with.get_point("line3end").unwrap();
+    let solved_line4_start = solved_with.get_point("line4start").unwrap();
+    let solved_line4_end = solved_with.get_point("line4end").unwrap();
+    let solved_arc = solved_with.get_arc("arc1").unwrap();
+
+    // Calculate how far line4_start is from the arc in the initial guess
+    // The arc center is at (1.06, -3.26) and arc.a is at (-1.44, -0.99)
+    // So the radius is the distance from center to arc.a
+    let initial_arc_radius = initial_arc_center.euclidean_distance(initial_arc_a);
+    let initial_line4_start_to_center = initial_line4_start.euclidean_distance(initial_arc_center);
+    let initial_distance_from_arc = (initial_line4_start_to_center - initial_arc_radius).abs();
+
+    // Verify that line4_start is already very close to the arc (within a reasonable tolerance)
+    // This should be a small value, showing the point is already basically on the arc
+    assert!(
+        initial_distance_from_arc < 0.5,
+        "line4_start should be close to the arc initially. Distance from arc: {}",
+        initial_distance_from_arc
+    );
+
+    // Calculate how much the solution changed from the initial guesses
+    let _change_line3_start = solved_line3_start.euclidean_distance(initial_line3_start);
+    let _change_line3_end = solved_line3_end.euclidean_distance(initial_line3_end);
+    let change_line4_start = solved_line4_start.euclidean_distance(initial_line4_start);
+    let _change_line4_end = solved_line4_end.euclidean_distance(initial_line4_end);
+    let _change_arc_center = solved_arc.center.euclidean_distance(initial_arc_center);
+    let _change_arc_a = solved_arc.a.euclidean_distance(initial_arc_a);
+    let _change_arc_b = solved_arc.b.euclidean_distance(initial_arc_b);
+
+    // The bug is that these changes are dramatically large even though line4_start
+    // is already basically on the arc. We expect the solver to make minimal changes.
+    // Debug logs intentionally removed to keep tests quiet by default.
+
+    // Compare with the solution without the constraint
+    let solved_without_line3_start = solved_without.get_point("line3start").unwrap();
+    let solved_without_line3_end = solved_without.get_point("line3end").unwrap();
+    let solved_without_line4_start = solved_without.get_point("line4start").unwrap();
+    let solved_without_line4_end = solved_without.get_point("line4end").unwrap();
+    let solved_without_arc = solved_without.get_arc("arc1").unwrap();
+
+    let _diff_line3_start = solved_line3_start.euclidean_distance(solved_without_line3_start);
+    let _diff_line3_end = solved_line3_end.euclidean_distance(solved_without_line3_end);
+    let _diff_line4_start = solved_line4_start.euclidean_distance(solved_without_line4_start);
+    let _diff_line4_end = solved_line4_end.euclidean_distance(solved_without_line4_end);
+    let _diff_arc_center = solved_arc
+        .center
+        .euclidean_distance(solved_without_arc.center);
+    let _diff_arc_a = solved_arc.a.euclidean_distance(solved_without_arc.a);
+    let _diff_arc_b = solved_arc.b.euclidean_distance(solved_without_arc.b);
+
+    // Debug logs intentionally removed to keep tests quiet by default.
+
+    // The test demonstrates the bug: adding the constraint causes dramatic changes
+    // This assertion will fail if the bug is present, showing the dramatic difference
+    // We use a threshold that's much larger than the initial distance from the arc
+    let max_expected_change = initial_distance_from_arc * 10.0;
+    assert!(
+        change_line4_start <= max_expected_change,
+        "BUG REPRODUCED: Adding point_arc_coincident constraint caused line4_start to move by {:.6}, \
+         but it was only {:.6} away from the arc initially. This is a dramatic change that shouldn't be necessary.",
+        change_line4_start,
+        initial_distance_from_arc
+    );
+}
+
+/// Test the "other side" of the stability goal: when a point is NOT on the circle or not in range,
+/// the constraint SHOULD move it meaningfully.
+///
+/// This test verifies that:
+/// - If the point is initially outside the angular range (e.g., near the arc center or outside
+///   the angular span), the constraint should cause it to move significantly onto the arc
+/// - The point ends up both on the circle (correct radius) and within the angular range
+/// - If the initial angular violation is large, movement should be at least ~30% of the radius
+///
+/// This test encodes the correctness goal: "do move meaningfully if far from satisfied".
+/// Together with `point_basically_already_on_arc_should_not_cause_much_change_in_sketch`,
+/// these tests ensure the constraint is both stable (doesn't over-correct) and effective
+/// (does correct when needed).
+#[test]
+fn arc_center_point_coincident() {
+    let solved = run("arc_center_point_coincident");
+
+    // Initial guesses from the problem file
+    let initial_line4_start = Point { x: -1.16, y: -2.63 };
+    let initial_arc_center = Point { x: 0.55, y: -3.31 };
+
+    // Get the solved values
+    let solved_line4_start = solved.get_point("line4start").unwrap();
+    let solved_arc = solved.get_arc("arc1").unwrap();
+
+    // Check initial angular position relative to the arc
+    let initial_arc_a = Point { x: 2.25, y: -3.99 };
+    let initial_arc_b = Point { x: 1.43, y: -1.71 };
+
+    // Calculate cross products to check if point is in angular range initially
+    let cx = initial_arc_center.x;
+    let cy = initial_arc_center.y;
+    let ax = initial_arc_a.x;
+    let ay = initial_arc_a.y;
+    let bx = initial_arc_b.x;
+    let by = initial_arc_b.y;
+    let px = initial_line4_start.x;
+    let py = initial_line4_start.y;
+
+    let initial_start_cross = (ax - cx) * (cy - py) - (ay - cy) * (cx - px);
+    let initial_end_cross = (bx - cx) * (cy - py) - (by - cy) * (cx - px);
+
+    // Debug logs intentionally removed to keep tests quiet by default.
+
+    // The point should initially NOT be in the angular range
+    // (either start_cross > 0 or end_cross >= 0)
+    let initially_in_range = initial_start_cross <= 0.0 && initial_end_cross < 0.0;
+    assert!(
+        !initially_in_range,
+        "line4_start should initially NOT be in the angular range. start_cross: {}, end_cross: {}",
+        initial_start_cross, initial_end_cross
+    );
+
+    // Calculate how much the point moved
+    let movement = solved_line4_start.euclidean_distance(initial_line4_start);
+    // Debug logs intentionally removed to keep tests quiet by default.
+
+    // Verify the point is now on the arc (at the correct radius)
+    let arc_radius = solved_arc.center.euclidean_distance(solved_arc.a);
+    let point_to_center_dist = solved_line4_start.euclidean_distance(solved_arc.center);
+    let distance_from_arc = (point_to_center_dist - arc_radius).abs();
+    // Debug logs intentionally removed to keep tests quiet by default.
+
+    // The point should be on the arc (within tolerance)
+    assert!(
+        distance_from_arc < 0.01,
+        "line4_start should be on the arc after solving. Distance from arc: {}, arc radius: {}",
+        distance_from_arc,
+        arc_radius
+    );
+
+    // The point should have moved to get into the angular range.
+    // If it was only slightly outside (start_cross small), movement might be small.
+    // But if it was far outside, it should move significantly.
+    // For now, just verify it ends up in the angular range (checked below).
+    // If the initial violation was large, we expect significant movement.
+    if initial_start_cross > 0.1 {
+        let min_expected_movement = arc_radius * 0.3; // At least 30% of the radius for large violations
+        assert!(
+            movement > min_expected_movement,
+            "line4_start should have moved significantly when initially far outside angular range. \
+             Movement: {}, minimum expected: {} (30% of arc radius {}), initial start_cross: {}",
+            movement,
+            min_expected_movement,
+            arc_radius,
+            initial_start_cross
+        );
+    }
+
+    // Also verify the point is within the angular range by checking cross products
+    let cx = solved_arc.center.x;
+    let cy = solved_arc.center.y;
+    let ax = solved_arc.a.x;
+    let ay = solved_arc.a.y;
+    let bx = solved_arc.b.x;
+    let by = solved_arc.b.y;
+    let px = solved_line4_start.x;
+    let py = solved_line4_start.y;
+
+    // For a CCW arc, the point should be:
+    // - CCW from the start vector (start_cross < 0)
+    // - Before the end (end is CCW from point, so end_cross < 0)
+    let start_cross = (ax - cx) * (cy - py) - (ay - cy) * (cx - px);
+    let end_cross = (bx - cx) * (cy - py) - (by - cy) * (cx - px);
+
+    // Debug logs intentionally removed to keep tests quiet by default.
+
+    // Allow small tolerance for numerical precision, but point should be clearly in range
+    assert!(
+        start_cross < 0.01,
+        "Point should be CCW from start angle (or very close to boundary). start_cross: {}",
+        start_cross
+    );
+    assert!(
+        end_cross < 1e-6,
+        "Point should be before end angle (end is CCW from point). end_cross: {}",
+        end_cross
+    );
+}
+
+#[test]
+fn lines_at_angle_isolated() {
+    use crate::datatypes::inputs::{DatumLineSegment, DatumPoint};
+
+    struct TestCase {
+        points: [[f64; 2]; 4],
+        angle: f64,
+        expected_iters: usize,
+    }
+
+    let test_cases = [
+        TestCase {
+            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
+            angle: 0.5 * PI,
+            expected_iters: 0,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
+            angle: -0.5 * PI,
+            expected_iters: 0,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
+            angle: 0.0,
+            expected_iters: 0,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
+            angle: PI,
+            expected_iters: 0,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [-1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
+            angle: 0.0,
+            expected_iters: 0,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [-1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
+            angle: PI,
+            expected_iters: 0,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
+            angle: 0.0,
+            expected_iters: 4,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
+            angle: PI,
+            expected_iters: 4,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [0.0, 1.0], [0.0, 0.0], [0.0, 2.0]],
+            angle: 0.5 * PI,
+            expected_iters: 4,
+        },
+        TestCase {
+            points: [[0.0, 0.0], [0.0, 1.0], [0.0, 0.0], [0.0, 2.0]],
+            angle: -0.5 * PI,
+            expected_iters: 4,
+        },
+    ];
+
+    let p0 = DatumPoint { x_id: 0, y_id: 1 };
+    let p1 = DatumPoint { x_id: 2, y_id: 3 };
+    let p2 = DatumPoint { x_id: 4, y_id: 5 };
+    let p3 = DatumPoint { x_id: 6, y_id: 7 };
+    let line0 = DatumLineSegment { p0, p1 };
+    let line1 = DatumLineSegment { p0: p2, p1: p3 };
+
+    for test_case in test_cases {
+        let constraints = [ConstraintRequest::highest_priority(
+            Constraint::LinesAtAngle(
+                line0,
+                line1,
+                AngleKind::Other(Angle::from_radians(test_case.angle)),
+            ),
+        )];
+        let initial_guesses: Vec<_> = test_case
+            .points
+            .into_iter()
+            .enumerate()
+            .flat_map(|(point_idx, [x, y])| {
+                [((point_idx as Id) * 2, x), ((point_idx as Id) * 2 + 1, y)]
+            })
+            .collect();
+
+        let outcome = solve(
+            &constraints,
+            initial_guesses,
+            Config::default().with_max_iterations(100),
+        )
+        .unwrap_or_else(|err| panic!("failed for angle {}: {err:?}", test_case.angle));
+
+        assert!(outcome.is_satisfied());
+        assert_eq!(
+            outcome.iterations(),
+            test_case.expected_iters,
+            "unexpected iteration count for angle {}",
+            test_case.angle
+        );
+    }
+}
+
+#[test]
+fn lines_angle_sign_check() {
+    use crate::datatypes::inputs::{DatumLineSegment, DatumPoint};
+
+    struct TestCase {
+        vars: [[f64; 2]; 3],
+        angle: f64,
+        expected_iters: usize,
+    }
+
+    let test_cases = [
+        TestCase {
+            vars: [[0.0, 0.0], [1.0, 0.0], [2.0, 1.0]],
+            angle: 0.1 * PI,
+            expected_iters: 3,
+        },
+        TestCase {
+            vars: [[0.0, 0.0], [1.0, 0.0], [2.0, 1.0]],
+            angle: -0.1 * PI,
+            expected_iters: 4,
+        },
+    ];
+
+    let p0 = DatumPoint { x_id: 0, y_id: 1 };
+    let p1 = DatumPoint { x_id: 2, y_id: 3 };
+    let p2 = DatumPoint { x_id: 4, y_id: 5 };
+    let line0 = DatumLineSegment { p0, p1 };
+    let line1 = DatumLineSegment { p0: p1, p1: p2 };
+
+    for test_case in test_cases {
+        let constraints = [
+            ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_x(), 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_x(), 1.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 0.0)),
+            ConstraintRequest::highest_priority(Constraint::LinesAtAngle(
+                line0,
+                line1,
+                AngleKind::Other(Angle::from_radians(test_case.angle)),
+            )),
+        ];
+        let initial_guesses: Vec<_> = test_case
+            .vars
+            .into_iter()
+            .enumerate()
+            .flat_map(|(point_idx, [x, y])| {
+                [((point_idx as Id) * 2, x), ((point_idx as Id) * 2 + 1, y)]
+            })
+            .collect();
+
+        let outcome = solve(
+            &constraints,
+            initial_guesses,
+            Config::default().with_max_iterations(100),
+        )
+        .unwrap_or_else(|err| panic!("failed for angle {}: {err:?}", test_case.angle));
+
+        assert!(outcome.is_satisfied());
+        assert_eq!(
+            outcome.iterations(),
+            test_case.expected_iters,
+            "unexpected iteration count for angle {}",
+            test_case.angle
+        );
+
+        // Ensure the resulting angle actually matches
+        {
+            let p0 = outcome.final_value_point(&p0);
+            let p1 = outcome.final_value_point(&p1);
+            let p2 = outcome.final_value_point(&p2);
+            let u = V::new(p1.x - p0.x, p1.y - p0.y);
+            let v = V::new(p2.x - p1.x, p2.y - p1.y);
+            assert_nearly_eq(u.signed_angle(v), test_case.angle);
+        }
+    }
+}
+
+/// Returns the signed angle from (p1 - p0) to (p2 - p0), reading variable
+/// ids 0..5 = `[p0_x, p0_y, p1_x, p1_y, p2_x, p2_y]` from `vals`.
+fn points_at_angle_from_vals(vals: &[f64]) -> f64 {
+    let u = V::new(vals[2] - vals[0], vals[3] - vals[1]);
+    let v = V::new(vals[4] - vals[0], vals[5] - vals[1]);
+    u.signed_angle(v)
+}
+
+#[test]
+fn points_at_angle_already_satisfied() {
+    // Cases where the geometry already satisfies the constraint: expect 0 Newton iterations.
+    struct TestCase {
+        p1: [f64; 2], // first arm endpoint; vertex is always at origin
+        p2: [f64; 2], // second arm endpoint
+        angle: f64,
+    }
+
+    let test_cases = [
+        TestCase {
+            p1: [1.0, 0.0],
+            p2: [0.0, 2.0],
+            angle: 0.5 * PI,
+        },
+        TestCase {
+            p1: [1.0, 0.0],
+            p2: [0.0, -2.0],
+            angle: -0.5 * PI,
+        },
+        TestCase {
+            p1: [1.0, 0.0],
+            p2: [3.0, 0.0],
+            angle: 0.0,
+        },
+        TestCase {
+            p1: [1.0, 0.0],
+            p2: [-2.0, 0.0],
+            angle: PI,
+        },
+        TestCase {
+            p1: [2.0, 0.0],
+            p2: [1.0, 1.0],
+            angle: 0.25 * PI,
+        },
+    ];
+
+    let vertex = DatumPoint { x_id: 0, y_id: 1 };
+    let p1 = DatumPoint { x_id: 2, y_id: 3 };
+    let p2 = DatumPoint { x_id: 4, y_id: 5 };
+
+    for tc in &test_cases {
+        let constraints = [ConstraintRequest::highest_priority(
+            Constraint::PointsAtAngle(
+                vertex,
+                p1,
+                p2,
+                AngleKind::Other(Angle::from_radians(tc.angle)),
+            ),
+        )];
+        let initial_guesses = vec![
+            (0, 0.0),
+            (1, 0.0),
+            (2, tc.p1[0]),
+            (3, tc.p1[1]),
+            (4, tc.p2[0]),
+            (5, tc.p2[1]),
+        ];
+        let outcome = solve(
+            &constraints,
+            initial_guesses,
+            Config::default().with_max_iterations(100),
+        )
+        .unwrap_or_else(|e| panic!("failed for angle {}: {e:?}", tc.angle));
+        assert!(outcome.is_satisfied());
+        assert_eq!(
+            outcome.iterations(),
+            0,
+            "angle {} should already be satisfied (0 iterations)",
+            tc.angle
+        );
+    }
+}
+
+#[test]
+fn points_at_angle_degenerate() {
+    let vertex = DatumPoint { x_id: 0, y_id: 1 };
+    let p1 = DatumPoint { x_id: 2, y_id: 3 };
+    let p2 = DatumPoint { x_id: 4, y_id: 5 };
+
+    let constraints = [ConstraintRequest::highest_priority(
+        Constraint::PointsAtAngle(vertex, p1, p2, AngleKind::Other(Angle::from_degrees(180.0))),
+    )];
+    let initial_guesses = vec![
+        (0, 0.0),
+        (1, 0.0),
+        (2, 13.0),
+        (3, 13.0),
+        (4, 13.0),
+        (5, 13.0),
+    ];
+    let outcome = solve(
+        &constraints,
+        initial_guesses,
+        Config::default().with_max_iterations(100),
+    )
+    .unwrap();
+    assert!(outcome.warnings.first().unwrap().content == WarningContent::Degenerate);
+}
+
+#[test]
+fn points_at_angle_unique_solution() {
+    // PointsAtAngle has exactly one solution unlike LinesAtAngle which has two solutions for each
+    // arm that differ by π
+    //
+    // Concretely, with target angle π/4 and u = (1,0):
+    //   - v = (1,1) direction satisfies PointsAtAngle
+    //   - v = (-1,-1) direction satisfies LinesAtAngle but not PointsAtAngle
+    //
+    // Starting from either initial condition, PointsAtAngle must converge to angle π/4.
+    let vertex = DatumPoint { x_id: 0, y_id: 1 };
+    let p1 = DatumPoint { x_id: 2, y_id: 3 };
+    let p2 = DatumPoint { x_id: 4, y_id: 5 };
+
+    let target_angle = 0.25 * PI;
+
+    let constraints = [
+        // Fix vertex and first arm
+        ConstraintRequest::highest_priority(Constraint::Fixed(0, 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(1, 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(2, 1.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(3, 0.0)),
+        ConstraintRequest::highest_priority(Constraint::PointsAtAngle(
+            vertex,
+            p1,
+            p2,
+            AngleKind::Other(Angle::from_radians(target_angle)),
+        )),
+    ];
 
-    // The bug is that these changes are dramatically large even though line4_start
-    // is already basically on the arc. We expect the solver to make minimal changes.
-    // Debug logs intentionally removed to keep tests quiet by default.
+    // p2 starts on the correct side: direction (1,1), angle π/4 so already satisfied
+    let guesses_correct: Vec<(u32, f64)> =
+        vec![(0, 0.0), (1, 0.0), (2, 1.0), (3, 0.0), (4, 1.0), (5, 1.0)];
 
-    // Compare with the solution without the constraint
-    let solved_without_line3_start = solved_without.get_point("line3start").unwrap();
-    let solved_without_line3_end = solved_without.get_point("line3end").unwrap();
-    let solved_without_line4_start = solved_without.get_point("line4start").unwrap();
-    let solved_without_line4_end = solved_without.get_point("line4end").unwrap();
-    let solved_without_arc = solved_without.get_arc("arc1").unwrap();
+    // p2 starts on the π-shifted side: direction (-1,-1), angle -3π/4.
+    // This is the other zero of LinesAtAngle, but it should not satisfy PointsAtAngle.
+    let guesses_shifted: Vec<(u32, f64)> =
+        vec![(0, 0.0), (1, 0.0), (2, 1.0), (3, 0.0), (4, -1.0), (5, -1.0)];
 
-    let _diff_line3_start = solved_line3_start.euclidean_distance(solved_without_line3_start);
-    let _diff_line3_end = solved_line3_end.euclidean_distance(solved_without_line3_end);
-    let _diff_line4_start = solved_line4_start.euclidean_distance(solved_without_line4_start);
-    let _diff_line4_end = solved_line4_end.euclidean_distance(solved_without_line4_end);
-    let _diff_arc_center = solved_arc
-        .center
-        .euclidean_distance(solved_without_arc.center);
-    let _diff_arc_a = solved_arc.a.euclidean_distance(solved_without_arc.a);
-    let _diff_arc_b = solved_arc.b.euclidean_distance(solved_without_arc.b);
+    let outcome_correct = solve(
+        &constraints,
+        guesses_correct,
+        Config::default().with_max_iterations(100),
+    )
+    .unwrap();
+    let outcome_shifted = solve(
+        &constraints,
+        guesses_shifted,
+        Config::default().with_max_iterations(100),
+    )
+    .unwrap();
 
-    // Debug logs intentionally removed to keep tests quiet by default.
+    assert!(outcome_correct.is_satisfied());
+    assert!(outcome_shifted.is_satisfied());
 
-    // The test demonstrates the bug: adding the constraint causes dramatic changes
-    // This assertion will fail if the bug is present, showing the dramatic difference
-    // We use a threshold that's much larger than the initial distance from the arc
-    let max_expected_change = initial_distance_from_arc * 10.0;
-    assert!(
-        change_line4_start <= max_expected_change,
-        "BUG REPRODUCED: Adding point_arc_coincident constraint caused line4_start to move by {:.6}, \
-         but it was only {:.6} away from the arc initially. This is a dramatic change that shouldn't be necessary.",
-        change_line4_start,
-        initial_distance_from_arc
+    // Both must converge to target_angle, not to target_angle - π.
+    assert_nearly_eq(
+        points_at_angle_from_vals(&outcome_correct.final_values),
+        target_angle,
+    );
+    assert_nearly_eq(
+        points_at_angle_from_vals(&outcome_shifted.final_values),
+        target_angle,
     );
 }
 
-/// Test the "other side" of the stability goal: when a point is NOT on the circle or not in range,
-/// the constraint SHOULD move it meaningfully.
-///
-/// This test verifies that:
-/// - If the point is initially outside the angular range (e.g., near the arc center or outside
-///   the angular span), the constraint should cause it to move significantly onto the arc
-/// - The point ends up both on the circle (correct radius) and within the angular range
-/// - If the initial angular violation is large, movement should be at least ~30% of the radius
-///
-/// This test encodes the correctness goal: "do move meaningfully if far from satisfied".
-/// Together with `point_basically_already_on_arc_should_not_cause_much_change_in_sketch`,
-/// these tests ensure the constraint is both stable (doesn't over-correct) and effective
-/// (does correct when needed).
 #[test]
-fn arc_center_point_coincident() {
-    let solved = run("arc_center_point_coincident");
-
-    // Initial guesses from the problem file
-    let initial_line4_start = Point { x: -1.16, y: -2.63 };
-    let initial_arc_center = Point { x: 0.55, y: -3.31 };
+fn points_at_angle_sign_distinguishable() {
+    // Checks that PointsAtAngle respects the sign of the specified angle i.e. using +θ and -θ
+    // should place the second arm on opposite sides of the first
+    let vertex = DatumPoint { x_id: 0, y_id: 1 };
+    let p1 = DatumPoint { x_id: 2, y_id: 3 };
+    let p2 = DatumPoint { x_id: 4, y_id: 5 };
+    let theta = 0.25 * PI;
 
-    // Get the solved values
-    let solved_line4_start = solved.get_point("line4start").unwrap();
-    let solved_arc = solved.get_arc("arc1").unwrap();
+    // (target_angle, initial_p2)
+    let cases: &[(f64, [f64; 2])] = &[
+        (theta, [1.0, 0.0]),
+        (-theta, [1.0, 0.0]),
+        (theta, [0.0, 1.0]),
+        (-theta, [0.0, 1.0]),
+        (theta, [-1.0, 0.0]),
+        (-theta, [-1.0, 0.0]),
+        (theta, [0.0, -1.0]),
+        (-theta, [0.0, -1.0]),
+    ];
 
-    // Check initial angular position relative to the arc
-    let initial_arc_a = Point { x: 2.25, y: -3.99 };
-    let initial_arc_b = Point { x: 1.43, y: -1.71 };
+    for &(target_angle, init_p2) in cases {
+        let constraints = [
+            // Fix vertex, first arm, and length of second arm
+            ConstraintRequest::highest_priority(Constraint::Fixed(0, 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(1, 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(2, 1.0)),
+            ConstraintRequest::highest_priority(Constraint::Fixed(3, 0.0)),
+            ConstraintRequest::highest_priority(Constraint::Distance(vertex, p2, 1.0)),
+            ConstraintRequest::highest_priority(Constraint::PointsAtAngle(
+                vertex,
+                p1,
+                p2,
+                AngleKind::Other(Angle::from_radians(target_angle)),
+            )),
+        ];
+        let initial_guesses: Vec<(u32, f64)> = vec![
+            (0, 0.0),
+            (1, 0.0),
+            (2, 1.0),
+            (3, 0.0),
+            (4, init_p2[0]),
+            (5, init_p2[1]),
+        ];
 
-    // Calculate cross products to check if point is in angular range initially
-    let cx = initial_arc_center.x;
-    let cy = initial_arc_center.y;
-    let ax = initial_arc_a.x;
-    let ay = initial_arc_a.y;
-    let bx = initial_arc_b.x;
-    let by = initial_arc_b.y;
-    let px = initial_line4_start.x;
-    let py = initial_line4_start.y;
+        let outcome = solve(
+            &constraints,
+            initial_guesses,
+            Config::default().with_max_iterations(100),
+        )
+        .unwrap_or_else(|e| panic!("failed for angle {target_angle}: {e:?}"));
+        assert!(outcome.is_satisfied());
+        assert_nearly_eq(
+            points_at_angle_from_vals(&outcome.final_values),
+            target_angle,
+        );
+    }
+}
 
-    let initial_start_cross = (ax - cx) * (cy - py) - (ay - cy) * (cx - px);
-    let initial_end_cross = (bx - cx) * (cy - py) - (by - cy) * (cx - px);
+#[test]
+fn point_on_parabola() {
+    // The parabola y = x², i.e. x² - y = 0.
+    let mut ids = IdGenerator::default();
+    let conic = DatumConic::new(&mut ids);
+    let p = DatumPoint::new(&mut ids);
+    let coefficients = [
+        (conic.a, 1.0),
+        (conic.b, 0.0),
+        (conic.c, 0.0),
+        (conic.d, 0.0),
+        (conic.e, -1.0),
+        (conic.f, 0.0),
+    ];
+    let mut constraints: Vec<_> = coefficients
+        .iter()
+        .map(|&(id, value)| ConstraintRequest::highest_priority(Constraint::Fixed(id, value)))
+        .collect();
+    constraints.push(ConstraintRequest::highest_priority(Constraint::Fixed(
+        p.id_x(),
+        2.0,
+    )));
+    constraints.push(ConstraintRequest::highest_priority(
+        Constraint::PointOnConic(p, conic),
+    ));
+    let mut initial_guesses = coefficients.to_vec();
+    initial_guesses.extend([(p.id_x(), 1.5), (p.id_y(), 1.0)]);
 
-    // Debug logs intentionally removed to keep tests quiet by default.
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    assert_points_eq(solved.final_value_point(&p), Point { x: 2.0, y: 4.0 });
+}
 
-    // The point should initially NOT be in the angular range
-    // (either start_cross > 0 or end_cross >= 0)
-    let initially_in_range = initial_start_cross <= 0.0 && initial_end_cross < 0.0;
-    assert!(
-        !initially_in_range,
-        "line4_start should initially NOT be in the angular range. start_cross: {}, end_cross: {}",
-        initial_start_cross, initial_end_cross
-    );
+#[test]
+fn point_infinite_line_distance() {
+    // The line x + y = 2, in normal form: angle 45°, offset √2.
+    let mut ids = IdGenerator::default();
+    let line = DatumLine::new(&mut ids);
+    let p = DatumPoint::new(&mut ids);
+    let constraints = [
+        Constraint::Fixed(line.angle, PI / 4.0),
+        Constraint::Fixed(line.offset, std::f64::consts::SQRT_2),
+        Constraint::Fixed(p.id_x(), 0.0),
+        // P is 1 unit away from the line, on the side its normal points to.
+        Constraint::PointInfiniteLineDistance(p, line, 1.0),
+    ]
+    .map(ConstraintRequest::highest_priority);
+    let initial_guesses = vec![
+        (line.angle, 0.7),
+        (line.offset, 1.5),
+        (p.id_x(), 0.5),
+        (p.id_y(), 2.0),
+    ];
 
-    // Calculate how much the point moved
-    let movement = solved_line4_start.euclidean_distance(initial_line4_start);
-    // Debug logs intentionally removed to keep tests quiet by default.
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    // x + y = 2 + √2, and x = 0.
+    let expected = Point {
+        x: 0.0,
+        y: 2.0 + std::f64::consts::SQRT_2,
+    };
+    assert_points_eq(solved.final_value_point(&p), expected);
+    let solved_line = solved.final_value_line(&line);
+    assert_nearly_eq(solved_line.signed_distance(expected), 1.0);
+}
 
-    // Verify the point is now on the arc (at the correct radius)
-    let arc_radius = solved_arc.center.euclidean_distance(solved_arc.a);
-    let point_to_center_dist = solved_line4_start.euclidean_distance(solved_arc.center);
-    let distance_from_arc = (point_to_center_dist - arc_radius).abs();
-    // Debug logs intentionally removed to keep tests quiet by default.
+#[test]
+fn normalize_angles() {
+    // The line x = 1, with its normal pointing along -X. Its angle starts near 7π,
+    // so that's where the solver leaves it, unless angles get normalized.
+    let mut ids = IdGenerator::default();
+    let line = DatumLine::new(&mut ids);
+    let a = DatumPoint::new(&mut ids);
+    let b = DatumPoint::new(&mut ids);
+    let constraints = [
+        Constraint::Fixed(a.id_x(), 1.0),
+        Constraint::Fixed(a.id_y(), 0.0),
+        Constraint::Fixed(b.id_x(), 1.0),
+        Constraint::Fixed(b.id_y(), 2.0),
+        Constraint::PointInfiniteLineDistance(a, line, 0.0),
+        Constraint::PointInfiniteLineDistance(b, line, 0.0),
+    ]
+    .map(ConstraintRequest::highest_priority);
+    let initial_guesses = vec![
+        (line.angle, 7.0 * PI - 0.2),
+        (line.offset, -0.8),
+        (a.id_x(), 1.0),
+        (a.id_y(), 0.0),
+        (b.id_x(), 1.0),
+        (b.id_y(), 2.0),
+    ];
 
-    // The point should be on the arc (within tolerance)
-    assert!(
-        distance_from_arc < 0.01,
-        "line4_start should be on the arc after solving. Distance from arc: {}, arc radius: {}",
-        distance_from_arc,
-        arc_radius
-    );
+    let drifted = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    let normalized = solve(
+        &constraints,
+        initial_guesses,
+        Config::default().with_normalize_angles(true),
+    )
+    .unwrap();
+    assert!(drifted.is_satisfied());
+    assert!(normalized.is_satisfied());
+    let drifted = drifted.final_value_line(&line);
+    let normalized = normalized.final_value_line(&line);
+    assert_nearly_eq(drifted.angle, 7.0 * PI);
+    assert_nearly_eq(normalized.angle, PI);
+    // It's still the same line.
+    assert_nearly_eq(normalized.offset, drifted.offset);
+    let origin = Point { x: 0.0, y: 0.0 };
+    assert_nearly_eq(normalized.signed_distance(origin), 1.0);
+}
 
-    // The point should have moved to get into the angular range.
-    // If it was only slightly outside (start_cross small), movement might be small.
-    // But if it was far outside, it should move significantly.
-    // For now, just verify it ends up in the angular range (checked below).
-    // If the initial violation was large, we expect significant movement.
-    if initial_start_cross > 0.1 {
-        let min_expected_movement = arc_radius * 0.3; // At least 30% of the radius for large violations
-        assert!(
-            movement > min_expected_movement,
-            "line4_start should have moved significantly when initially far outside angular range. \
-             Movement: {}, minimum expected: {} (30% of arc radius {}), initial start_cross: {}",
-            movement,
-            min_expected_movement,
-            arc_radius,
-            initial_start_cross
-        );
-    }
+#[test]
+fn infinite_line_tangent_to_circle() {
+    // A vertical line (normal along +X) should slide along until it touches the circle.
+    let mut ids = IdGenerator::default();
+    let line = DatumLine::new(&mut ids);
+    let circle = DatumCircle {
+        center: DatumPoint::new(&mut ids),
+        radius: DatumDistance::new(ids.next_id()),
+    };
+    let constraints = [
+        Constraint::Fixed(line.angle, 0.0),
+        Constraint::Fixed(circle.center.id_x(), 3.0),
+        Constraint::Fixed(circle.center.id_y(), 1.0),
+        Constraint::Fixed(circle.radius.id, 2.0),
+        Constraint::InfiniteLineTangentToCircle(line, circle, LineSide::Undefined),
+    ]
+    .map(ConstraintRequest::highest_priority);
+    let initial_guesses = vec![
+        (line.angle, 0.1),
+        (line.offset, 0.5),
+        (circle.center.id_x(), 3.0),
+        (circle.center.id_y(), 1.0),
+        (circle.radius.id, 2.0),
+    ];
 
-    // Also verify the point is within the angular range by checking cross products
-    let cx = solved_arc.center.x;
-    let cy = solved_arc.center.y;
-    let ax = solved_arc.a.x;
-    let ay = solved_arc.a.y;
-    let bx = solved_arc.b.x;
-    let by = solved_arc.b.y;
-    let px = solved_line4_start.x;
-    let py = solved_line4_start.y;
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    // The circle starts on the left of the line, so the line ends up at x = 3 - 2.
+    assert_nearly_eq(solved.final_value_line(&line).offset, 1.0);
+}
 
-    // For a CCW arc, the point should be:
-    // - CCW from the start vector (start_cross < 0)
-    // - Before the end (end is CCW from point, so end_cross < 0)
-    let start_cross = (ax - cx) * (cy - py) - (ay - cy) * (cx - px);
-    let end_cross = (bx - cx) * (cy - py) - (by - cy) * (cx - px);
+#[test]
+fn rounded_rectangle_path() {
+    use crate::path::{PathBuilder, PathSegment};
 
-    // Debug logs intentionally removed to keep tests quiet by default.
+    // A 10 by 6 rectangle with corners of radius 1, drawn counterclockwise.
+    let p = |x, y| Point { x, y };
+    let mut ids = IdGenerator::default();
+    let (path, requests) = PathBuilder::new()
+        .move_to(p(1.0, 0.0))
+        .line_to(p(9.0, 0.0))
+        .arc_to(p(10.0, 1.0), 1.0)
+        .line_to(p(10.0, 5.0))
+        .arc_to(p(9.0, 6.0), 1.0)
+        .line_to(p(1.0, 6.0))
+        .arc_to(p(0.0, 5.0), 1.0)
+        .line_to(p(0.0, 1.0))
+        .arc_to(p(1.0, 0.0), 1.0)
+        .close()
+        .build(&mut ids);
+    assert_eq!(path.segments.len(), 8);
+    // Pull the segments apart, so the solver has to join them back up.
+    let initial_guesses = path
+        .initial_guesses()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (id, guess))| (id, guess + 0.1 * libm::sin(i as f64)))
+        .collect();
+    let solved = solve(&requests, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
 
-    // Allow small tolerance for numerical precision, but point should be clearly in range
-    assert!(
-        start_cross < 0.01,
-        "Point should be CCW from start angle (or very close to boundary). start_cross: {}",
-        start_cross
-    );
-    assert!(
-        end_cross < 1e-6,
-        "Point should be before end angle (end is CCW from point). end_cross: {}",
-        end_cross
-    );
+    // Each segment starts where the previous one ended, all the way around.
+    let ends: Vec<_> = path
+        .segments
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Line(line) => (
+                solved.final_value_point(&line.p0),
+                solved.final_value_point(&line.p1),
+            ),
+            PathSegment::Arc(arc) => {
+                let arc = solved.final_value_arc(arc);
+                assert_nearly_eq(arc.center.euclidean_distance(arc.a), 1.0);
+                assert_nearly_eq(arc.center.euclidean_distance(arc.b), 1.0);
+                (arc.a, arc.b)
+            }
+        })
+        .collect();
+    for (i, &(_start, end)) in ends.iter().enumerate() {
+        let (next_start, _end) = ends[(i + 1) % ends.len()];
+        assert_points_eq(end, next_start);
+    }
 }
 
 #[test]
-fn lines_at_angle_isolated() {
-    use crate::datatypes::inputs::{DatumLineSegment, DatumPoint};
+fn slot_shape() {
+    let (length, width) = (10.0, 4.0);
+    let mut ids = IdGenerator::default();
+    let (slot, requests) = shapes::slot(&mut ids, length, width);
+    // Start from a skewed guess, so the solver has to do some work.
+    let initial_guesses = slot
+        .initial_guesses()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (id, guess))| (id, guess + 0.3 * libm::sin(i as f64)))
+        .collect();
+    let solved = solve_analysis(&requests, initial_guesses, Config::default()).unwrap();
+    // Only the slot's position and rotation are free.
+    assert_eq!(solved.analysis.remaining_dof(), 3);
+    let solved = solved.outcome;
+    assert!(solved.is_satisfied());
 
-    struct TestCase {
-        points: [[f64; 2]; 4],
-        angle: f64,
-        expected_iters: usize,
+    let point = |p: DatumPoint| {
+        let p = solved.final_value_point(&p);
+        V::new(p.x, p.y)
+    };
+    let side = |line: DatumLineSegment| point(line.p1) - point(line.p0);
+    // The sides are parallel, opposite directions around the outline, and the right length.
+    let (bottom, top) = (side(slot.bottom), side(slot.top));
+    assert_nearly_eq(bottom.cross_2d(top), 0.0);
+    assert!(bottom.dot(top) < 0.0);
+    assert_nearly_eq(bottom.magnitude(), length);
+    assert_nearly_eq(top.magnitude(), length);
+    // Each end is a semicircle of the right radius.
+    for end in [slot.left, slot.right] {
+        let arc = solved.final_value_arc(&end);
+        assert_nearly_eq(arc.center.euclidean_distance(arc.a), width / 2.0);
+        assert_nearly_eq(arc.center.euclidean_distance(arc.b), width / 2.0);
+        assert_points_eq(
+            arc.center,
+            Point {
+                x: (arc.a.x + arc.b.x) / 2.0,
+                y: (arc.a.y + arc.b.y) / 2.0,
+            },
+        );
     }
+}
 
-    let test_cases = [
-        TestCase {
-            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
-            angle: 0.5 * PI,
-            expected_iters: 0,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
-            angle: -0.5 * PI,
-            expected_iters: 0,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
-            angle: 0.0,
-            expected_iters: 0,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
-            angle: PI,
-            expected_iters: 0,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [-1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
-            angle: 0.0,
-            expected_iters: 0,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [-1.0, 0.0], [0.0, 0.0], [2.0, 0.0]],
-            angle: PI,
-            expected_iters: 0,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
-            angle: 0.0,
-            expected_iters: 4,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 2.0]],
-            angle: PI,
-            expected_iters: 4,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [0.0, 1.0], [0.0, 0.0], [0.0, 2.0]],
-            angle: 0.5 * PI,
-            expected_iters: 4,
-        },
-        TestCase {
-            points: [[0.0, 0.0], [0.0, 1.0], [0.0, 0.0], [0.0, 2.0]],
-            angle: -0.5 * PI,
-            expected_iters: 4,
-        },
+#[test]
+fn min_distance_to_any_pushes_point_clear() {
+    // P starts too close to both obstacles, and should be pushed out until it's clear of them.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let obstacles = [DatumPoint::new(&mut ids), DatumPoint::new(&mut ids)];
+    let clearance = 1.5;
+    let mut constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(obstacles[0].id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(obstacles[0].id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(obstacles[1].id_x(), 2.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(obstacles[1].id_y(), 0.0)),
+    ];
+    constraints.extend(
+        Constraint::min_distance_to_any(p, &obstacles, clearance)
+            .map(ConstraintRequest::highest_priority),
+    );
+    let initial_guesses = vec![
+        (p.id_x(), 0.8),
+        (p.id_y(), 0.3),
+        (obstacles[0].id_x(), 0.0),
+        (obstacles[0].id_y(), 0.0),
+        (obstacles[1].id_x(), 2.0),
+        (obstacles[1].id_y(), 0.0),
     ];
 
-    let p0 = DatumPoint { x_id: 0, y_id: 1 };
-    let p1 = DatumPoint { x_id: 2, y_id: 3 };
-    let p2 = DatumPoint { x_id: 4, y_id: 5 };
-    let p3 = DatumPoint { x_id: 6, y_id: 7 };
-    let line0 = DatumLineSegment { p0, p1 };
-    let line1 = DatumLineSegment { p0: p2, p1: p3 };
-
-    for test_case in test_cases {
-        let constraints = [ConstraintRequest::highest_priority(
-            Constraint::LinesAtAngle(
-                line0,
-                line1,
-                AngleKind::Other(Angle::from_radians(test_case.angle)),
-            ),
-        )];
-        let initial_guesses: Vec<_> = test_case
-            .points
-            .into_iter()
-            .enumerate()
-            .flat_map(|(point_idx, [x, y])| {
-                [((point_idx as Id) * 2, x), ((point_idx as Id) * 2 + 1, y)]
-            })
-            .collect();
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let p = solved.final_value_point(&p);
+    for obstacle in obstacles {
+        let dist = p.euclidean_distance(solved.final_value_point(&obstacle));
+        assert!(
+            dist >= clearance - EPSILON,
+            "P was only {dist} from an obstacle"
+        );
+    }
+}
 
-        let outcome = solve(
-            &constraints,
-            initial_guesses,
-            Config::default().with_max_iterations(100),
-        )
-        .unwrap_or_else(|err| panic!("failed for angle {}: {err:?}", test_case.angle));
+#[test]
+fn cocircular_recovers_center() {
+    // Five fixed points sampled from the circle of radius 3 around (2, -1).
+    // The center is free, so the solver should find the circle they all lie on.
+    let true_center = Point { x: 2.0, y: -1.0 };
+    let mut ids = IdGenerator::default();
+    let center = DatumPoint::new(&mut ids);
+    let points: Vec<_> = (0..5).map(|_| DatumPoint::new(&mut ids)).collect();
+    let mut constraints = Vec::with_capacity(points.len() * 3);
+    let mut initial_guesses = vec![(center.id_x(), 0.0), (center.id_y(), 0.0)];
+    for (i, point) in points.iter().enumerate() {
+        let angle = 0.3 + 1.1 * i as f64;
+        let x = true_center.x + 3.0 * libm::cos(angle);
+        let y = true_center.y + 3.0 * libm::sin(angle);
+        constraints.push(ConstraintRequest::highest_priority(Constraint::Fixed(
+            point.id_x(),
+            x,
+        )));
+        constraints.push(ConstraintRequest::highest_priority(Constraint::Fixed(
+            point.id_y(),
+            y,
+        )));
+        initial_guesses.extend([(point.id_x(), x), (point.id_y(), y)]);
+    }
+    constraints
+        .extend(Constraint::cocircular(center, &points).map(ConstraintRequest::highest_priority));
 
-        assert!(outcome.is_satisfied());
-        assert_eq!(
-            outcome.iterations(),
-            test_case.expected_iters,
-            "unexpected iteration count for angle {}",
-            test_case.angle
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let center = solved.final_value_point(&center);
+    assert_points_eq(center, true_center);
+    for point in &points {
+        assert_nearly_eq(
+            solved.final_value_point(point).euclidean_distance(center),
+            3.0,
         );
     }
 }
 
 #[test]
-fn lines_angle_sign_check() {
-    use crate::datatypes::inputs::{DatumLineSegment, DatumPoint};
+fn anchor_near() {
+    // P must stay on the X axis, and the user is dragging it towards the cursor at (3, 2).
+    // The drag overshot and left P way out at (10, 5), outside the anchor radius,
+    // so it should be pulled back to the nearest point that's both on the axis
+    // and within the anchor radius.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let anchor = Point { x: 3.0, y: 2.0 };
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::AnchorNear(p, anchor, 2.5)),
+    ];
+    let initial_guesses = vec![(p.id_x(), 10.0), (p.id_y(), 5.0)];
 
-    struct TestCase {
-        vars: [[f64; 2]; 3],
-        angle: f64,
-        expected_iters: usize,
-    }
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let p = solved.final_value_point(&p);
+    // The axis crosses the anchor circle at x = 1.5 and x = 4.5.
+    assert_points_eq(p, Point { x: 4.5, y: 0.0 });
+    assert!(p.euclidean_distance(anchor) <= 2.5 + EPSILON);
+}
 
-    let test_cases = [
-        TestCase {
-            vars: [[0.0, 0.0], [1.0, 0.0], [2.0, 1.0]],
-            angle: 0.1 * PI,
-            expected_iters: 3,
-        },
-        TestCase {
-            vars: [[0.0, 0.0], [1.0, 0.0], [2.0, 1.0]],
-            angle: -0.1 * PI,
-            expected_iters: 4,
-        },
+#[test]
+fn mixed_equality_and_inequality_residuals() {
+    // P is fixed to the origin, and Q must stay on the X axis (equalities).
+    // Q starts too close to P, so its clearance from P is violated and should push it out.
+    // Q also needs clearance from R, but R starts far away, so that inequality is already
+    // satisfied and mustn't pull R towards Q like an equality would.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let r = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::MinDistance(p, q, 2.0)),
+        ConstraintRequest::highest_priority(Constraint::MinDistance(q, r, 1.0)),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 0.1),
+        (p.id_y(), 0.1),
+        (q.id_x(), 0.5),
+        (q.id_y(), 0.5),
+        (r.id_x(), 10.0),
+        (r.id_y(), 0.0),
     ];
 
-    let p0 = DatumPoint { x_id: 0, y_id: 1 };
-    let p1 = DatumPoint { x_id: 2, y_id: 3 };
-    let p2 = DatumPoint { x_id: 4, y_id: 5 };
-    let line0 = DatumLineSegment { p0, p1 };
-    let line1 = DatumLineSegment { p0: p1, p1: p2 };
+    let solved = solve(&constraints, initial_guesses, Config::default()).unwrap();
+    assert!(solved.is_satisfied());
+    let p = solved.final_value_point(&p);
+    let q = solved.final_value_point(&q);
+    assert_points_eq(p, Point { x: 0.0, y: 0.0 });
+    assert!(q.y.abs() < EPSILON);
+    assert!(
+        q.x >= 2.0 - EPSILON,
+        "Q should have been pushed clear of P, got {q:?}"
+    );
+    assert_points_eq(solved.final_value_point(&r), Point { x: 10.0, y: 0.0 });
+}
 
-    for test_case in test_cases {
-        let constraints = [
-            ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_x(), 0.0)),
-            ConstraintRequest::highest_priority(Constraint::Fixed(p0.id_y(), 0.0)),
-            ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_x(), 1.0)),
-            ConstraintRequest::highest_priority(Constraint::Fixed(p1.id_y(), 0.0)),
-            ConstraintRequest::highest_priority(Constraint::LinesAtAngle(
-                line0,
-                line1,
-                AngleKind::Other(Angle::from_radians(test_case.angle)),
-            )),
-        ];
-        let initial_guesses: Vec<_> = test_case
-            .vars
-            .into_iter()
-            .enumerate()
-            .flat_map(|(point_idx, [x, y])| {
-                [((point_idx as Id) * 2, x), ((point_idx as Id) * 2 + 1, y)]
-            })
-            .collect();
+#[test]
+fn termination_reasons() {
+    // Q can't be both 2 and 3 away from P, so the residual never reaches 0,
+    // and the best the solver can do is split the difference.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(p, q, 2.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(p, q, 3.0)),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 1.0),
+        (q.id_y(), 0.5),
+    ];
 
-        let outcome = solve(
-            &constraints,
-            initial_guesses,
-            Config::default().with_max_iterations(100),
-        )
-        .unwrap_or_else(|err| panic!("failed for angle {}: {err:?}", test_case.angle));
+    // By default, it stops once the steps stop getting anywhere.
+    let solved = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    assert_eq!(
+        solved.termination_reason(),
+        TerminationReason::StepTolerance
+    );
+    assert!(solved.converged());
+    assert_nearly_eq(solved.final_value_point(&q).x, 2.5);
 
-        assert!(outcome.is_satisfied());
-        assert_eq!(
-            outcome.iterations(),
-            test_case.expected_iters,
-            "unexpected iteration count for angle {}",
-            test_case.angle
-        );
+    // With a gradient tolerance, it can recognize the least-squares solution sooner.
+    let config = Config::default().with_gradient_tolerance(1e-6);
+    let gradient = solve(&constraints, initial_guesses.clone(), config).unwrap();
+    assert_eq!(
+        gradient.termination_reason(),
+        TerminationReason::GradientTolerance
+    );
+    assert!(gradient.converged());
+    assert!(gradient.iterations() <= solved.iterations());
+    assert_nearly_eq(gradient.final_value_point(&q).x, 2.5);
 
-        // Ensure the resulting angle actually matches
-        {
-            let p0 = outcome.final_value_point(&p0);
-            let p1 = outcome.final_value_point(&p1);
-            let p2 = outcome.final_value_point(&p2);
-            let u = V::new(p1.x - p0.x, p1.y - p0.y);
-            let v = V::new(p2.x - p1.x, p2.y - p1.y);
-            assert_nearly_eq(u.signed_angle(v), test_case.angle);
+    // Without enough iterations, it gives up.
+    let config = Config::default().with_max_iterations(1);
+    let capped = solve(&constraints, initial_guesses, config).unwrap();
+    assert_eq!(
+        capped.termination_reason(),
+        TerminationReason::MaxIterations
+    );
+    assert!(!capped.converged());
+}
+
+#[test]
+fn stop_when() {
+    // Q has to end up 10 from P, starting from 1 away. Heavy damping makes the solver
+    // creep towards it over many iterations, so it passes through the target range
+    // well before the residual converges.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(q.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(p, q, 10.0)),
+    ];
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 1.0),
+        (q.id_y(), 0.0),
+    ];
+    let config = Config::default()
+        .with_initial_lambda(100.0)
+        .with_max_iterations(100);
+
+    let full = solve(&constraints, initial_guesses.clone(), config.clone()).unwrap();
+    assert_eq!(
+        full.termination_reason(),
+        TerminationReason::ResidualTolerance
+    );
+
+    let qx_id = q.id_x() as usize;
+    let config = config.with_stop_when(move |values, _residuals| values[qx_id] > 9.0);
+    let stopped = solve(&constraints, initial_guesses, config).unwrap();
+    assert_eq!(
+        stopped.termination_reason(),
+        TerminationReason::StopCriterion
+    );
+    assert!(stopped.converged());
+    assert!(stopped.iterations() < full.iterations());
+    let qx = stopped.final_value_point(&q).x;
+    assert!(
+        (9.0..10.0 - 1e-3).contains(&qx),
+        "Q should have stopped as soon as it got past 9, got {qx}"
+    );
+}
+
+#[test]
+fn abort_on_divergence() {
+    // P can't be 1 away from both A and B, because they're 4 apart.
+    // Starting just off the line between them, the Jacobian is nearly singular in Y,
+    // so the first undamped step flings P thousands of units away.
+    let mut ids = IdGenerator::default();
+    let a = DatumPoint::new(&mut ids);
+    let b = DatumPoint::new(&mut ids);
+    let p = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(b.id_x(), 4.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(b.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(a, p, 1.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(b, p, 1.0)),
+    ];
+    let initial_guesses = vec![
+        (a.id_x(), 0.0),
+        (a.id_y(), 0.0),
+        (b.id_x(), 4.0),
+        (b.id_y(), 0.0),
+        (p.id_x(), 2.0),
+        (p.id_y(), 1e-3),
+    ];
+
+    // By default the solver backs off and keeps going.
+    let recovered = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    assert!(recovered.iterations() > 1);
+
+    let config = Config::default().with_abort_on_divergence(true);
+    let err = solve(&constraints, initial_guesses, config).unwrap_err();
+    match err.error {
+        NonLinearSystemError::Diverged {
+            iterations,
+            residual_norm,
+            best_residual_norm,
+        } => {
+            assert_eq!(iterations, 1);
+            assert!(residual_norm > 100.0 * best_residual_norm);
+        }
+        other => panic!("expected the solve to diverge, got {other}"),
+    }
+}
+
+#[test]
+fn stall_window() {
+    // Same setup as `abort_on_divergence`, where the least-squares solution is P = (2, 0).
+    // Full Gauss-Newton steps overshoot it, so the solver alternates between rejected steps
+    // and heavily damped accepted ones, which barely reduce the residual.
+    let mut ids = IdGenerator::default();
+    let a = DatumPoint::new(&mut ids);
+    let b = DatumPoint::new(&mut ids);
+    let p = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(b.id_x(), 4.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(b.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(a, p, 1.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(b, p, 1.0)),
+    ];
+    let initial_guesses = vec![
+        (a.id_x(), 0.0),
+        (a.id_y(), 0.0),
+        (b.id_x(), 4.0),
+        (b.id_y(), 0.0),
+        (p.id_x(), 2.0),
+        (p.id_y(), 1e-3),
+    ];
+
+    // By default it runs until the iteration cap.
+    let capped = solve(&constraints, initial_guesses.clone(), Config::default()).unwrap();
+    assert_eq!(
+        capped.termination_reason(),
+        TerminationReason::MaxIterations
+    );
+
+    let config = Config::default().with_stall_window(4);
+    let err = solve(&constraints, initial_guesses.clone(), config.clone()).unwrap_err();
+    match err.error {
+        NonLinearSystemError::Stalled {
+            iterations,
+            residual_norm,
+            window,
+        } => {
+            assert_eq!(window, 4);
+            assert!(iterations < capped.iterations());
+            // Both distances are off by about 1 at the least-squares solution.
+            assert!((residual_norm - 2f64.sqrt()).abs() < 1e-2);
         }
+        other => panic!("expected the solve to stall, got {other}"),
     }
-}
 
-/// Returns the signed angle from (p1 - p0) to (p2 - p0), reading variable
-/// ids 0..5 = `[p0_x, p0_y, p1_x, p1_y, p2_x, p2_y]` from `vals`.
-fn points_at_angle_from_vals(vals: &[f64]) -> f64 {
-    let u = V::new(vals[2] - vals[0], vals[3] - vals[1]);
-    let v = V::new(vals[4] - vals[0], vals[5] - vals[1]);
-    u.signed_angle(v)
+    // With the fallback, gradient descent takes over from the stall instead.
+    let fallback = solve(
+        &constraints,
+        initial_guesses,
+        config.with_gradient_descent_fallback(true),
+    )
+    .unwrap();
+    assert!(fallback.converged());
+    assert_nearly_eq(fallback.final_value_point(&p).x, 2.0);
 }
 
 #[test]
-fn points_at_angle_already_satisfied() {
-    // Cases where the geometry already satisfies the constraint: expect 0 Newton iterations.
-    struct TestCase {
-        p1: [f64; 2], // first arm endpoint; vertex is always at origin
-        p2: [f64; 2], // second arm endpoint
-        angle: f64,
+fn stall_window_uses_best_residual() {
+    let config = Config::default().with_stall_window(3);
+    let mut history = std::collections::VecDeque::new();
+    for residual_sq in [4.0, 1.0, 4.0] {
+        config.record_stall_history(&mut history, residual_sq);
     }
+    // Back up where the window started, but it reached a much better residual along the way.
+    assert!(!config.has_stalled(&history, 4.0));
+    // The history only keeps the last 3 iterations.
+    config.record_stall_history(&mut history, 4.0);
+    assert_eq!(history, [1.0, 4.0, 4.0]);
+    // Nothing in the window beat the 1.0 it started at.
+    assert!(config.has_stalled(&history, 4.0));
+}
 
-    let test_cases = [
-        TestCase {
-            p1: [1.0, 0.0],
-            p2: [0.0, 2.0],
-            angle: 0.5 * PI,
-        },
-        TestCase {
-            p1: [1.0, 0.0],
-            p2: [0.0, -2.0],
-            angle: -0.5 * PI,
-        },
-        TestCase {
-            p1: [1.0, 0.0],
-            p2: [3.0, 0.0],
-            angle: 0.0,
-        },
-        TestCase {
-            p1: [1.0, 0.0],
-            p2: [-2.0, 0.0],
-            angle: PI,
-        },
-        TestCase {
-            p1: [2.0, 0.0],
-            p2: [1.0, 1.0],
-            angle: 0.25 * PI,
-        },
+#[test]
+fn on_iteration_reports_rejected_steps() {
+    // Same setup as `abort_on_divergence`: the first step overshoots,
+    // so the solver has to undo it and retry with more damping.
+    let mut ids = IdGenerator::default();
+    let a = DatumPoint::new(&mut ids);
+    let b = DatumPoint::new(&mut ids);
+    let p = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(a.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(b.id_x(), 4.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(b.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(a, p, 1.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(b, p, 1.0)),
     ];
-
-    let vertex = DatumPoint { x_id: 0, y_id: 1 };
-    let p1 = DatumPoint { x_id: 2, y_id: 3 };
-    let p2 = DatumPoint { x_id: 4, y_id: 5 };
-
-    for tc in &test_cases {
-        let constraints = [ConstraintRequest::highest_priority(
-            Constraint::PointsAtAngle(
-                vertex,
-                p1,
-                p2,
-                AngleKind::Other(Angle::from_radians(tc.angle)),
-            ),
-        )];
-        let initial_guesses = vec![
-            (0, 0.0),
-            (1, 0.0),
-            (2, tc.p1[0]),
-            (3, tc.p1[1]),
-            (4, tc.p2[0]),
-            (5, tc.p2[1]),
-        ];
-        let outcome = solve(
-            &constraints,
-            initial_guesses,
-            Config::default().with_max_iterations(100),
-        )
-        .unwrap_or_else(|e| panic!("failed for angle {}: {e:?}", tc.angle));
-        assert!(outcome.is_satisfied());
-        assert_eq!(
-            outcome.iterations(),
-            0,
-            "angle {} should already be satisfied (0 iterations)",
-            tc.angle
-        );
+    let initial_guesses = vec![
+        (a.id_x(), 0.0),
+        (a.id_y(), 0.0),
+        (b.id_x(), 4.0),
+        (b.id_y(), 0.0),
+        (p.id_x(), 2.0),
+        (p.id_y(), 1e-3),
+    ];
+    let stats = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&stats);
+    let config = Config::default().with_on_iteration(move |s| recorded.lock().unwrap().push(*s));
+    let solved = solve(&constraints, initial_guesses, config).unwrap();
+
+    let stats = stats.lock().unwrap();
+    assert_eq!(stats.len(), solved.iterations());
+    let first = stats[0];
+    assert_eq!(first.iteration(), 0);
+    assert!(!first.accepted());
+    assert!(first.next_residual_norm() > first.residual_norm());
+    // The retry is damped harder, so it takes a smaller step.
+    let second = stats[1];
+    assert!(second.damping() > first.damping());
+    assert!(second.step_norm() < first.step_norm());
+    assert!(stats.iter().any(|s| s.accepted()));
+    for s in stats.iter().filter(|s| s.accepted()) {
+        assert!(s.next_residual_norm() < s.residual_norm());
     }
 }
 
 #[test]
-fn points_at_angle_degenerate() {
-    let vertex = DatumPoint { x_id: 0, y_id: 1 };
-    let p1 = DatumPoint { x_id: 2, y_id: 3 };
-    let p2 = DatumPoint { x_id: 4, y_id: 5 };
-
-    let constraints = [ConstraintRequest::highest_priority(
-        Constraint::PointsAtAngle(vertex, p1, p2, AngleKind::Other(Angle::from_degrees(180.0))),
-    )];
+fn gradient_descent_fallback() {
+    // Q only has to be 5 from the fixed P, so its direction is free and the Jacobian is
+    // rank-deficient. With no damping at all, JᵀJ + λI is singular and can't be factored.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_x(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Fixed(p.id_y(), 0.0)),
+        ConstraintRequest::highest_priority(Constraint::Distance(p, q, 5.0)),
+    ];
     let initial_guesses = vec![
-        (0, 0.0),
-        (1, 0.0),
-        (2, 13.0),
-        (3, 13.0),
-        (4, 13.0),
-        (5, 13.0),
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 1.0),
+        (q.id_y(), 1.0),
     ];
-    let outcome = solve(
+    let distance = |solved: &SolveOutcome| {
+        let q = solved.final_value_point(&q);
+        libm::hypot(q.x, q.y)
+    };
+    let config = Config::default().with_initial_lambda(0.0);
+
+    // Every factorization fails, so the solver never moves.
+    let stuck = solve(&constraints, initial_guesses.clone(), config.clone()).unwrap();
+    assert_eq!(stuck.termination_reason(), TerminationReason::MaxIterations);
+    let start = std::f64::consts::SQRT_2;
+    assert_nearly_eq(distance(&stuck), start);
+
+    let fallback = solve(
         &constraints,
         initial_guesses,
-        Config::default().with_max_iterations(100),
+        config.with_gradient_descent_fallback(true),
     )
     .unwrap();
-    assert!(outcome.warnings.first().unwrap().content == WarningContent::Degenerate);
+    assert!((distance(&fallback) - 5.0).abs() < (5.0 - start) / 100.0);
+    // Gradient descent moves Q straight away from P, so its direction shouldn't change.
+    let q = fallback.final_value_point(&q);
+    assert_nearly_eq(q.x, q.y);
 }
 
 #[test]
-fn points_at_angle_unique_solution() {
-    // PointsAtAngle has exactly one solution unlike LinesAtAngle which has two solutions for each
-    // arm that differ by π
-    //
-    // Concretely, with target angle π/4 and u = (1,0):
-    //   - v = (1,1) direction satisfies PointsAtAngle
-    //   - v = (-1,-1) direction satisfies LinesAtAngle but not PointsAtAngle
-    //
-    // Starting from either initial condition, PointsAtAngle must converge to angle π/4.
-    let vertex = DatumPoint { x_id: 0, y_id: 1 };
-    let p1 = DatumPoint { x_id: 2, y_id: 3 };
-    let p2 = DatumPoint { x_id: 4, y_id: 5 };
+fn initial_lambda_fn() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn heavy_damping(problem: &InitialLambdaContext<'_>) -> f64 {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        assert!(problem.constraints().count() > 0);
+        assert!(!problem.initial_values().is_empty());
+        100.0
+    }
+    fn moderate_damping(_problem: &InitialLambdaContext<'_>) -> f64 {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        1.0
+    }
 
-    let target_angle = 0.25 * PI;
+    let default = run("square");
+    let damped = run_with_config(
+        "square",
+        Config::default().with_initial_lambda_fn(heavy_damping),
+    );
+    assert!(CALLS.load(Ordering::Relaxed) > 0);
+    assert!(damped.is_satisfied());
+    // Heavier damping means shorter steps, so the solver takes longer to reach the solution.
+    assert!(damped.outcome.iterations > default.outcome.iterations);
 
-    let constraints = [
-        // Fix vertex and first arm
-        ConstraintRequest::highest_priority(Constraint::Fixed(0, 0.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(1, 0.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(2, 1.0)),
-        ConstraintRequest::highest_priority(Constraint::Fixed(3, 0.0)),
-        ConstraintRequest::highest_priority(Constraint::PointsAtAngle(
-            vertex,
-            p1,
-            p2,
-            AngleKind::Other(Angle::from_radians(target_angle)),
-        )),
-    ];
+    // All-linear systems take their own fast path, which should use the hook too.
+    // It never adapts λ, so heavy damping would leave it crawling towards the solution.
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let requests: Vec<_> = [
+        Constraint::Fixed(p.id_x(), 1.0),
+        Constraint::Fixed(p.id_y(), 2.0),
+        Constraint::Vertical(DatumLineSegment::new(p, q)),
+        Constraint::VerticalDistance(p, q, 3.0),
+    ]
+    .into_iter()
+    .map(ConstraintRequest::highest_priority)
+    .collect();
+    assert!(requests.iter().all(|r| r.constraint().is_linear()));
+    let initial_guesses = || {
+        vec![
+            (p.id_x(), 0.0),
+            (p.id_y(), 0.0),
+            (q.id_x(), 0.0),
+            (q.id_y(), 0.0),
+        ]
+    };
+    let calls_before = CALLS.load(Ordering::Relaxed);
+    let default = solve(&requests, initial_guesses(), Config::default()).unwrap();
+    let damped = solve(
+        &requests,
+        initial_guesses(),
+        Config::default().with_initial_lambda_fn(moderate_damping),
+    )
+    .unwrap();
+    assert!(CALLS.load(Ordering::Relaxed) > calls_before);
+    assert!(damped.is_satisfied());
+    assert!(damped.iterations() > default.iterations());
+}
 
-    // p2 starts on the correct side: direction (1,1), angle π/4 so already satisfied
-    let guesses_correct: Vec<(u32, f64)> =
-        vec![(0, 0.0), (1, 0.0), (2, 1.0), (3, 0.0), (4, 1.0), (5, 1.0)];
+#[test]
+fn regularization_lambda_underdetermined_lines() {
+    // Without regularization, P1 moves all the way from its guess to satisfy the constraints.
+    let default = run("underdetermined_lines");
+    let unregularized = run_with_config(
+        "underdetermined_lines",
+        Config::default().with_regularization_lambda(0.0),
+    );
+    for label in ["p0", "p1", "p2"] {
+        assert_points_eq(
+            unregularized.get_point(label).unwrap(),
+            default.get_point(label).unwrap(),
+        );
+    }
+    assert_points_eq(default.get_point("p1").unwrap(), Point { x: 4.0, y: 0.0 });
+
+    // A strong pull towards the guesses competes with the constraints,
+    // so P1 ends up closer to its guess, and the constraints can't all be met.
+    let guess = Point { x: 0.0, y: 3.2 };
+    let regularized = run_with_config(
+        "underdetermined_lines",
+        Config::default().with_regularization_lambda(0.5),
+    );
+    assert!(!regularized.is_satisfied());
+    let p1 = regularized.get_point("p1").unwrap();
+    assert!(
+        p1.euclidean_distance(guess)
+            < default.get_point("p1").unwrap().euclidean_distance(guess) - 0.1
+    );
+}
 
-    // p2 starts on the π-shifted side: direction (-1,-1), angle -3π/4.
-    // This is the other zero of LinesAtAngle, but it should not satisfy PointsAtAngle.
-    let guesses_shifted: Vec<(u32, f64)> =
-        vec![(0, 0.0), (1, 0.0), (2, 1.0), (3, 0.0), (4, -1.0), (5, -1.0)];
+#[test]
+fn regularization_weights_anchor_some_variables() {
+    let mut ids = IdGenerator::default();
+    let p = DatumPoint::new(&mut ids);
+    let q = DatumPoint::new(&mut ids);
+    let constraints = vec![ConstraintRequest::highest_priority(Constraint::Distance(
+        p, q, 5.0,
+    ))];
+    let initial_guesses = vec![
+        (p.id_x(), 0.0),
+        (p.id_y(), 0.0),
+        (q.id_x(), 1.0),
+        (q.id_y(), 0.0),
+    ];
 
-    let outcome_correct = solve(
+    // A uniform pull spreads the movement over both points.
+    let uniform = solve(
         &constraints,
-        guesses_correct,
-        Config::default().with_max_iterations(100),
+        initial_guesses.clone(),
+        Config::default().with_regularization_lambda(1e-3),
     )
     .unwrap();
-    let outcome_shifted = solve(
+    assert!(uniform.final_values()[0] < -1.0);
+
+    // Anchoring P and leaving Q free makes Q do all the moving.
+    let anchored = HashMap::from([(p.id_x(), 1.0), (p.id_y(), 1.0)]);
+    let weighted = solve(
         &constraints,
-        guesses_shifted,
-        Config::default().with_max_iterations(100),
+        initial_guesses,
+        Config::default().with_regularization_weights(anchored),
     )
     .unwrap();
-
-    assert!(outcome_correct.is_satisfied());
-    assert!(outcome_shifted.is_satisfied());
-
-    // Both must converge to target_angle, not to target_angle - π.
-    assert_nearly_eq(
-        points_at_angle_from_vals(&outcome_correct.final_values),
-        target_angle,
+    assert!(weighted.is_satisfied());
+    let values = weighted.final_values();
+    assert_points_eq(
+        Point {
+            x: values[0],
+            y: values[1],
+        },
+        Point { x: 0.0, y: 0.0 },
     );
-    assert_nearly_eq(
-        points_at_angle_from_vals(&outcome_shifted.final_values),
-        target_angle,
+    assert_points_eq(
+        Point {
+            x: values[2],
+            y: values[3],
+        },
+        Point { x: 5.0, y: 0.0 },
     );
 }
 
 #[test]
-fn points_at_angle_sign_distinguishable() {
-    // Checks that PointsAtAngle respects the sign of the specified angle i.e. using +θ and -θ
-    // should place the second arm on opposite sides of the first
-    let vertex = DatumPoint { x_id: 0, y_id: 1 };
-    let p1 = DatumPoint { x_id: 2, y_id: 3 };
-    let p2 = DatumPoint { x_id: 4, y_id: 5 };
-    let theta = 0.25 * PI;
-
-    // (target_angle, initial_p2)
-    let cases: &[(f64, [f64; 2])] = &[
-        (theta, [1.0, 0.0]),
-        (-theta, [1.0, 0.0]),
-        (theta, [0.0, 1.0]),
-        (-theta, [0.0, 1.0]),
-        (theta, [-1.0, 0.0]),
-        (-theta, [-1.0, 0.0]),
-        (theta, [0.0, -1.0]),
-        (-theta, [0.0, -1.0]),
-    ];
-
-    for &(target_angle, init_p2) in cases {
-        let constraints = [
-            // Fix vertex, first arm, and length of second arm
-            ConstraintRequest::highest_priority(Constraint::Fixed(0, 0.0)),
-            ConstraintRequest::highest_priority(Constraint::Fixed(1, 0.0)),
-            ConstraintRequest::highest_priority(Constraint::Fixed(2, 1.0)),
-            ConstraintRequest::highest_priority(Constraint::Fixed(3, 0.0)),
-            ConstraintRequest::highest_priority(Constraint::Distance(vertex, p2, 1.0)),
-            ConstraintRequest::highest_priority(Constraint::PointsAtAngle(
-                vertex,
-                p1,
-                p2,
-                AngleKind::Other(Angle::from_radians(target_angle)),
-            )),
-        ];
-        let initial_guesses: Vec<(u32, f64)> = vec![
-            (0, 0.0),
-            (1, 0.0),
-            (2, 1.0),
-            (3, 0.0),
-            (4, init_p2[0]),
-            (5, init_p2[1]),
-        ];
-
-        let outcome = solve(
-            &constraints,
-            initial_guesses,
-            Config::default().with_max_iterations(100),
-        )
-        .unwrap_or_else(|e| panic!("failed for angle {target_angle}: {e:?}"));
-        assert!(outcome.is_satisfied());
-        assert_nearly_eq(
-            points_at_angle_from_vals(&outcome.final_values),
-            target_angle,
-        );
-    }
+fn adaptive_regularization_underdetermined_lines() {
+    // Scaling λ by the residual damps harder while far from the solution and fades out
+    // as it converges. The free P2.y should still stay at its guess, and the solve
+    // shouldn't take any more iterations than with the plain λ schedule.
+    let fixed = run("underdetermined_lines");
+    let adaptive = run_with_config(
+        "underdetermined_lines",
+        Config::default().with_adaptive_regularization(true),
+    );
+    assert!(adaptive.is_satisfied());
+    assert!(adaptive.outcome.iterations <= fixed.outcome.iterations);
+    assert_points_eq(adaptive.get_point("p0").unwrap(), Point { x: 0.0, y: 0.0 });
+    assert_points_eq(adaptive.get_point("p1").unwrap(), Point { x: 4.0, y: 0.0 });
+    assert_points_eq(adaptive.get_point("p2").unwrap(), Point { x: 4.0, y: 4.0 });
 }
@@ -4,10 +4,12 @@ use std::f64::consts::PI;
 use proptest::prelude::*;
 
 use crate::{
-    CircleSide, Config, Constraint, ConstraintRequest, EPSILON, Id, IdGenerator, LineSide,
+    CircleSide, Config, Constraint, ConstraintRequest, EPSILON, Endpoint, Id, IdGenerator,
+    LineSide, TangencyKind,
     constraints::JacobianVar,
     datatypes::inputs::{
-        DatumCircle, DatumCircularArc, DatumDistance, DatumLineSegment, DatumPoint,
+        DatumCircle, DatumCircularArc, DatumConic, DatumDistance, DatumLine, DatumLineSegment,
+        DatumPoint,
     },
     datatypes::outputs::Point,
     datatypes::{Angle, AngleKind},
@@ -58,6 +60,18 @@ fn arb_arc() -> BoxedStrategy<DatumCircularArc> {
         .boxed()
 }
 
+fn arb_conic() -> BoxedStrategy<DatumConic> {
+    (arb_id(), arb_id(), arb_id(), arb_id(), arb_id(), arb_id())
+        .prop_map(|(a, b, c, d, e, f)| DatumConic { a, b, c, d, e, f })
+        .boxed()
+}
+
+fn arb_infinite_line() -> BoxedStrategy<DatumLine> {
+    (arb_id(), arb_id())
+        .prop_map(|(angle, offset)| DatumLine { angle, offset })
+        .boxed()
+}
+
 fn arb_angle() -> BoxedStrategy<Angle> {
     ((-360i16..=360), any::<bool>())
         .prop_map(|(value, degrees)| {
@@ -88,6 +102,14 @@ fn arb_circle_side() -> BoxedStrategy<CircleSide> {
     prop_oneof![Just(CircleSide::Exterior), Just(CircleSide::Interior)].boxed()
 }
 
+fn arb_tangency_kind() -> BoxedStrategy<TangencyKind> {
+    prop_oneof![Just(TangencyKind::External), Just(TangencyKind::Internal)].boxed()
+}
+
+fn arb_endpoint() -> BoxedStrategy<Endpoint> {
+    prop_oneof![Just(Endpoint::Start), Just(Endpoint::End)].boxed()
+}
+
 fn arb_constraint() -> BoxedStrategy<Constraint> {
     prop_oneof![
         (arb_line(), arb_circle(), arb_line_side())
@@ -108,6 +130,8 @@ fn arb_constraint() -> BoxedStrategy<Constraint> {
         (arb_line(), arb_line(), arb_angle_kind())
             .prop_map(|(line0, line1, angle)| Constraint::LinesAtAngle(line0, line1, angle)),
         (arb_id(), arb_scalar()).prop_map(|(id, value)| Constraint::Fixed(id, value)),
+        (arb_point(), arb_scalar(), arb_scalar())
+            .prop_map(|(p, x, y)| Constraint::FixedPoint(p, x, y)),
         (arb_id(), arb_id()).prop_map(|(x, y)| Constraint::ScalarEqual(x, y)),
         (arb_point(), arb_point()).prop_map(|(p0, p1)| Constraint::PointsCoincident(p0, p1)),
         (arb_circle(), arb_scalar())
@@ -134,6 +158,107 @@ fn arb_constraint() -> BoxedStrategy<Constraint> {
         (arb_arc(), arb_angle()).prop_map(|(arc, angle)| Constraint::ArcAngle(arc, angle)),
         (arb_point(), arb_point(), arb_point(), arb_angle_kind())
             .prop_map(|(p0, p1, p2, angle)| Constraint::PointsAtAngle(p0, p1, p2, angle)),
+        (arb_point(), arb_point(), arb_scalar(), arb_angle())
+            .prop_map(|(from, to, r, theta)| Constraint::PolarOffset(from, to, r, theta)),
+        (arb_point(), arb_conic())
+            .prop_map(|(point, conic)| Constraint::PointOnConic(point, conic)),
+        (arb_point(), arb_id(), arb_point(), arb_point()).prop_map(
+            |(pivot, scale, original, copy)| Constraint::ScaledCopy(pivot, scale, original, copy)
+        ),
+        (arb_point(), arb_point(), arb_point(), arb_scalar()).prop_map(
+            |(center, original, image, ratio)| Constraint::Homothety(
+                center, original, image, ratio
+            )
+        ),
+        (arb_point(), arb_infinite_line(), arb_scalar()).prop_map(|(point, line, distance)| {
+            Constraint::PointInfiniteLineDistance(point, line, distance)
+        }),
+        (arb_infinite_line(), arb_circle(), arb_line_side()).prop_map(|(line, circle, side)| {
+            Constraint::InfiniteLineTangentToCircle(line, circle, side)
+        }),
+        (arb_point(), arb_point(), arb_scalar())
+            .prop_map(|(p0, p1, dist)| Constraint::MinDistance(p0, p1, dist)),
+        (arb_line(), arb_line(), arb_scalar(), arb_scalar())
+            .prop_map(|(a, b, min, max)| { Constraint::LengthRatioBounds(a, b, min, max) }),
+        (arb_line(), arb_point(), arb_point()).prop_map(|(line, reference, point)| {
+            Constraint::SameSideOfLine(line, reference, point)
+        }),
+        (arb_arc(), arb_circle())
+            .prop_map(|(arc, circle)| Constraint::ArcCircleEqualRadius(arc, circle)),
+        (arb_arc(), arb_arc()).prop_map(|(arc0, arc1)| Constraint::Concyclic(arc0, arc1)),
+        (arb_point(), arb_point(), arb_point(), arb_point())
+            .prop_map(|(center, a, b, c)| Constraint::Circumcenter(center, a, b, c)),
+        (arb_arc(), arb_scalar()).prop_map(|(arc, sagitta)| Constraint::ArcSagitta(arc, sagitta)),
+        (arb_arc(), arb_scalar()).prop_map(|(arc, area)| Constraint::ArcSegmentArea(arc, area)),
+        (arb_point(), arb_point(), arb_point())
+            .prop_map(|(common, a, b)| Constraint::EqualLengthToCommon(common, a, b)),
+        (arb_point(), arb_scalar(), arb_scalar(), arb_scalar())
+            .prop_map(|(p, x, y, radius)| { Constraint::AnchorNear(p, Point { x, y }, radius) }),
+        (arb_point(), arb_scalar(), arb_scalar(), arb_scalar())
+            .prop_map(|(p, spacing, x, y)| { Constraint::OnGrid(p, spacing, Point { x, y }) }),
+        (arb_point(), arb_point(), arb_point(), arb_scalar())
+            .prop_map(|(p, f0, f1, sum)| Constraint::DistanceSum(p, f0, f1, sum)),
+        (arb_line(), arb_line(), arb_circle())
+            .prop_map(|(line0, line1, circle)| Constraint::FilletRadius(line0, line1, circle)),
+        (arb_point(), arb_circle())
+            .prop_map(|(point, circle)| Constraint::PointOnCircle(point, circle)),
+        (arb_point(), arb_line()).prop_map(|(point, line)| Constraint::PointOnLine(point, line)),
+        (arb_point(), arb_line(), arb_circle()).prop_map(|(point, line, circle)| {
+            Constraint::LineCircleIntersection(point, line, circle)
+        }),
+        (arb_point(), arb_point(), arb_point())
+            .prop_map(|(a, b, c)| Constraint::Collinear(a, b, c)),
+        (arb_line(), arb_circle(), arb_circle(), arb_tangency_kind()).prop_map(
+            |(line, circle0, circle1, kind)| Constraint::CommonTangent(
+                line, circle0, circle1, kind
+            )
+        ),
+        (arb_arc(), arb_point()).prop_map(|(arc, target)| Constraint::ArcMidpoint(arc, target)),
+        (arb_line(), arb_angle()).prop_map(|(line, angle)| Constraint::LineAngle(line, angle)),
+        (arb_line(), arb_line(), arb_line(), arb_line())
+            .prop_map(|(a, b, c, d)| Constraint::EqualAngle(a, b, c, d)),
+        (arb_line(), arb_arc(), arb_endpoint())
+            .prop_map(|(line, arc, end)| Constraint::LineArcTangent(line, arc, end)),
+        (arb_arc(), arb_angle()).prop_map(|(arc, angle)| Constraint::ArcChordAngle(arc, angle)),
+        (arb_arc(), arb_arc()).prop_map(|(arc0, arc1)| Constraint::ReverseTangentArcs(arc0, arc1)),
+        (
+            arb_id(),
+            arb_id(),
+            arb_scalar(),
+            arb_id(),
+            arb_scalar(),
+            arb_scalar()
+        )
+            .prop_map(
+                |(result, term0, coefficient0, term1, coefficient1, constant)| {
+                    Constraint::DistanceLinearCombo(
+                        result,
+                        [(term0, coefficient0), (term1, coefficient1)],
+                        constant,
+                    )
+                }
+            ),
+        (arb_line(), arb_line(), arb_line())
+            .prop_map(|(bisector, a, b)| Constraint::AngleBisectorLine(bisector, a, b)),
+        (arb_line(), arb_line(), arb_line())
+            .prop_map(|(reference, a, b)| Constraint::SymmetricAngle(reference, a, b)),
+        (arb_circle(), arb_id(), arb_scalar())
+            .prop_map(|(circle, scalar, ratio)| Constraint::RadiusRatio(circle, scalar, ratio)),
+        (arb_point(), arb_line(), arb_point()).prop_map(|(point, directrix, focus)| {
+            Constraint::EquidistantLinePoint(point, directrix, focus)
+        }),
+        (arb_line(), arb_circle(), arb_angle())
+            .prop_map(|(line, circle, angle)| Constraint::TangentAtAngle(line, circle, angle)),
+        (arb_line(), arb_line(), arb_scalar())
+            .prop_map(|(a, b, offset)| Constraint::ParallelOffset(a, b, offset)),
+        (arb_line(), arb_line(), arb_id())
+            .prop_map(|(a, b, scalar)| Constraint::ParallelOffsetScalar(a, b, scalar)),
+        (arb_line(), arb_line(), arb_id())
+            .prop_map(|(a, b, scalar)| Constraint::AngleEqualsScalar(a, b, scalar)),
+        (arb_arc(), arb_id())
+            .prop_map(|(arc, scalar)| Constraint::ArcCentralAngleScalar(arc, scalar)),
+        (arb_point(), arb_point(), arb_point(), arb_angle())
+            .prop_map(|(p, a, b, angle)| Constraint::SubtendsAngle(p, a, b, angle)),
     ]
     .boxed()
 }
@@ -152,9 +277,31 @@ fn scale_constraint(c: &Constraint, k: f64) -> Constraint {
         CircleRadius(circle, r) => CircleRadius(circle, r * k),
         ArcRadius(arc, r) => ArcRadius(arc, r * k),
         ArcLength(arc, d) => ArcLength(arc, d * k),
+        ArcSagitta(arc, s) => ArcSagitta(arc, s * k),
+        ArcSegmentArea(arc, area) => ArcSegmentArea(arc, area * k * k),
         PointLineDistance(p, l, d) => PointLineDistance(p, l, d * k),
         VerticalPointLineDistance(p, l, d) => VerticalPointLineDistance(p, l, d * k),
         HorizontalPointLineDistance(p, l, d) => HorizontalPointLineDistance(p, l, d * k),
+        PolarOffset(from, to, r, theta) => PolarOffset(from, to, r * k, theta),
+        ParallelOffset(a, b, offset) => ParallelOffset(a, b, offset * k),
+        MinDistance(p0, p1, d) => MinDistance(p0, p1, d * k),
+        DistanceSum(p, f0, f1, sum) => DistanceSum(p, f0, f1, sum * k),
+        OnGrid(p, spacing, origin) => OnGrid(
+            p,
+            spacing * k,
+            Point {
+                x: origin.x * k,
+                y: origin.y * k,
+            },
+        ),
+        AnchorNear(p, anchor, r) => AnchorNear(
+            p,
+            Point {
+                x: anchor.x * k,
+                y: anchor.y * k,
+            },
+            r * k,
+        ),
         other => other,
     }
 }
@@ -198,7 +345,7 @@ proptest! {
         let layout = Layout::new(
             &(0..n as Id).collect::<Vec<_>>(),
             &[&constraint],
-            Config::default(),
+            &Config::default(),
         );
 
         let (mut row0, mut row1, mut row2) = (Vec::new(), Vec::new(), Vec::new());
@@ -254,7 +401,7 @@ proptest! {
         let layout = Layout::new(
             &(0..n as Id).collect::<Vec<_>>(),
             &[&constraint],
-            Config::default(),
+            &Config::default(),
         );
 
         let jac = |c: &Constraint, v: &[f64]| {
@@ -264,6 +411,25 @@ proptest! {
             (r0, r1, r2, degenerate)
         };
 
+        // A general implicit conic mixes terms of every degree from 0 to 3 in its variables, so no
+        // choice of units makes it homogeneous. A scaled copy's scale factor, an infinite
+        // line's normal angle and a driving angle scalar are dimensionless, so rescaling them
+        // along with the lengths changes the shape of the problem.
+        // Collinearity's and both arc tangencies' residuals are areas, which are homogeneous
+        // but of degree 2.
+        prop_assume!(!matches!(
+            constraint,
+            Constraint::PointOnConic(..)
+                | Constraint::Collinear(..)
+                | Constraint::LineArcTangent(..)
+                | Constraint::ReverseTangentArcs(..)
+                | Constraint::ScaledCopy(..)
+                | Constraint::PointInfiniteLineDistance(..)
+                | Constraint::InfiniteLineTangentToCircle(..)
+                | Constraint::AngleEqualsScalar(..)
+                | Constraint::ArcCentralAngleScalar(..)
+        ));
+
         let (a0, a1, a2, deg_a) = jac(&constraint, &vals);
         prop_assume!(!deg_a);
 
@@ -328,6 +494,61 @@ proptest! {
         assert!(solved.unsatisfied.is_empty());
     }
 
+    #[test]
+    fn equal_angle(
+        first_direction in -3.0..3.0f64,
+        wedge in 0.2..2.5f64,
+        second_direction in -3.0..3.0f64,
+        guess_offset in -1.0..1.0f64,
+    ) {
+        // The first wedge is fixed, between lines from the origin to A and B.
+        // The second wedge starts from the fixed line CD, and its other line CE is free
+        // except for always being 5 long.
+        let mut ids = IdGenerator::default();
+        let [o, a, b, c, d, e] = std::array::from_fn(|_| DatumPoint::new(&mut ids));
+        let polar = |(x, y): (f64, f64), angle: f64| {
+            (x + 5.0 * libm::cos(angle), y + 5.0 * libm::sin(angle))
+        };
+        let c_at = (10.0, 0.0);
+        let fixed = [
+            (o, (0.0, 0.0)),
+            (a, polar((0.0, 0.0), first_direction)),
+            (b, polar((0.0, 0.0), first_direction + wedge)),
+            (c, c_at),
+            (d, polar(c_at, second_direction)),
+        ];
+        let mut requests = Vec::with_capacity(2 * fixed.len() + 2);
+        let mut initial_guesses = Vec::with_capacity(12);
+        for (point, (x, y)) in fixed {
+            requests.push(ConstraintRequest::highest_priority(Constraint::Fixed(point.id_x(), x)));
+            requests.push(ConstraintRequest::highest_priority(Constraint::Fixed(point.id_y(), y)));
+            initial_guesses.extend([(point.id_x(), x), (point.id_y(), y)]);
+        }
+        let (ex, ey) = polar(c_at, second_direction + wedge + guess_offset);
+        initial_guesses.extend([(e.id_x(), ex), (e.id_y(), ey)]);
+        requests.extend([
+            Constraint::Distance(c, e, 5.0),
+            Constraint::EqualAngle(
+                DatumLineSegment::new(o, a),
+                DatumLineSegment::new(o, b),
+                DatumLineSegment::new(c, d),
+                DatumLineSegment::new(c, e),
+            ),
+        ].map(ConstraintRequest::highest_priority));
+
+        let outcome = solve(&requests, initial_guesses, Config::default())
+            .expect("this constraint system should converge and be solvable");
+        assert!(outcome.is_satisfied(), "the wedges should have equalized");
+        // Invariant: the second wedge is as wide as the first.
+        let e_solved = outcome.final_value_point(&e);
+        let actual_direction = libm::atan2(e_solved.y - c_at.1, e_solved.x - c_at.0);
+        let error = (actual_direction - second_direction - wedge).rem_euclid(2.0 * PI);
+        assert!(
+            !(1e-4..=2.0 * PI - 1e-4).contains(&error),
+            "wedge was off by {error}"
+        );
+    }
+
     #[test]
     fn scalar_eq(
         guess_x in -10.0..10.0,
@@ -790,7 +1011,7 @@ fn make_distance_var_constraint(
     let constraint = Constraint::DistanceVar(p, q, dist);
     let all_variables = vec![p.id_x(), p.id_y(), q.id_x(), q.id_y(), dist.id];
     let constraints = [&constraint];
-    let layout = Layout::new(&all_variables, constraints.as_slice(), Config::default());
+    let layout = Layout::new(&all_variables, constraints.as_slice(), &Config::default());
 
     let mut current_assignments = vec![0.0; dist.id as usize + 1];
     current_assignments[p.id_x() as usize] = px;
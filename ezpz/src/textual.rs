@@ -8,9 +8,10 @@ use std::str::FromStr;
 pub use executor::ConstraintSystem;
 pub use executor::Outcome;
 pub use executor::OutcomeAnalysis;
-use instruction::Instruction;
+use instruction::Statement;
 use winnow::Parser;
 
+use crate::TextualError;
 use crate::datatypes::outputs::Point;
 use crate::textual::parser::parse_problem;
 
@@ -31,20 +32,65 @@ pub struct ScalarGuess {
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub struct Problem {
-    instructions: Vec<Instruction>,
+    instructions: Vec<Statement>,
     inner_points: Vec<Label>,
     inner_circles: Vec<Label>,
     inner_arcs: Vec<Label>,
     inner_lines: Vec<(Label, Label)>,
+    inner_scalars: Vec<Label>,
     point_guesses: Vec<PointGuess>,
     scalar_guesses: Vec<ScalarGuess>,
 }
 
 impl FromStr for Problem {
-    type Err = String;
+    type Err = TextualError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        parse_problem.parse(s).map_err(|e| e.to_string())
+        parse_problem
+            .parse(s)
+            .map_err(|e| unexpected_token(s, e.offset()))
+    }
+}
+
+/// The error for a parse which failed at this byte offset into `s`.
+/// The token runs until the next whitespace or punctuation, so an editor can underline it.
+fn unexpected_token(s: &str, offset: usize) -> TextualError {
+    let rest = &s[offset..];
+    let token_len = rest
+        .char_indices()
+        .find(|&(_, c)| c.is_whitespace() || matches!(c, '(' | ')' | ','))
+        .map_or(rest.len(), |(i, _)| i);
+    // If the parse failed on punctuation, underline just that.
+    let token_len = match rest.chars().next() {
+        Some(c) if token_len == 0 && !c.is_whitespace() => c.len_utf8(),
+        _ => token_len,
+    };
+    let before = &s[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    TextualError::UnexpectedToken {
+        span: offset..offset + token_len,
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Writes the problem out in the ezpz text format, so that parsing the output
+/// gives back an equivalent problem. Useful for regenerating canonical problem text.
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# constraints")?;
+        for statement in &self.instructions {
+            writeln!(f, "{statement}")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "# guesses")?;
+        for PointGuess { point, guess } in &self.point_guesses {
+            writeln!(f, "{point} roughly ({}, {})", guess.x, guess.y)?;
+        }
+        for ScalarGuess { scalar, guess } in &self.scalar_guesses {
+            writeln!(f, "{scalar} roughly {guess}")?;
+        }
+        Ok(())
     }
 }
 
@@ -53,6 +99,12 @@ impl FromStr for Problem {
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub struct Label(String);
 
+impl std::fmt::Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 impl From<&str> for Label {
     fn from(value: &str) -> Self {
         Self(value.to_owned())
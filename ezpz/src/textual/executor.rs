@@ -30,7 +30,6 @@ use crate::textual::geometry_variables::PointsState;
 use crate::textual::geometry_variables::VARS_PER_ARC;
 use crate::textual::instruction::*;
 
-use super::Instruction;
 use super::Problem;
 
 impl Problem {
@@ -106,6 +105,15 @@ impl Problem {
             };
             initial_guesses.push_arc(&mut id_generator, a_guess, b_guess, center_guess);
         }
+        let mut initial_guesses = initial_guesses.done();
+        for scalar in &self.inner_scalars {
+            let Some(guess) = guessmap_scalars.remove(&scalar.0) else {
+                return Err(TextualError::MissingGuess {
+                    label: scalar.0.clone(),
+                });
+            };
+            initial_guesses.push_scalar(&mut id_generator, guess);
+        }
         if !guessmap_points.is_empty() {
             let labels: Vec<String> = guessmap_points.keys().cloned().collect();
             return Err(TextualError::UnusedGuesses { labels });
@@ -181,16 +189,27 @@ impl Problem {
                 let ids = initial_guesses.circle_ids(circle_id);
                 return Ok(DatumDistance { id: ids.radius });
             }
+            if let Some(scalar_id) = self.inner_scalars.iter().position(|s| s == label) {
+                let id = initial_guesses.scalar_id(scalar_id);
+                return Ok(DatumDistance { id });
+            }
             Err(TextualError::UndefinedPoint {
                 label: label.0.clone(),
             })
         };
 
-        for instr in &self.instructions {
+        // The priority of each constraint, from the statement which added it.
+        let mut priorities = Vec::new();
+        for Statement {
+            instruction: instr,
+            priority,
+        } in &self.instructions
+        {
             match instr {
                 Instruction::DeclarePoint(_) => {}
                 Instruction::DeclareCircle(_) => {}
                 Instruction::DeclareArc(_) => {}
+                Instruction::DeclareScalar(_) => {}
                 Instruction::Line(_) => {}
                 Instruction::CircleRadius(CircleRadius { circle, radius }) => {
                     let circ = &circle.0;
@@ -204,6 +223,19 @@ impl Problem {
                         *radius,
                     ));
                 }
+                Instruction::RadiusRatio(RadiusRatio {
+                    circle,
+                    scalar,
+                    ratio,
+                }) => {
+                    let circ = &circle.0;
+                    let circle = datatypes::inputs::DatumCircle {
+                        center: datum_point_for_label(&Label(format!("{circ}.center")))?,
+                        radius: datum_distance_for_label(&Label(format!("{circ}.radius")))?,
+                    };
+                    let scalar = datum_distance_for_label(scalar)?;
+                    constraints.push(Constraint::RadiusRatio(circle, scalar.id, *ratio));
+                }
                 Instruction::ArcRadius(ArcRadius { arc_label, radius }) => {
                     let arc_label = &arc_label.0;
                     let circular_arc = DatumCircularArc {
@@ -213,6 +245,34 @@ impl Problem {
                     };
                     constraints.push(Constraint::ArcRadius(circular_arc, *radius));
                 }
+                Instruction::ArcCircleEqualRadius(ArcCircleEqualRadius { arc, circle }) => {
+                    let arc = &arc.0;
+                    let circular_arc = DatumCircularArc {
+                        center: datum_point_for_label(&Label(format!("{arc}.center")))?,
+                        start: datum_point_for_label(&Label(format!("{arc}.a")))?,
+                        end: datum_point_for_label(&Label(format!("{arc}.b")))?,
+                    };
+                    let circ = &circle.0;
+                    let circle = datatypes::inputs::DatumCircle {
+                        center: datum_point_for_label(&Label(format!("{circ}.center")))?,
+                        radius: datum_distance_for_label(&Label(format!("{circ}.radius")))?,
+                    };
+                    constraints.push(Constraint::ArcCircleEqualRadius(circular_arc, circle));
+                }
+                Instruction::Concyclic(Concyclic { arc0, arc1 }) => {
+                    let datum_arc_for_label = |arc: &Label| -> Result<_, TextualError> {
+                        let arc = &arc.0;
+                        Ok(DatumCircularArc {
+                            center: datum_point_for_label(&Label(format!("{arc}.center")))?,
+                            start: datum_point_for_label(&Label(format!("{arc}.a")))?,
+                            end: datum_point_for_label(&Label(format!("{arc}.b")))?,
+                        })
+                    };
+                    constraints.push(Constraint::Concyclic(
+                        datum_arc_for_label(arc0)?,
+                        datum_arc_for_label(arc1)?,
+                    ));
+                }
                 Instruction::IsArc(IsArc { arc_label }) => {
                     let arc_label = &arc_label.0;
                     let circular_arc = DatumCircularArc {
@@ -287,6 +347,10 @@ impl Problem {
                         });
                     }
                 }
+                Instruction::FixPoint(FixPoint { point, value }) => {
+                    let point = datum_point_for_label(point)?;
+                    constraints.push(Constraint::FixedPoint(point, value.x, value.y));
+                }
                 Instruction::FixCenterPointComponent(FixCenterPointComponent {
                     object,
                     center_component,
@@ -422,16 +486,477 @@ impl Problem {
                     };
                     constraints.push(Constraint::ArcLength(circular_arc, length));
                 }
+                Instruction::EqualLengthTo(EqualLengthTo { common, a, b }) => {
+                    constraints.push(Constraint::EqualLengthToCommon(
+                        datum_point_for_label(common)?,
+                        datum_point_for_label(a)?,
+                        datum_point_for_label(b)?,
+                    ));
+                }
+                Instruction::BisectorLine(BisectorLine {
+                    bisector: (bis0, bis1),
+                    a: (a0, a1),
+                    b: (b0, b1),
+                }) => {
+                    let p0 = datum_point_for_label(bis0)?;
+                    let p1 = datum_point_for_label(bis1)?;
+                    let p2 = datum_point_for_label(a0)?;
+                    let p3 = datum_point_for_label(a1)?;
+                    let p4 = datum_point_for_label(b0)?;
+                    let p5 = datum_point_for_label(b1)?;
+                    constraints.push(Constraint::AngleBisectorLine(
+                        DatumLineSegment { p0, p1 },
+                        DatumLineSegment { p0: p2, p1: p3 },
+                        DatumLineSegment { p0: p4, p1: p5 },
+                    ));
+                }
+                Instruction::EqualAngle(EqualAngle { a, b, c, d }) => {
+                    let [a, b, c, d] = [a, b, c, d].map(|(p0, p1)| {
+                        Ok::<_, TextualError>(DatumLineSegment {
+                            p0: datum_point_for_label(p0)?,
+                            p1: datum_point_for_label(p1)?,
+                        })
+                    });
+                    constraints.push(Constraint::EqualAngle(a?, b?, c?, d?));
+                }
+                Instruction::SymmetricAngle(SymmetricAngle {
+                    reference: (r0, r1),
+                    a: (a0, a1),
+                    b: (b0, b1),
+                }) => {
+                    let p0 = datum_point_for_label(r0)?;
+                    let p1 = datum_point_for_label(r1)?;
+                    let p2 = datum_point_for_label(a0)?;
+                    let p3 = datum_point_for_label(a1)?;
+                    let p4 = datum_point_for_label(b0)?;
+                    let p5 = datum_point_for_label(b1)?;
+                    constraints.push(Constraint::SymmetricAngle(
+                        DatumLineSegment { p0, p1 },
+                        DatumLineSegment { p0: p2, p1: p3 },
+                        DatumLineSegment { p0: p4, p1: p5 },
+                    ));
+                }
+                Instruction::LengthRatioBounds(LengthRatioBounds {
+                    line0: (p0, p1),
+                    line1: (p2, p3),
+                    min_ratio,
+                    max_ratio,
+                }) => {
+                    let p0 = datum_point_for_label(p0)?;
+                    let p1 = datum_point_for_label(p1)?;
+                    let p2 = datum_point_for_label(p2)?;
+                    let p3 = datum_point_for_label(p3)?;
+                    constraints.push(Constraint::LengthRatioBounds(
+                        DatumLineSegment { p0, p1 },
+                        DatumLineSegment { p0: p2, p1: p3 },
+                        *min_ratio,
+                        *max_ratio,
+                    ));
+                }
+                Instruction::EquidistantLinePoint(EquidistantLinePoint {
+                    point,
+                    directrix: (p0, p1),
+                    focus,
+                }) => {
+                    let point = datum_point_for_label(point)?;
+                    let p0 = datum_point_for_label(p0)?;
+                    let p1 = datum_point_for_label(p1)?;
+                    let focus = datum_point_for_label(focus)?;
+                    constraints.push(Constraint::EquidistantLinePoint(
+                        point,
+                        DatumLineSegment { p0, p1 },
+                        focus,
+                    ));
+                }
+                Instruction::Homothety(Homothety {
+                    center,
+                    original,
+                    image,
+                    ratio,
+                }) => {
+                    constraints.push(Constraint::Homothety(
+                        datum_point_for_label(center)?,
+                        datum_point_for_label(original)?,
+                        datum_point_for_label(image)?,
+                        *ratio,
+                    ));
+                }
+                Instruction::Subtends(Subtends { point, a, b, angle }) => {
+                    constraints.push(Constraint::SubtendsAngle(
+                        datum_point_for_label(point)?,
+                        datum_point_for_label(a)?,
+                        datum_point_for_label(b)?,
+                        *angle,
+                    ));
+                }
+                Instruction::TangentAtAngle(TangentAtAngle {
+                    line: (p0, p1),
+                    circle,
+                    angle,
+                }) => {
+                    let circ = &circle.0;
+                    let center = datum_point_for_label(&Label(format!("{circ}.center")))?;
+                    let radius = datum_distance_for_label(&Label(format!("{circ}.radius")))?;
+                    let p0 = datum_point_for_label(p0)?;
+                    let p1 = datum_point_for_label(p1)?;
+                    constraints.push(Constraint::TangentAtAngle(
+                        DatumLineSegment { p0, p1 },
+                        datatypes::inputs::DatumCircle { center, radius },
+                        *angle,
+                    ));
+                }
+                Instruction::LineAngle(LineAngle {
+                    line: (p0, p1),
+                    angle,
+                }) => {
+                    let line = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    constraints.push(Constraint::LineAngle(line, *angle));
+                }
+                Instruction::AngleEqualsScalar(AngleEqualsScalar {
+                    line0: (p0, p1),
+                    line1: (p2, p3),
+                    scalar,
+                }) => {
+                    let line0 = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let line1 = DatumLineSegment {
+                        p0: datum_point_for_label(p2)?,
+                        p1: datum_point_for_label(p3)?,
+                    };
+                    let scalar = datum_distance_for_label(scalar)?;
+                    constraints.push(Constraint::AngleEqualsScalar(line0, line1, scalar.id));
+                }
+                Instruction::ParallelOffset(ParallelOffset {
+                    line0: (p0, p1),
+                    line1: (p2, p3),
+                    offset,
+                }) => {
+                    let line0 = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let line1 = DatumLineSegment {
+                        p0: datum_point_for_label(p2)?,
+                        p1: datum_point_for_label(p3)?,
+                    };
+                    constraints.push(Constraint::ParallelOffset(line0, line1, *offset));
+                }
+                Instruction::OnGrid(OnGrid {
+                    point,
+                    spacing,
+                    origin,
+                }) => {
+                    constraints.push(Constraint::OnGrid(
+                        datum_point_for_label(point)?,
+                        *spacing,
+                        *origin,
+                    ));
+                }
+                Instruction::TangentCircles(TangentCircles {
+                    circle0,
+                    circle1,
+                    side,
+                }) => {
+                    let datum_circle_for_label = |circle: &Label| -> Result<_, TextualError> {
+                        let circle = &circle.0;
+                        Ok(datatypes::inputs::DatumCircle {
+                            center: datum_point_for_label(&Label(format!("{circle}.center")))?,
+                            radius: datum_distance_for_label(&Label(format!("{circle}.radius")))?,
+                        })
+                    };
+                    constraints.push(Constraint::CircleTangentToCircle(
+                        datum_circle_for_label(circle0)?,
+                        datum_circle_for_label(circle1)?,
+                        *side,
+                    ));
+                }
+                Instruction::Fillet(Fillet {
+                    line0: (p0, p1),
+                    line1: (p2, p3),
+                    circle,
+                }) => {
+                    let line0 = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let line1 = DatumLineSegment {
+                        p0: datum_point_for_label(p2)?,
+                        p1: datum_point_for_label(p3)?,
+                    };
+                    let circ = &circle.0;
+                    let circle = datatypes::inputs::DatumCircle {
+                        center: datum_point_for_label(&Label(format!("{circ}.center")))?,
+                        radius: datum_distance_for_label(&Label(format!("{circ}.radius")))?,
+                    };
+                    constraints.push(Constraint::FilletRadius(line0, line1, circle));
+                }
+                Instruction::PointOnCircle(PointOnCircle { point, circle }) => {
+                    let circ = &circle.0;
+                    let circle = datatypes::inputs::DatumCircle {
+                        center: datum_point_for_label(&Label(format!("{circ}.center")))?,
+                        radius: datum_distance_for_label(&Label(format!("{circ}.radius")))?,
+                    };
+                    constraints.push(Constraint::PointOnCircle(
+                        datum_point_for_label(point)?,
+                        circle,
+                    ));
+                }
+                Instruction::PointOnLine(PointOnLine {
+                    point,
+                    line: (p0, p1),
+                }) => {
+                    let line = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    constraints.push(Constraint::PointOnLine(datum_point_for_label(point)?, line));
+                }
+                Instruction::LineCircleIntersection(LineCircleIntersection {
+                    point,
+                    line: (p0, p1),
+                    circle,
+                }) => {
+                    let line = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let circ = &circle.0;
+                    let circle = datatypes::inputs::DatumCircle {
+                        center: datum_point_for_label(&Label(format!("{circ}.center")))?,
+                        radius: datum_distance_for_label(&Label(format!("{circ}.radius")))?,
+                    };
+                    constraints.push(Constraint::LineCircleIntersection(
+                        datum_point_for_label(point)?,
+                        line,
+                        circle,
+                    ));
+                }
+                Instruction::ArcCentralAngleScalar(ArcCentralAngleScalar { arc, scalar }) => {
+                    let arc_label = &arc.0;
+                    let circular_arc = DatumCircularArc {
+                        center: datum_point_for_label(&Label(format!("{arc_label}.center")))?,
+                        start: datum_point_for_label(&Label(format!("{arc_label}.a")))?,
+                        end: datum_point_for_label(&Label(format!("{arc_label}.b")))?,
+                    };
+                    let scalar = datum_distance_for_label(scalar)?;
+                    constraints.push(Constraint::ArcCentralAngleScalar(circular_arc, scalar.id));
+                }
+                Instruction::CommonTangent(CommonTangent {
+                    line: (p0, p1),
+                    circle0,
+                    circle1,
+                    kind,
+                }) => {
+                    let line = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let datum_circle_for_label = |circle: &Label| -> Result<_, TextualError> {
+                        let circle = &circle.0;
+                        Ok(datatypes::inputs::DatumCircle {
+                            center: datum_point_for_label(&Label(format!("{circle}.center")))?,
+                            radius: datum_distance_for_label(&Label(format!("{circle}.radius")))?,
+                        })
+                    };
+                    constraints.push(Constraint::CommonTangent(
+                        line,
+                        datum_circle_for_label(circle0)?,
+                        datum_circle_for_label(circle1)?,
+                        *kind,
+                    ));
+                }
+                Instruction::DistanceSum(DistanceSum {
+                    point,
+                    focus0,
+                    focus1,
+                    sum,
+                }) => {
+                    constraints.push(Constraint::DistanceSum(
+                        datum_point_for_label(point)?,
+                        datum_point_for_label(focus0)?,
+                        datum_point_for_label(focus1)?,
+                        *sum,
+                    ));
+                }
+                Instruction::ParallelOffsetScalar(ParallelOffsetScalar {
+                    line0: (p0, p1),
+                    line1: (p2, p3),
+                    offset,
+                }) => {
+                    let line0 = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let line1 = DatumLineSegment {
+                        p0: datum_point_for_label(p2)?,
+                        p1: datum_point_for_label(p3)?,
+                    };
+                    let offset = datum_distance_for_label(offset)?;
+                    constraints.push(Constraint::ParallelOffsetScalar(line0, line1, offset.id));
+                }
+                Instruction::Circumscribed(Circumscribed { circle, vertices }) => {
+                    let circ = &circle.0;
+                    let circle = datatypes::inputs::DatumCircle {
+                        center: datum_point_for_label(&Label(format!("{circ}.center")))?,
+                        radius: datum_distance_for_label(&Label(format!("{circ}.radius")))?,
+                    };
+                    let vertices = vertices
+                        .iter()
+                        .map(datum_point_for_label)
+                        .collect::<Result<Vec<_>, _>>()?;
+                    constraints.extend(Constraint::circumscribed_circle(circle, &vertices));
+                }
+                Instruction::Sagitta(Sagitta { arc, distance }) => {
+                    let arc_label = &arc.0;
+                    let circular_arc = DatumCircularArc {
+                        center: datum_point_for_label(&Label(format!("{arc_label}.center")))?,
+                        start: datum_point_for_label(&Label(format!("{arc_label}.a")))?,
+                        end: datum_point_for_label(&Label(format!("{arc_label}.b")))?,
+                    };
+                    constraints.push(Constraint::ArcSagitta(circular_arc, *distance));
+                }
+                Instruction::LineArcTangent(LineArcTangent {
+                    line: (p0, p1),
+                    arc,
+                    end,
+                }) => {
+                    let line = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let arc_label = &arc.0;
+                    let circular_arc = DatumCircularArc {
+                        center: datum_point_for_label(&Label(format!("{arc_label}.center")))?,
+                        start: datum_point_for_label(&Label(format!("{arc_label}.a")))?,
+                        end: datum_point_for_label(&Label(format!("{arc_label}.b")))?,
+                    };
+                    constraints.push(Constraint::LineArcTangent(line, circular_arc, *end));
+                }
+                Instruction::ArcChordAngle(ArcChordAngle { arc, angle }) => {
+                    let arc_label = &arc.0;
+                    let circular_arc = DatumCircularArc {
+                        center: datum_point_for_label(&Label(format!("{arc_label}.center")))?,
+                        start: datum_point_for_label(&Label(format!("{arc_label}.a")))?,
+                        end: datum_point_for_label(&Label(format!("{arc_label}.b")))?,
+                    };
+                    constraints.push(Constraint::ArcChordAngle(circular_arc, *angle));
+                }
+                Instruction::ReverseTangentArcs(ReverseTangentArcs { arc0, arc1 }) => {
+                    let datum_arc_for_label = |arc: &Label| -> Result<_, TextualError> {
+                        let arc = &arc.0;
+                        Ok(DatumCircularArc {
+                            center: datum_point_for_label(&Label(format!("{arc}.center")))?,
+                            start: datum_point_for_label(&Label(format!("{arc}.a")))?,
+                            end: datum_point_for_label(&Label(format!("{arc}.b")))?,
+                        })
+                    };
+                    constraints.push(Constraint::ReverseTangentArcs(
+                        datum_arc_for_label(arc0)?,
+                        datum_arc_for_label(arc1)?,
+                    ));
+                }
+                Instruction::LinearCombo(LinearCombo {
+                    result,
+                    terms: [(term0, coefficient0), (term1, coefficient1)],
+                    constant,
+                }) => {
+                    constraints.push(Constraint::DistanceLinearCombo(
+                        datum_distance_for_label(result)?.id,
+                        [
+                            (datum_distance_for_label(term0)?.id, *coefficient0),
+                            (datum_distance_for_label(term1)?.id, *coefficient1),
+                        ],
+                        *constant,
+                    ));
+                }
+                Instruction::ArcMidpoint(ArcMidpoint { arc, midpoint }) => {
+                    let arc_label = &arc.0;
+                    let circular_arc = DatumCircularArc {
+                        center: datum_point_for_label(&Label(format!("{arc_label}.center")))?,
+                        start: datum_point_for_label(&Label(format!("{arc_label}.a")))?,
+                        end: datum_point_for_label(&Label(format!("{arc_label}.b")))?,
+                    };
+                    constraints.push(Constraint::ArcMidpoint(
+                        circular_arc,
+                        datum_point_for_label(midpoint)?,
+                    ));
+                }
+                Instruction::ArcSegmentArea(ArcSegmentArea { arc, area }) => {
+                    let arc_label = &arc.0;
+                    let circular_arc = DatumCircularArc {
+                        center: datum_point_for_label(&Label(format!("{arc_label}.center")))?,
+                        start: datum_point_for_label(&Label(format!("{arc_label}.a")))?,
+                        end: datum_point_for_label(&Label(format!("{arc_label}.b")))?,
+                    };
+                    constraints.push(Constraint::ArcSegmentArea(circular_arc, *area));
+                }
+                Instruction::Polar(Polar {
+                    from,
+                    to,
+                    distance,
+                    angle,
+                }) => {
+                    let from = datum_point_for_label(from)?;
+                    let to = datum_point_for_label(to)?;
+                    constraints.push(Constraint::PolarOffset(from, to, *distance, *angle));
+                }
+                Instruction::FixScalar(FixScalar { scalar, value }) => {
+                    let Some(scalar_id) = self.inner_scalars.iter().position(|s| s == scalar)
+                    else {
+                        return Err(TextualError::UndefinedPoint {
+                            label: scalar.0.clone(),
+                        });
+                    };
+                    let id = initial_guesses.scalar_id(scalar_id);
+                    constraints.push(Constraint::Fixed(id, *value));
+                }
+                Instruction::ScaledCopy(ScaledCopy {
+                    pivot,
+                    scale,
+                    original,
+                    copy,
+                }) => {
+                    let pivot = datum_point_for_label(pivot)?;
+                    let scale = datum_distance_for_label(scale)?;
+                    let original = datum_point_for_label(original)?;
+                    let copy = datum_point_for_label(copy)?;
+                    constraints.push(Constraint::ScaledCopy(pivot, scale.id, original, copy));
+                }
+                Instruction::Circumcenter(Circumcenter { center, a, b, c }) => {
+                    constraints.push(Constraint::Circumcenter(
+                        datum_point_for_label(center)?,
+                        datum_point_for_label(a)?,
+                        datum_point_for_label(b)?,
+                        datum_point_for_label(c)?,
+                    ));
+                }
+                Instruction::SameSide(SameSide {
+                    line: (p0, p1),
+                    reference,
+                    point,
+                }) => {
+                    let line = DatumLineSegment {
+                        p0: datum_point_for_label(p0)?,
+                        p1: datum_point_for_label(p1)?,
+                    };
+                    let reference = datum_point_for_label(reference)?;
+                    let point = datum_point_for_label(point)?;
+                    constraints.push(Constraint::SameSideOfLine(line, reference, point));
+                }
             }
+            priorities.resize(constraints.len(), *priority);
         }
         let initial_guesses = initial_guesses.done();
 
-        // At some point, the textual format should support setting priority.
-        // For now, set it to max priority.
-        let priority = 0;
         let constraints = constraints
             .into_iter()
-            .map(|c| ConstraintRequest::new(c, priority))
+            .zip(priorities)
+            .map(|(c, priority)| ConstraintRequest::new(c, priority))
             .collect();
 
         Ok(ConstraintSystem {
@@ -441,6 +966,7 @@ impl Problem {
             inner_circles: &self.inner_circles,
             inner_arcs: &self.inner_arcs,
             inner_lines: &self.inner_lines,
+            inner_scalars: &self.inner_scalars,
         })
     }
 }
@@ -456,6 +982,7 @@ pub struct ConstraintSystem<'a> {
     inner_circles: &'a [Label],
     inner_arcs: &'a [Label],
     inner_lines: &'a [(Label, Label)],
+    inner_scalars: &'a [Label],
 }
 
 impl ConstraintSystem<'_> {
@@ -464,6 +991,11 @@ impl ConstraintSystem<'_> {
         crate::solve(&self.constraints, self.initial_guesses.variables(), config)
     }
 
+    /// Initial guesses for every variable, as given in the text input.
+    pub fn initial_guesses(&self) -> Vec<(crate::Id, f64)> {
+        self.initial_guesses.variables()
+    }
+
     fn solve_no_metadata_inner<A: Analysis>(
         &self,
         config: Config,
@@ -472,9 +1004,23 @@ impl ConstraintSystem<'_> {
             &self.constraints,
             self.initial_guesses.variables(),
             config,
+            &[],
+            None,
+            None,
         )
     }
 
+    /// Serialize this system, so it can be solved again exactly with [`crate::replay`].
+    #[cfg(feature = "serde")]
+    pub fn dump_repro(&self, config: Config) -> Result<String, crate::ReproError> {
+        crate::dump_repro(&self.constraints, &self.initial_guesses.variables(), config)
+    }
+
+    /// Solve, and gather everything about the solve into one report with [`crate::diagnostic_report`].
+    pub fn diagnostic_report(&self, config: Config) -> crate::DiagnosticReport {
+        crate::diagnostic_report(&self.constraints, self.initial_guesses.variables(), config)
+    }
+
     /// Solve, with metadata about the solve.
     pub fn solve(&self) -> Result<Outcome, FailureOutcome> {
         self.solve_with_config(Default::default())
@@ -485,26 +1031,44 @@ impl ConstraintSystem<'_> {
         &self,
         config: Config,
     ) -> Result<OutcomeAnalysis, FailureOutcome> {
-        let (analysis, outcome) = self.solve_with_config_inner::<FreedomAnalysis>(config)?;
+        let (analysis, outcome, _iterates) =
+            self.solve_with_config_inner::<FreedomAnalysis>(config)?;
         Ok(OutcomeAnalysis { analysis, outcome })
     }
 
     /// Solve, but give a non-default config.
     pub fn solve_with_config(&self, config: Config) -> Result<Outcome, FailureOutcome> {
-        let (NoAnalysis, outcome) = self.solve_with_config_inner::<NoAnalysis>(config)?;
+        let (NoAnalysis, outcome, _iterates) =
+            self.solve_with_config_inner::<NoAnalysis>(config)?;
         Ok(outcome)
     }
 
-    fn solve_with_config_inner<A: Analysis>(
+    /// Solve, and also get the geometry after every iteration, e.g. to play the solve back.
+    /// Returns the final outcome, and one outcome per iteration, whose last one has the same
+    /// geometry as the final outcome. The per-iteration outcomes only have the geometry and
+    /// which iteration they're from, the rest of their fields are left empty.
+    pub fn solve_recording(
         &self,
         config: Config,
-    ) -> Result<(A, Outcome), FailureOutcome> {
-        let num_vars = self.initial_guesses.len();
-        let num_eqs = self
-            .constraints
+    ) -> Result<(Outcome, Vec<Outcome>), FailureOutcome> {
+        let (NoAnalysis, outcome, iterates) =
+            self.solve_with_config_inner::<NoAnalysis>(config.with_recorded_iterates())?;
+        let frames = iterates
             .iter()
-            .map(|c| c.constraint().residual_dim())
-            .sum();
+            .enumerate()
+            .map(|(i, values)| Outcome {
+                iterations: i + 1,
+                priority_solved: outcome.priority_solved,
+                ..self.outcome_from_values(values)
+            })
+            .collect();
+        Ok((outcome, frames))
+    }
+
+    fn solve_with_config_inner<A: Analysis>(
+        &self,
+        config: Config,
+    ) -> Result<(A, Outcome, Vec<Vec<f64>>), FailureOutcome> {
         // Pass into the solver.
         let SolveOutcomeAnalysis {
             analysis,
@@ -514,14 +1078,37 @@ impl ConstraintSystem<'_> {
                     warnings,
                     final_values,
                     unsatisfied,
+                    residuals: _,
                     priority_solved,
-                    converged,
+                    termination_reason,
+                    priority_progress: _,
+                    per_priority_cost: _,
                 },
+            iterates,
         } = self.solve_no_metadata_inner::<A>(config)?;
+        let outcome = Outcome {
+            converged: termination_reason.converged(),
+            priority_solved,
+            unsatisfied,
+            iterations,
+            warnings,
+            ..self.outcome_from_values(&final_values)
+        };
+        Ok((analysis, outcome, iterates))
+    }
+
+    /// The geometry for these values of the variables. Only the geometry and problem size
+    /// are filled in, the rest is left for the caller.
+    fn outcome_from_values(&self, final_values: &[f64]) -> Outcome {
+        let num_vars = self.initial_guesses.len();
+        let num_eqs = self
+            .constraints
+            .iter()
+            .map(|c| c.constraint().residual_dim())
+            .sum();
         let num_points = self.inner_points.len();
         let num_circles = self.inner_circles.len();
         let num_arcs = self.inner_arcs.len();
-
         let mut final_points = IndexMap::with_capacity(num_points);
         for (i, point) in self.inner_points.iter().enumerate() {
             let x_id = 2 * i;
@@ -564,22 +1151,25 @@ impl ConstraintSystem<'_> {
                 },
             );
         }
-        Ok((
-            analysis,
-            Outcome {
-                converged,
-                priority_solved,
-                unsatisfied,
-                iterations,
-                warnings,
-                points: final_points,
-                circles: final_circles,
-                arcs: final_arcs,
-                num_vars,
-                lines: self.inner_lines.to_vec(),
-                num_eqs,
-            },
-        ))
+        let start_of_scalars = start_of_arcs + VARS_PER_ARC * self.inner_arcs.len();
+        let mut final_scalars = IndexMap::with_capacity(self.inner_scalars.len());
+        for (i, scalar_label) in self.inner_scalars.iter().enumerate() {
+            final_scalars.insert(scalar_label.0.clone(), final_values[start_of_scalars + i]);
+        }
+        Outcome {
+            converged: false,
+            priority_solved: 0,
+            unsatisfied: Vec::new(),
+            iterations: 0,
+            warnings: Vec::new(),
+            points: final_points,
+            circles: final_circles,
+            arcs: final_arcs,
+            scalars: final_scalars,
+            num_vars,
+            lines: self.inner_lines.to_vec(),
+            num_eqs,
+        }
     }
 }
 
@@ -598,6 +1188,8 @@ pub struct Outcome {
     pub circles: IndexMap<String, Circle>,
     /// Arcs the user defined, with their final solved values.
     pub arcs: IndexMap<String, Arc>,
+    /// Scalars the user defined, with their final solved values.
+    pub scalars: IndexMap<String, f64>,
     /// Lines the user defined, with labels for their two points.
     pub lines: Vec<(Label, Label)>,
     /// Size of the constraint system. Number of variables being solved for.
@@ -636,6 +1228,11 @@ impl Outcome {
     pub fn get_arc(&self, label: &str) -> Option<Arc> {
         self.arcs.get(label).copied()
     }
+
+    /// Look up a scalar by its label.
+    pub fn get_scalar(&self, label: &str) -> Option<f64> {
+        self.scalars.get(label).copied()
+    }
 }
 
 impl OutcomeAnalysis {
@@ -682,6 +1279,7 @@ mod tests {
             inner_circles: Vec::new(),
             inner_arcs: Vec::new(),
             inner_lines: Vec::new(),
+            inner_scalars: Vec::new(),
             point_guesses: Vec::new(),
             scalar_guesses: Vec::new(),
         }
@@ -727,13 +1325,14 @@ mod tests {
             point: Label::from("p"),
             guess: Point { x: 0.0, y: 0.0 },
         });
-        problem
-            .instructions
-            .push(Instruction::FixPointComponent(FixPointComponent {
+        problem.instructions.push(Statement {
+            instruction: Instruction::FixPointComponent(FixPointComponent {
                 point: Label::from("missing"),
                 component: Component::X,
                 value: 2.5,
-            }));
+            }),
+            priority: 0,
+        });
 
         let err = problem
             .to_constraint_system()
@@ -5,6 +5,7 @@ use crate::{Id, IdGenerator, datatypes::inputs::DatumPoint, datatypes::outputs::
 const VARS_PER_POINT: usize = 2;
 const VARS_PER_CIRCLE: usize = 3;
 pub const VARS_PER_ARC: usize = 6;
+const VARS_PER_SCALAR: usize = 1;
 
 /// Stores variables for different constrainable geometry.
 #[derive(Clone, Debug)]
@@ -12,7 +13,7 @@ pub struct GeometryVariables<S> {
     /// List of variables, each with an ID and a value.
     // Layout of this vec:
     // - All variables for points are stored first,
-    //   then all variables for circles.
+    //   then all variables for circles, then arcs, then standalone scalars.
     // - For a point, its variables are stored `[x, y]`.
     // - For a circle, its variables are stored `[center_x, center_y, radius]`.
     // So for example, storing two points and a circle would be
@@ -21,6 +22,7 @@ pub struct GeometryVariables<S> {
     num_points: usize,
     num_circles: usize,
     num_arcs: usize,
+    num_scalars: usize,
     state: PhantomData<S>,
 }
 
@@ -33,6 +35,7 @@ impl<S> Default for GeometryVariables<S> {
             num_points: Default::default(),
             num_circles: Default::default(),
             num_arcs: Default::default(),
+            num_scalars: Default::default(),
             state: Default::default(),
         }
     }
@@ -49,6 +52,9 @@ impl State for CirclesState {}
 pub struct ArcsState;
 impl State for ArcsState {}
 
+pub struct ScalarsState;
+impl State for ScalarsState {}
+
 #[derive(Clone)]
 pub struct DoneState;
 impl State for DoneState {}
@@ -64,7 +70,7 @@ impl<S: State> GeometryVariables<S> {
     }
 
     /// Add a single variable.
-    fn push_scalar(&mut self, id_generator: &mut IdGenerator, guess: f64) {
+    fn push_variable(&mut self, id_generator: &mut IdGenerator, guess: f64) {
         self.variables.push((id_generator.next_id(), guess));
     }
 
@@ -89,7 +95,7 @@ impl<S: State> GeometryVariables<S> {
 
     /// Look up the variables for a given arc.
     pub fn arc_ids(&self, arc_id: usize) -> ArcVars {
-        let start_of_arcs = VARS_PER_POINT * self.num_points;
+        let start_of_arcs = VARS_PER_POINT * self.num_points + VARS_PER_CIRCLE * self.num_circles;
         let ax = self.variables[start_of_arcs + VARS_PER_ARC * arc_id].0;
         let ay = self.variables[start_of_arcs + VARS_PER_ARC * arc_id + 1].0;
         let start = PointVars { x: ax, y: ay };
@@ -101,6 +107,14 @@ impl<S: State> GeometryVariables<S> {
         let center = PointVars { x: cx, y: cy };
         ArcVars { start, end, center }
     }
+
+    /// Look up the variable for a given standalone scalar.
+    pub fn scalar_id(&self, scalar_id: usize) -> Id {
+        let start_of_scalars = VARS_PER_POINT * self.num_points
+            + VARS_PER_CIRCLE * self.num_circles
+            + VARS_PER_ARC * self.num_arcs;
+        self.variables[start_of_scalars + VARS_PER_SCALAR * scalar_id].0
+    }
 }
 
 impl GeometryVariables<PointsState> {
@@ -110,8 +124,8 @@ impl GeometryVariables<PointsState> {
         assert!(self.num_circles == 0, "You must add points before circles");
         assert!(self.num_arcs == 0, "You must add points before arcs");
         self.num_points += 1;
-        self.push_scalar(id_generator, x);
-        self.push_scalar(id_generator, y);
+        self.push_variable(id_generator, x);
+        self.push_variable(id_generator, y);
     }
 
     pub fn done(self) -> GeometryVariables<CirclesState> {
@@ -120,6 +134,7 @@ impl GeometryVariables<PointsState> {
             num_points: self.num_points,
             num_circles: self.num_circles,
             num_arcs: self.num_arcs,
+            num_scalars: self.num_scalars,
             state: PhantomData,
         }
     }
@@ -147,6 +162,7 @@ impl GeometryVariables<CirclesState> {
             num_points: self.num_points,
             num_circles: self.num_circles,
             num_arcs: self.num_arcs,
+            num_scalars: self.num_scalars,
             state: PhantomData,
         }
     }
@@ -165,12 +181,32 @@ impl GeometryVariables<ArcsState> {
         self.variables.push((id_generator.next_id(), c.y));
     }
 
+    pub fn done(self) -> GeometryVariables<ScalarsState> {
+        GeometryVariables {
+            variables: self.variables,
+            num_points: self.num_points,
+            num_circles: self.num_circles,
+            num_arcs: self.num_arcs,
+            num_scalars: self.num_scalars,
+            state: PhantomData,
+        }
+    }
+}
+
+impl GeometryVariables<ScalarsState> {
+    /// Add the variable for a standalone scalar.
+    pub fn push_scalar(&mut self, id_generator: &mut IdGenerator, guess: f64) {
+        self.num_scalars += 1;
+        self.push_variable(id_generator, guess);
+    }
+
     pub fn done(self) -> GeometryVariables<DoneState> {
         GeometryVariables {
             variables: self.variables,
             num_points: self.num_points,
             num_circles: self.num_circles,
             num_arcs: self.num_arcs,
+            num_scalars: self.num_scalars,
             state: PhantomData,
         }
     }
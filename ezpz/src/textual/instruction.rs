@@ -1,4 +1,10 @@
-use crate::datatypes::{Angle, outputs::Component};
+use crate::{
+    CircleSide, Endpoint, TangencyKind,
+    datatypes::{
+        Angle,
+        outputs::{Component, Point},
+    },
+};
 
 use super::Label;
 
@@ -7,7 +13,10 @@ pub(crate) enum Instruction {
     DeclarePoint(DeclarePoint),
     DeclareCircle(DeclareCircle),
     DeclareArc(DeclareArc),
+    DeclareScalar(DeclareScalar),
+    FixScalar(FixScalar),
     FixPointComponent(FixPointComponent),
+    FixPoint(FixPoint),
     Vertical(Vertical),
     Horizontal(Horizontal),
     Distance(Distance),
@@ -27,6 +36,364 @@ pub(crate) enum Instruction {
     PointLineDistance(PointLineDistance),
     Line(Line),
     ArcLength(ArcLength),
+    Polar(Polar),
+    ScaledCopy(ScaledCopy),
+    SameSide(SameSide),
+    ArcCircleEqualRadius(ArcCircleEqualRadius),
+    Concyclic(Concyclic),
+    Circumcenter(Circumcenter),
+    Sagitta(Sagitta),
+    EqualLengthTo(EqualLengthTo),
+    BisectorLine(BisectorLine),
+    RadiusRatio(RadiusRatio),
+    EquidistantLinePoint(EquidistantLinePoint),
+    TangentAtAngle(TangentAtAngle),
+    Circumscribed(Circumscribed),
+    ParallelOffset(ParallelOffset),
+    AngleEqualsScalar(AngleEqualsScalar),
+    ArcSegmentArea(ArcSegmentArea),
+    Subtends(Subtends),
+    Homothety(Homothety),
+    SymmetricAngle(SymmetricAngle),
+    LengthRatioBounds(LengthRatioBounds),
+    ParallelOffsetScalar(ParallelOffsetScalar),
+    OnGrid(OnGrid),
+    DistanceSum(DistanceSum),
+    TangentCircles(TangentCircles),
+    Fillet(Fillet),
+    PointOnCircle(PointOnCircle),
+    PointOnLine(PointOnLine),
+    CommonTangent(CommonTangent),
+    ArcMidpoint(ArcMidpoint),
+    LineAngle(LineAngle),
+    EqualAngle(EqualAngle),
+    LineArcTangent(LineArcTangent),
+    ArcChordAngle(ArcChordAngle),
+    ReverseTangentArcs(ReverseTangentArcs),
+    LinearCombo(LinearCombo),
+    LineCircleIntersection(LineCircleIntersection),
+    ArcCentralAngleScalar(ArcCentralAngleScalar),
+}
+
+/// One line of the constraints section: an instruction, and the priority of any constraints
+/// it adds. The priority is 0 (the highest) unless the line ends with `@priority N`.
+#[derive(Debug)]
+pub(crate) struct Statement {
+    pub instruction: Instruction,
+    pub priority: u32,
+}
+
+/// Writes the statement in the same syntax the parser accepts.
+impl std::fmt::Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.instruction)?;
+        if self.priority != 0 {
+            write!(f, " @priority {}", self.priority)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the instruction in the same syntax the parser accepts.
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::DeclarePoint(DeclarePoint { label }) => write!(f, "point {label}"),
+            Instruction::DeclareCircle(DeclareCircle { label }) => write!(f, "circle {label}"),
+            Instruction::DeclareArc(DeclareArc { label }) => write!(f, "arc {label}"),
+            Instruction::DeclareScalar(DeclareScalar { label }) => write!(f, "scalar {label}"),
+            Instruction::FixScalar(FixScalar { scalar, value }) => write!(f, "{scalar} = {value}"),
+            Instruction::FixPointComponent(FixPointComponent {
+                point,
+                component,
+                value,
+            }) => write!(f, "{point}.{} = {value}", component_name(*component)),
+            Instruction::FixPoint(FixPoint { point, value }) => write!(f, "{point} = {value}"),
+            Instruction::FixCenterPointComponent(FixCenterPointComponent {
+                object,
+                center_component,
+                value,
+            }) => write!(
+                f,
+                "{object}.center.{} = {value}",
+                component_name(*center_component)
+            ),
+            Instruction::Vertical(Vertical { label: (p0, p1) }) => {
+                write!(f, "vertical({p0}, {p1})")
+            }
+            Instruction::Horizontal(Horizontal { label: (p0, p1) }) => {
+                write!(f, "horizontal({p0}, {p1})")
+            }
+            Instruction::Distance(Distance {
+                label: (p0, p1),
+                distance,
+            }) => write!(f, "distance({p0}, {p1}, {distance})"),
+            Instruction::Parallel(Parallel {
+                line0: (p0, p1),
+                line1: (p2, p3),
+            }) => write!(f, "parallel({p0}, {p1}, {p2}, {p3})"),
+            Instruction::Perpendicular(Perpendicular {
+                line0: (p0, p1),
+                line1: (p2, p3),
+            }) => write!(f, "perpendicular({p0}, {p1}, {p2}, {p3})"),
+            Instruction::AngleLine(AngleLine {
+                line0: (p0, p1),
+                line1: (p2, p3),
+                angle,
+            }) => write!(f, "lines_at_angle({p0}, {p1}, {p2}, {p3}, {angle})"),
+            Instruction::PointsCoincident(PointsCoincident { point0, point1 }) => {
+                write!(f, "coincident({point0}, {point1})")
+            }
+            Instruction::PointArcCoincident(PointArcCoincident { point, arc }) => {
+                write!(f, "point_arc_coincident({point}, {arc})")
+            }
+            Instruction::Midpoint(Midpoint { point0, point1, mp }) => {
+                write!(f, "midpoint({point0}, {point1}, {mp})")
+            }
+            Instruction::Symmetric(Symmetric {
+                line: (p, q),
+                p0,
+                p1,
+            }) => write!(f, "symmetric({p}, {q}, {p0}, {p1})"),
+            Instruction::CircleRadius(CircleRadius { circle, radius }) => {
+                write!(f, "radius({circle}, {radius})")
+            }
+            Instruction::Tangent(Tangent {
+                circle,
+                line_p0,
+                line_p1,
+            }) => write!(f, "tangent({line_p0}, {line_p1}, {circle})"),
+            Instruction::ArcRadius(ArcRadius { arc_label, radius }) => {
+                write!(f, "arc_radius({arc_label}, {radius})")
+            }
+            Instruction::LinesEqualLength(LinesEqualLength {
+                line0: (p0, p1),
+                line1: (p2, p3),
+            }) => write!(f, "lines_equal_length({p0}, {p1}, {p2}, {p3})"),
+            Instruction::IsArc(IsArc { arc_label }) => write!(f, "is_arc({arc_label})"),
+            Instruction::PointLineDistance(PointLineDistance {
+                point,
+                line_p0,
+                line_p1,
+                distance,
+            }) => write!(
+                f,
+                "point_line_distance({point}, {line_p0}, {line_p1}, {distance})"
+            ),
+            Instruction::Line(Line { p0, p1 }) => write!(f, "line({p0}, {p1})"),
+            Instruction::ArcLength(ArcLength { arc, distance }) => {
+                write!(f, "arc_length({arc}, {distance})")
+            }
+            Instruction::Polar(Polar {
+                from,
+                to,
+                distance,
+                angle,
+            }) => write!(f, "polar({from}, {to}, {distance}, {angle})"),
+            Instruction::ScaledCopy(ScaledCopy {
+                pivot,
+                scale,
+                original,
+                copy,
+            }) => write!(f, "scaled_copy({pivot}, {scale}, {original}, {copy})"),
+            Instruction::SameSide(SameSide {
+                line: (p0, p1),
+                reference,
+                point,
+            }) => write!(f, "same_side({p0}, {p1}, {reference}, {point})"),
+            Instruction::ArcCircleEqualRadius(ArcCircleEqualRadius { arc, circle }) => {
+                write!(f, "arc_circle_equal_radius({arc}, {circle})")
+            }
+            Instruction::Concyclic(Concyclic { arc0, arc1 }) => {
+                write!(f, "concyclic({arc0}, {arc1})")
+            }
+            Instruction::Circumcenter(Circumcenter { center, a, b, c }) => {
+                write!(f, "circumcenter({center}, {a}, {b}, {c})")
+            }
+            Instruction::Sagitta(Sagitta { arc, distance }) => {
+                write!(f, "sagitta({arc}, {distance})")
+            }
+            Instruction::ArcSegmentArea(ArcSegmentArea { arc, area }) => {
+                write!(f, "arc_segment_area({arc}, {area})")
+            }
+            Instruction::EqualLengthTo(EqualLengthTo { common, a, b }) => {
+                write!(f, "equal_length_to({common}, {a}, {b})")
+            }
+            Instruction::BisectorLine(BisectorLine {
+                bisector: (bis0, bis1),
+                a: (a0, a1),
+                b: (b0, b1),
+            }) => write!(f, "bisector_line({bis0}, {bis1}, {a0}, {a1}, {b0}, {b1})"),
+            Instruction::RadiusRatio(RadiusRatio {
+                circle,
+                scalar,
+                ratio,
+            }) => write!(f, "radius_ratio({circle}, {scalar}, {ratio})"),
+            Instruction::EquidistantLinePoint(EquidistantLinePoint {
+                point,
+                directrix: (p0, p1),
+                focus,
+            }) => write!(f, "equidistant_line_point({point}, {p0}, {p1}, {focus})"),
+            Instruction::TangentAtAngle(TangentAtAngle {
+                line: (p0, p1),
+                circle,
+                angle,
+            }) => write!(f, "tangent_at_angle({p0}, {p1}, {circle}, {angle})"),
+            Instruction::Homothety(Homothety {
+                center,
+                original,
+                image,
+                ratio,
+            }) => write!(f, "homothety({center}, {original}, {image}, {ratio})"),
+            Instruction::SymmetricAngle(SymmetricAngle {
+                reference: (r0, r1),
+                a: (a0, a1),
+                b: (b0, b1),
+            }) => write!(f, "symmetric_angle({r0}, {r1}, {a0}, {a1}, {b0}, {b1})"),
+            Instruction::LengthRatioBounds(LengthRatioBounds {
+                line0: (p0, p1),
+                line1: (p2, p3),
+                min_ratio,
+                max_ratio,
+            }) => write!(
+                f,
+                "length_ratio_bounds({p0}, {p1}, {p2}, {p3}, {min_ratio}, {max_ratio})"
+            ),
+            Instruction::Subtends(Subtends { point, a, b, angle }) => {
+                write!(f, "subtends({point}, {a}, {b}, {angle})")
+            }
+            Instruction::AngleEqualsScalar(AngleEqualsScalar {
+                line0: (p0, p1),
+                line1: (p2, p3),
+                scalar,
+            }) => write!(f, "angle_equals_scalar({p0}, {p1}, {p2}, {p3}, {scalar})"),
+            Instruction::ParallelOffset(ParallelOffset {
+                line0: (p0, p1),
+                line1: (p2, p3),
+                offset,
+            }) => write!(f, "parallel_offset({p0}, {p1}, {p2}, {p3}, {offset})"),
+            Instruction::OnGrid(OnGrid {
+                point,
+                spacing,
+                origin,
+            }) => write!(f, "on_grid({point}, {spacing}, {origin})"),
+            Instruction::DistanceSum(DistanceSum {
+                point,
+                focus0,
+                focus1,
+                sum,
+            }) => write!(f, "distance_sum({point}, {focus0}, {focus1}, {sum})"),
+            Instruction::TangentCircles(TangentCircles {
+                circle0,
+                circle1,
+                side,
+            }) => {
+                let side = match side {
+                    CircleSide::Interior => "internal",
+                    _ => "external",
+                };
+                write!(f, "tangent_circles({circle0}, {circle1}, {side})")
+            }
+            Instruction::Fillet(Fillet {
+                line0: (p0, p1),
+                line1: (p2, p3),
+                circle,
+            }) => write!(f, "fillet({p0}, {p1}, {p2}, {p3}, {circle})"),
+            Instruction::PointOnCircle(PointOnCircle { point, circle }) => {
+                write!(f, "point_on_circle({point}, {circle})")
+            }
+            Instruction::PointOnLine(PointOnLine {
+                point,
+                line: (p0, p1),
+            }) => write!(f, "point_on_line({point}, {p0}, {p1})"),
+            Instruction::LineCircleIntersection(LineCircleIntersection {
+                point,
+                line: (p0, p1),
+                circle,
+            }) => write!(f, "line_circle_intersect({point}, {p0}, {p1}, {circle})"),
+            Instruction::ArcCentralAngleScalar(ArcCentralAngleScalar { arc, scalar }) => {
+                write!(f, "arc_central_angle_scalar({arc}, {scalar})")
+            }
+            Instruction::CommonTangent(CommonTangent {
+                line: (p0, p1),
+                circle0,
+                circle1,
+                kind,
+            }) => {
+                let kind = match kind {
+                    TangencyKind::Internal => "internal",
+                    _ => "external",
+                };
+                write!(
+                    f,
+                    "common_tangent({p0}, {p1}, {circle0}, {circle1}, {kind})"
+                )
+            }
+            Instruction::LineArcTangent(LineArcTangent {
+                line: (p0, p1),
+                arc,
+                end,
+            }) => {
+                let end = match end {
+                    Endpoint::End => "end",
+                    _ => "start",
+                };
+                write!(f, "line_arc_tangent({p0}, {p1}, {arc}, {end})")
+            }
+            Instruction::ArcChordAngle(ArcChordAngle { arc, angle }) => {
+                write!(f, "arc_chord_angle({arc}, {angle})")
+            }
+            Instruction::ReverseTangentArcs(ReverseTangentArcs { arc0, arc1 }) => {
+                write!(f, "reverse_tangent_arcs({arc0}, {arc1})")
+            }
+            Instruction::LinearCombo(LinearCombo {
+                result,
+                terms: [(term0, coefficient0), (term1, coefficient1)],
+                constant,
+            }) => write!(
+                f,
+                "linear_combo({result}, {term0}, {coefficient0}, {term1}, {coefficient1}, {constant})"
+            ),
+            Instruction::ArcMidpoint(ArcMidpoint { arc, midpoint }) => {
+                write!(f, "arc_midpoint({arc}, {midpoint})")
+            }
+            Instruction::LineAngle(LineAngle {
+                line: (p0, p1),
+                angle,
+            }) => write!(f, "line_angle({p0}, {p1}, {angle})"),
+            Instruction::EqualAngle(EqualAngle {
+                a: (a0, a1),
+                b: (b0, b1),
+                c: (c0, c1),
+                d: (d0, d1),
+            }) => write!(
+                f,
+                "equal_angle({a0}, {a1}, {b0}, {b1}, {c0}, {c1}, {d0}, {d1})"
+            ),
+            Instruction::ParallelOffsetScalar(ParallelOffsetScalar {
+                line0: (p0, p1),
+                line1: (p2, p3),
+                offset,
+            }) => write!(
+                f,
+                "parallel_offset_scalar({p0}, {p1}, {p2}, {p3}, {offset})"
+            ),
+            Instruction::Circumscribed(Circumscribed { circle, vertices }) => {
+                write!(f, "circumscribed({circle}")?;
+                for vertex in vertices {
+                    write!(f, ", {vertex}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+fn component_name(component: Component) -> char {
+    match component {
+        Component::X => 'x',
+        Component::Y => 'y',
+    }
 }
 
 #[derive(Debug)]
@@ -54,6 +421,18 @@ pub struct Tangent {
     pub line_p1: Label,
 }
 
+#[derive(Debug)]
+pub struct ArcCircleEqualRadius {
+    pub arc: Label,
+    pub circle: Label,
+}
+
+#[derive(Debug)]
+pub struct Concyclic {
+    pub arc0: Label,
+    pub arc1: Label,
+}
+
 #[derive(Debug)]
 pub struct ArcRadius {
     pub arc_label: Label,
@@ -123,6 +502,80 @@ pub struct ArcLength {
     pub distance: f64,
 }
 
+#[derive(Debug)]
+pub struct Sagitta {
+    pub arc: Label,
+    pub distance: f64,
+}
+
+#[derive(Debug)]
+pub struct Polar {
+    pub from: Label,
+    pub to: Label,
+    pub distance: f64,
+    pub angle: Angle,
+}
+
+#[derive(Debug)]
+pub struct ScaledCopy {
+    /// Scale about this point.
+    pub pivot: Label,
+    /// Scalar variable for the scale factor.
+    pub scale: Label,
+    pub original: Label,
+    pub copy: Label,
+}
+
+#[derive(Debug)]
+pub struct SameSide {
+    /// Stay on one side of this line.
+    pub line: (Label, Label),
+    /// The side this point is on.
+    pub reference: Label,
+    pub point: Label,
+}
+
+#[derive(Debug)]
+pub struct Circumcenter {
+    /// Equidistant from the other three points.
+    pub center: Label,
+    pub a: Label,
+    pub b: Label,
+    pub c: Label,
+}
+
+#[derive(Debug)]
+pub struct EqualLengthTo {
+    /// Both other points are the same distance from this one.
+    pub common: Label,
+    pub a: Label,
+    pub b: Label,
+}
+
+#[derive(Debug)]
+pub struct BisectorLine {
+    /// Bisects the angle between the other two lines.
+    pub bisector: (Label, Label),
+    pub a: (Label, Label),
+    pub b: (Label, Label),
+}
+
+#[derive(Debug)]
+pub struct RadiusRatio {
+    pub circle: Label,
+    pub scalar: Label,
+    /// The circle's radius is this many times the scalar.
+    pub ratio: f64,
+}
+
+#[derive(Debug)]
+pub struct EquidistantLinePoint {
+    /// Same distance from the line as from the focus.
+    pub point: Label,
+    pub directrix: (Label, Label),
+    pub focus: Label,
+}
+
 #[derive(Debug)]
 pub struct Symmetric {
     /// Be symmetric across this line.
@@ -156,6 +609,17 @@ pub struct DeclareArc {
     pub label: Label,
 }
 
+#[derive(Debug)]
+pub struct DeclareScalar {
+    pub label: Label,
+}
+
+#[derive(Debug)]
+pub struct FixScalar {
+    pub scalar: Label,
+    pub value: f64,
+}
+
 #[derive(Debug)]
 pub struct FixPointComponent {
     pub point: Label,
@@ -163,9 +627,213 @@ pub struct FixPointComponent {
     pub value: f64,
 }
 
+#[derive(Debug)]
+pub struct FixPoint {
+    pub point: Label,
+    pub value: Point,
+}
+
 #[derive(Debug)]
 pub struct FixCenterPointComponent {
     pub object: Label,
     pub center_component: Component,
     pub value: f64,
 }
+
+#[derive(Debug)]
+pub struct TangentAtAngle {
+    pub line: (Label, Label),
+    pub circle: Label,
+    /// Direction of the line, from its first point to its second.
+    pub angle: Angle,
+}
+
+#[derive(Debug)]
+pub struct Circumscribed {
+    pub circle: Label,
+    /// Every edge between consecutive vertices (wrapping around) is tangent to the circle.
+    pub vertices: Vec<Label>,
+}
+
+#[derive(Debug)]
+pub struct ParallelOffset {
+    pub line0: (Label, Label),
+    pub line1: (Label, Label),
+    /// Signed perpendicular distance from `line0` to the first point of `line1`.
+    pub offset: f64,
+}
+
+#[derive(Debug)]
+pub struct AngleEqualsScalar {
+    pub line0: (Label, Label),
+    pub line1: (Label, Label),
+    /// Scalar variable for the angle from `line0` to `line1`, in radians.
+    pub scalar: Label,
+}
+
+#[derive(Debug)]
+pub struct ArcSegmentArea {
+    pub arc: Label,
+    pub area: f64,
+}
+
+#[derive(Debug)]
+pub struct Subtends {
+    pub point: Label,
+    pub a: Label,
+    pub b: Label,
+    pub angle: Angle,
+}
+
+#[derive(Debug)]
+pub struct Homothety {
+    pub center: Label,
+    pub original: Label,
+    pub image: Label,
+    pub ratio: f64,
+}
+
+#[derive(Debug)]
+pub struct SymmetricAngle {
+    /// The other two lines make equal but opposite angles to this one.
+    pub reference: (Label, Label),
+    pub a: (Label, Label),
+    pub b: (Label, Label),
+}
+
+#[derive(Debug)]
+pub struct LengthRatioBounds {
+    /// This line's length divided by the other's stays within the bounds.
+    pub line0: (Label, Label),
+    pub line1: (Label, Label),
+    pub min_ratio: f64,
+    pub max_ratio: f64,
+}
+
+#[derive(Debug)]
+pub struct ParallelOffsetScalar {
+    pub line0: (Label, Label),
+    pub line1: (Label, Label),
+    /// Scalar variable for the signed perpendicular distance from `line0` to the first point of `line1`.
+    pub offset: Label,
+}
+
+#[derive(Debug)]
+pub struct OnGrid {
+    pub point: Label,
+    /// Distance between grid lines.
+    pub spacing: f64,
+    /// One of the grid's intersections.
+    pub origin: Point,
+}
+
+#[derive(Debug)]
+pub struct DistanceSum {
+    pub point: Label,
+    pub focus0: Label,
+    pub focus1: Label,
+    /// Total of the distances from `point` to each focus.
+    pub sum: f64,
+}
+
+#[derive(Debug)]
+pub struct TangentCircles {
+    pub circle0: Label,
+    pub circle1: Label,
+    /// [`CircleSide::Exterior`] if they touch from outside, [`CircleSide::Interior`] if one
+    /// is inside the other.
+    pub side: CircleSide,
+}
+
+#[derive(Debug)]
+pub struct Fillet {
+    pub line0: (Label, Label),
+    pub line1: (Label, Label),
+    pub circle: Label,
+}
+
+#[derive(Debug)]
+pub struct PointOnCircle {
+    pub point: Label,
+    pub circle: Label,
+}
+
+#[derive(Debug)]
+pub struct PointOnLine {
+    pub point: Label,
+    pub line: (Label, Label),
+}
+
+#[derive(Debug)]
+pub struct LineCircleIntersection {
+    pub point: Label,
+    pub line: (Label, Label),
+    pub circle: Label,
+}
+
+#[derive(Debug)]
+pub struct ArcCentralAngleScalar {
+    pub arc: Label,
+    /// Scalar variable for the arc's central angle, in radians.
+    pub scalar: Label,
+}
+
+#[derive(Debug)]
+pub struct CommonTangent {
+    pub line: (Label, Label),
+    pub circle0: Label,
+    pub circle1: Label,
+    pub kind: TangencyKind,
+}
+
+#[derive(Debug)]
+pub struct ArcMidpoint {
+    pub arc: Label,
+    pub midpoint: Label,
+}
+
+#[derive(Debug)]
+pub struct ArcChordAngle {
+    pub arc: Label,
+    /// Direction of the chord, from the arc's start to its end.
+    pub angle: Angle,
+}
+
+/// The arcs meet at both their ends with reversed curvature, like an S-curve.
+#[derive(Debug)]
+pub struct ReverseTangentArcs {
+    pub arc0: Label,
+    pub arc1: Label,
+}
+
+/// The result scalar is the sum of each term scalar times its coefficient, plus the constant.
+#[derive(Debug)]
+pub struct LinearCombo {
+    pub result: Label,
+    pub terms: [(Label, f64); 2],
+    pub constant: f64,
+}
+
+#[derive(Debug)]
+pub struct LineArcTangent {
+    pub line: (Label, Label),
+    pub arc: Label,
+    /// Which end of the arc the line is tangent at.
+    pub end: Endpoint,
+}
+
+#[derive(Debug)]
+pub struct LineAngle {
+    pub line: (Label, Label),
+    /// Direction of the line, from its first point to its second.
+    pub angle: Angle,
+}
+
+/// The angle from `a` to `b` equals the angle from `c` to `d`.
+#[derive(Debug)]
+pub struct EqualAngle {
+    pub a: (Label, Label),
+    pub b: (Label, Label),
+    pub c: (Label, Label),
+    pub d: (Label, Label),
+}
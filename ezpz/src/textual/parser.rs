@@ -1,20 +1,30 @@
 use crate::{
+    CircleSide, Endpoint, TangencyKind,
     datatypes::Angle,
     datatypes::outputs::{Component, Point},
     textual::{
         ScalarGuess,
         instruction::{
-            AngleLine, ArcLength, ArcRadius, CircleRadius, DeclareArc, DeclareCircle, Distance,
-            FixCenterPointComponent, IsArc, Line, LinesEqualLength, Midpoint, Parallel,
-            Perpendicular, PointArcCoincident, PointLineDistance, PointsCoincident, Symmetric,
-            Tangent,
+            AngleEqualsScalar, AngleLine, ArcCentralAngleScalar, ArcChordAngle,
+            ArcCircleEqualRadius, ArcLength, ArcMidpoint, ArcRadius, ArcSegmentArea, BisectorLine,
+            CircleRadius, Circumcenter, Circumscribed, CommonTangent, Concyclic, DeclareArc,
+            DeclareCircle, Distance, DistanceSum, EqualAngle, EqualLengthTo, EquidistantLinePoint,
+            Fillet, FixCenterPointComponent, Homothety, IsArc, LengthRatioBounds, Line, LineAngle,
+            LineArcTangent, LineCircleIntersection, LinearCombo, LinesEqualLength, Midpoint,
+            OnGrid, Parallel, ParallelOffset, ParallelOffsetScalar, Perpendicular,
+            PointArcCoincident, PointLineDistance, PointOnCircle, PointOnLine, PointsCoincident,
+            Polar, RadiusRatio, ReverseTangentArcs, Sagitta, SameSide, ScaledCopy, Subtends,
+            Symmetric, SymmetricAngle, Tangent, TangentAtAngle, TangentCircles,
         },
     },
 };
 
 use super::{
     Label, PointGuess, Problem,
-    instruction::{DeclarePoint, FixPointComponent, Horizontal, Instruction, Vertical},
+    instruction::{
+        DeclarePoint, DeclareScalar, FixPoint, FixPointComponent, FixScalar, Horizontal,
+        Instruction, Statement, Vertical,
+    },
 };
 use winnow::{
     ModalResult as WResult,
@@ -28,12 +38,13 @@ use winnow::{
 
 pub fn parse_problem(i: &mut &str) -> WResult<Problem> {
     constraint_header.parse_next(i)?;
-    let instructions: Vec<_> = separated(1.., parse_instruction, newline).parse_next(i)?;
+    let instructions: Vec<_> = separated(1.., parse_statements, newline).parse_next(i)?;
     let mut inner_points = Vec::new();
     let mut inner_circles = Vec::new();
     let mut inner_arcs = Vec::new();
     let mut inner_lines = Vec::new();
-    for instr in instructions.iter().flatten() {
+    let mut inner_scalars = Vec::new();
+    for instr in instructions.iter().flatten().map(|s| &s.instruction) {
         if let Instruction::DeclarePoint(dp) = instr {
             inner_points.push(dp.label.clone());
         }
@@ -46,6 +57,9 @@ pub fn parse_problem(i: &mut &str) -> WResult<Problem> {
         if let Instruction::Line(line) = instr {
             inner_lines.push((line.p0.clone(), line.p1.clone()));
         }
+        if let Instruction::DeclareScalar(ds) = instr {
+            inner_scalars.push(ds.label.clone());
+        }
     }
     newline.parse_next(i)?;
     newline.parse_next(i)?;
@@ -70,6 +84,7 @@ pub fn parse_problem(i: &mut &str) -> WResult<Problem> {
         inner_circles,
         inner_arcs,
         inner_lines,
+        inner_scalars,
         point_guesses,
         scalar_guesses,
     })
@@ -152,6 +167,12 @@ pub fn parse_declare_arc(i: &mut &str) -> WResult<DeclareArc> {
         .parse_next(i)
 }
 
+pub fn parse_declare_scalar(i: &mut &str) -> WResult<DeclareScalar> {
+    ("scalar", ws, parse_label)
+        .map(|(_, _, label)| DeclareScalar { label })
+        .parse_next(i)
+}
+
 pub fn parse_horizontal(i: &mut &str) -> WResult<Horizontal> {
     let _ = "horizontal".parse_next(i)?;
     ignore_ws(i);
@@ -240,6 +261,247 @@ pub fn parse_angle_line(i: &mut &str) -> WResult<AngleLine> {
     })
 }
 
+pub fn parse_polar(i: &mut &str) -> WResult<Polar> {
+    let _ = "polar".parse_next(i)?;
+    ignore_ws(i);
+    let ([from, to], _, distance, _, angle) = inside_brackets(
+        (
+            two_points,
+            commasep,
+            parse_number_expr,
+            commasep,
+            parse_angle,
+        ),
+        i,
+    )?;
+    Ok(Polar {
+        from,
+        to,
+        distance,
+        angle,
+    })
+}
+
+pub fn parse_scaled_copy(i: &mut &str) -> WResult<ScaledCopy> {
+    let _ = "scaled_copy".parse_next(i)?;
+    ignore_ws(i);
+    let [pivot, scale, original, copy] = inside_brackets(four_points, i)?;
+    Ok(ScaledCopy {
+        pivot,
+        scale,
+        original,
+        copy,
+    })
+}
+
+pub fn parse_same_side(i: &mut &str) -> WResult<SameSide> {
+    let _ = "same_side".parse_next(i)?;
+    ignore_ws(i);
+    let [p0, p1, reference, point] = inside_brackets(four_points, i)?;
+    Ok(SameSide {
+        line: (p0, p1),
+        reference,
+        point,
+    })
+}
+
+pub fn parse_circumcenter(i: &mut &str) -> WResult<Circumcenter> {
+    let _ = "circumcenter".parse_next(i)?;
+    ignore_ws(i);
+    let [center, a, b, c] = inside_brackets(four_points, i)?;
+    Ok(Circumcenter { center, a, b, c })
+}
+
+pub fn parse_equal_length_to(i: &mut &str) -> WResult<EqualLengthTo> {
+    let _ = "equal_length_to".parse_next(i)?;
+    ignore_ws(i);
+    let [common, a, b] = inside_brackets(three_points, i)?;
+    Ok(EqualLengthTo { common, a, b })
+}
+
+pub fn parse_equidistant_line_point(i: &mut &str) -> WResult<EquidistantLinePoint> {
+    let _ = "equidistant_line_point".parse_next(i)?;
+    ignore_ws(i);
+    let [point, p0, p1, focus] = inside_brackets(four_points, i)?;
+    Ok(EquidistantLinePoint {
+        point,
+        directrix: (p0, p1),
+        focus,
+    })
+}
+
+pub fn parse_line_angle(i: &mut &str) -> WResult<LineAngle> {
+    let _ = "line_angle".parse_next(i)?;
+    ignore_ws(i);
+    let ([p0, p1], _, angle) = inside_brackets((two_points, commasep, parse_angle), i)?;
+    Ok(LineAngle {
+        line: (p0, p1),
+        angle,
+    })
+}
+
+pub fn parse_arc_chord_angle(i: &mut &str) -> WResult<ArcChordAngle> {
+    let _ = "arc_chord_angle".parse_next(i)?;
+    ignore_ws(i);
+    let (arc, _, angle) = inside_brackets((parse_label, commasep, parse_angle), i)?;
+    Ok(ArcChordAngle { arc, angle })
+}
+
+pub fn parse_tangent_at_angle(i: &mut &str) -> WResult<TangentAtAngle> {
+    let _ = "tangent_at_angle".parse_next(i)?;
+    ignore_ws(i);
+    let ([p0, p1], _, circle, _, angle) = inside_brackets(
+        (two_points, commasep, parse_label, commasep, parse_angle),
+        i,
+    )?;
+    Ok(TangentAtAngle {
+        line: (p0, p1),
+        circle,
+        angle,
+    })
+}
+
+pub fn parse_homothety(i: &mut &str) -> WResult<Homothety> {
+    let _ = "homothety".parse_next(i)?;
+    ignore_ws(i);
+    let ([center, original, image], _, ratio) =
+        inside_brackets((three_points, commasep, parse_number), i)?;
+    Ok(Homothety {
+        center,
+        original,
+        image,
+        ratio,
+    })
+}
+
+pub fn parse_symmetric_angle(i: &mut &str) -> WResult<SymmetricAngle> {
+    let _ = "symmetric_angle".parse_next(i)?;
+    ignore_ws(i);
+    let [r0, r1, a0, a1, b0, b1] = inside_brackets(six_points, i)?;
+    Ok(SymmetricAngle {
+        reference: (r0, r1),
+        a: (a0, a1),
+        b: (b0, b1),
+    })
+}
+
+pub fn parse_equal_angle(i: &mut &str) -> WResult<EqualAngle> {
+    let _ = "equal_angle".parse_next(i)?;
+    ignore_ws(i);
+    let ([a0, a1, b0, b1], _, [c0, c1, d0, d1]) =
+        inside_brackets((four_points, commasep, four_points), i)?;
+    Ok(EqualAngle {
+        a: (a0, a1),
+        b: (b0, b1),
+        c: (c0, c1),
+        d: (d0, d1),
+    })
+}
+
+pub fn parse_length_ratio_bounds(i: &mut &str) -> WResult<LengthRatioBounds> {
+    let _ = "length_ratio_bounds".parse_next(i)?;
+    ignore_ws(i);
+    let ([p0, p1, p2, p3], _, min_ratio, _, max_ratio) = inside_brackets(
+        (four_points, commasep, parse_number, commasep, parse_number),
+        i,
+    )?;
+    Ok(LengthRatioBounds {
+        line0: (p0, p1),
+        line1: (p2, p3),
+        min_ratio,
+        max_ratio,
+    })
+}
+
+pub fn parse_subtends(i: &mut &str) -> WResult<Subtends> {
+    let _ = "subtends".parse_next(i)?;
+    ignore_ws(i);
+    let ([point, a, b], _, angle) = inside_brackets((three_points, commasep, parse_angle), i)?;
+    Ok(Subtends { point, a, b, angle })
+}
+
+pub fn parse_angle_equals_scalar(i: &mut &str) -> WResult<AngleEqualsScalar> {
+    let _ = "angle_equals_scalar".parse_next(i)?;
+    ignore_ws(i);
+    let ([p0, p1, p2, p3], _, scalar) = inside_brackets((four_points, commasep, parse_label), i)?;
+    Ok(AngleEqualsScalar {
+        line0: (p0, p1),
+        line1: (p2, p3),
+        scalar,
+    })
+}
+
+pub fn parse_parallel_offset(i: &mut &str) -> WResult<ParallelOffset> {
+    let _ = "parallel_offset".parse_next(i)?;
+    ignore_ws(i);
+    let ([p0, p1, p2, p3], _, offset) =
+        inside_brackets((four_points, commasep, parse_number_expr), i)?;
+    Ok(ParallelOffset {
+        line0: (p0, p1),
+        line1: (p2, p3),
+        offset,
+    })
+}
+
+pub fn parse_parallel_offset_scalar(i: &mut &str) -> WResult<ParallelOffsetScalar> {
+    let _ = "parallel_offset_scalar".parse_next(i)?;
+    ignore_ws(i);
+    let ([p0, p1, p2, p3], _, offset) = inside_brackets((four_points, commasep, parse_label), i)?;
+    Ok(ParallelOffsetScalar {
+        line0: (p0, p1),
+        line1: (p2, p3),
+        offset,
+    })
+}
+
+pub fn parse_on_grid(i: &mut &str) -> WResult<OnGrid> {
+    let _ = "on_grid".parse_next(i)?;
+    ignore_ws(i);
+    let (point, _, spacing, _, origin) = inside_brackets(
+        (parse_label, commasep, parse_number, commasep, parse_point),
+        i,
+    )?;
+    Ok(OnGrid {
+        point,
+        spacing,
+        origin,
+    })
+}
+
+pub fn parse_distance_sum(i: &mut &str) -> WResult<DistanceSum> {
+    let _ = "distance_sum".parse_next(i)?;
+    ignore_ws(i);
+    let ([point, focus0, focus1], _, sum) =
+        inside_brackets((three_points, commasep, parse_number), i)?;
+    Ok(DistanceSum {
+        point,
+        focus0,
+        focus1,
+        sum,
+    })
+}
+
+pub fn parse_circumscribed(i: &mut &str) -> WResult<Circumscribed> {
+    let _ = "circumscribed".parse_next(i)?;
+    ignore_ws(i);
+    let (circle, _, vertices) = inside_brackets(
+        (parse_label, commasep, separated(3.., parse_label, commasep)),
+        i,
+    )?;
+    Ok(Circumscribed { circle, vertices })
+}
+
+pub fn parse_bisector_line(i: &mut &str) -> WResult<BisectorLine> {
+    let _ = "bisector_line".parse_next(i)?;
+    ignore_ws(i);
+    let [bis0, bis1, a0, a1, b0, b1] = inside_brackets(six_points, i)?;
+    Ok(BisectorLine {
+        bisector: (bis0, bis1),
+        a: (a0, a1),
+        b: (b0, b1),
+    })
+}
+
 pub fn parse_angle(i: &mut &str) -> WResult<Angle> {
     let value = parse_number(i)?;
     let is_degrees = alt(("deg".map(|_| true), "rad".map(|_| false))).parse_next(i)?;
@@ -266,6 +528,141 @@ pub fn parse_circle_radius(i: &mut &str) -> WResult<CircleRadius> {
     Ok(CircleRadius { circle, radius })
 }
 
+pub fn parse_radius_ratio(i: &mut &str) -> WResult<RadiusRatio> {
+    let _ = "radius_ratio".parse_next(i)?;
+    ignore_ws(i);
+    let (circle, _, scalar, _, ratio) = inside_brackets(
+        (
+            parse_label,
+            commasep,
+            parse_label,
+            commasep,
+            parse_number_expr,
+        ),
+        i,
+    )?;
+    Ok(RadiusRatio {
+        circle,
+        scalar,
+        ratio,
+    })
+}
+
+pub fn parse_fillet(i: &mut &str) -> WResult<Fillet> {
+    let _ = "fillet".parse_next(i)?;
+    ignore_ws(i);
+    let ([p0, p1, p2, p3], _, circle) = inside_brackets((four_points, commasep, parse_label), i)?;
+    Ok(Fillet {
+        line0: (p0, p1),
+        line1: (p2, p3),
+        circle,
+    })
+}
+
+pub fn parse_point_on_circle(i: &mut &str) -> WResult<PointOnCircle> {
+    let _ = "point_on_circle".parse_next(i)?;
+    ignore_ws(i);
+    let (point, _, circle) = inside_brackets((parse_label, commasep, parse_label), i)?;
+    Ok(PointOnCircle { point, circle })
+}
+
+pub fn parse_point_on_line(i: &mut &str) -> WResult<PointOnLine> {
+    let _ = "point_on_line".parse_next(i)?;
+    ignore_ws(i);
+    let [point, p0, p1] = inside_brackets(three_points, i)?;
+    Ok(PointOnLine {
+        point,
+        line: (p0, p1),
+    })
+}
+
+pub fn parse_line_circle_intersect(i: &mut &str) -> WResult<LineCircleIntersection> {
+    let _ = "line_circle_intersect".parse_next(i)?;
+    ignore_ws(i);
+    let ([point, p0, p1], _, circle) = inside_brackets((three_points, commasep, parse_label), i)?;
+    Ok(LineCircleIntersection {
+        point,
+        line: (p0, p1),
+        circle,
+    })
+}
+
+pub fn parse_common_tangent(i: &mut &str) -> WResult<CommonTangent> {
+    let _ = "common_tangent".parse_next(i)?;
+    ignore_ws(i);
+    let (p0, _, p1, _, circle0, _, circle1, _, kind) = inside_brackets(
+        (
+            parse_label,
+            commasep,
+            parse_label,
+            commasep,
+            parse_label,
+            commasep,
+            parse_label,
+            commasep,
+            alt((
+                "external".map(|_| TangencyKind::External),
+                "internal".map(|_| TangencyKind::Internal),
+            )),
+        ),
+        i,
+    )?;
+    Ok(CommonTangent {
+        line: (p0, p1),
+        circle0,
+        circle1,
+        kind,
+    })
+}
+
+pub fn parse_line_arc_tangent(i: &mut &str) -> WResult<LineArcTangent> {
+    let _ = "line_arc_tangent".parse_next(i)?;
+    ignore_ws(i);
+    let (p0, _, p1, _, arc, _, end) = inside_brackets(
+        (
+            parse_label,
+            commasep,
+            parse_label,
+            commasep,
+            parse_label,
+            commasep,
+            alt((
+                "start".map(|_| Endpoint::Start),
+                "end".map(|_| Endpoint::End),
+            )),
+        ),
+        i,
+    )?;
+    Ok(LineArcTangent {
+        line: (p0, p1),
+        arc,
+        end,
+    })
+}
+
+pub fn parse_tangent_circles(i: &mut &str) -> WResult<TangentCircles> {
+    let _ = "tangent_circles".parse_next(i)?;
+    ignore_ws(i);
+    let (circle0, _, circle1, _, side) = inside_brackets(
+        (
+            parse_label,
+            commasep,
+            parse_label,
+            commasep,
+            alt((
+                "external".map(|_| CircleSide::Exterior),
+                "internal".map(|_| CircleSide::Interior),
+            )),
+        ),
+        i,
+    )?;
+    Ok(TangentCircles {
+        circle0,
+        circle1,
+        side,
+    })
+}
+
 pub fn parse_tangent(i: &mut &str) -> WResult<Tangent> {
     let _ = "tangent".parse_next(i)?;
     ignore_ws(i);
@@ -287,6 +684,54 @@ pub fn parse_arc_radius(i: &mut &str) -> WResult<ArcRadius> {
     Ok(ArcRadius { arc_label, radius })
 }
 
+pub fn parse_arc_circle_equal_radius(i: &mut &str) -> WResult<ArcCircleEqualRadius> {
+    let _ = "arc_circle_equal_radius".parse_next(i)?;
+    ignore_ws(i);
+    let [arc, circle] = inside_brackets(two_points, i)?;
+    Ok(ArcCircleEqualRadius { arc, circle })
+}
+
+pub fn parse_concyclic(i: &mut &str) -> WResult<Concyclic> {
+    let _ = "concyclic".parse_next(i)?;
+    ignore_ws(i);
+    let [arc0, arc1] = inside_brackets(two_points, i)?;
+    Ok(Concyclic { arc0, arc1 })
+}
+
+pub fn parse_reverse_tangent_arcs(i: &mut &str) -> WResult<ReverseTangentArcs> {
+    let _ = "reverse_tangent_arcs".parse_next(i)?;
+    ignore_ws(i);
+    let [arc0, arc1] = inside_brackets(two_points, i)?;
+    Ok(ReverseTangentArcs { arc0, arc1 })
+}
+
+pub fn parse_linear_combo(i: &mut &str) -> WResult<LinearCombo> {
+    let _ = "linear_combo".parse_next(i)?;
+    ignore_ws(i);
+    let (result, _, term0, _, coefficient0, _, term1, _, coefficient1, _, constant) =
+        inside_brackets(
+            (
+                parse_label,
+                commasep,
+                parse_label,
+                commasep,
+                parse_number_expr,
+                commasep,
+                parse_label,
+                commasep,
+                parse_number_expr,
+                commasep,
+                parse_number_expr,
+            ),
+            i,
+        )?;
+    Ok(LinearCombo {
+        result,
+        terms: [(term0, coefficient0), (term1, coefficient1)],
+        constant,
+    })
+}
+
 pub fn parse_arc_length(i: &mut &str) -> WResult<ArcLength> {
     let _ = "arc_length".parse_next(i)?;
     ignore_ws(i);
@@ -294,6 +739,34 @@ pub fn parse_arc_length(i: &mut &str) -> WResult<ArcLength> {
     Ok(ArcLength { arc, distance })
 }
 
+pub fn parse_arc_central_angle_scalar(i: &mut &str) -> WResult<ArcCentralAngleScalar> {
+    let _ = "arc_central_angle_scalar".parse_next(i)?;
+    ignore_ws(i);
+    let (arc, _, scalar) = inside_brackets((parse_label, commasep, parse_label), i)?;
+    Ok(ArcCentralAngleScalar { arc, scalar })
+}
+
+pub fn parse_arc_segment_area(i: &mut &str) -> WResult<ArcSegmentArea> {
+    let _ = "arc_segment_area".parse_next(i)?;
+    ignore_ws(i);
+    let (arc, _, area) = inside_brackets((parse_label, commasep, parse_number), i)?;
+    Ok(ArcSegmentArea { arc, area })
+}
+
+pub fn parse_arc_midpoint(i: &mut &str) -> WResult<ArcMidpoint> {
+    let _ = "arc_midpoint".parse_next(i)?;
+    ignore_ws(i);
+    let (arc, _, midpoint) = inside_brackets((parse_label, commasep, parse_label), i)?;
+    Ok(ArcMidpoint { arc, midpoint })
+}
+
+pub fn parse_sagitta(i: &mut &str) -> WResult<Sagitta> {
+    let _ = "sagitta".parse_next(i)?;
+    ignore_ws(i);
+    let (arc, _, distance) = inside_brackets((parse_label, commasep, parse_number), i)?;
+    Ok(Sagitta { arc, distance })
+}
+
 pub fn parse_is_arc(i: &mut &str) -> WResult<IsArc> {
     let _ = "is_arc".parse_next(i)?;
     ignore_ws(i);
@@ -350,6 +823,13 @@ fn four_points(i: &mut &str) -> WResult<[Label; 4]> {
     Ok([p0, p1, p2, p3])
 }
 
+fn six_points(i: &mut &str) -> WResult<[Label; 6]> {
+    let [p0, p1, p2, p3] = four_points(i)?;
+    commasep(i)?;
+    let [p4, p5] = two_points(i)?;
+    Ok([p0, p1, p2, p3, p4, p5])
+}
+
 fn two_points(i: &mut &str) -> WResult<[Label; 2]> {
     let p0 = parse_label(i)?;
     commasep(i)?;
@@ -385,6 +865,28 @@ fn sv<T>(t: T) -> Vec<T> {
     vec![t]
 }
 
+/// One line's instructions, all with the priority from its optional `@priority N` suffix.
+fn parse_statements(i: &mut &str) -> WResult<Vec<Statement>> {
+    let instructions = parse_instruction(i)?;
+    let priority = opt(parse_priority).parse_next(i)?.unwrap_or(0);
+    Ok(instructions
+        .into_iter()
+        .map(|instruction| Statement {
+            instruction,
+            priority,
+        })
+        .collect())
+}
+
+fn parse_priority(i: &mut &str) -> WResult<u32> {
+    ignore_ws(i);
+    let _ = "@priority".parse_next(i)?;
+    ignore_ws(i);
+    digit1
+        .verify_map(|s: &str| s.parse::<u32>().ok())
+        .parse_next(i)
+}
+
 fn parse_instruction(i: &mut &str) -> WResult<Vec<Instruction>> {
     ignore_ws(i);
     alt((
@@ -392,13 +894,15 @@ fn parse_instruction(i: &mut &str) -> WResult<Vec<Instruction>> {
             parse_declare_point.map(Instruction::DeclarePoint).map(sv),
             parse_declare_circle.map(Instruction::DeclareCircle).map(sv),
             parse_declare_arc.map(Instruction::DeclareArc).map(sv),
+            parse_declare_scalar.map(Instruction::DeclareScalar).map(sv),
             parse_fix_point_component
                 .map(Instruction::FixPointComponent)
                 .map(sv),
             parse_fix_center_point_component
                 .map(Instruction::FixCenterPointComponent)
                 .map(sv),
-            assign_point,
+            assign_point.map(Instruction::FixPoint).map(sv),
+            assign_scalar.map(Instruction::FixScalar).map(sv),
         )),
         alt((
             parse_horizontal.map(Instruction::Horizontal).map(sv),
@@ -436,6 +940,85 @@ fn parse_other_instructions(i: &mut &str) -> WResult<Vec<Instruction>> {
             parse_lines_equal_length
                 .map(Instruction::LinesEqualLength)
                 .map(sv),
+            parse_polar.map(Instruction::Polar).map(sv),
+            parse_scaled_copy.map(Instruction::ScaledCopy).map(sv),
+            parse_same_side.map(Instruction::SameSide).map(sv),
+            parse_arc_circle_equal_radius
+                .map(Instruction::ArcCircleEqualRadius)
+                .map(sv),
+            parse_concyclic.map(Instruction::Concyclic).map(sv),
+        )),
+        alt((
+            parse_circumcenter.map(Instruction::Circumcenter).map(sv),
+            parse_sagitta.map(Instruction::Sagitta).map(sv),
+            parse_equal_length_to
+                .map(Instruction::EqualLengthTo)
+                .map(sv),
+            parse_bisector_line.map(Instruction::BisectorLine).map(sv),
+            parse_radius_ratio.map(Instruction::RadiusRatio).map(sv),
+            parse_equidistant_line_point
+                .map(Instruction::EquidistantLinePoint)
+                .map(sv),
+            parse_tangent_at_angle
+                .map(Instruction::TangentAtAngle)
+                .map(sv),
+            parse_circumscribed.map(Instruction::Circumscribed).map(sv),
+            parse_parallel_offset
+                .map(Instruction::ParallelOffset)
+                .map(sv),
+        )),
+        alt((
+            parse_angle_equals_scalar
+                .map(Instruction::AngleEqualsScalar)
+                .map(sv),
+            parse_arc_segment_area
+                .map(Instruction::ArcSegmentArea)
+                .map(sv),
+            parse_subtends.map(Instruction::Subtends).map(sv),
+            parse_homothety.map(Instruction::Homothety).map(sv),
+            parse_symmetric_angle
+                .map(Instruction::SymmetricAngle)
+                .map(sv),
+            parse_length_ratio_bounds
+                .map(Instruction::LengthRatioBounds)
+                .map(sv),
+            parse_parallel_offset_scalar
+                .map(Instruction::ParallelOffsetScalar)
+                .map(sv),
+            parse_on_grid.map(Instruction::OnGrid).map(sv),
+            parse_distance_sum.map(Instruction::DistanceSum).map(sv),
+        )),
+        alt((
+            parse_tangent_circles
+                .map(Instruction::TangentCircles)
+                .map(sv),
+            parse_fillet.map(Instruction::Fillet).map(sv),
+            parse_point_on_circle
+                .map(Instruction::PointOnCircle)
+                .map(sv),
+            parse_point_on_line.map(Instruction::PointOnLine).map(sv),
+            parse_common_tangent.map(Instruction::CommonTangent).map(sv),
+            parse_arc_midpoint.map(Instruction::ArcMidpoint).map(sv),
+            parse_line_angle.map(Instruction::LineAngle).map(sv),
+            parse_equal_angle.map(Instruction::EqualAngle).map(sv),
+        )),
+        alt((
+            parse_line_arc_tangent
+                .map(Instruction::LineArcTangent)
+                .map(sv),
+            parse_arc_chord_angle
+                .map(Instruction::ArcChordAngle)
+                .map(sv),
+            parse_reverse_tangent_arcs
+                .map(Instruction::ReverseTangentArcs)
+                .map(sv),
+            parse_linear_combo.map(Instruction::LinearCombo).map(sv),
+            parse_line_circle_intersect
+                .map(Instruction::LineCircleIntersection)
+                .map(sv),
+            parse_arc_central_angle_scalar
+                .map(Instruction::ArcCentralAngleScalar)
+                .map(sv),
         )),
     ))
     .parse_next(i)
@@ -449,25 +1032,27 @@ fn ignore_ws(i: &mut &str) {
     let _ = ws.parse_next(i);
 }
 
-fn assign_point(i: &mut &str) -> WResult<Vec<Instruction>> {
+fn assign_point(i: &mut &str) -> WResult<FixPoint> {
     // p0 = (0, 0)
     let label = parse_label_opt_suffix(i)?;
     ignore_ws(i);
     '='.parse_next(i)?;
     ignore_ws(i);
-    let pt = parse_point(i)?;
-    Ok(vec![
-        Instruction::FixPointComponent(FixPointComponent {
-            point: label.clone(),
-            component: Component::X,
-            value: pt.x,
-        }),
-        Instruction::FixPointComponent(FixPointComponent {
-            point: label,
-            component: Component::Y,
-            value: pt.y,
-        }),
-    ])
+    let value = parse_point(i)?;
+    Ok(FixPoint {
+        point: label,
+        value,
+    })
+}
+
+fn assign_scalar(i: &mut &str) -> WResult<FixScalar> {
+    // s = 2
+    let scalar = parse_label(i)?;
+    ignore_ws(i);
+    '='.parse_next(i)?;
+    ignore_ws(i);
+    let value = parse_number(i)?;
+    Ok(FixScalar { scalar, value })
 }
 
 fn parse_component(i: &mut &str) -> WResult<Component> {
@@ -560,6 +1145,16 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_parse_priority() {
+        let statements = parse_statements(&mut "distance(a, b, 5) @priority 2").unwrap();
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].priority, 2);
+        // Without a suffix, it's the highest priority.
+        let statements = parse_statements(&mut "distance(a, b, 5)").unwrap();
+        assert_eq!(statements[0].priority, 0);
+    }
+
     #[test]
     fn test_parse_angle() {
         let i = parse_angle(&mut "0deg").unwrap();
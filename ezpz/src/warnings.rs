@@ -7,6 +7,7 @@ use crate::{
 /// Something bad that users should know about.
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Warning {
     /// If this warning is about a particular constraint, which constraint?
     /// Refers to each constraint by ID.
@@ -18,6 +19,7 @@ pub struct Warning {
 /// What went wrong, or should be done differently.
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(not(feature = "unstable-exhaustive"), non_exhaustive)]
 pub enum WarningContent {
     /// The constraint was satisfied, but only by a degenerate solution,
@@ -29,6 +31,13 @@ pub enum WarningContent {
     /// This constraint used a specific angle measurement, but
     /// it would be more accurate to use the Perpendicular constraint.
     ShouldBePerpendicular(Angle),
+    /// One of this constraint's rows of the Jacobian was all zeros, so the solver couldn't tell
+    /// how to move the variables to satisfy it, e.g. a distance between two coincident points.
+    /// The rest of the system is still solved.
+    DegenerateJacobianRow {
+        /// Which of the constraint's residuals had the all-zero row, starting from 0.
+        row: usize,
+    },
 }
 
 pub(crate) fn lint(constraints: &[ConstraintEntry<'_>]) -> Vec<Warning> {
@@ -78,6 +87,10 @@ impl std::fmt::Display for WarningContent {
                     "Instead of constraining to {angle}, constraint to Perpendicular"
                 )
             }
+            Self::DegenerateJacobianRow { row } => write!(
+                f,
+                "Residual {row} of this constraint doesn't change when any of its variables move, so the solver can't satisfy it. This usually means its geometry is degenerate, e.g. two points are in the same place."
+            ),
         }
     }
 }